@@ -30,14 +30,19 @@ Glance is a modern Linux system optimizer that combines monitoring, cleaning, an
 export CARGO_HOME="$(pwd)/.cargo"
 npm ci
 npm run tauri build -- --bundles rpm
+cargo build --release --manifest-path src-tauri/Cargo.toml --bin glance-helper
 
 %install
 install -Dm755 src-tauri/target/release/glance-linuxoptimizer %{buildroot}%{_bindir}/glance
+install -Dm755 src-tauri/target/release/glance-helper %{buildroot}/usr/lib/glance/glance-helper
+install -Dm644 packaging/polkit/com.github.WRVbit.glance.policy %{buildroot}%{_datadir}/polkit-1/actions/com.github.WRVbit.glance.policy
 install -Dm644 com.github.WRVbit.glance.desktop %{buildroot}%{_datadir}/applications/com.github.WRVbit.glance.desktop
 install -Dm644 src-tauri/icons/icon.png %{buildroot}%{_datadir}/icons/hicolor/512x512/apps/com.github.WRVbit.glance.png
 
 %files
 %{_bindir}/glance
+/usr/lib/glance/glance-helper
+%{_datadir}/polkit-1/actions/com.github.WRVbit.glance.policy
 %{_datadir}/applications/com.github.WRVbit.glance.desktop
 %{_datadir}/icons/hicolor/512x512/apps/com.github.WRVbit.glance.png
 
@@ -1,54 +1,313 @@
 //! Arch Linux Package Manager Adapter
 //! Uses pacman for package management
 
-use super::{PackageInfo, PackageAction, CleanupResult, PackageManager, detect_package_category};
+use super::{PackageInfo, PackageOrigin, PackageAction, PackageDetails, SearchResult, UpgradablePackage, CleanupResult, PackageManager, detect_package_category};
+use super::{ChangeKind, PackageChange, PackageTransaction, find_transaction, newest_first, nothing_to_undo, undo_action};
+use super::{RemovalPreview, dry_run_output};
+use super::{DependencyGraph, Providers};
+use super::{command_exists, dependency_name, escape_regex, package_not_found, parse_fields, parse_size, query_output, sort_by_relevance, MAX_SEARCH_RESULTS};
 use crate::error::{AppError, Result};
-use crate::utils::privileged;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
 use async_trait::async_trait;
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 use tokio::process::Command;
 
-pub struct ArchAdapter;
+const PACMAN_CONF: &str = "/etc/pacman.conf";
+/// pacman's local database: a `<name>-<version>` directory with a `desc` file per installed package
+const PACMAN_LOCAL_DB: &str = "/var/lib/pacman/local";
+
+pub struct ArchAdapter {
+    /// Parsed local database entries by directory, reused while their `desc` is unchanged
+    local_db: Mutex<HashMap<String, (SystemTime, PackageInfo)>>,
+    executor: Arc<dyn PrivilegedExecutor>,
+}
 
 impl ArchAdapter {
-    pub fn new() -> Self {
-        Self
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { local_db: Mutex::new(HashMap::new()), executor }
+    }
+    
+    /// Installed packages from pacman's local database; only entries added or changed since
+    /// the last read are parsed again
+    fn read_local_db(&self) -> Result<Vec<PackageInfo>> {
+        let mut cache = self.local_db.lock().unwrap();
+        let mut current = HashMap::new();
+        
+        for entry in std::fs::read_dir(PACMAN_LOCAL_DB)? {
+            let entry = entry?;
+            // Skip ALPM_DB_VERSION
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let desc = entry.path().join("desc");
+            let Ok(modified) = desc.metadata().and_then(|m| m.modified()) else { continue };
+            let dir = entry.file_name().to_string_lossy().to_string();
+            
+            let package = match cache.remove(&dir) {
+                Some((cached, package)) if cached == modified => package,
+                _ => match std::fs::read_to_string(&desc).ok().as_deref().and_then(parse_desc) {
+                    Some(package) => package,
+                    None => continue,
+                },
+            };
+            current.insert(dir, (modified, package));
+        }
+        *cache = current;
+        
+        let mut packages: Vec<PackageInfo> = cache.values().map(|(_, package)| package.clone()).collect();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(packages)
     }
     
     /// Check if paccache is available (from pacman-contrib)
     async fn has_paccache(&self) -> bool {
-        Command::new("which")
-            .arg("paccache")
-            .output()
-            .await
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+        command_exists("paccache").await
+    }
+    
+    /// Check if checkupdates is available (from pacman-contrib)
+    async fn has_checkupdates(&self) -> bool {
+        command_exists("checkupdates").await
+    }
+    
+    /// What `pacman <remove_flags> <names>` would remove, printed by `-p` (needs no root);
+    /// pacman refuses with the dependencies a removal would break
+    async fn simulate(&self, action: &str, remove_flags: &str, names: &[&str]) -> Result<RemovalPreview> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] Nothing to remove for Arch");
+            return Ok(RemovalPreview::empty(action));
+        }
+        if names.is_empty() {
+            return Ok(RemovalPreview::empty(action));
+        }
+        
+        let flags = format!("{}p", remove_flags);
+        let mut args = vec![flags.as_str(), "--print-format", "%n"];
+        args.extend_from_slice(names);
+        let removed: Vec<String> = dry_run_output("pacman", &args)
+            .await?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+        
+        let installed = self.get_installed_packages().await?;
+        Ok(RemovalPreview::new(action, &removed, &installed))
     }
+}
+
+/// Uncomment the `[multilib]` section of pacman.conf; None if it is already enabled
+fn uncomment_multilib(conf: &str) -> Option<String> {
+    let mut in_section = false;
+    let mut changed = false;
+    let mut lines = Vec::new();
     
-    /// Parse size string like "12.5 MiB" to bytes
-    fn parse_size(size_str: &str) -> u64 {
-        let parts: Vec<&str> = size_str.split_whitespace().collect();
-        if parts.len() < 2 {
-            return 0;
+    for line in conf.lines() {
+        let trimmed = line.trim();
+        let uncommented = trimmed.trim_start_matches('#').trim();
+        let is_header = uncommented.starts_with('[');
+        if is_header {
+            in_section = uncommented == "[multilib]";
         }
         
-        let num: f64 = parts[0].parse().unwrap_or(0.0);
-        let unit = parts[1].to_lowercase();
+        if in_section && trimmed.starts_with('#') && (is_header || uncommented.starts_with("Include")) {
+            lines.push(uncommented.to_string());
+            changed = true;
+        } else {
+            lines.push(line.to_string());
+        }
         
-        match unit.as_str() {
-            "b" => num as u64,
-            "kib" | "kb" => (num * 1024.0) as u64,
-            "mib" | "mb" => (num * 1024.0 * 1024.0) as u64,
-            "gib" | "gb" => (num * 1024.0 * 1024.0 * 1024.0) as u64,
-            _ => 0,
+        // Like `sed '/\[multilib\]/,/Include/'`: the section ends at its Include line
+        if in_section && uncommented.starts_with("Include") {
+            in_section = false;
         }
     }
+    
+    changed.then(|| lines.join("\n") + "\n")
 }
 
-impl Default for ArchAdapter {
-    fn default() -> Self {
-        Self::new()
+/// Parse pacman.log into transactions, oldest first; each is identified by its start time
+/// `[2024-01-10T12:00:02+0100] [ALPM] upgraded bash (5.2.021-1 -> 5.2.026-1)`
+fn parse_history(log: &str) -> Vec<PackageTransaction> {
+    let mut transactions = Vec::new();
+    let mut command: Option<String> = None;
+    let mut current: Option<(String, Vec<PackageChange>)> = None;
+    
+    for line in log.lines() {
+        let Some((timestamp, rest)) = line.strip_prefix('[').and_then(|l| l.split_once("] ")) else { continue };
+        
+        if let Some(running) = rest.strip_prefix("[PACMAN] Running ") {
+            command = Some(running.trim_matches('\'').to_string());
+            continue;
+        }
+        let Some(message) = rest.strip_prefix("[ALPM] ") else { continue };
+        
+        if message == "transaction started" {
+            current = Some((timestamp.to_string(), Vec::new()));
+            continue;
+        }
+        if message.starts_with("transaction ") {
+            if let Some((start, changes)) = current.take().filter(|(_, changes)| !changes.is_empty()) {
+                transactions.push(PackageTransaction::new(start.clone(), start, command.take(), changes));
+            }
+            continue;
+        }
+        
+        let Some((verb, package)) = message.split_once(' ') else { continue };
+        let kind = match verb {
+            "installed" => ChangeKind::Install,
+            "upgraded" => ChangeKind::Upgrade,
+            "downgraded" => ChangeKind::Downgrade,
+            "reinstalled" => ChangeKind::Reinstall,
+            "removed" => ChangeKind::Remove,
+            _ => continue,
+        };
+        let Some((name, versions)) = package.split_once(" (") else { continue };
+        let versions = versions.trim_end_matches(')');
+        let (old_version, new_version) = match kind {
+            ChangeKind::Install => (None, Some(versions.to_string())),
+            ChangeKind::Remove => (Some(versions.to_string()), None),
+            ChangeKind::Reinstall => (Some(versions.to_string()), Some(versions.to_string())),
+            ChangeKind::Upgrade | ChangeKind::Downgrade => match versions.split_once(" -> ") {
+                Some((old, new)) => (Some(old.to_string()), Some(new.to_string())),
+                None => continue,
+            },
+        };
+        if let Some((_, changes)) = current.as_mut() {
+            changes.push(PackageChange { name: name.to_string(), kind, old_version, new_version });
+        }
     }
+    
+    transactions
+}
+
+/// File name of `name` `version` in the package cache, e.g. `bash-5.2.026-2-x86_64.pkg.tar.zst`
+fn cached_package(cache_files: &[String], name: &str, version: &str) -> Option<String> {
+    let prefix = format!("{}-{}-", name, version);
+    cache_files
+        .iter()
+        .find(|file| {
+            file.strip_prefix(&prefix).is_some_and(|rest| {
+                !rest.contains('-') && (rest.ends_with(".pkg.tar.zst") || rest.ends_with(".pkg.tar.xz"))
+            })
+        })
+        .cloned()
+}
+
+/// Parse `checkupdates` / `pacman -Qu` lines: `linux 6.9.1.arch1-1 -> 6.9.2.arch1-1`
+fn parse_upgradable(output: &str) -> Vec<UpgradablePackage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let (name, current, arrow, new) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+            (arrow == "->").then(|| UpgradablePackage {
+                name: name.to_string(),
+                current_version: current.to_string(),
+                new_version: new.to_string(),
+                repository: None,
+            })
+        })
+        .collect()
+}
+
+/// Parse `pacman -Ss` output: a `repo/name version [installed]` line, then the indented description
+fn parse_sync_search(output: &str) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = Vec::new();
+    
+    for line in output.lines() {
+        if line.starts_with(char::is_whitespace) {
+            if let Some(last) = results.last_mut() {
+                last.package.description = line.trim().to_string();
+                last.package.category = detect_package_category(&last.package.name, &last.package.description);
+            }
+            continue;
+        }
+        
+        let mut parts = line.split_whitespace();
+        let (Some(path), Some(version)) = (parts.next(), parts.next()) else { continue };
+        let Some((repo, name)) = path.split_once('/') else { continue };
+        
+        results.push(SearchResult {
+            package: PackageInfo {
+                name: name.to_string(),
+                version: version.to_string(),
+                size_bytes: 0,
+                description: String::new(),
+                is_auto: false,
+                category: detect_package_category(name, ""),
+                origin: PackageOrigin::Native,
+            },
+            // "[installed]" or "[installed: 6.9.0]" when an older version is installed
+            installed: line.contains("[installed"),
+            repository: Some(repo.to_string()),
+        });
+    }
+    
+    results
+}
+
+/// Whitespace-separated pacman list field ("None" when empty)
+fn list_field(value: Option<&String>) -> Vec<String> {
+    value
+        .map(|v| v.split_whitespace().filter(|d| *d != "None").map(dependency_name).collect())
+        .unwrap_or_default()
+}
+
+/// Parse a local database `desc` file: `%NAME%` headers, each followed by its value lines.
+/// `%REASON%` is 1 for packages installed as a dependency
+fn parse_desc(desc: &str) -> Option<PackageInfo> {
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+    let mut lines = desc.lines();
+    while let Some(line) = lines.next() {
+        if let Some(key) = line.strip_prefix('%').and_then(|l| l.strip_suffix('%')) {
+            fields.insert(key, lines.next().unwrap_or(""));
+        }
+    }
+    
+    let name = fields.get("NAME")?.to_string();
+    let description = fields.get("DESC").unwrap_or(&"").to_string();
+    Some(PackageInfo {
+        category: detect_package_category(&name, &description),
+        version: fields.get("VERSION").unwrap_or(&"").to_string(),
+        size_bytes: fields.get("SIZE").and_then(|s| s.parse().ok()).unwrap_or(0),
+        is_auto: fields.get("REASON") == Some(&"1"),
+        description,
+        name,
+        origin: PackageOrigin::Native,
+    })
+}
+
+/// Parse `pacman -Qi` into each package's installed dependencies; virtual names
+/// (`sh`, `libfoo.so=1-64`) resolve to the packages providing them
+fn parse_dependencies(output: &str) -> HashMap<String, Vec<String>> {
+    let blocks: Vec<HashMap<String, String>> = output
+        .split("\n\n")
+        .map(parse_fields)
+        .filter(|fields| fields.contains_key("Name"))
+        .collect();
+    
+    let mut providers = Providers::new();
+    for fields in &blocks {
+        let name = &fields["Name"];
+        providers.add(name, name);
+        for capability in list_field(fields.get("Provides")) {
+            providers.add(&capability, name);
+        }
+    }
+    
+    blocks
+        .iter()
+        .map(|fields| {
+            let depends = list_field(fields.get("Depends On"))
+                .iter()
+                .flat_map(|dep| providers.resolve(dep).to_vec())
+                .collect();
+            (fields["Name"].clone(), depends)
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -65,8 +324,12 @@ impl PackageManager for ArchAdapter {
         "/var/log/pacman.log"
     }
     
+    fn database_paths(&self) -> &'static [&'static str] {
+        &[PACMAN_LOCAL_DB]
+    }
+    
     async fn refresh_repositories(&self) -> Result<String> {
-        privileged::run_privileged("pacman", &["-Sy"]).await
+        self.executor.execute(&[PrivilegedOp::refresh_repositories("pacman")]).await
     }
     
     async fn get_installed_packages(&self) -> Result<Vec<PackageInfo>> {
@@ -76,66 +339,7 @@ impl PackageManager for ArchAdapter {
             return Ok(super::generate_mock_packages("pacman"));
         }
         
-        // Get explicitly installed packages
-        let explicit_output = Command::new("pacman")
-            .args(["-Qe", "-q"])
-            .output()
-            .await
-            .map_err(|e| AppError::CommandFailed(e.to_string()))?;
-        
-        let explicit_packages: HashSet<String> = 
-            String::from_utf8_lossy(&explicit_output.stdout)
-                .lines()
-                .map(|s| s.to_string())
-                .collect();
-        
-        // Get all installed packages with info
-        let output = Command::new("pacman")
-            .args(["-Qi"])
-            .output()
-            .await
-            .map_err(|e| AppError::CommandFailed(e.to_string()))?;
-        
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut packages = Vec::new();
-        
-        let mut name = String::new();
-        let mut version = String::new();
-        let mut size_bytes: u64 = 0;
-        let mut description = String::new();
-        
-        for line in stdout.lines() {
-            if line.starts_with("Name            :") {
-                name = line.split(':').nth(1).unwrap_or("").trim().to_string();
-            } else if line.starts_with("Version         :") {
-                version = line.split(':').nth(1).unwrap_or("").trim().to_string();
-            } else if line.starts_with("Installed Size  :") {
-                let size_str = line.split(':').nth(1).unwrap_or("").trim();
-                size_bytes = Self::parse_size(size_str);
-            } else if line.starts_with("Description     :") {
-                description = line.split(':').nth(1).unwrap_or("").trim().to_string();
-            } else if line.is_empty() && !name.is_empty() {
-                let category = detect_package_category(&name, &description);
-                let is_auto = !explicit_packages.contains(&name);
-                
-                packages.push(PackageInfo {
-                    name: name.clone(),
-                    version: version.clone(),
-                    size_bytes,
-                    description: description.clone(),
-                    is_auto,
-                    category,
-                });
-                
-                name.clear();
-                version.clear();
-                size_bytes = 0;
-                description.clear();
-            }
-        }
-        
-        packages.sort_by(|a, b| a.name.cmp(&b.name));
-        Ok(packages)
+        self.read_local_db()
     }
     
     async fn search_packages(&self, query: &str) -> Result<Vec<PackageInfo>> {
@@ -151,8 +355,146 @@ impl PackageManager for ArchAdapter {
             .collect())
     }
     
+    async fn install_packages(&self, names: &[&str]) -> Result<String> {
+        let mut args = vec!["-S", "--needed", "--noconfirm"];
+        args.extend_from_slice(names);
+        self.executor.execute(&[PrivilegedOp::package("pacman", &args)]).await
+    }
+    
+    async fn enable_multilib(&self) -> Result<String> {
+        // Uncomment [multilib] in pacman.conf, then sync it with a full upgrade (never a bare -Sy)
+        let conf = std::fs::read_to_string(PACMAN_CONF)?;
+        let Some(updated) = uncomment_multilib(&conf) else {
+            return Ok("[multilib] repository already enabled".to_string());
+        };
+        self.executor.execute(&[
+            PrivilegedOp::write_repo_file(PACMAN_CONF, &updated),
+            PrivilegedOp::package("pacman", &["-Syu", "--noconfirm"]),
+        ]).await?;
+        Ok("[multilib] repository enabled".to_string())
+    }
+    
+    async fn transaction_history(&self) -> Result<Vec<PackageTransaction>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No transaction history for Arch");
+            return Ok(Vec::new());
+        }
+        
+        let log = std::fs::read_to_string(self.log_path())?;
+        Ok(newest_first(parse_history(&log)))
+    }
+    
+    async fn undo_transaction(&self, id: &str) -> Result<PackageAction> {
+        let transaction = find_transaction(self.transaction_history().await?, id)?;
+        let cache_files: Vec<String> = std::fs::read_dir(self.cache_path())
+            .map(|entries| entries.flatten().map(|e| e.file_name().to_string_lossy().to_string()).collect())
+            .unwrap_or_default();
+        
+        // Removed packages and replaced versions come back from the package cache
+        let mut files = Vec::new();
+        let mut skipped = Vec::new();
+        for (name, version) in transaction.removed().into_iter().chain(transaction.version_changes()) {
+            match cached_package(&cache_files, name, version) {
+                Some(file) => files.push(file),
+                None => skipped.push(format!("{} {} (not in the package cache)", name, version)),
+            }
+        }
+        
+        let mut ops = Vec::new();
+        if !files.is_empty() {
+            ops.push(PrivilegedOp::install_cached_packages(&files));
+        }
+        // Remove after downgrading, so nothing still depends on the new packages
+        let installed = transaction.installed();
+        if !installed.is_empty() {
+            let mut args = vec!["-R", "--noconfirm"];
+            args.extend(installed);
+            ops.push(PrivilegedOp::package("pacman", &args));
+        }
+        if ops.is_empty() {
+            return Err(nothing_to_undo(id));
+        }
+        
+        Ok(undo_action(&transaction, self.executor.execute(&ops).await, &skipped))
+    }
+    
+    async fn search_remote(&self, query: &str) -> Result<Vec<SearchResult>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No remote search results for Arch");
+            return Ok(Vec::new());
+        }
+        
+        let output = query_output("pacman", &["-Ss", &escape_regex(query)]).await?;
+        let mut results = parse_sync_search(&output);
+        sort_by_relevance(&mut results, query, |r| &r.package.name);
+        results.truncate(MAX_SEARCH_RESULTS);
+        Ok(results)
+    }
+    
+    async fn list_upgradable(&self) -> Result<Vec<UpgradablePackage>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No upgradable packages for Arch");
+            return Ok(Vec::new());
+        }
+        
+        // checkupdates syncs a temporary database copy; pacman -Qu relies on the last -Sy
+        let output = if self.has_checkupdates().await {
+            query_output("checkupdates", &[]).await?
+        } else {
+            query_output("pacman", &["-Qu"]).await?
+        };
+        Ok(parse_upgradable(&output))
+    }
+    
+    async fn upgrade_packages(&self, _names: &[&str]) -> Result<PackageAction> {
+        // Partial upgrades are unsupported on Arch, and the local sync DB is older than checkupdates' one
+        Err(AppError::NotSupported(
+            "Arch does not support upgrading single packages; use Upgrade All (pacman -Syu)".to_string(),
+        ))
+    }
+    
+    async fn upgrade_all(&self) -> Result<PackageAction> {
+        let result = self.executor.execute(&[PrivilegedOp::package("pacman", &["-Syu", "--noconfirm"])]).await;
+        
+        Ok(PackageAction {
+            name: "system".to_string(),
+            action: "upgrade".to_string(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e.to_string()),
+        })
+    }
+    
+    async fn package_details(&self, name: &str) -> Result<PackageDetails> {
+        let local = parse_fields(&query_output("pacman", &["-Qi", name]).await?);
+        let sync = parse_fields(&query_output("pacman", &["-Si", name]).await?);
+        let installed = !local.is_empty();
+        let fields = if installed { &local } else { &sync };
+        if fields.is_empty() {
+            return Err(package_not_found(name));
+        }
+        
+        let files = if installed {
+            query_output("pacman", &["-Qlq", name]).await?.lines().map(|f| f.to_string()).collect()
+        } else {
+            Vec::new()
+        };
+        
+        Ok(PackageDetails {
+            name: name.to_string(),
+            version: fields.get("Version").cloned().unwrap_or_default(),
+            description: fields.get("Description").cloned().unwrap_or_default(),
+            installed,
+            size_bytes: fields.get("Installed Size").map(|s| parse_size(s)).unwrap_or(0),
+            // Installed packages missing from the sync databases are foreign (AUR, local builds)
+            repository: sync.get("Repository").cloned().or_else(|| installed.then(|| "local".to_string())),
+            dependencies: list_field(fields.get("Depends On")),
+            reverse_dependencies: list_field(local.get("Required By")),
+            files,
+        })
+    }
+    
     async fn uninstall_package(&self, name: &str) -> Result<PackageAction> {
-        let result = privileged::run_privileged("pacman", &["-R", "--noconfirm", name]).await;
+        let result = self.executor.execute(&[PrivilegedOp::package("pacman", &["-R", "--noconfirm", name])]).await;
         
         Ok(PackageAction {
             name: name.to_string(),
@@ -163,7 +505,7 @@ impl PackageManager for ArchAdapter {
     }
     
     async fn purge_package(&self, name: &str) -> Result<PackageAction> {
-        let result = privileged::run_privileged("pacman", &["-Rns", "--noconfirm", name]).await;
+        let result = self.executor.execute(&[PrivilegedOp::package("pacman", &["-Rns", "--noconfirm", name])]).await;
         
         Ok(PackageAction {
             name: name.to_string(),
@@ -192,9 +534,10 @@ impl PackageManager for ArchAdapter {
             });
         }
         
-        // Remove orphans via shell
-        let script = format!("pacman -Rns --noconfirm {}", orphan_list.replace('\n', " "));
-        let result = privileged::run_privileged_shell(&script).await;
+        // Remove orphans in one transaction
+        let mut args = vec!["-Rns", "--noconfirm"];
+        args.extend(orphan_list.split_whitespace());
+        let result = self.executor.execute(&[PrivilegedOp::package("pacman", &args)]).await;
         
         Ok(PackageAction {
             name: "autoremove".to_string(),
@@ -204,11 +547,40 @@ impl PackageManager for ArchAdapter {
         })
     }
     
+    async fn simulate_uninstall(&self, name: &str) -> Result<RemovalPreview> {
+        self.simulate("uninstall", "-R", &[name]).await
+    }
+    
+    async fn simulate_purge(&self, name: &str) -> Result<RemovalPreview> {
+        self.simulate("purge", "-Rns", &[name]).await
+    }
+    
+    async fn simulate_autoremove(&self) -> Result<RemovalPreview> {
+        // Same orphans `autoremove` would pass to pacman
+        let orphans = if super::is_mock_mode() {
+            String::new()
+        } else {
+            query_output("pacman", &["-Qdtq"]).await?
+        };
+        let names: Vec<&str> = orphans.split_whitespace().collect();
+        self.simulate("autoremove", "-Rns", &names).await
+    }
+    
+    async fn dependency_graph(&self) -> Result<DependencyGraph> {
+        let packages = self.get_installed_packages().await?;
+        if super::is_mock_mode() {
+            return Ok(DependencyGraph::new(&packages, &HashMap::new()));
+        }
+        
+        let output = query_output("pacman", &["-Qi"]).await?;
+        Ok(DependencyGraph::new(&packages, &parse_dependencies(&output)))
+    }
+    
     async fn clean_cache(&self) -> Result<CleanupResult> {
         let result = if self.has_paccache().await {
-            privileged::run_privileged("paccache", &["-r", "-k", "1"]).await
+            self.executor.execute(&[PrivilegedOp::clean_package_cache("paccache")]).await
         } else {
-            privileged::run_privileged("pacman", &["-Sc", "--noconfirm"]).await
+            self.executor.execute(&[PrivilegedOp::clean_package_cache("pacman")]).await
         };
         
         Ok(CleanupResult {
@@ -230,3 +602,89 @@ impl PackageManager for ArchAdapter {
         Ok((total, auto, size))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_desc() {
+        let desc = "%NAME%\nbash\n\n%VERSION%\n5.2.026-2\n\n%DESC%\nThe GNU Bourne Again shell\n\n%SIZE%\n9510080\n\n%REASON%\n1\n\n%DEPENDS%\nreadline\nglibc\n";
+        let package = parse_desc(desc).unwrap();
+        assert_eq!(package.name, "bash");
+        assert_eq!(package.version, "5.2.026-2");
+        assert_eq!(package.size_bytes, 9510080);
+        assert!(package.is_auto);
+        assert!(!parse_desc("%NAME%\nvim\n").unwrap().is_auto);
+    }
+
+    #[test]
+    fn test_parse_dependencies() {
+        let output = "Name            : bash\nVersion         : 5.2.026-2\nProvides        : sh\nDepends On      : readline>=7.0  glibc  ncurses\n\n\
+                      Name            : glibc\nProvides        : None\nDepends On      : linux-api-headers>=4.10  tzdata\n\n\
+                      Name            : xdg-utils\nProvides        : None\nDepends On      : sh  coreutils\n\n";
+        let deps = parse_dependencies(output);
+        assert_eq!(deps["bash"], vec!["glibc"]);
+        assert!(deps["glibc"].is_empty());
+        assert_eq!(deps["xdg-utils"], vec!["bash"]);
+    }
+
+    #[test]
+    fn test_parse_upgradable() {
+        let packages = parse_upgradable("linux 6.9.1.arch1-1 -> 6.9.2.arch1-1\nglibc 2.39-1 -> 2.39-2 [ignored]\n");
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "linux");
+        assert_eq!(packages[0].current_version, "6.9.1.arch1-1");
+        assert_eq!(packages[1].new_version, "2.39-2");
+    }
+
+    #[test]
+    fn test_parse_sync_search() {
+        let output = "extra/firefox 126.0-1 [installed]\n    Fast, Private & Safe Web Browser\ncore/linux 6.9.2.arch1-1 [installed: 6.9.1.arch1-1]\n    The Linux kernel and modules\nextra/firefox-i18n-de 126.0-1\n    German language pack for Firefox\n";
+        let results = parse_sync_search(output);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].package.name, "firefox");
+        assert_eq!(results[0].repository.as_deref(), Some("extra"));
+        assert_eq!(results[0].package.description, "Fast, Private & Safe Web Browser");
+        assert!(results[1].installed);
+        assert!(!results[2].installed);
+    }
+
+    #[test]
+    fn test_parse_history() {
+        let log = "[2024-01-10T12:00:01+0100] [PACMAN] Running 'pacman -Syu'\n\
+                   [2024-01-10T12:00:01+0100] [ALPM] transaction started\n\
+                   [2024-01-10T12:00:02+0100] [ALPM] upgraded bash (5.2.021-1 -> 5.2.026-1)\n\
+                   [2024-01-10T12:00:02+0100] [ALPM] installed vim-runtime (9.0.2-1)\n\
+                   [2024-01-10T12:00:02+0100] [ALPM-SCRIPTLET] ok\n\
+                   [2024-01-10T12:00:03+0100] [ALPM] transaction completed\n\
+                   [2024-01-11T08:00:00+0100] [PACMAN] Running 'pacman -Rns nano'\n\
+                   [2024-01-11T08:00:00+0100] [ALPM] transaction started\n\
+                   [2024-01-11T08:00:00+0100] [ALPM] removed nano (7.2-1)\n\
+                   [2024-01-11T08:00:01+0100] [ALPM] transaction completed\n";
+        let transactions = parse_history(log);
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].id, "2024-01-10T12:00:01+0100");
+        assert_eq!(transactions[0].command.as_deref(), Some("pacman -Syu"));
+        assert_eq!(transactions[0].version_changes(), vec![("bash", "5.2.021-1")]);
+        assert_eq!(transactions[0].installed(), vec!["vim-runtime"]);
+        assert_eq!(transactions[1].action, "remove");
+        
+        let cache = vec![
+            "bash-5.2.021-1-x86_64.pkg.tar.zst".to_string(),
+            "bash-5.2.021-1-x86_64.pkg.tar.zst.sig".to_string(),
+            "bash-completion-2.11-3-any.pkg.tar.zst".to_string(),
+        ];
+        assert_eq!(cached_package(&cache, "bash", "5.2.021-1").as_deref(), Some("bash-5.2.021-1-x86_64.pkg.tar.zst"));
+        assert_eq!(cached_package(&cache, "bash", "5.2.026-1"), None);
+    }
+
+    #[test]
+    fn test_uncomment_multilib() {
+        let conf = "[core]\nInclude = /etc/pacman.d/mirrorlist\n\n#[multilib-testing]\n#Include = /etc/pacman.d/mirrorlist\n\n#[multilib]\n#Include = /etc/pacman.d/mirrorlist\n";
+        let updated = uncomment_multilib(conf).unwrap();
+        assert!(updated.contains("\n[multilib]\nInclude = /etc/pacman.d/mirrorlist\n"));
+        assert!(updated.contains("#[multilib-testing]\n#Include"));
+        assert_eq!(uncomment_multilib(&updated), None);
+    }
+}
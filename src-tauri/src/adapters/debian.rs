@@ -1,35 +1,300 @@
 //! Debian/Ubuntu Package Manager Adapter
 //! Uses apt/dpkg for package management
 
-use super::{PackageInfo, PackageAction, CleanupResult, PackageManager, detect_package_category};
-use crate::error::{AppError, Result};
-use crate::utils::privileged;
+use super::{PackageInfo, PackageOrigin, PackageAction, PackageDetails, SearchResult, UpgradablePackage, CleanupResult, PackageManager, detect_package_category};
+use super::{ChangeKind, PackageChange, PackageTransaction, find_transaction, newest_first, nothing_to_undo, undo_action};
+use super::{RemovalPreview, dry_run_output};
+use super::{DependencyGraph, Providers};
+use super::{command_exists, dependency_name, escape_regex, package_not_found, parse_fields, query_output, sort_by_relevance, MAX_SEARCH_RESULTS};
+use std::collections::HashMap;
+use crate::error::Result;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
 use async_trait::async_trait;
+use std::sync::Arc;
 use std::collections::HashSet;
-use tokio::process::Command;
 
-pub struct DebianAdapter;
+pub struct DebianAdapter {
+    executor: Arc<dyn PrivilegedExecutor>,
+}
 
 impl DebianAdapter {
-    pub fn new() -> Self {
-        Self
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { executor }
     }
     
     /// Check if apt-fast is available
     async fn has_apt_fast(&self) -> bool {
-        Command::new("which")
-            .arg("apt-fast")
-            .output()
-            .await
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+        command_exists("apt-fast").await
+    }
+    
+    /// What `apt-get <args>` would remove, from its simulation (`-s` needs no root)
+    async fn simulate(&self, action: &str, args: &[&str]) -> Result<RemovalPreview> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] Nothing to remove for Debian");
+            return Ok(RemovalPreview::empty(action));
+        }
+        
+        let mut argv = vec!["-s"];
+        argv.extend_from_slice(args);
+        let names = parse_simulated_removals(&dry_run_output("apt-get", &argv).await?);
+        if names.is_empty() {
+            return Ok(RemovalPreview::empty(action));
+        }
+        
+        let installed = self.get_installed_packages().await?;
+        Ok(RemovalPreview::new(action, &names, &installed))
     }
 }
 
-impl Default for DebianAdapter {
-    fn default() -> Self {
-        Self::new()
+/// Name of one dependency alternative, e.g. `libc6 (>= 2.34)` or `python3:any`
+fn dependency_alternative(alternative: &str) -> &str {
+    let name = alternative.trim().split([' ', '(']).next().unwrap_or("");
+    name.split(':').next().unwrap_or(name)
+}
+
+/// dpkg-query format read by `parse_dependencies`
+const DEPENDENCY_FORMAT: &str =
+    "-f=${db:Status-Abbrev}\t${Package}\t${Essential}\t${Protected}\t${Provides}\t${Pre-Depends}, ${Depends}, ${Recommends}\n";
+
+/// Parse `DEPENDENCY_FORMAT` rows into each installed package's installed dependencies, and the
+/// Essential / Protected packages apt never removes. Recommends count, as they keep packages
+/// from `apt autoremove`; of alternatives (`a | b`) the first installed one is used
+fn parse_dependencies(output: &str) -> (HashMap<String, Vec<String>>, HashSet<String>) {
+    let mut protected = HashSet::new();
+    let installed: Vec<(&str, &str, &str)> = output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [status, name, essential, important, provides, depends] = fields[..] else { return None };
+            // Removed packages keep a database entry until purged ("rc")
+            if matches!(status.chars().nth(1), Some('n') | Some('c')) {
+                return None;
+            }
+            if essential == "yes" || important == "yes" {
+                protected.insert(name.to_string());
+            }
+            Some((name, provides, depends))
+        })
+        .collect();
+    
+    let mut providers = Providers::new();
+    for (name, provides, _) in &installed {
+        providers.add(name, name);
+        for capability in provides.split(',').map(dependency_alternative).filter(|c| !c.is_empty()) {
+            providers.add(capability, name);
+        }
     }
+    
+    let depends = installed
+        .iter()
+        .map(|(name, _, depends)| {
+            let resolved = depends
+                .split(',')
+                .filter_map(|group| {
+                    group
+                        .split('|')
+                        .map(|alternative| providers.resolve(dependency_alternative(alternative)))
+                        .find(|found| !found.is_empty())
+                })
+                .flatten()
+                .cloned()
+                .collect();
+            (name.to_string(), resolved)
+        })
+        .collect();
+    (depends, protected)
+}
+
+/// Packages of the `Remv` / `Purg` lines of `apt-get -s`, e.g. `Remv vim [2:9.0.1378-2]`
+fn parse_simulated_removals(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("Remv ").or_else(|| line.strip_prefix("Purg ")))
+        .filter_map(|rest| rest.split_whitespace().next())
+        .map(String::from)
+        .collect()
+}
+
+/// dpkg's database of known packages
+const DPKG_STATUS: &str = "/var/lib/dpkg/status";
+/// apt's auto-installed marks
+const APT_EXTENDED_STATES: &str = "/var/lib/apt/extended_states";
+
+/// Installed packages of /var/lib/dpkg/status; auto-installed ones are marked
+/// `Auto-Installed: 1` in apt's extended_states
+fn parse_status(status: &str, extended_states: &str) -> Vec<PackageInfo> {
+    let auto: HashSet<String> = extended_states
+        .split("\n\n")
+        .map(parse_fields)
+        .filter(|fields| fields.get("Auto-Installed").map(String::as_str) == Some("1"))
+        .filter_map(|mut fields| fields.remove("Package"))
+        .collect();
+    
+    status
+        .split("\n\n")
+        .map(parse_fields)
+        .filter(|fields| {
+            // Removed packages keep a stanza until purged ("deinstall ok config-files")
+            let state = fields.get("Status").and_then(|s| s.split_whitespace().nth(2));
+            !matches!(state, None | Some("not-installed") | Some("config-files"))
+        })
+        .filter_map(|mut fields| {
+            let name = fields.remove("Package")?;
+            // Only the synopsis; the long description follows on continuation lines
+            let description = fields.get("Description").and_then(|d| d.lines().next()).unwrap_or("").to_string();
+            let size_kb: u64 = fields.get("Installed-Size").and_then(|s| s.parse().ok()).unwrap_or(0);
+            Some(PackageInfo {
+                category: detect_package_category(&name, &description),
+                is_auto: auto.contains(&name),
+                version: fields.remove("Version").unwrap_or_default(),
+                size_bytes: size_kb * 1024,
+                description,
+                name,
+                origin: PackageOrigin::Native,
+            })
+        })
+        .collect()
+}
+
+/// apt's transaction log; logrotate keeps older ones as history.log.N.gz
+const APT_HISTORY: &str = "/var/log/apt/history.log";
+
+/// Parse one package list of history.log, e.g. `vim:amd64 (2:9.0-1), xxd:amd64 (2:9.0-1, automatic)`;
+/// upgrades and downgrades list (old, new) versions
+fn parse_history_packages(list: &str, kind: ChangeKind, native_arch: &str) -> Vec<PackageChange> {
+    let native_suffix = format!(":{}", native_arch);
+    
+    list.split("), ")
+        .filter_map(|entry| {
+            let (name, versions) = entry.trim().trim_end_matches(')').split_once(" (")?;
+            let name = name
+                .strip_suffix(native_suffix.as_str())
+                .or_else(|| name.strip_suffix(":all"))
+                .unwrap_or(name);
+            let versions: Vec<String> = versions
+                .split(", ")
+                .filter(|v| *v != "automatic")
+                .map(String::from)
+                .collect();
+            let (old_version, new_version) = match kind {
+                ChangeKind::Install => (None, versions.first().cloned()),
+                ChangeKind::Remove => (versions.first().cloned(), None),
+                ChangeKind::Reinstall => (versions.first().cloned(), versions.first().cloned()),
+                ChangeKind::Upgrade | ChangeKind::Downgrade => (versions.first().cloned(), versions.get(1).cloned()),
+            };
+            Some(PackageChange { name: name.to_string(), kind, old_version, new_version })
+        })
+        .collect()
+}
+
+/// Parse history.log into transactions, oldest first; each is identified by its start time
+fn parse_history(log: &str, native_arch: &str) -> Vec<PackageTransaction> {
+    let mut transactions = Vec::new();
+    
+    for block in log.split("\n\n") {
+        let mut start = None;
+        let mut command = None;
+        let mut changes = Vec::new();
+        
+        for line in block.lines() {
+            let Some((key, value)) = line.split_once(": ") else { continue };
+            let kind = match key {
+                "Start-Date" => {
+                    start = Some(value.split_whitespace().collect::<Vec<_>>().join(" "));
+                    continue;
+                }
+                "Commandline" => {
+                    command = Some(value.to_string());
+                    continue;
+                }
+                "Install" => ChangeKind::Install,
+                "Upgrade" => ChangeKind::Upgrade,
+                "Downgrade" => ChangeKind::Downgrade,
+                "Reinstall" => ChangeKind::Reinstall,
+                "Remove" | "Purge" => ChangeKind::Remove,
+                _ => continue,
+            };
+            changes.extend(parse_history_packages(value, kind, native_arch));
+        }
+        
+        if let Some(start) = start.filter(|_| !changes.is_empty()) {
+            transactions.push(PackageTransaction::new(start.clone(), start, command, changes));
+        }
+    }
+    
+    transactions
+}
+
+/// Parse `apt list --upgradable`:
+/// `bash/noble-updates 5.2.21-2ubuntu4 amd64 [upgradable from: 5.2.21-2ubuntu3]`
+fn parse_upgradable(output: &str) -> Vec<UpgradablePackage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (package, current) = line.split_once(" [upgradable from: ")?;
+            let mut parts = package.split_whitespace();
+            let (name, suite) = parts.next()?.split_once('/')?;
+            let new_version = parts.next()?;
+            
+            Some(UpgradablePackage {
+                name: name.to_string(),
+                current_version: current.trim_end_matches(']').to_string(),
+                new_version: new_version.to_string(),
+                repository: Some(suite.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// `Installed:` / `Candidate:` value of one package's `apt-cache policy` block
+fn policy_field<'a>(block: &'a str, key: &str) -> Option<&'a str> {
+    block.lines().find_map(|line| line.trim().strip_prefix(key)).map(str::trim)
+}
+
+/// Split multi-package `apt-cache policy` output into blocks by package name
+fn split_policy(output: &str) -> HashMap<String, String> {
+    let mut blocks: HashMap<String, String> = HashMap::new();
+    let mut current = None;
+    
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) && line.ends_with(':') {
+            current = Some(line.trim_end_matches(':').to_string());
+        }
+        if let Some(block) = current.as_ref().map(|name| blocks.entry(name.clone()).or_default()) {
+            block.push_str(line);
+            block.push('\n');
+        }
+    }
+    
+    blocks
+}
+
+/// Origin of the installed (or candidate) version in `apt-cache policy` output,
+/// e.g. "http://archive.ubuntu.com/ubuntu noble-updates/main"
+fn parse_policy_origin(output: &str) -> Option<String> {
+    let version = match policy_field(output, "Installed:")? {
+        "(none)" => policy_field(output, "Candidate:")?,
+        installed => installed,
+    };
+    
+    let table = output.split("Version table:").nth(1)?;
+    let mut in_version = false;
+    for line in table.lines() {
+        let mut parts = line.trim_start_matches(" *** ").split_whitespace();
+        // Version lines are indented 5 columns (" *** " marks the installed one), origins 8
+        if !line.starts_with("        ") {
+            in_version = parts.next() == Some(version);
+            continue;
+        }
+        
+        // "500 http://archive.ubuntu.com/ubuntu noble-updates/main amd64 Packages", not "100 /var/lib/dpkg/status"
+        if let (true, Some(_), Some(url), Some(suite)) = (in_version, parts.next(), parts.next(), parts.next()) {
+            if url.contains("://") {
+                return Some(format!("{} {}", url, suite));
+            }
+        }
+    }
+    None
 }
 
 #[async_trait]
@@ -46,9 +311,13 @@ impl PackageManager for DebianAdapter {
         "/var/log/apt"
     }
     
+    fn database_paths(&self) -> &'static [&'static str] {
+        &[DPKG_STATUS, APT_EXTENDED_STATES]
+    }
+    
     async fn refresh_repositories(&self) -> Result<String> {
         let apt = if self.has_apt_fast().await { "apt-fast" } else { "apt-get" };
-        privileged::run_privileged(apt, &["update"]).await
+        self.executor.execute(&[PrivilegedOp::refresh_repositories(apt)]).await
     }
     
     async fn get_installed_packages(&self) -> Result<Vec<PackageInfo>> {
@@ -58,52 +327,10 @@ impl PackageManager for DebianAdapter {
             return Ok(super::generate_mock_packages("apt"));
         }
         
-        // Get auto-installed packages first
-        let auto_output = Command::new("apt-mark")
-            .arg("showauto")
-            .output()
-            .await
-            .map_err(|e| AppError::CommandFailed(e.to_string()))?;
-        
-        let auto_packages: HashSet<String> = String::from_utf8_lossy(&auto_output.stdout)
-            .lines()
-            .map(|s| s.to_string())
-            .collect();
-        
-        // Get all installed packages
-        let output = Command::new("dpkg-query")
-            .args([
-                "-W",
-                "-f=${Package}\t${Version}\t${Installed-Size}\t${Description}\n",
-            ])
-            .output()
-            .await
-            .map_err(|e| AppError::CommandFailed(e.to_string()))?;
-        
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut packages = Vec::new();
-        
-        for line in stdout.lines() {
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() < 3 {
-                continue;
-            }
-            
-            let name = parts[0].to_string();
-            let version = parts[1].to_string();
-            let size_kb: u64 = parts[2].parse().unwrap_or(0);
-            let description = parts.get(3).unwrap_or(&"").to_string();
-            let category = detect_package_category(&name, &description);
-            
-            packages.push(PackageInfo {
-                name: name.clone(),
-                version,
-                size_bytes: size_kb * 1024,
-                description,
-                is_auto: auto_packages.contains(&name),
-                category,
-            });
-        }
+        // Read dpkg's database directly; dpkg-query and apt-mark take seconds on large installs
+        let status = std::fs::read_to_string(DPKG_STATUS)?;
+        let extended_states = std::fs::read_to_string(APT_EXTENDED_STATES).unwrap_or_default();
+        let mut packages = parse_status(&status, &extended_states);
         
         packages.sort_by(|a, b| a.name.cmp(&b.name));
         Ok(packages)
@@ -124,8 +351,224 @@ impl PackageManager for DebianAdapter {
         Ok(filtered)
     }
     
+    async fn install_packages(&self, names: &[&str]) -> Result<String> {
+        let mut args = vec!["install", "-y"];
+        args.extend_from_slice(names);
+        self.executor.execute(&[PrivilegedOp::package("apt-get", &args)]).await
+    }
+    
+    async fn enable_multilib(&self) -> Result<String> {
+        // Multiarch: add i386, then refresh so the :i386 packages show up
+        self.executor.execute(&[PrivilegedOp::add_architecture("i386")]).await?;
+        self.refresh_repositories().await?;
+        Ok("32-bit (i386) architecture enabled".to_string())
+    }
+    
+    async fn transaction_history(&self) -> Result<Vec<PackageTransaction>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No transaction history for Debian");
+            return Ok(Vec::new());
+        }
+        
+        let native_arch = query_output("dpkg", &["--print-architecture"]).await?;
+        
+        // Rotated logs, oldest (highest number) first
+        let mut rotated: Vec<(u32, String)> = std::fs::read_dir(self.log_path())
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| {
+                        let name = entry.file_name().to_string_lossy().to_string();
+                        let n = name.strip_prefix("history.log.")?.strip_suffix(".gz")?.parse().ok()?;
+                        Some((n, entry.path().to_string_lossy().to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        rotated.sort_by_key(|(n, _)| std::cmp::Reverse(*n));
+        
+        let mut log = String::new();
+        for (_, path) in rotated {
+            log.push_str(&query_output("zcat", &[&path]).await?);
+            log.push_str("\n\n");
+        }
+        log.push_str(&std::fs::read_to_string(APT_HISTORY).unwrap_or_default());
+        
+        Ok(newest_first(parse_history(&log, native_arch.trim())))
+    }
+    
+    async fn undo_transaction(&self, id: &str) -> Result<PackageAction> {
+        let transaction = find_transaction(self.transaction_history().await?, id)?;
+        
+        let mut ops = Vec::new();
+        let installed = transaction.installed();
+        if !installed.is_empty() {
+            let mut args = vec!["remove", "-y"];
+            args.extend(installed);
+            ops.push(PrivilegedOp::package("apt-get", &args));
+        }
+        // Removed packages come back in the candidate version
+        let removed = transaction.removed();
+        if !removed.is_empty() {
+            let mut args = vec!["install", "-y"];
+            args.extend(removed.iter().map(|(name, _)| *name));
+            ops.push(PrivilegedOp::package("apt-get", &args));
+        }
+        if ops.is_empty() {
+            return Err(nothing_to_undo(id));
+        }
+        
+        // apt can't go back to a version the repositories no longer carry
+        let skipped: Vec<String> = transaction
+            .version_changes()
+            .iter()
+            .map(|(name, version)| format!("{} (was {})", name, version))
+            .collect();
+        Ok(undo_action(&transaction, self.executor.execute(&ops).await, &skipped))
+    }
+    
+    async fn search_remote(&self, query: &str) -> Result<Vec<SearchResult>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No remote search results for Debian");
+            return Ok(Vec::new());
+        }
+        
+        // "name - short description"
+        let output = query_output("apt-cache", &["search", &escape_regex(query)]).await?;
+        let mut hits: Vec<(&str, &str)> = output.lines().filter_map(|line| line.split_once(" - ")).collect();
+        sort_by_relevance(&mut hits, query, |(name, _)| name);
+        hits.truncate(MAX_SEARCH_RESULTS);
+        if hits.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        // One policy call for versions, installed state and origin
+        let mut args = vec!["policy"];
+        args.extend(hits.iter().map(|(name, _)| *name));
+        let policy = split_policy(&query_output("apt-cache", &args).await?);
+        
+        Ok(hits
+            .into_iter()
+            .map(|(name, description)| {
+                let block = policy.get(name).map(String::as_str).unwrap_or("");
+                let installed_version = policy_field(block, "Installed:").filter(|v| *v != "(none)");
+                let version = installed_version.or_else(|| policy_field(block, "Candidate:")).unwrap_or("");
+                
+                SearchResult {
+                    package: PackageInfo {
+                        name: name.to_string(),
+                        version: version.to_string(),
+                        size_bytes: 0,
+                        description: description.to_string(),
+                        is_auto: false,
+                        category: detect_package_category(name, description),
+                        origin: PackageOrigin::Native,
+                    },
+                    installed: installed_version.is_some(),
+                    repository: parse_policy_origin(block).or_else(|| installed_version.map(|_| "local".to_string())),
+                }
+            })
+            .collect())
+    }
+    
+    async fn list_upgradable(&self) -> Result<Vec<UpgradablePackage>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No upgradable packages for Debian");
+            return Ok(Vec::new());
+        }
+        
+        Ok(parse_upgradable(&query_output("apt", &["list", "--upgradable"]).await?))
+    }
+    
+    async fn upgrade_packages(&self, names: &[&str]) -> Result<PackageAction> {
+        let mut args = vec!["install", "--only-upgrade", "-y"];
+        args.extend_from_slice(names);
+        let result = self.executor.execute(&[PrivilegedOp::package("apt-get", &args)]).await;
+        
+        Ok(PackageAction {
+            name: names.join(" "),
+            action: "upgrade".to_string(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e.to_string()),
+        })
+    }
+    
+    async fn upgrade_all(&self) -> Result<PackageAction> {
+        // Like `apt upgrade`: new dependencies may be installed, nothing is removed
+        let result = self.executor.execute(&[PrivilegedOp::package("apt-get", &["upgrade", "-y", "--with-new-pkgs"])]).await;
+        
+        Ok(PackageAction {
+            name: "system".to_string(),
+            action: "upgrade".to_string(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e.to_string()),
+        })
+    }
+    
+    async fn package_details(&self, name: &str) -> Result<PackageDetails> {
+        let fields = parse_fields(&query_output("apt-cache", &["show", "--no-all-versions", name]).await?);
+        if fields.is_empty() {
+            return Err(package_not_found(name));
+        }
+        
+        let status = query_output("dpkg-query", &["-W", "-f=${Status}\t${Version}", name]).await?;
+        let (status, installed_version) = status.split_once('\t').unwrap_or(("", ""));
+        let installed = status.ends_with(" installed");
+        
+        // Keep alternatives together: "default-mta | mail-transport-agent"
+        let dependencies = fields.get("Depends")
+            .map(|deps| {
+                deps.split(',')
+                    .map(|dep| dep.split('|').map(dependency_name).collect::<Vec<_>>().join(" | "))
+                    .filter(|dep| !dep.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        // Skips the package name and the "Reverse Depends:" header
+        let rdepends = query_output("apt-cache", &["rdepends", "--installed", name]).await?;
+        let mut reverse_dependencies: Vec<String> = Vec::new();
+        for line in rdepends.lines().skip(2) {
+            let dep = line.trim().trim_start_matches('|');
+            if !dep.is_empty() && dep != name && !reverse_dependencies.iter().any(|d| d == dep) {
+                reverse_dependencies.push(dep.to_string());
+            }
+        }
+        
+        let files = if installed {
+            query_output("dpkg", &["-L", name])
+                .await?
+                .lines()
+                .filter(|path| *path != "/.")
+                .map(|path| path.to_string())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        
+        let repository = parse_policy_origin(&query_output("apt-cache", &["policy", name]).await?)
+            .or_else(|| installed.then(|| "local".to_string()));
+        let version = if installed {
+            installed_version.to_string()
+        } else {
+            fields.get("Version").cloned().unwrap_or_default()
+        };
+        
+        Ok(PackageDetails {
+            name: name.to_string(),
+            version,
+            description: fields.get("Description").or_else(|| fields.get("Description-en")).cloned().unwrap_or_default(),
+            installed,
+            size_bytes: fields.get("Installed-Size").and_then(|kb| kb.parse::<u64>().ok()).unwrap_or(0) * 1024,
+            repository,
+            dependencies,
+            reverse_dependencies,
+            files,
+        })
+    }
+    
     async fn uninstall_package(&self, name: &str) -> Result<PackageAction> {
-        let result = privileged::run_privileged("apt-get", &["remove", "-y", name]).await;
+        let result = self.executor.execute(&[PrivilegedOp::package("apt-get", &["remove", "-y", name])]).await;
         
         Ok(PackageAction {
             name: name.to_string(),
@@ -136,7 +579,7 @@ impl PackageManager for DebianAdapter {
     }
     
     async fn purge_package(&self, name: &str) -> Result<PackageAction> {
-        let result = privileged::run_privileged("apt-get", &["purge", "-y", name]).await;
+        let result = self.executor.execute(&[PrivilegedOp::package("apt-get", &["purge", "-y", name])]).await;
         
         Ok(PackageAction {
             name: name.to_string(),
@@ -147,7 +590,7 @@ impl PackageManager for DebianAdapter {
     }
     
     async fn autoremove(&self) -> Result<PackageAction> {
-        let result = privileged::run_privileged("apt-get", &["autoremove", "-y"]).await;
+        let result = self.executor.execute(&[PrivilegedOp::package("apt-get", &["autoremove", "-y"])]).await;
         
         Ok(PackageAction {
             name: "autoremove".to_string(),
@@ -157,8 +600,32 @@ impl PackageManager for DebianAdapter {
         })
     }
     
+    async fn simulate_uninstall(&self, name: &str) -> Result<RemovalPreview> {
+        self.simulate("uninstall", &["remove", name]).await
+    }
+    
+    async fn simulate_purge(&self, name: &str) -> Result<RemovalPreview> {
+        self.simulate("purge", &["purge", name]).await
+    }
+    
+    async fn simulate_autoremove(&self) -> Result<RemovalPreview> {
+        self.simulate("autoremove", &["autoremove"]).await
+    }
+    
+    async fn dependency_graph(&self) -> Result<DependencyGraph> {
+        let packages = self.get_installed_packages().await?;
+        if super::is_mock_mode() {
+            return Ok(DependencyGraph::new(&packages, &HashMap::new()));
+        }
+        
+        let (depends, protected) = parse_dependencies(&query_output("dpkg-query", &["-W", DEPENDENCY_FORMAT]).await?);
+        let mut graph = DependencyGraph::new(&packages, &depends);
+        graph.protect(&protected);
+        Ok(graph)
+    }
+    
     async fn clean_cache(&self) -> Result<CleanupResult> {
-        let result = privileged::run_privileged("apt-get", &["clean"]).await;
+        let result = self.executor.execute(&[PrivilegedOp::clean_package_cache("apt-get")]).await;
         
         Ok(CleanupResult {
             category: "apt_cache".to_string(),
@@ -183,3 +650,85 @@ impl PackageManager for DebianAdapter {
         Ok(self.has_apt_fast().await)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_upgradable() {
+        let output = "Listing...\nbash/noble-updates 5.2.21-2ubuntu4 amd64 [upgradable from: 5.2.21-2ubuntu3]\n";
+        assert_eq!(parse_upgradable(output), vec![UpgradablePackage {
+            name: "bash".to_string(),
+            current_version: "5.2.21-2ubuntu3".to_string(),
+            new_version: "5.2.21-2ubuntu4".to_string(),
+            repository: Some("noble-updates".to_string()),
+        }]);
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let status = "Package: vim\nStatus: install ok installed\nInstalled-Size: 4032\nVersion: 2:9.0.1378-2\nDescription: Vi IMproved - enhanced vi editor\n VIM is an almost compatible version of vi.\n\n\
+                      Package: nano\nStatus: deinstall ok config-files\nVersion: 7.2-1\n\n\
+                      Package: xxd\nStatus: install ok installed\nInstalled-Size: 86\nVersion: 2:9.0.1378-2\nDescription: tool to make (or reverse) a hex dump\n";
+        let extended_states = "Package: xxd\nArchitecture: amd64\nAuto-Installed: 1\n\nPackage: vim\nArchitecture: amd64\nAuto-Installed: 0\n";
+        let packages = parse_status(status, extended_states);
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].description, "Vi IMproved - enhanced vi editor");
+        assert_eq!(packages[0].size_bytes, 4032 * 1024);
+        assert!(!packages[0].is_auto && packages[1].is_auto);
+    }
+
+    #[test]
+    fn test_parse_dependencies() {
+        let output = "ii \tvim\t\t\t\t, vim-runtime (= 2:9.0), libc6 (>= 2.34), , xxd | vim-common\n\
+                      ii \tvim-runtime\t\t\t\t, , \n\
+                      ii \tlibc6\t\tyes\t\t, , \n\
+                      ii \tmawk\tyes\t\tawk\t, libc6:any, \n\
+                      ii \tcron\t\t\t\t, awk | gawk, \n\
+                      rc \told\t\t\t\t, libc6, \n";
+        let (deps, protected) = parse_dependencies(output);
+        assert_eq!(protected, HashSet::from(["libc6".to_string(), "mawk".to_string()]));
+        assert_eq!(deps["vim"], vec!["vim-runtime", "libc6"]);
+        assert_eq!(deps["mawk"], vec!["libc6"]);
+        assert_eq!(deps["cron"], vec!["mawk"]);
+        assert!(!deps.contains_key("old"));
+    }
+
+    #[test]
+    fn test_parse_simulated_removals() {
+        let output = "NOTE: This is only a simulation!\nReading package lists...\nThe following packages will be REMOVED:\n  vim* vim-runtime*\nPurg vim [2:9.0.1378-2]\nPurg vim-runtime [2:9.0.1378-2]\nRemv libc6:i386 [2.36-9]\n";
+        assert_eq!(parse_simulated_removals(output), vec!["vim", "vim-runtime", "libc6:i386"]);
+    }
+
+    #[test]
+    fn test_parse_history() {
+        let log = "\nStart-Date: 2026-05-09  07:28:46\nCommandline: apt-get install -y vim\nInstall: vim:amd64 (2:9.0-2), xxd:amd64 (2:9.0-2, automatic), libc6:i386 (2.36-9, automatic)\nUpgrade: tzdata:all (2025a-1, 2025b-1)\nEnd-Date: 2026-05-09  07:28:50\n\nStart-Date: 2026-05-10  09:00:00\nCommandline: apt-get autoremove -y\nPurge: nano:amd64 (7.2-1)\nEnd-Date: 2026-05-10  09:00:02\n";
+        let transactions = parse_history(log, "amd64");
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].id, "2026-05-09 07:28:46");
+        assert_eq!(transactions[0].action, "mixed");
+        assert_eq!(transactions[0].installed(), vec!["vim", "xxd", "libc6:i386"]);
+        assert_eq!(transactions[0].version_changes(), vec![("tzdata", "2025a-1")]);
+        assert_eq!(transactions[1].command.as_deref(), Some("apt-get autoremove -y"));
+        assert_eq!(transactions[1].removed(), vec![("nano", "7.2-1")]);
+    }
+
+    #[test]
+    fn test_split_policy() {
+        let policy = "bash:\n  Installed: 5.2\n  Candidate: 5.2\nfoo:\n  Installed: (none)\n  Candidate: 1.0\n";
+        let blocks = split_policy(policy);
+        assert_eq!(policy_field(&blocks["bash"], "Installed:"), Some("5.2"));
+        assert_eq!(policy_field(&blocks["foo"], "Installed:"), Some("(none)"));
+        assert_eq!(policy_field(&blocks["foo"], "Candidate:"), Some("1.0"));
+    }
+
+    #[test]
+    fn test_parse_policy_origin() {
+        let policy = "bash:\n  Installed: 5.2.21-2ubuntu4\n  Candidate: 5.2.21-2ubuntu4\n  Version table:\n *** 5.2.21-2ubuntu4 500\n        500 http://archive.ubuntu.com/ubuntu noble-updates/main amd64 Packages\n        100 /var/lib/dpkg/status\n     5.2.21-2ubuntu3 500\n        500 http://archive.ubuntu.com/ubuntu noble/main amd64 Packages\n";
+        assert_eq!(parse_policy_origin(policy).as_deref(), Some("http://archive.ubuntu.com/ubuntu noble-updates/main"));
+        
+        let local = "foo:\n  Installed: 1.0\n  Candidate: 1.0\n  Version table:\n *** 1.0 100\n        100 /var/lib/dpkg/status\n";
+        assert_eq!(parse_policy_origin(local), None);
+    }
+}
@@ -0,0 +1,412 @@
+//! Dependency Graph
+//! Installed packages linked to the installed packages they depend on; finds orphans and
+//! leaves, and how much removing a package frees together with the dependencies only it needs
+
+use super::{query_output, PackageInfo};
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// How one installed package sits in the graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageUsage {
+    pub name: String,
+    pub size_bytes: u64,
+    pub is_auto: bool,
+    /// Installed packages that depend on this one directly
+    pub required_by: usize,
+    /// Its own size plus every dependency nothing else keeps installed
+    pub exclusive_size_bytes: u64,
+    /// Auto-installed and not needed, even indirectly, by any explicitly installed package
+    pub orphan: bool,
+    /// Explicitly installed and not needed by any other package
+    pub leaf: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageAnalysis {
+    /// Largest exclusive size first
+    pub packages: Vec<PackageUsage>,
+    pub orphan_count: usize,
+    /// What removing every orphan would free
+    pub orphan_bytes: u64,
+    pub leaf_count: usize,
+}
+
+// ============================================================================
+// Capability Resolution
+// ============================================================================
+
+/// Installed packages by the names and capabilities they provide
+#[derive(Debug, Default)]
+pub struct Providers {
+    map: HashMap<String, Vec<String>>,
+}
+
+impl Providers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `package` provides `capability` (every package provides its own name)
+    pub fn add(&mut self, capability: &str, package: &str) {
+        let providers = self.map.entry(capability.to_string()).or_default();
+        if !providers.iter().any(|p| p == package) {
+            providers.push(package.to_string());
+        }
+    }
+
+    /// Installed packages satisfying `capability`; empty when nothing installed does
+    pub fn resolve(&self, capability: &str) -> &[String] {
+        self.map.get(capability).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// `name<TAB>value` rows of an `rpm -qa --qf '[%{NAME}\t%{...}\n]'` query
+fn rpm_rows(output: &str) -> impl Iterator<Item = (&str, &str)> {
+    output.lines().filter_map(|line| line.split_once('\t'))
+}
+
+/// Resolve rpm requirements (`rpm -qa` provides and requires rows) to installed packages.
+/// rpm provides don't list files, so file requirements come from `file_owners`;
+/// rpmlib features and rich dependencies (`(a if b)`) are skipped
+fn resolve_rpm_requirements(provides: &str, requires: &str, file_owners: &HashMap<String, Vec<String>>) -> HashMap<String, Vec<String>> {
+    let mut providers = Providers::new();
+    for (name, capability) in rpm_rows(provides) {
+        providers.add(name, name);
+        providers.add(capability, name);
+    }
+    for (path, owners) in file_owners {
+        for owner in owners {
+            providers.add(path, owner);
+        }
+    }
+
+    let mut depends: HashMap<String, Vec<String>> = HashMap::new();
+    for (name, requirement) in rpm_rows(requires) {
+        if requirement.starts_with("rpmlib(") || requirement.starts_with('(') {
+            continue;
+        }
+        depends
+            .entry(name.to_string())
+            .or_default()
+            .extend(providers.resolve(requirement).iter().cloned());
+    }
+    depends
+}
+
+/// Installed dependencies of every installed rpm (Fedora, openSUSE)
+pub async fn rpm_dependencies() -> Result<HashMap<String, Vec<String>>> {
+    let provides = query_output("rpm", &["-qa", "--qf", "[%{NAME}\t%{PROVIDENAME}\n]"]).await?;
+    let requires = query_output("rpm", &["-qa", "--qf", "[%{NAME}\t%{REQUIRENAME}\n]"]).await?;
+
+    // Only a few dozen distinct paths are required (/bin/sh, /usr/bin/python3, ...)
+    let provided: HashSet<&str> = rpm_rows(&provides).map(|(_, capability)| capability).collect();
+    let paths: HashSet<&str> = rpm_rows(&requires)
+        .map(|(_, requirement)| requirement)
+        .filter(|r| r.starts_with('/') && !provided.contains(r))
+        .collect();
+    let mut file_owners = HashMap::new();
+    for path in paths {
+        let owners = query_output("rpm", &["-q", "--whatprovides", "--qf", "%{NAME}\n", path]).await?;
+        let owners: Vec<String> = owners
+            .lines()
+            .filter(|line| !line.is_empty() && !line.contains(' '))
+            .map(String::from)
+            .collect();
+        file_owners.insert(path.to_string(), owners);
+    }
+
+    Ok(resolve_rpm_requirements(&provides, &requires, &file_owners))
+}
+
+// ============================================================================
+// Graph
+// ============================================================================
+
+struct Node {
+    name: String,
+    size_bytes: u64,
+    is_auto: bool,
+    /// Kept regardless of what depends on it (Debian's Essential and Protected packages)
+    protected: bool,
+    depends: Vec<usize>,
+}
+
+pub struct DependencyGraph {
+    nodes: Vec<Node>,
+}
+
+impl DependencyGraph {
+    /// Graph of `packages`; `depends` maps a package to the installed packages it needs,
+    /// names not in `packages` are ignored
+    pub fn new(packages: &[PackageInfo], depends: &HashMap<String, Vec<String>>) -> Self {
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let mut nodes: Vec<Node> = Vec::new();
+        for p in packages {
+            // Multiarch installs list a name once per architecture
+            if let Some(&i) = index.get(p.name.as_str()) {
+                nodes[i].size_bytes += p.size_bytes;
+                continue;
+            }
+            index.insert(&p.name, nodes.len());
+            nodes.push(Node {
+                name: p.name.clone(),
+                size_bytes: p.size_bytes,
+                is_auto: p.is_auto,
+                protected: false,
+                depends: Vec::new(),
+            });
+        }
+
+        for node in &mut nodes {
+            let mut deps: Vec<usize> = depends
+                .get(&node.name)
+                .into_iter()
+                .flatten()
+                .filter_map(|d| index.get(d.as_str()).copied())
+                .collect();
+            deps.sort_unstable();
+            deps.dedup();
+            node.depends = deps;
+        }
+        for (i, node) in nodes.iter_mut().enumerate() {
+            node.depends.retain(|&d| d != i);
+        }
+
+        Self { nodes }
+    }
+
+    /// Treat `names` like explicitly installed packages that are never leaves
+    pub fn protect(&mut self, names: &HashSet<String>) {
+        for node in &mut self.nodes {
+            node.protected |= names.contains(&node.name);
+        }
+    }
+
+    pub fn analyze(&self) -> PackageAnalysis {
+        let n = self.nodes.len();
+        let mut required_by = vec![0usize; n];
+        for node in &self.nodes {
+            for &d in &node.depends {
+                required_by[d] += 1;
+            }
+        }
+
+        let explicit: Vec<usize> = (0..n).filter(|&i| !self.nodes[i].is_auto || self.nodes[i].protected).collect();
+        let needed = self.reachable(&explicit);
+        let exclusive = self.exclusive_sizes(explicit, &required_by);
+
+        let mut packages: Vec<PackageUsage> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| PackageUsage {
+                name: node.name.clone(),
+                size_bytes: node.size_bytes,
+                is_auto: node.is_auto,
+                required_by: required_by[i],
+                exclusive_size_bytes: exclusive[i],
+                orphan: node.is_auto && !needed[i],
+                leaf: !node.is_auto && !node.protected && required_by[i] == 0,
+            })
+            .collect();
+        packages.sort_by(|a, b| b.exclusive_size_bytes.cmp(&a.exclusive_size_bytes).then_with(|| a.name.cmp(&b.name)));
+
+        let orphans = packages.iter().filter(|p| p.orphan);
+        PackageAnalysis {
+            orphan_count: orphans.clone().count(),
+            orphan_bytes: orphans.map(|p| p.size_bytes).sum(),
+            leaf_count: packages.iter().filter(|p| p.leaf).count(),
+            packages,
+        }
+    }
+
+    /// Nodes reachable from `roots` along dependencies
+    fn reachable(&self, roots: &[usize]) -> Vec<bool> {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = roots.to_vec();
+        while let Some(i) = stack.pop() {
+            if !seen[i] {
+                seen[i] = true;
+                stack.extend(&self.nodes[i].depends);
+            }
+        }
+        seen
+    }
+
+    /// Size of each package plus the packages it dominates: those every dependency path from
+    /// an explicit package (or, for orphans, from the top of their chain) runs through it
+    fn exclusive_sizes(&self, mut roots: Vec<usize>, required_by: &[usize]) -> Vec<u64> {
+        let n = self.nodes.len();
+        // Orphans hang off a virtual root too: first those nothing depends on, then one
+        // member of each remaining dependency cycle
+        let mut seen = self.reachable(&roots);
+        let tops: Vec<usize> = (0..n).filter(|&i| !seen[i] && required_by[i] == 0).collect();
+        roots.extend(&tops);
+        seen = self.reachable(&roots);
+        while let Some(i) = (0..n).find(|&i| !seen[i]) {
+            roots.push(i);
+            seen = self.reachable(&roots);
+        }
+
+        // Virtual root is node `n`
+        let successors = |i: usize| -> &[usize] {
+            if i == n { &roots } else { &self.nodes[i].depends }
+        };
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n + 1];
+        for i in 0..=n {
+            for &s in successors(i) {
+                predecessors[s].push(i);
+            }
+        }
+
+        // Postorder by iterative DFS from the root
+        let mut postorder: Vec<usize> = Vec::with_capacity(n + 1);
+        let mut visited = vec![false; n + 1];
+        let mut stack = vec![(n, 0usize)];
+        visited[n] = true;
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            match successors(node).get(*next) {
+                Some(&s) => {
+                    *next += 1;
+                    if !visited[s] {
+                        visited[s] = true;
+                        stack.push((s, 0));
+                    }
+                }
+                None => {
+                    postorder.push(node);
+                    stack.pop();
+                }
+            }
+        }
+        let mut order = vec![0usize; n + 1];
+        for (position, &node) in postorder.iter().enumerate() {
+            order[node] = position;
+        }
+
+        // Immediate dominators (Cooper, Harvey and Kennedy's iterative algorithm)
+        let mut idom: Vec<Option<usize>> = vec![None; n + 1];
+        idom[n] = Some(n);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in postorder.iter().rev().skip(1) {
+                let mut new_idom: Option<usize> = None;
+                for &p in &predecessors[node] {
+                    if idom[p].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => p,
+                        Some(current) => {
+                            let (mut a, mut b) = (p, current);
+                            while a != b {
+                                while order[a] < order[b] {
+                                    a = idom[a].unwrap();
+                                }
+                                while order[b] < order[a] {
+                                    b = idom[b].unwrap();
+                                }
+                            }
+                            a
+                        }
+                    });
+                }
+                if new_idom.is_some() && idom[node] != new_idom {
+                    idom[node] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        // Postorder visits dominated packages before their dominator
+        let mut exclusive: Vec<u64> = self.nodes.iter().map(|node| node.size_bytes).chain([0]).collect();
+        for &node in &postorder {
+            if node == n {
+                continue;
+            }
+            if let Some(parent) = idom[node].filter(|&p| p != n) {
+                exclusive[parent] += exclusive[node];
+            }
+        }
+        exclusive.truncate(n);
+        exclusive
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::PackageOrigin;
+
+    fn package(name: &str, size_bytes: u64, is_auto: bool) -> PackageInfo {
+        PackageInfo {
+            name: name.to_string(),
+            version: "1".to_string(),
+            size_bytes,
+            description: String::new(),
+            is_auto,
+            category: String::new(),
+            origin: PackageOrigin::Native,
+        }
+    }
+
+    #[test]
+    fn test_resolve_rpm_requirements() {
+        let provides = "bash\tbash\nbash\tconfig(bash)\nglibc\tlibc.so.6()(64bit)\nglibc\tglibc\n";
+        let requires = "bash\tlibc.so.6()(64bit)\nbash\trpmlib(PayloadIsZstd)\nbash\tconfig(bash)\n\
+                        dracut\t/bin/sh\ndracut\t(systemd if systemd-udev)\n";
+        let file_owners = HashMap::from([("/bin/sh".to_string(), vec!["bash".to_string()])]);
+        let depends = resolve_rpm_requirements(provides, requires, &file_owners);
+        assert_eq!(depends["bash"], vec!["glibc", "bash"]);
+        assert_eq!(depends["dracut"], vec!["bash"]);
+    }
+
+    #[test]
+    fn test_analyze() {
+        // editor -> libedit -> libc, shell -> libc; old-tool -> libold <-> libcycle; base is essential
+        let packages = [
+            package("base", 2, true),
+            package("editor", 10, false),
+            package("shell", 20, false),
+            package("libedit", 5, true),
+            package("libc", 100, true),
+            package("libold", 7, true),
+            package("libcycle", 3, true),
+            package("old-tool", 1, true),
+        ];
+        let depends: HashMap<String, Vec<String>> = [
+            ("editor", vec!["libedit", "missing"]),
+            ("libedit", vec!["libc"]),
+            ("shell", vec!["libc"]),
+            ("old-tool", vec!["libold"]),
+            ("libold", vec!["libcycle"]),
+            ("libcycle", vec!["libold"]),
+        ]
+        .into_iter()
+        .map(|(name, deps)| (name.to_string(), deps.into_iter().map(String::from).collect()))
+        .collect();
+
+        let mut graph = DependencyGraph::new(&packages, &depends);
+        graph.protect(&HashSet::from(["base".to_string(), "shell".to_string()]));
+        let analysis = graph.analyze();
+        let usage = |name: &str| analysis.packages.iter().find(|p| p.name == name).unwrap().clone();
+
+        // libc is shared, so neither editor nor shell frees it
+        assert_eq!(usage("editor").exclusive_size_bytes, 15);
+        assert_eq!(usage("shell").exclusive_size_bytes, 20);
+        assert_eq!(usage("libc").required_by, 2);
+        assert!(usage("editor").leaf && !usage("shell").leaf);
+        assert!(!usage("libedit").orphan && !usage("base").orphan);
+
+        assert_eq!(usage("old-tool").exclusive_size_bytes, 11);
+        assert!(usage("old-tool").orphan && usage("libcycle").orphan);
+        assert_eq!(analysis.orphan_count, 3);
+        assert_eq!(analysis.orphan_bytes, 11);
+        assert_eq!(analysis.leaf_count, 1);
+        assert_eq!(analysis.packages[0].name, "libc");
+    }
+}
@@ -1,25 +1,235 @@
 //! Fedora/RHEL Package Manager Adapter
 //! Uses dnf/rpm for package management
 
-use super::{PackageInfo, PackageAction, CleanupResult, PackageManager, detect_package_category};
+use super::{PackageInfo, PackageOrigin, PackageAction, PackageDetails, SearchResult, UpgradablePackage, CleanupResult, PackageManager, detect_package_category};
+use super::{ChangeKind, PackageChange, PackageTransaction, find_transaction, undo_action, MAX_HISTORY};
+use super::RemovalPreview;
+use super::{DependencyGraph, rpm_dependencies};
+use super::{package_not_found, parse_fields, parse_size, query_output, sort_by_relevance, MAX_SEARCH_RESULTS};
 use crate::error::{AppError, Result};
-use crate::utils::privileged;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
 use async_trait::async_trait;
-use std::collections::HashSet;
+use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
 use tokio::process::Command;
 
-pub struct FedoraAdapter;
+pub struct FedoraAdapter {
+    executor: Arc<dyn PrivilegedExecutor>,
+}
 
 impl FedoraAdapter {
-    pub fn new() -> Self {
-        Self
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { executor }
+    }
+    
+    /// What `dnf <args>` would remove: `--assumeno` resolves the transaction and stops at the prompt.
+    /// dnf 4 won't resolve removals without root, so there the names come from `fallback`
+    /// (a `dnf repoquery`), which misses dependencies `clean_requirements_on_remove` would take along
+    async fn simulate(&self, action: &str, args: &[&str], fallback: &[&str], target: Option<&str>) -> Result<RemovalPreview> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] Nothing to remove for Fedora");
+            return Ok(RemovalPreview::empty(action));
+        }
+        
+        let output = Command::new("dnf")
+            .args(args)
+            .arg("--assumeno")
+            .output()
+            .await
+            .map_err(|e| AppError::CommandFailed(format!("Failed to run dnf: {}", e)))?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut names = parse_transaction_removals(&String::from_utf8_lossy(&output.stdout));
+        
+        if names.is_empty() && stderr.contains("superuser privileges") {
+            names = repoquery_names(&query_output("dnf", fallback).await?, "");
+            if let Some(target) = target {
+                names.retain(|n| n != target);
+                names.insert(0, target.to_string());
+            }
+        } else if names.is_empty() && !output.status.success() {
+            // "No match for argument", unresolvable dependencies
+            return Err(AppError::from_command(format!("dnf {} --assumeno", args.join(" ")), output.status.code(), &stderr));
+        }
+        if names.is_empty() {
+            return Ok(RemovalPreview::empty(action));
+        }
+        
+        let installed = self.get_installed_packages().await?;
+        Ok(RemovalPreview::new(action, &names, &installed))
     }
 }
 
-impl Default for FedoraAdapter {
-    fn default() -> Self {
-        Self::new()
+/// Names in the "Removing" sections of a dnf transaction table (dnf 4 and 5):
+/// ` vim-enhanced   x86_64   2:9.1.0-1.fc40   @updates   4.0 M`; dnf 4 wraps long names
+/// onto their own line
+fn parse_transaction_removals(output: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut removing = false;
+    
+    for line in output.lines() {
+        if !line.starts_with(' ') {
+            let header = line.trim();
+            if header.starts_with("Transaction Summary") {
+                break;
+            }
+            if header.ends_with(':') {
+                removing = header.starts_with("Removing");
+            }
+            continue;
+        }
+        
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        // Full rows end with a two-token size; a lone token is a wrapped name
+        if removing && (columns.len() >= 6 || columns.len() == 1) && !names.iter().any(|n| n == columns[0]) {
+            names.push(columns[0].to_string());
+        }
     }
+    names
+}
+
+/// Parse `dnf list --upgrades` rows (`bash.x86_64  5.2.26-3.fc40  updates`); current versions come from rpm
+fn parse_upgrades(output: &str) -> Vec<UpgradablePackage> {
+    let mut packages: Vec<UpgradablePackage> = Vec::new();
+    
+    for line in output.lines() {
+        // Obsoleted packages are listed after the upgrades
+        if line.starts_with("Obsoleting") {
+            break;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let [package, version, repo] = parts[..] else { continue };
+        let Some((name, _arch)) = package.rsplit_once('.') else { continue };
+        
+        // One entry per name, even with both x86_64 and i686 installed
+        if !packages.iter().any(|p| p.name == name) {
+            packages.push(UpgradablePackage {
+                name: name.to_string(),
+                current_version: String::new(),
+                new_version: version.to_string(),
+                repository: Some(repo.to_string()),
+            });
+        }
+    }
+    
+    packages
+}
+
+/// Parse `dnf search` hits into (name, summary), one per name
+/// dnf4 prints `firefox.x86_64 : Mozilla Firefox`, dnf5 `firefox.x86_64\tMozilla Firefox`
+fn parse_search(output: &str) -> Vec<(String, String)> {
+    let mut hits: Vec<(String, String)> = Vec::new();
+    
+    for line in output.lines() {
+        let line = line.trim();
+        let Some((package, summary)) = line.split_once(" : ").or_else(|| line.split_once('\t')) else { continue };
+        // Section headers ("Matched fields: ...", "=== Name Matched ===")
+        if package.contains(' ') {
+            continue;
+        }
+        
+        let name = package.rsplit_once('.').map(|(name, _arch)| name).unwrap_or(package);
+        if !hits.iter().any(|(n, _)| n == name) {
+            hits.push((name.to_string(), summary.trim().to_string()));
+        }
+    }
+    
+    hits
+}
+
+/// Split `vim-enhanced-2:9.0.2120-1.fc39.x86_64` into name and `[epoch:]version-release`
+fn split_nevra(nevra: &str) -> Option<(String, String)> {
+    let (nevr, _arch) = nevra.rsplit_once('.')?;
+    let mut parts = nevr.rsplitn(3, '-');
+    let release = parts.next()?;
+    let version = parts.next()?;
+    let name = parts.next()?;
+    Some((name.to_string(), format!("{}-{}", version, release)))
+}
+
+/// Transaction ids in `dnf history list`, newest first
+fn parse_history_ids(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|id| id.parse::<u32>().is_ok())
+        .map(String::from)
+        .collect()
+}
+
+/// Parse `dnf history info <id>...` blocks (dnf4 and dnf5 layouts)
+fn parse_history_info(output: &str) -> Vec<PackageTransaction> {
+    let mut transactions = Vec::new();
+    let mut current: Option<(String, String, Option<String>, Vec<PackageChange>)> = None;
+    
+    for line in output.lines() {
+        if !line.starts_with(' ') {
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let value = value.trim();
+            match key.trim() {
+                "Transaction ID" => {
+                    if let Some((id, timestamp, command, changes)) = current.take() {
+                        transactions.push(PackageTransaction::new(id, timestamp, command, changes));
+                    }
+                    current = Some((value.to_string(), String::new(), None, Vec::new()));
+                }
+                "Begin time" | "Start time" => {
+                    if let Some(transaction) = current.as_mut() {
+                        transaction.1 = value.to_string();
+                    }
+                }
+                "Command Line" | "Command line" => {
+                    if let Some(transaction) = current.as_mut() {
+                        transaction.2 = Some(value.to_string()).filter(|c| !c.is_empty());
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        
+        // Altered packages: `    Upgrade  bash-5.2.26-1.fc39.x86_64  @updates`
+        let Some((_, _, _, changes)) = current.as_mut() else { continue };
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        let [verb, nevra, ..] = columns[..] else { continue };
+        let Some((name, version)) = split_nevra(nevra) else { continue };
+        let kind = match verb {
+            "Install" | "Dep-Install" => ChangeKind::Install,
+            "Upgrade" => ChangeKind::Upgrade,
+            "Downgrade" => ChangeKind::Downgrade,
+            "Reinstall" => ChangeKind::Reinstall,
+            "Removed" | "Remove" | "Erase" | "Obsoleted" => ChangeKind::Remove,
+            // The version an upgrade, downgrade or reinstall replaced
+            "Upgraded" | "Downgraded" | "Reinstalled" | "Replaced" => {
+                if let Some(change) = changes.iter_mut().find(|c| c.name == name && c.old_version.is_none()) {
+                    change.old_version = Some(version);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let (old_version, new_version) = match kind {
+            ChangeKind::Remove => (Some(version), None),
+            _ => (None, Some(version)),
+        };
+        changes.push(PackageChange { name, kind, old_version, new_version });
+    }
+    
+    if let Some((id, timestamp, command, changes)) = current {
+        transactions.push(PackageTransaction::new(id, timestamp, command, changes));
+    }
+    transactions
+}
+
+/// Package names printed one per line by `dnf repoquery`
+fn repoquery_names(output: &str, exclude: &str) -> Vec<String> {
+    let mut names: Vec<String> = output
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty() && l != exclude)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
 }
 
 #[async_trait]
@@ -36,9 +246,26 @@ impl PackageManager for FedoraAdapter {
         "/var/log/dnf.log"
     }
     
+    fn database_paths(&self) -> &'static [&'static str] {
+        // rpmdb (moved below /usr on newer releases) and dnf's user-installed marks
+        // SQLite commits land in the -wal file first, so watch it and the directory as well
+        &[
+            "/usr/lib/sysimage/rpm",
+            "/usr/lib/sysimage/rpm/rpmdb.sqlite",
+            "/usr/lib/sysimage/rpm/rpmdb.sqlite-wal",
+            "/var/lib/rpm",
+            "/var/lib/rpm/rpmdb.sqlite",
+            "/var/lib/rpm/rpmdb.sqlite-wal",
+            "/var/lib/dnf/history.sqlite",
+            "/var/lib/dnf/history.sqlite-wal",
+            "/usr/lib/sysimage/libdnf5/transaction_history.sqlite",
+            "/usr/lib/sysimage/libdnf5/transaction_history.sqlite-wal",
+        ]
+    }
+    
     async fn refresh_repositories(&self) -> Result<String> {
         // dnf check-update returns 100 if updates available, 0 if none - both are OK
-        let _ = privileged::run_privileged("dnf", &["check-update", "-y"]).await;
+        let _ = self.executor.execute(&[PrivilegedOp::refresh_repositories("dnf")]).await;
         Ok("Package database updated".to_string())
     }
     
@@ -49,19 +276,18 @@ impl PackageManager for FedoraAdapter {
             return Ok(super::generate_mock_packages("dnf"));
         }
         
-        // Get all installed packages with detailed info
-        let output = Command::new("rpm")
-            .args(["-qa", "--queryformat", "%{NAME}\t%{VERSION}-%{RELEASE}\t%{SIZE}\t%{SUMMARY}\n"])
-            .output()
-            .await
-            .map_err(|e| AppError::CommandFailed(e.to_string()))?;
-        
-        // Get user-installed packages (not dependencies)
-        let userinstalled = Command::new("dnf")
-            .args(["repoquery", "--userinstalled", "--qf", "%{name}"])
-            .output()
-            .await
-            .ok();
+        // All installed packages from rpm and the user-installed ones (not dependencies)
+        // from dnf; dnf loads its metadata slowly, so both run at once
+        let (output, userinstalled) = tokio::join!(
+            Command::new("rpm")
+                .args(["-qa", "--queryformat", "%{NAME}\t%{VERSION}-%{RELEASE}\t%{SIZE}\t%{SUMMARY}\n"])
+                .output(),
+            Command::new("dnf")
+                .args(["repoquery", "--userinstalled", "--qf", "%{name}"])
+                .output(),
+        );
+        let output = output.map_err(|e| AppError::CommandFailed(e.to_string()))?;
+        let userinstalled = userinstalled.ok();
         
         let user_packages: HashSet<String> = userinstalled
             .map(|o| {
@@ -94,6 +320,7 @@ impl PackageManager for FedoraAdapter {
                 description,
                 is_auto: !user_packages.contains(&name),
                 category,
+                origin: PackageOrigin::Native,
             });
         }
         
@@ -114,8 +341,217 @@ impl PackageManager for FedoraAdapter {
             .collect())
     }
     
+    async fn install_packages(&self, names: &[&str]) -> Result<String> {
+        let mut args = vec!["install", "-y"];
+        args.extend_from_slice(names);
+        self.executor.execute(&[PrivilegedOp::package("dnf", &args)]).await
+    }
+    
+    async fn enable_multilib(&self) -> Result<String> {
+        // i686 packages ship in the main repos; Steam and the NVIDIA driver need RPM Fusion
+        let output = Command::new("rpm")
+            .args(["-E", "%fedora"])
+            .output()
+            .await
+            .map_err(|e| AppError::CommandFailed(e.to_string()))?;
+        
+        // RHEL clones leave the macro unexpanded; their i686 packages still work
+        let Ok(release) = String::from_utf8_lossy(&output.stdout).trim().parse::<u32>() else {
+            return Ok("i686 packages available (RPM Fusion is Fedora-only)".to_string());
+        };
+        
+        self.executor.execute(&[PrivilegedOp::add_rpm_fusion(release)]).await?;
+        Ok(format!("RPM Fusion enabled (Fedora {})", release))
+    }
+    
+    async fn transaction_history(&self) -> Result<Vec<PackageTransaction>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No transaction history for Fedora");
+            return Ok(Vec::new());
+        }
+        
+        // dnf.log is a debug log; the history database has the transactions
+        let mut ids = parse_history_ids(&query_output("dnf", &["history", "list"]).await?);
+        ids.truncate(MAX_HISTORY);
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let mut args = vec!["history", "info"];
+        args.extend(ids.iter().map(String::as_str));
+        let mut transactions = parse_history_info(&query_output("dnf", &args).await?);
+        transactions.sort_by_key(|t| std::cmp::Reverse(t.id.parse::<u32>().unwrap_or(0)));
+        Ok(transactions)
+    }
+    
+    async fn undo_transaction(&self, id: &str) -> Result<PackageAction> {
+        if id.parse::<u32>().is_err() {
+            return Err(AppError::InvalidInput(format!("Invalid dnf transaction id: {}", id)));
+        }
+        let transaction = find_transaction(self.transaction_history().await?, id)?;
+        
+        // dnf reverses every change itself, including upgrades
+        let result = self.executor.execute(&[PrivilegedOp::package("dnf", &["history", "undo", "-y", id])]).await;
+        Ok(undo_action(&transaction, result, &[]))
+    }
+    
+    async fn search_remote(&self, query: &str) -> Result<Vec<SearchResult>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No remote search results for Fedora");
+            return Ok(Vec::new());
+        }
+        
+        let mut hits = parse_search(&query_output("dnf", &["search", "-q", query]).await?);
+        sort_by_relevance(&mut hits, query, |(name, _)| name);
+        hits.truncate(MAX_SEARCH_RESULTS);
+        if hits.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        // Latest version and repository of each hit
+        let mut args = vec!["repoquery", "-q", "--latest-limit", "1", "--qf", "%{name}\t%{version}-%{release}\t%{repoid}\n"];
+        args.extend(hits.iter().map(|(name, _)| name.as_str()));
+        let repoquery = query_output("dnf", &args).await?;
+        let mut versions: HashMap<&str, (&str, &str)> = HashMap::new();
+        for line in repoquery.lines() {
+            if let [name, version, repo] = line.split('\t').collect::<Vec<_>>()[..] {
+                // Prefer a real repository over the installed copy ("@System")
+                let known = versions.get(name).is_some_and(|(_, r)| !r.starts_with('@'));
+                if !known {
+                    versions.insert(name, (version, repo));
+                }
+            }
+        }
+        
+        let installed: HashSet<String> = query_output("rpm", &["-qa", "--qf", "%{NAME}\n"])
+            .await?
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        
+        Ok(hits
+            .into_iter()
+            .map(|(name, description)| {
+                let (version, repo) = versions.get(name.as_str()).copied().unwrap_or(("", ""));
+                SearchResult {
+                    installed: installed.contains(&name),
+                    repository: (!repo.is_empty()).then(|| repo.trim_start_matches('@').to_string()),
+                    package: PackageInfo {
+                        category: detect_package_category(&name, &description),
+                        origin: PackageOrigin::Native,
+                        name,
+                        version: version.to_string(),
+                        size_bytes: 0,
+                        description,
+                        is_auto: false,
+                    },
+                }
+            })
+            .collect())
+    }
+    
+    async fn list_upgradable(&self) -> Result<Vec<UpgradablePackage>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No upgradable packages for Fedora");
+            return Ok(Vec::new());
+        }
+        
+        let mut packages = parse_upgrades(&query_output("dnf", &["list", "--upgrades", "-q"]).await?);
+        if packages.is_empty() {
+            return Ok(packages);
+        }
+        
+        let mut args = vec!["-q", "--qf", "%{NAME}\t%{VERSION}-%{RELEASE}\n"];
+        args.extend(packages.iter().map(|p| p.name.as_str()));
+        let installed = query_output("rpm", &args).await?;
+        for line in installed.lines() {
+            if let Some((name, version)) = line.split_once('\t') {
+                if let Some(package) = packages.iter_mut().find(|p| p.name == name && p.current_version.is_empty()) {
+                    package.current_version = version.to_string();
+                }
+            }
+        }
+        
+        Ok(packages)
+    }
+    
+    async fn upgrade_packages(&self, names: &[&str]) -> Result<PackageAction> {
+        let mut args = vec!["upgrade", "-y"];
+        args.extend_from_slice(names);
+        let result = self.executor.execute(&[PrivilegedOp::package("dnf", &args)]).await;
+        
+        Ok(PackageAction {
+            name: names.join(" "),
+            action: "upgrade".to_string(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e.to_string()),
+        })
+    }
+    
+    async fn upgrade_all(&self) -> Result<PackageAction> {
+        let result = self.executor.execute(&[PrivilegedOp::package("dnf", &["upgrade", "-y"])]).await;
+        
+        Ok(PackageAction {
+            name: "system".to_string(),
+            action: "upgrade".to_string(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e.to_string()),
+        })
+    }
+    
+    async fn package_details(&self, name: &str) -> Result<PackageDetails> {
+        // rpm prints "package x is not installed" on stdout, so match the name column
+        let rpm = query_output("rpm", &["-q", "--qf", "%{NAME}\t%{VERSION}-%{RELEASE}\t%{SIZE}\t%{SUMMARY}\n", name]).await?;
+        let rpm: Vec<&str> = rpm.lines().next().unwrap_or("").split('\t').collect();
+        let installed = rpm.len() == 4 && rpm[0] == name;
+        
+        // dnf lists the installed version first
+        let info = parse_fields(&query_output("dnf", &["info", "-q", name]).await?);
+        if !installed && info.is_empty() {
+            return Err(package_not_found(name));
+        }
+        
+        // dnf4 says "From repo", dnf5 "From repository"; "@System" means installed
+        let repository = ["From repo", "From repository", "Repository"]
+            .iter()
+            .find_map(|key| info.get(*key))
+            .map(|repo| repo.trim_start_matches('@').to_string())
+            .filter(|repo| repo != "System" && repo != "commandline")
+            .or_else(|| installed.then(|| "local".to_string()));
+        
+        let requires = query_output("dnf", &["repoquery", "-q", "--requires", "--resolve", "--qf", "%{name}\n", name]).await?;
+        let required_by = query_output("dnf", &["repoquery", "-q", "--installed", "--whatrequires", name, "--qf", "%{name}\n"]).await?;
+        let files = if installed {
+            query_output("rpm", &["-ql", name]).await?.lines().map(|f| f.to_string()).collect()
+        } else {
+            Vec::new()
+        };
+        
+        let (version, size_bytes, description) = if installed {
+            (rpm[1].to_string(), rpm[2].parse().unwrap_or(0), rpm[3].to_string())
+        } else {
+            (
+                format!("{}-{}", info.get("Version").map(String::as_str).unwrap_or(""), info.get("Release").map(String::as_str).unwrap_or("")),
+                info.get("Size").map(|s| parse_size(s)).unwrap_or(0),
+                info.get("Summary").cloned().unwrap_or_default(),
+            )
+        };
+        
+        Ok(PackageDetails {
+            name: name.to_string(),
+            version,
+            description,
+            installed,
+            size_bytes,
+            repository,
+            dependencies: repoquery_names(&requires, name),
+            reverse_dependencies: repoquery_names(&required_by, name),
+            files,
+        })
+    }
+    
     async fn uninstall_package(&self, name: &str) -> Result<PackageAction> {
-        let result = privileged::run_privileged("dnf", &["remove", "-y", name]).await;
+        let result = self.executor.execute(&[PrivilegedOp::package("dnf", &["remove", "-y", name])]).await;
         
         Ok(PackageAction {
             name: name.to_string(),
@@ -131,7 +567,7 @@ impl PackageManager for FedoraAdapter {
     }
     
     async fn autoremove(&self) -> Result<PackageAction> {
-        let result = privileged::run_privileged("dnf", &["autoremove", "-y"]).await;
+        let result = self.executor.execute(&[PrivilegedOp::package("dnf", &["autoremove", "-y"])]).await;
         
         Ok(PackageAction {
             name: "autoremove".to_string(),
@@ -141,8 +577,34 @@ impl PackageManager for FedoraAdapter {
         })
     }
     
+    async fn simulate_uninstall(&self, name: &str) -> Result<RemovalPreview> {
+        let fallback = ["repoquery", "-q", "--installed", "--whatrequires", name, "--recursive", "--qf", "%{name}\n"];
+        self.simulate("uninstall", &["remove", name], &fallback, Some(name)).await
+    }
+    
+    async fn simulate_purge(&self, name: &str) -> Result<RemovalPreview> {
+        // Same as uninstall, like `purge_package`
+        let mut preview = self.simulate_uninstall(name).await?;
+        preview.action = "purge".to_string();
+        Ok(preview)
+    }
+    
+    async fn simulate_autoremove(&self) -> Result<RemovalPreview> {
+        let fallback = ["repoquery", "-q", "--unneeded", "--qf", "%{name}\n"];
+        self.simulate("autoremove", &["autoremove"], &fallback, None).await
+    }
+    
+    async fn dependency_graph(&self) -> Result<DependencyGraph> {
+        let packages = self.get_installed_packages().await?;
+        if super::is_mock_mode() {
+            return Ok(DependencyGraph::new(&packages, &HashMap::new()));
+        }
+        
+        Ok(DependencyGraph::new(&packages, &rpm_dependencies().await?))
+    }
+    
     async fn clean_cache(&self) -> Result<CleanupResult> {
-        let result = privileged::run_privileged("dnf", &["clean", "all"]).await;
+        let result = self.executor.execute(&[PrivilegedOp::clean_package_cache("dnf")]).await;
         
         Ok(CleanupResult {
             category: "dnf_cache".to_string(),
@@ -163,3 +625,41 @@ impl PackageManager for FedoraAdapter {
         Ok((total, auto, size))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search() {
+        let dnf4 = "======== Name Exactly Matched: firefox ========\nfirefox.x86_64 : Mozilla Firefox Web browser\nfirefox.i686 : Mozilla Firefox Web browser\n";
+        assert_eq!(parse_search(dnf4), vec![("firefox".to_string(), "Mozilla Firefox Web browser".to_string())]);
+        let dnf5 = "Matched fields: name (exact)\n firefox.x86_64\tMozilla Firefox Web browser\n";
+        assert_eq!(parse_search(dnf5), vec![("firefox".to_string(), "Mozilla Firefox Web browser".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_transaction_removals() {
+        let dnf4 = "Dependencies resolved.\n================\n Package              Arch    Version           Repository  Size\n================\nRemoving:\n vim-enhanced         x86_64  2:9.1.0-1.fc40    @updates    4.0 M\nRemoving unused dependencies:\n vim-filesystem-and-a-very-long-name\n                      noarch  2:9.1.0-1.fc40    @updates     40 k\n gpm-libs             x86_64  1.20.7-46.fc40    @fedora      28 k\n\nTransaction Summary\n================\nRemove  3 Packages\n";
+        assert_eq!(parse_transaction_removals(dnf4), vec!["vim-enhanced", "vim-filesystem-and-a-very-long-name", "gpm-libs"]);
+        
+        let dnf5 = "Package          Arch   Version         Repository      Size\nRemoving:\n vim-enhanced    x86_64 2:9.1.0-1.fc41  updates      4.0 MiB\nRemoving dependent packages:\n vim-default-editor noarch 2:9.1.0-1.fc41 updates 226.0   B\n\nTransaction Summary:\n Removing:          2 packages\n";
+        assert_eq!(parse_transaction_removals(dnf5), vec!["vim-enhanced", "vim-default-editor"]);
+    }
+
+    #[test]
+    fn test_parse_history_info() {
+        let list = "ID     | Command line | Date and time    | Action(s) | Altered\n-------\n    13 | remove nano  | 2024-01-11 08:00 | Removed   |    1\n    12 | upgrade      | 2024-01-10 12:00 | Upgrade   |    2\n";
+        assert_eq!(parse_history_ids(list), vec!["13", "12"]);
+        
+        let dnf4 = "Transaction ID : 12\nBegin time     : Wed 10 Jan 2024 12:00:01 PM UTC\nCommand Line   : upgrade\nPackages Altered:\n    Upgrade  bash-5.2.26-1.fc39.x86_64             @updates\n    Upgraded bash-5.2.21-1.fc39.x86_64             @@System\n    Dep-Install vim-data-2:9.1.0-1.fc39.noarch     @updates\nScriptlet output:\n   1 done\n";
+        let dnf5 = "Transaction ID : 13\nStart time     : 2024-01-11 08:00:00\nCommand line   : dnf remove nano\nPackages altered:\n  Action  Package                 Reason Repository\n  Removed nano-7.2-5.fc40.x86_64  User   @System\n";
+        let transactions = parse_history_info(&format!("{}{}", dnf4, dnf5));
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].command.as_deref(), Some("upgrade"));
+        assert_eq!(transactions[0].version_changes(), vec![("bash", "5.2.21-1.fc39")]);
+        assert_eq!(transactions[0].installed(), vec!["vim-data"]);
+        assert_eq!(transactions[0].changes[1].new_version.as_deref(), Some("2:9.1.0-1.fc39"));
+        assert_eq!(transactions[1].removed(), vec![("nano", "7.2-5.fc40")]);
+    }
+}
@@ -0,0 +1,207 @@
+//! Flatpak Adapter
+//! Lists apps and runtimes of the system and user installations; system refs are removed as root
+
+use super::{PackageInfo, PackageOrigin, PackageAction, CleanupResult, PackageSource, detect_package_category};
+use super::{command_exists, is_mock_mode, parse_size, query_output};
+use crate::error::{AppError, Result};
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::process::Command;
+
+/// Tab-separated columns of `flatpak list` (no header when stdout is not a terminal)
+const LIST_COLUMNS: &str = "--columns=application,name,version,branch,size,installation";
+
+pub struct FlatpakAdapter {
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+/// One installed app or runtime
+#[derive(Debug, Clone, PartialEq)]
+struct InstalledRef {
+    id: String,
+    name: String,
+    version: String,
+    branch: String,
+    size_bytes: u64,
+    /// "system", "user" or a custom installation name
+    installation: String,
+    runtime: bool,
+}
+
+impl InstalledRef {
+    /// Name shown in Glance; runtimes are often installed in several branches, so they carry theirs
+    fn display_ref(&self) -> String {
+        if self.runtime {
+            format!("{}//{}", self.id, self.branch)
+        } else {
+            self.id.clone()
+        }
+    }
+}
+
+/// Parse `flatpak list --app|--runtime` with `LIST_COLUMNS`
+fn parse_list(output: &str, runtime: bool) -> Vec<InstalledRef> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split('\t').map(str::trim).collect();
+            if columns.len() < 6 || columns[0].is_empty() || columns[0] == "Application ID" {
+                return None;
+            }
+            Some(InstalledRef {
+                id: columns[0].to_string(),
+                name: columns[1].to_string(),
+                version: columns[2].to_string(),
+                branch: columns[3].to_string(),
+                size_bytes: parse_size(columns[4]),
+                installation: columns[5].to_string(),
+                runtime,
+            })
+        })
+        .collect()
+}
+
+impl FlatpakAdapter {
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { executor }
+    }
+
+    /// Installed apps followed by installed runtimes
+    async fn installed_refs(&self) -> Result<Vec<InstalledRef>> {
+        let apps = query_output("flatpak", &["list", "--app", LIST_COLUMNS]).await?;
+        let runtimes = query_output("flatpak", &["list", "--runtime", LIST_COLUMNS]).await?;
+
+        let mut refs = parse_list(&apps, false);
+        refs.extend(parse_list(&runtimes, true));
+        Ok(refs)
+    }
+
+    /// Run flatpak on the user installation, which needs no root
+    async fn run_user(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("flatpak")
+            .args(args)
+            .output()
+            .await
+            .map_err(|e| AppError::CommandFailed(format!("Failed to run flatpak: {}", e)))?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(AppError::CommandFailed(String::from_utf8_lossy(&output.stderr).trim().to_string()))
+        }
+    }
+}
+
+#[async_trait]
+impl PackageSource for FlatpakAdapter {
+    fn origin(&self) -> PackageOrigin {
+        PackageOrigin::Flatpak
+    }
+
+    async fn is_available(&self) -> bool {
+        !is_mock_mode() && command_exists("flatpak").await
+    }
+
+    async fn get_installed_packages(&self) -> Result<Vec<PackageInfo>> {
+        let packages = self
+            .installed_refs()
+            .await?
+            .into_iter()
+            .map(|r| PackageInfo {
+                name: r.display_ref(),
+                version: if r.version.is_empty() { r.branch.clone() } else { r.version.clone() },
+                size_bytes: r.size_bytes,
+                category: if r.runtime { "Runtime".to_string() } else { detect_package_category(&r.id, &r.name) },
+                description: r.name,
+                // Runtimes are pulled in by the apps that need them
+                is_auto: r.runtime,
+                origin: PackageOrigin::Flatpak,
+            })
+            .collect();
+
+        Ok(packages)
+    }
+
+    async fn uninstall_package(&self, name: &str) -> Result<PackageAction> {
+        let installed = self
+            .installed_refs()
+            .await?
+            .into_iter()
+            .find(|r| r.display_ref() == name)
+            .ok_or_else(|| AppError::InvalidInput(format!("Flatpak not installed: {}", name)))?;
+
+        let result = if installed.installation == "user" {
+            self.run_user(&["uninstall", "--user", "-y", "--noninteractive", name]).await
+        } else {
+            self.executor.execute(&[PrivilegedOp::package("flatpak", &["uninstall", "--system", "-y", "--noninteractive", name])]).await
+        };
+
+        Ok(PackageAction {
+            name: name.to_string(),
+            action: "uninstall".to_string(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e.to_string()),
+        })
+    }
+
+    async fn clean_unused(&self) -> Result<CleanupResult> {
+        let runtimes = |refs: &[InstalledRef]| -> (u32, u64) {
+            refs.iter()
+                .filter(|r| r.runtime)
+                .fold((0, 0), |(count, size), r| (count + 1, size + r.size_bytes))
+        };
+        let before = self.installed_refs().await?;
+
+        let mut errors = Vec::new();
+        if before.iter().any(|r| r.runtime && r.installation == "user") {
+            if let Err(e) = self.run_user(&["uninstall", "--unused", "--user", "-y", "--noninteractive"]).await {
+                errors.push(e.to_string());
+            }
+        }
+        if before.iter().any(|r| r.runtime && r.installation != "user") {
+            let op = PrivilegedOp::package("flatpak", &["uninstall", "--unused", "--system", "-y", "--noninteractive"]);
+            match self.executor.execute(&[op]).await {
+                Err(AppError::UserCancelled) => return Err(AppError::UserCancelled),
+                Err(e) => errors.push(e.to_string()),
+                Ok(_) => {}
+            }
+        }
+
+        let (count_before, size_before) = runtimes(&before);
+        let (count_after, size_after) = runtimes(&self.installed_refs().await?);
+        let items_removed = count_before.saturating_sub(count_after);
+
+        Ok(CleanupResult {
+            category: "flatpak_unused".to_string(),
+            items_removed,
+            bytes_freed: size_before.saturating_sub(size_after),
+            success: errors.is_empty(),
+            message: if !errors.is_empty() {
+                errors.join("; ")
+            } else if items_removed == 0 {
+                "No unused Flatpak runtimes".to_string()
+            } else {
+                format!("Removed {} unused Flatpak runtimes", items_removed)
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list() {
+        let output = "org.mozilla.firefox\tFirefox\t131.0\tstable\t293.1\u{a0}MB\tsystem\n\
+                      org.freedesktop.Platform\tFreedesktop Platform\t23.08.22\t23.08\t1.2\u{a0}GB\tuser\n\
+                      \n";
+        let refs = parse_list(output, true);
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].size_bytes, (293.1 * 1024.0 * 1024.0) as u64);
+        assert_eq!(refs[1].installation, "user");
+        assert_eq!(refs[1].display_ref(), "org.freedesktop.Platform//23.08");
+        assert_eq!(parse_list(output, false)[0].display_ref(), "org.mozilla.firefox");
+    }
+}
@@ -2,13 +2,19 @@
 //! Provides a unified interface for different Linux package managers
 
 pub mod package_manager;
+pub mod dependency_graph;
 pub mod debian;
 pub mod arch;
 pub mod fedora;
 pub mod suse;
+pub mod flatpak;
+pub mod snap;
 
 pub use package_manager::*;
+pub use dependency_graph::{DependencyGraph, PackageAnalysis, PackageUsage, Providers, rpm_dependencies};
 pub use debian::DebianAdapter;
 pub use arch::ArchAdapter;
 pub use fedora::FedoraAdapter;
 pub use suse::SuseAdapter;
+pub use flatpak::FlatpakAdapter;
+pub use snap::SnapAdapter;
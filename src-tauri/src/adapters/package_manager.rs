@@ -1,9 +1,11 @@
 //! PackageManager trait definition
 //! Abstract interface for package management operations across distros
 
-use crate::error::Result;
+use super::DependencyGraph;
+use crate::error::{AppError, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // ============================================================================
 // Mock Mode Helpers
@@ -24,6 +26,7 @@ pub fn generate_mock_packages(pm_name: &str) -> Vec<PackageInfo> {
             description: "Base system package (mock)".to_string(),
             is_auto: false,
             category: "System".to_string(),
+            origin: PackageOrigin::Native,
         },
         PackageInfo {
             name: format!("{}-test", pm_name),
@@ -32,6 +35,7 @@ pub fn generate_mock_packages(pm_name: &str) -> Vec<PackageInfo> {
             description: "Test package for simulation".to_string(),
             is_auto: true,
             category: "System".to_string(),
+            origin: PackageOrigin::Native,
         },
         PackageInfo {
             name: "gnome-shell".to_string(),
@@ -40,6 +44,7 @@ pub fn generate_mock_packages(pm_name: &str) -> Vec<PackageInfo> {
             description: "GNOME desktop shell".to_string(),
             is_auto: false,
             category: "GNOME".to_string(),
+            origin: PackageOrigin::Native,
         },
         PackageInfo {
             name: "firefox".to_string(),
@@ -48,6 +53,7 @@ pub fn generate_mock_packages(pm_name: &str) -> Vec<PackageInfo> {
             description: "Mozilla Firefox web browser".to_string(),
             is_auto: false,
             category: "Internet".to_string(),
+            origin: PackageOrigin::Native,
         },
         PackageInfo {
             name: "vim".to_string(),
@@ -56,6 +62,7 @@ pub fn generate_mock_packages(pm_name: &str) -> Vec<PackageInfo> {
             description: "Vi IMproved text editor".to_string(),
             is_auto: false,
             category: "Development".to_string(),
+            origin: PackageOrigin::Native,
         },
     ]
 }
@@ -72,6 +79,32 @@ pub struct PackageInfo {
     pub description: String,
     pub is_auto: bool,
     pub category: String,
+    #[serde(default)]
+    pub origin: PackageOrigin,
+}
+
+/// Where an installed package comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageOrigin {
+    /// The distro package manager (apt, pacman, dnf, zypper)
+    #[default]
+    Native,
+    Flatpak,
+    Snap,
+}
+
+impl std::str::FromStr for PackageOrigin {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "native" => Ok(Self::Native),
+            "flatpak" => Ok(Self::Flatpak),
+            "snap" => Ok(Self::Snap),
+            other => Err(AppError::InvalidInput(format!("Unknown package origin: {}", other))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +115,176 @@ pub struct PackageAction {
     pub message: String,
 }
 
+/// A remote search hit: repository metadata plus whether it is installed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub package: PackageInfo,
+    pub installed: bool,
+    /// Repository providing the package, when the package manager reports it
+    pub repository: Option<String>,
+}
+
+/// A package with a newer version in the repositories
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpgradablePackage {
+    pub name: String,
+    pub current_version: String,
+    pub new_version: String,
+    /// Repository offering the new version, when the package manager reports it
+    pub repository: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageDetails {
+    pub name: String,
+    /// Installed version, or the candidate version if not installed
+    pub version: String,
+    pub description: String,
+    pub installed: bool,
+    pub size_bytes: u64,
+    /// Repository of origin ("local" for packages no repository provides)
+    pub repository: Option<String>,
+    pub dependencies: Vec<String>,
+    /// Installed packages that depend on this one
+    pub reverse_dependencies: Vec<String>,
+    /// Installed files (empty if not installed)
+    pub files: Vec<String>,
+}
+
+/// How a transaction changed one package
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Install,
+    Upgrade,
+    Downgrade,
+    Reinstall,
+    Remove,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Install => "install",
+            ChangeKind::Upgrade => "upgrade",
+            ChangeKind::Downgrade => "downgrade",
+            ChangeKind::Reinstall => "reinstall",
+            ChangeKind::Remove => "remove",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageChange {
+    pub name: String,
+    pub kind: ChangeKind,
+    /// Version before the transaction (None for installs)
+    pub old_version: Option<String>,
+    /// Version after the transaction (None for removals)
+    pub new_version: Option<String>,
+}
+
+/// One past package manager run, as its log recorded it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageTransaction {
+    /// dnf history id; the start time for backends without transaction ids
+    pub id: String,
+    /// Start time as the log prints it
+    pub timestamp: String,
+    /// The kind shared by all changes, or "mixed"
+    pub action: String,
+    /// Command line that started the transaction, when logged
+    pub command: Option<String>,
+    pub changes: Vec<PackageChange>,
+}
+
+impl PackageTransaction {
+    pub fn new(id: String, timestamp: String, command: Option<String>, changes: Vec<PackageChange>) -> Self {
+        let action = match changes.first() {
+            Some(first) if changes.iter().all(|c| c.kind == first.kind) => first.kind.as_str().to_string(),
+            _ => "mixed".to_string(),
+        };
+        Self { id, timestamp, action, command, changes }
+    }
+
+    /// Packages the transaction installed
+    pub fn installed(&self) -> Vec<&str> {
+        self.names_of(&[ChangeKind::Install])
+    }
+
+    /// Packages the transaction removed, with the version they had
+    pub fn removed(&self) -> Vec<(&str, &str)> {
+        self.previous_versions(&[ChangeKind::Remove])
+    }
+
+    /// Packages the transaction upgraded or downgraded, with the version they had before
+    pub fn version_changes(&self) -> Vec<(&str, &str)> {
+        self.previous_versions(&[ChangeKind::Upgrade, ChangeKind::Downgrade])
+    }
+
+    fn names_of(&self, kinds: &[ChangeKind]) -> Vec<&str> {
+        self.changes.iter().filter(|c| kinds.contains(&c.kind)).map(|c| c.name.as_str()).collect()
+    }
+
+    fn previous_versions(&self, kinds: &[ChangeKind]) -> Vec<(&str, &str)> {
+        self.changes
+            .iter()
+            .filter(|c| kinds.contains(&c.kind))
+            .filter_map(|c| Some((c.name.as_str(), c.old_version.as_deref()?)))
+            .collect()
+    }
+}
+
+/// A package a removal would take out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemovedPackage {
+    pub name: String,
+    pub version: String,
+    pub size_bytes: u64,
+}
+
+/// Dry run of an uninstall, purge or autoremove: the requested packages plus every
+/// dependent and unneeded dependency the package manager would remove with them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemovalPreview {
+    /// "uninstall", "purge" or "autoremove"
+    pub action: String,
+    pub packages: Vec<RemovedPackage>,
+    pub bytes_freed: u64,
+}
+
+impl RemovalPreview {
+    /// Preview of removing `names`, with versions and sizes taken from `installed`
+    pub fn new(action: &str, names: &[String], installed: &[PackageInfo]) -> Self {
+        let by_name: HashMap<&str, &PackageInfo> = installed.iter().map(|p| (p.name.as_str(), p)).collect();
+        let packages: Vec<RemovedPackage> = names
+            .iter()
+            .map(|name| {
+                // Foreign-architecture packages are listed as `name:arch` by some backends
+                let info = by_name
+                    .get(name.as_str())
+                    .or_else(|| by_name.get(name.split(':').next().unwrap_or(name)));
+                RemovedPackage {
+                    name: name.clone(),
+                    version: info.map(|p| p.version.clone()).unwrap_or_default(),
+                    size_bytes: info.map_or(0, |p| p.size_bytes),
+                }
+            })
+            .collect();
+
+        Self {
+            action: action.to_string(),
+            bytes_freed: packages.iter().map(|p| p.size_bytes).sum(),
+            packages,
+        }
+    }
+
+    pub fn empty(action: &str) -> Self {
+        Self::new(action, &[], &[])
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CleanupResult {
     pub category: String,
@@ -109,6 +312,48 @@ pub trait PackageManager: Send + Sync {
     /// Search packages by name
     async fn search_packages(&self, query: &str) -> Result<Vec<PackageInfo>>;
     
+    /// Search the repositories by name and description, best matches first
+    /// (at most `MAX_SEARCH_RESULTS`)
+    async fn search_remote(&self, query: &str) -> Result<Vec<SearchResult>>;
+    
+    /// Install packages in one transaction (already installed ones are kept)
+    async fn install_packages(&self, names: &[&str]) -> Result<String>;
+    
+    /// Install a single package
+    async fn install_package(&self, name: &str) -> Result<PackageAction> {
+        let result = self.install_packages(&[name]).await;
+        
+        Ok(PackageAction {
+            name: name.to_string(),
+            action: "install".to_string(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e.to_string()),
+        })
+    }
+    
+    /// Installed packages with a newer version in the (already refreshed) repositories
+    async fn list_upgradable(&self) -> Result<Vec<UpgradablePackage>>;
+    
+    /// Upgrade the given installed packages
+    async fn upgrade_packages(&self, names: &[&str]) -> Result<PackageAction>;
+    
+    /// Upgrade the whole system
+    async fn upgrade_all(&self) -> Result<PackageAction>;
+    
+    /// Version, origin, dependencies, reverse dependencies and files of a package
+    async fn package_details(&self, name: &str) -> Result<PackageDetails>;
+    
+    /// Make 32-bit packages installable (Debian i386, Arch [multilib], RPM Fusion on Fedora)
+    async fn enable_multilib(&self) -> Result<String>;
+    
+    /// Past transactions from the package manager's log, newest first (at most `MAX_HISTORY`)
+    async fn transaction_history(&self) -> Result<Vec<PackageTransaction>>;
+    
+    /// Reverse a transaction: remove what it installed, reinstall what it removed and,
+    /// where the backend can, restore the versions it replaced
+    async fn undo_transaction(&self, id: &str) -> Result<PackageAction>;
+    
+
     /// Uninstall a package
     async fn uninstall_package(&self, name: &str) -> Result<PackageAction>;
     
@@ -118,6 +363,18 @@ pub trait PackageManager: Send + Sync {
     /// Remove unused dependencies
     async fn autoremove(&self) -> Result<PackageAction>;
     
+    /// Dry run of `uninstall_package`: everything it would remove, without changing the system
+    async fn simulate_uninstall(&self, name: &str) -> Result<RemovalPreview>;
+    
+    /// Dry run of `purge_package`
+    async fn simulate_purge(&self, name: &str) -> Result<RemovalPreview>;
+    
+    /// Dry run of `autoremove`
+    async fn simulate_autoremove(&self) -> Result<RemovalPreview>;
+    
+    /// Installed packages linked to the installed packages they need
+    async fn dependency_graph(&self) -> Result<DependencyGraph>;
+    
     /// Clean package cache
     async fn clean_cache(&self) -> Result<CleanupResult>;
     
@@ -130,12 +387,223 @@ pub trait PackageManager: Send + Sync {
     /// Get log directory path
     fn log_path(&self) -> &'static str;
     
+    /// Package database files; the installed package list is reused until one of them changes
+    fn database_paths(&self) -> &'static [&'static str];
+    
     /// Check if fast download tool is available (apt-fast, aria2c for pacman, etc.)
     async fn check_fast_download(&self) -> Result<bool> {
         Ok(false) // Default: not available
     }
 }
 
+// ============================================================================
+// PackageSource Trait
+// ============================================================================
+
+/// A package format installed next to the distro's own packages (Flatpak, Snap)
+#[async_trait]
+pub trait PackageSource: Send + Sync {
+    /// Origin tag of the packages this source lists
+    fn origin(&self) -> PackageOrigin;
+    
+    /// Whether the tool is installed on this system
+    async fn is_available(&self) -> bool;
+    
+    /// Get all installed packages, tagged with `origin()`
+    async fn get_installed_packages(&self) -> Result<Vec<PackageInfo>>;
+    
+    /// Uninstall a package by the name `get_installed_packages` listed
+    async fn uninstall_package(&self, name: &str) -> Result<PackageAction>;
+    
+    /// Remove what no installed package needs any more (unused runtimes, disabled revisions)
+    async fn clean_unused(&self) -> Result<CleanupResult>;
+}
+
+/// Whether `program` is on the PATH
+pub async fn command_exists(program: &str) -> bool {
+    tokio::process::Command::new("which")
+        .arg(program)
+        .output()
+        .await
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+// ============================================================================
+// Output Parsing (Shared logic)
+// ============================================================================
+
+/// Parse the first `Key : value` record of `pacman -Qi`, `dnf info`, `zypper info` or
+/// `apt-cache show` output; indented continuation lines are appended after a newline
+pub fn parse_fields(output: &str) -> HashMap<String, String> {
+    let mut fields: HashMap<String, String> = HashMap::new();
+    let mut current: Option<String> = None;
+    
+    for line in output.lines() {
+        if line.trim().is_empty() {
+            if fields.is_empty() {
+                continue;
+            }
+            break;
+        }
+        
+        if line.starts_with(char::is_whitespace) {
+            // dnf repeats the separator on continuation lines
+            let value = line.trim().trim_start_matches(':').trim();
+            if let Some(field) = current.as_ref().and_then(|key| fields.get_mut(key)) {
+                field.push('\n');
+                field.push_str(value);
+            }
+            continue;
+        }
+        
+        if let Some((key, value)) = line.split_once(':') {
+            let key = key.trim().to_string();
+            // Keep the first occurrence (dnf lists installed before available)
+            if !fields.contains_key(&key) {
+                fields.insert(key.clone(), value.trim().to_string());
+                current = Some(key);
+            } else {
+                current = None;
+            }
+        }
+    }
+    
+    fields
+}
+
+/// Parse size string like "12.5 MiB" (pacman, zypper) or "1.7 M" (dnf) to bytes
+pub fn parse_size(size_str: &str) -> u64 {
+    let parts: Vec<&str> = size_str.split_whitespace().collect();
+    if parts.len() < 2 {
+        return 0;
+    }
+    
+    let num: f64 = parts[0].parse().unwrap_or(0.0);
+    let unit = parts[1].to_lowercase();
+    
+    match unit.as_str() {
+        "b" | "bytes" => num as u64,
+        "k" | "kib" | "kb" => (num * 1024.0) as u64,
+        "m" | "mib" | "mb" => (num * 1024.0 * 1024.0) as u64,
+        "g" | "gib" | "gb" => (num * 1024.0 * 1024.0 * 1024.0) as u64,
+        _ => 0,
+    }
+}
+
+/// Stdout of a read-only query command; the exit status is ignored, callers check what they parsed
+pub async fn query_output(program: &str, args: &[&str]) -> Result<String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| AppError::CommandFailed(format!("Failed to run {}: {}", program, e)))?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Stdout of a dry-run command, or its stderr as the error when the package manager refuses
+/// (unknown package, broken dependencies)
+pub async fn dry_run_output(program: &str, args: &[&str]) -> Result<String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| AppError::CommandFailed(format!("Failed to run {}: {}", program, e)))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(AppError::from_command(format!("{} {}", program, args.join(" ")), output.status.code(), &stderr))
+    }
+}
+
+/// Error for a package no repository or database knows
+pub fn package_not_found(name: &str) -> AppError {
+    AppError::InvalidInput(format!("Package not found: {}", name))
+}
+
+/// Error for a transaction with nothing the backend can reverse
+pub fn nothing_to_undo(id: &str) -> AppError {
+    AppError::InvalidInput(format!("Nothing in transaction {} can be undone", id))
+}
+
+/// Cap on listed transactions; logs of old installs hold thousands
+pub const MAX_HISTORY: usize = 200;
+
+/// Oldest-first transactions to the newest `MAX_HISTORY`, newest first
+pub fn newest_first(mut transactions: Vec<PackageTransaction>) -> Vec<PackageTransaction> {
+    transactions.reverse();
+    transactions.truncate(MAX_HISTORY);
+    transactions
+}
+
+/// The transaction with this id, or an error naming it
+pub fn find_transaction(transactions: Vec<PackageTransaction>, id: &str) -> Result<PackageTransaction> {
+    transactions
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| AppError::InvalidInput(format!("Transaction not found: {}", id)))
+}
+
+/// Result of undoing `transaction`, noting changes the backend could not reverse
+pub fn undo_action(transaction: &PackageTransaction, result: Result<String>, skipped: &[String]) -> PackageAction {
+    let mut message = match &result {
+        Ok(output) => output.clone(),
+        Err(e) => e.to_string(),
+    };
+    if !skipped.is_empty() {
+        message = format!("{}\nNot undone: {}", message.trim_end(), skipped.join(", "));
+    }
+    PackageAction {
+        name: transaction.id.clone(),
+        action: "undo".to_string(),
+        success: result.is_ok(),
+        message,
+    }
+}
+
+/// Cap on remote search results; broad queries like "lib" match thousands of packages
+pub const MAX_SEARCH_RESULTS: usize = 200;
+
+/// Order search hits: exact name, then name prefix, then name substring, then description-only
+pub fn sort_by_relevance<T>(items: &mut [T], query: &str, name: impl Fn(&T) -> &str) {
+    let query = query.to_lowercase();
+    items.sort_by_cached_key(|item| {
+        let name = name(item).to_lowercase();
+        let rank = if name == query {
+            0
+        } else if name.starts_with(&query) {
+            1
+        } else if name.contains(&query) {
+            2
+        } else {
+            3
+        };
+        (rank, name)
+    });
+}
+
+/// Escape a validated package-name query for the POSIX regexes of `apt-cache search` and `pacman -Ss`
+pub fn escape_regex(query: &str) -> String {
+    query.chars().fold(String::new(), |mut escaped, c| {
+        if matches!(c, '.' | '+') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        escaped
+    })
+}
+
+/// Package name of a dependency, without version constraint or architecture qualifier
+pub fn dependency_name(dep: &str) -> String {
+    dep.trim()
+        .split(|c: char| c.is_whitespace() || matches!(c, '(' | '<' | '>' | '='))
+        .next()
+        .unwrap_or("")
+        .to_string()
+}
+
 // ============================================================================
 // Category Detection (Shared logic)
 // ============================================================================
@@ -230,3 +698,55 @@ pub fn detect_package_category(name: &str, description: &str) -> String {
         "System".to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fields() {
+        let pacman = "Name            : bash\nVersion         : 5.2.026-2\nDepends On      : readline>=7.0  glibc\n                  ncurses\nRequired By     : None\n\nName            : other\n";
+        let fields = parse_fields(pacman);
+        assert_eq!(fields["Name"], "bash");
+        assert_eq!(fields["Depends On"], "readline>=7.0  glibc\nncurses");
+        assert_eq!(fields["Required By"], "None");
+
+        let dnf = "Name         : bash\nSummary      : The GNU Bourne Again shell\nDescription  : The GNU Bourne Again shell (Bash) is a shell\n             : compatible with sh.\n";
+        let fields = parse_fields(dnf);
+        assert_eq!(fields["Description"], "The GNU Bourne Again shell (Bash) is a shell\ncompatible with sh.");
+
+        assert_eq!(dependency_name("libc6 (>= 2.34)"), "libc6");
+        assert_eq!(dependency_name("readline>=7.0"), "readline");
+    }
+
+    #[test]
+    fn test_sort_by_relevance() {
+        let mut names = vec!["libfirefox", "firefox-esr", "iceweasel", "firefox"];
+        sort_by_relevance(&mut names, "Firefox", |n| n);
+        assert_eq!(names, vec!["firefox", "firefox-esr", "libfirefox", "iceweasel"]);
+        assert_eq!(escape_regex("c++.x"), "c\\+\\+\\.x");
+    }
+
+    #[test]
+    fn test_transaction_summary() {
+        let change = |name: &str, kind, old: Option<&str>| PackageChange {
+            name: name.to_string(),
+            kind,
+            old_version: old.map(String::from),
+            new_version: None,
+        };
+        let only_installs = PackageTransaction::new("1".into(), String::new(), None, vec![change("vim", ChangeKind::Install, None)]);
+        assert_eq!(only_installs.action, "install");
+
+        let mixed = PackageTransaction::new(
+            "2".into(),
+            String::new(),
+            None,
+            vec![change("vim", ChangeKind::Install, None), change("nano", ChangeKind::Remove, Some("7.2")), change("bash", ChangeKind::Upgrade, Some("5.1"))],
+        );
+        assert_eq!(mixed.action, "mixed");
+        assert_eq!(mixed.installed(), vec!["vim"]);
+        assert_eq!(mixed.removed(), vec![("nano", "7.2")]);
+        assert_eq!(mixed.version_changes(), vec![("bash", "5.1")]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Snap Adapter
+//! Lists installed snaps and removes them (and superseded revisions) through snapd
+
+use super::{PackageInfo, PackageOrigin, PackageAction, CleanupResult, PackageSource, detect_package_category};
+use super::{command_exists, is_mock_mode, query_output};
+use crate::error::{AppError, Result};
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::path::Path;
+
+/// Mounted snap images, `<name>_<revision>.snap`
+const SNAPS_DIR: &str = "/var/lib/snapd/snaps";
+
+pub struct SnapAdapter {
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+/// One row of `snap list --all`
+#[derive(Debug, Clone, PartialEq)]
+struct InstalledSnap {
+    name: String,
+    version: String,
+    revision: String,
+    publisher: String,
+    /// Comma-separated notes, e.g. "base", "disabled,classic" or "-"
+    notes: String,
+}
+
+impl InstalledSnap {
+    fn has_note(&self, note: &str) -> bool {
+        self.notes.split(',').any(|n| n == note)
+    }
+
+    fn size_bytes(&self) -> u64 {
+        Path::new(SNAPS_DIR)
+            .join(format!("{}_{}.snap", self.name, self.revision))
+            .metadata()
+            .map(|m| m.len())
+            .unwrap_or(0)
+    }
+}
+
+/// Parse `snap list --all`:
+/// `Name  Version  Rev  Tracking  Publisher  Notes`
+fn parse_list(output: &str) -> Vec<InstalledSnap> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            if columns.len() < 6 {
+                return None;
+            }
+            Some(InstalledSnap {
+                name: columns[0].to_string(),
+                version: columns[1].to_string(),
+                revision: columns[2].to_string(),
+                // Verified publishers carry a check mark or "**"
+                publisher: columns[4].trim_end_matches(['✓', '✪', '*']).to_string(),
+                notes: columns[5].to_string(),
+            })
+        })
+        .collect()
+}
+
+impl SnapAdapter {
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { executor }
+    }
+
+    /// Every installed revision, including disabled (superseded) ones
+    async fn installed_revisions(&self) -> Result<Vec<InstalledSnap>> {
+        let output = query_output("snap", &["list", "--all"]).await?;
+        Ok(parse_list(&output))
+    }
+}
+
+#[async_trait]
+impl PackageSource for SnapAdapter {
+    fn origin(&self) -> PackageOrigin {
+        PackageOrigin::Snap
+    }
+
+    async fn is_available(&self) -> bool {
+        !is_mock_mode() && command_exists("snap").await
+    }
+
+    async fn get_installed_packages(&self) -> Result<Vec<PackageInfo>> {
+        let packages = self
+            .installed_revisions()
+            .await?
+            .into_iter()
+            .filter(|s| !s.has_note("disabled"))
+            .map(|s| {
+                // Bases and snapd itself are only there for other snaps
+                let is_auto = s.has_note("base") || s.has_note("snapd");
+                PackageInfo {
+                    size_bytes: s.size_bytes(),
+                    category: if is_auto { "System".to_string() } else { detect_package_category(&s.name, "") },
+                    description: format!("Published by {}", s.publisher),
+                    name: s.name,
+                    version: s.version,
+                    is_auto,
+                    origin: PackageOrigin::Snap,
+                }
+            })
+            .collect();
+
+        Ok(packages)
+    }
+
+    async fn uninstall_package(&self, name: &str) -> Result<PackageAction> {
+        let result = self.executor.execute(&[PrivilegedOp::package("snap", &["remove", name])]).await;
+
+        Ok(PackageAction {
+            name: name.to_string(),
+            action: "uninstall".to_string(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e.to_string()),
+        })
+    }
+
+    async fn clean_unused(&self) -> Result<CleanupResult> {
+        let disabled: Vec<InstalledSnap> = self
+            .installed_revisions()
+            .await?
+            .into_iter()
+            .filter(|s| s.has_note("disabled"))
+            .collect();
+
+        if disabled.is_empty() {
+            return Ok(CleanupResult {
+                category: "snap_revisions".to_string(),
+                items_removed: 0,
+                bytes_freed: 0,
+                success: true,
+                message: "No old snap revisions".to_string(),
+            });
+        }
+
+        let bytes: u64 = disabled.iter().map(InstalledSnap::size_bytes).sum();
+        let removals: Vec<PrivilegedOp> = disabled
+            .iter()
+            .map(|s| PrivilegedOp::package("snap", &["remove", &s.name, &format!("--revision={}", s.revision)]))
+            .collect();
+
+        match self.executor.execute(&removals).await {
+            Ok(_) => Ok(CleanupResult {
+                category: "snap_revisions".to_string(),
+                items_removed: disabled.len() as u32,
+                bytes_freed: bytes,
+                success: true,
+                message: format!("Removed {} old snap revisions", disabled.len()),
+            }),
+            Err(AppError::UserCancelled) => Err(AppError::UserCancelled),
+            Err(e) => Ok(CleanupResult {
+                category: "snap_revisions".to_string(),
+                items_removed: 0,
+                bytes_freed: 0,
+                success: false,
+                message: e.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list() {
+        let output = "Name      Version        Rev    Tracking         Publisher   Notes\n\
+                      core22    20240111       1122   latest/stable    canonical✓  base\n\
+                      firefox   131.0-1        4955   latest/stable/…  mozilla✓    disabled\n\
+                      firefox   131.0.2-1      5014   latest/stable/…  mozilla**   -\n";
+        let snaps = parse_list(output);
+        assert_eq!(snaps.len(), 3);
+        assert!(snaps[0].has_note("base"));
+        assert!(snaps[1].has_note("disabled"));
+        assert_eq!(snaps[2].revision, "5014");
+        assert_eq!(snaps[2].publisher, "mozilla");
+    }
+}
@@ -1,24 +1,191 @@
 //! OpenSUSE Package Manager Adapter
 //! Uses zypper/rpm for package management
 
-use super::{PackageInfo, PackageAction, CleanupResult, PackageManager, detect_package_category};
+use super::{PackageInfo, PackageOrigin, PackageAction, PackageDetails, SearchResult, UpgradablePackage, CleanupResult, PackageManager, detect_package_category};
+use super::{ChangeKind, PackageChange, PackageTransaction, find_transaction, newest_first, nothing_to_undo, undo_action};
+use super::RemovalPreview;
+use super::{DependencyGraph, rpm_dependencies};
+use super::{dependency_name, package_not_found, parse_fields, parse_size, query_output, sort_by_relevance, MAX_SEARCH_RESULTS};
+use std::collections::HashMap;
 use crate::error::{AppError, Result};
-use crate::utils::privileged;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
 use async_trait::async_trait;
+use std::sync::Arc;
 use tokio::process::Command;
 
-pub struct SuseAdapter;
+/// libzypp's transaction log (zypper, YaST and PackageKit all write to it)
+const ZYPP_HISTORY: &str = "/var/log/zypp/history";
+
+pub struct SuseAdapter {
+    executor: Arc<dyn PrivilegedExecutor>,
+}
 
 impl SuseAdapter {
-    pub fn new() -> Self {
-        Self
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { executor }
+    }
+    
+    /// Preview of removing `names`, with versions and sizes from rpm
+    async fn preview(&self, action: &str, names: Vec<String>) -> Result<RemovalPreview> {
+        if names.is_empty() {
+            return Ok(RemovalPreview::empty(action));
+        }
+        let installed = self.get_installed_packages().await?;
+        Ok(RemovalPreview::new(action, &names, &installed))
     }
 }
 
-impl Default for SuseAdapter {
-    fn default() -> Self {
-        Self::new()
+/// Names listed under "The following N packages are going to be REMOVED:" by `zypper remove --dry-run`
+fn parse_dry_run_removals(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .skip_while(|line| !line.contains("going to be REMOVED:"))
+        .skip(1)
+        .take_while(|line| !line.trim().is_empty())
+        .flat_map(str::split_whitespace)
+        .map(String::from)
+        .collect()
+}
+
+/// Installed packages of the `zypper packages --unneeded` table (`i | @System | name | version | arch`)
+fn parse_unneeded(output: &str) -> Vec<String> {
+    table_rows(output)
+        .into_iter()
+        .filter(|cols| cols.len() >= 3 && cols[0].starts_with('i'))
+        .map(|cols| cols[2].to_string())
+        .collect()
+}
+
+/// Parse /var/log/zypp/history into transactions, oldest first; each starts at a `command`
+/// line and is identified by its time:
+/// `2024-01-10 12:00:02|install|vim|9.0.2-1.1|x86_64|root@host|repo-oss|<checksum>|`
+fn parse_history(log: &str) -> Vec<PackageTransaction> {
+    let mut transactions = Vec::new();
+    let mut current: Option<(String, Option<String>, Vec<PackageChange>)> = None;
+    // The log has no upgrade records; an install of a known package is one
+    let mut versions: HashMap<String, String> = HashMap::new();
+    
+    let mut finish = |current: Option<(String, Option<String>, Vec<PackageChange>)>| {
+        if let Some((start, command, changes)) = current.filter(|(_, _, changes)| !changes.is_empty()) {
+            transactions.push(PackageTransaction::new(start.clone(), start, command, changes));
+        }
+    };
+    
+    for line in log.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        let [timestamp, action, ..] = fields[..] else { continue };
+        
+        if action == "command" {
+            finish(current.take());
+            let command = fields.get(3).map(|c| c.replace('\'', ""));
+            current = Some((timestamp.to_string(), command, Vec::new()));
+            continue;
+        }
+        let (Some(name), Some(version)) = (fields.get(2), fields.get(3)) else { continue };
+        let change = match action {
+            "install" => {
+                let previous = versions.insert(name.to_string(), version.to_string());
+                PackageChange {
+                    name: name.to_string(),
+                    kind: if previous.is_some() { ChangeKind::Upgrade } else { ChangeKind::Install },
+                    old_version: previous,
+                    new_version: Some(version.to_string()),
+                }
+            }
+            "remove" => {
+                versions.remove(*name);
+                PackageChange {
+                    name: name.to_string(),
+                    kind: ChangeKind::Remove,
+                    old_version: Some(version.to_string()),
+                    new_version: None,
+                }
+            }
+            _ => continue,
+        };
+        
+        // Changes logged without a command (e.g. the initial installation) form their own transaction
+        current
+            .get_or_insert_with(|| (timestamp.to_string(), None, Vec::new()))
+            .2
+            .push(change);
     }
+    
+    finish(current);
+    transactions
+}
+
+/// Parse the `zypper list-updates` table:
+/// `v | repo-oss | bash | 5.2.15-1.1 | 5.2.21-1.1 | x86_64`
+fn parse_list_updates(output: &str) -> Vec<UpgradablePackage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split('|').map(str::trim).collect();
+            match cols[..] {
+                ["v", repo, name, current, available, _arch] => Some(UpgradablePackage {
+                    name: name.to_string(),
+                    current_version: current.to_string(),
+                    new_version: available.to_string(),
+                    repository: Some(repo.to_string()),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Rows of a zypper table, split on `|` (header and separator lines dropped)
+fn table_rows(output: &str) -> Vec<Vec<&str>> {
+    output
+        .lines()
+        .filter(|line| line.contains('|') && !line.starts_with("--"))
+        .map(|line| line.split('|').map(str::trim).collect::<Vec<_>>())
+        .filter(|cols| cols.get(1) != Some(&"Name"))
+        .collect()
+}
+
+/// Combine `zypper search` (status, name, summary, type) with `zypper search --details`
+/// (status, name, type, version, arch, repository); "i" / "i+" marks installed
+fn parse_search(summaries: &str, details: &str) -> Vec<SearchResult> {
+    let summaries: HashMap<&str, &str> = table_rows(summaries)
+        .into_iter()
+        .filter_map(|cols| match cols[..] {
+            [_, name, summary, _] => Some((name, summary)),
+            _ => None,
+        })
+        .collect();
+    
+    let mut results: Vec<SearchResult> = Vec::new();
+    for cols in table_rows(details) {
+        let [status, name, _, version, _, repo] = cols[..] else { continue };
+        let installed = status.starts_with('i');
+        
+        if let Some(existing) = results.iter_mut().find(|r| r.package.name == name) {
+            existing.installed |= installed;
+            continue;
+        }
+        
+        let description = summaries.get(name).copied().unwrap_or("");
+        results.push(SearchResult {
+            package: PackageInfo {
+                name: name.to_string(),
+                version: version.to_string(),
+                size_bytes: 0,
+                description: description.to_string(),
+                is_auto: false,
+                category: detect_package_category(name, description),
+                origin: PackageOrigin::Native,
+            },
+            installed,
+            repository: Some(repo.to_string()),
+        });
+    }
+    
+    results
 }
 
 #[async_trait]
@@ -35,8 +202,20 @@ impl PackageManager for SuseAdapter {
         "/var/log/zypper.log"
     }
     
+    fn database_paths(&self) -> &'static [&'static str] {
+        // rpmdb: ndb, or sqlite (whose commits land in the -wal file first) on newer releases
+        &[
+            "/usr/lib/sysimage/rpm",
+            "/usr/lib/sysimage/rpm/Packages.db",
+            "/usr/lib/sysimage/rpm/rpmdb.sqlite",
+            "/usr/lib/sysimage/rpm/rpmdb.sqlite-wal",
+            "/var/lib/rpm",
+            "/var/lib/rpm/Packages.db",
+        ]
+    }
+    
     async fn refresh_repositories(&self) -> Result<String> {
-        privileged::run_privileged("zypper", &["refresh"]).await
+        self.executor.execute(&[PrivilegedOp::refresh_repositories("zypper")]).await
     }
     
     async fn get_installed_packages(&self) -> Result<Vec<PackageInfo>> {
@@ -75,6 +254,7 @@ impl PackageManager for SuseAdapter {
                 description,
                 is_auto: false, // zypper doesn't track this easily
                 category,
+                origin: PackageOrigin::Native,
             });
         }
         
@@ -95,8 +275,152 @@ impl PackageManager for SuseAdapter {
             .collect())
     }
     
+    async fn install_packages(&self, names: &[&str]) -> Result<String> {
+        let mut args = vec!["install", "-y"];
+        args.extend_from_slice(names);
+        self.executor.execute(&[PrivilegedOp::package("zypper", &args)]).await
+    }
+    
+    async fn enable_multilib(&self) -> Result<String> {
+        // 32-bit libraries are -32bit packages in the main repositories
+        self.install_packages(&["glibc-32bit"]).await?;
+        Ok("32-bit glibc installed".to_string())
+    }
+    
+    async fn transaction_history(&self) -> Result<Vec<PackageTransaction>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No transaction history for openSUSE");
+            return Ok(Vec::new());
+        }
+        
+        let log = std::fs::read_to_string(ZYPP_HISTORY)?;
+        Ok(newest_first(parse_history(&log)))
+    }
+    
+    async fn undo_transaction(&self, id: &str) -> Result<PackageAction> {
+        let transaction = find_transaction(self.transaction_history().await?, id)?;
+        
+        let mut ops = Vec::new();
+        let installed = transaction.installed();
+        if !installed.is_empty() {
+            let mut args = vec!["remove", "-y"];
+            args.extend(installed);
+            ops.push(PrivilegedOp::package("zypper", &args));
+        }
+        // Removed packages come back in the newest available version
+        let removed = transaction.removed();
+        if !removed.is_empty() {
+            let mut args = vec!["install", "-y"];
+            args.extend(removed.iter().map(|(name, _)| *name));
+            ops.push(PrivilegedOp::package("zypper", &args));
+        }
+        if ops.is_empty() {
+            return Err(nothing_to_undo(id));
+        }
+        
+        let skipped: Vec<String> = transaction
+            .version_changes()
+            .iter()
+            .map(|(name, version)| format!("{} (was {})", name, version))
+            .collect();
+        Ok(undo_action(&transaction, self.executor.execute(&ops).await, &skipped))
+    }
+    
+    async fn search_remote(&self, query: &str) -> Result<Vec<SearchResult>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No remote search results for openSUSE");
+            return Ok(Vec::new());
+        }
+        
+        // Summaries only come with the short table, versions and repositories with --details
+        let summaries = query_output("zypper", &["--non-interactive", "--quiet", "search", "-t", "package", query]).await?;
+        let details = query_output("zypper", &["--non-interactive", "--quiet", "search", "--details", "-t", "package", query]).await?;
+        
+        let mut results = parse_search(&summaries, &details);
+        sort_by_relevance(&mut results, query, |r| &r.package.name);
+        results.truncate(MAX_SEARCH_RESULTS);
+        Ok(results)
+    }
+    
+    async fn list_upgradable(&self) -> Result<Vec<UpgradablePackage>> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] No upgradable packages for openSUSE");
+            return Ok(Vec::new());
+        }
+        
+        let output = query_output("zypper", &["--non-interactive", "--quiet", "list-updates"]).await?;
+        Ok(parse_list_updates(&output))
+    }
+    
+    async fn upgrade_packages(&self, names: &[&str]) -> Result<PackageAction> {
+        let mut args = vec!["update", "-y"];
+        args.extend_from_slice(names);
+        let result = self.executor.execute(&[PrivilegedOp::package("zypper", &args)]).await;
+        
+        Ok(PackageAction {
+            name: names.join(" "),
+            action: "upgrade".to_string(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e.to_string()),
+        })
+    }
+    
+    async fn upgrade_all(&self) -> Result<PackageAction> {
+        let result = self.executor.execute(&[PrivilegedOp::package("zypper", &["update", "-y"])]).await;
+        
+        Ok(PackageAction {
+            name: "system".to_string(),
+            action: "upgrade".to_string(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e.to_string()),
+        })
+    }
+    
+    async fn package_details(&self, name: &str) -> Result<PackageDetails> {
+        let info = parse_fields(&query_output("zypper", &["--non-interactive", "--quiet", "info", "--requires", name]).await?);
+        if !info.contains_key("Version") {
+            return Err(package_not_found(name));
+        }
+        // "Yes", "Yes (automatically)" or "No"
+        let installed = info.get("Installed").is_some_and(|v| v.starts_with("Yes"));
+        
+        // "Requires : [3]" followed by one capability per line
+        let mut dependencies: Vec<String> = info.get("Requires")
+            .map(|reqs| reqs.lines().skip(1).map(dependency_name).filter(|d| !d.is_empty()).collect())
+            .unwrap_or_default();
+        dependencies.sort();
+        dependencies.dedup();
+        
+        // rpm prints "no package requires x" when there are none
+        let reverse_dependencies = query_output("rpm", &["-q", "--whatrequires", name, "--qf", "%{NAME}\n"])
+            .await?
+            .lines()
+            .filter(|l| !l.is_empty() && !l.contains(' '))
+            .map(|l| l.to_string())
+            .collect();
+        
+        let files = if installed {
+            query_output("rpm", &["-ql", name]).await?.lines().map(|f| f.to_string()).collect()
+        } else {
+            Vec::new()
+        };
+        
+        Ok(PackageDetails {
+            name: name.to_string(),
+            version: info.get("Version").cloned().unwrap_or_default(),
+            description: info.get("Summary").cloned().unwrap_or_default(),
+            installed,
+            size_bytes: info.get("Installed Size").map(|s| parse_size(s)).unwrap_or(0),
+            // "@System" for packages no enabled repository provides
+            repository: info.get("Repository").map(|r| if r == "@System" { "local".to_string() } else { r.clone() }),
+            dependencies,
+            reverse_dependencies,
+            files,
+        })
+    }
+    
     async fn uninstall_package(&self, name: &str) -> Result<PackageAction> {
-        let result = privileged::run_privileged("zypper", &["remove", "-y", name]).await;
+        let result = self.executor.execute(&[PrivilegedOp::package("zypper", &["remove", "-y", name])]).await;
         
         Ok(PackageAction {
             name: name.to_string(),
@@ -113,7 +437,7 @@ impl PackageManager for SuseAdapter {
     
     async fn autoremove(&self) -> Result<PackageAction> {
         // zypper packages --unneeded then remove
-        let result = privileged::run_privileged("zypper", &["remove", "-y", "--clean-deps"]).await;
+        let result = self.executor.execute(&[PrivilegedOp::package("zypper", &["remove", "-y", "--clean-deps"])]).await;
         
         Ok(PackageAction {
             name: "autoremove".to_string(),
@@ -123,8 +447,45 @@ impl PackageManager for SuseAdapter {
         })
     }
     
+    async fn simulate_uninstall(&self, name: &str) -> Result<RemovalPreview> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] Nothing to remove for openSUSE");
+            return Ok(RemovalPreview::empty("uninstall"));
+        }
+        
+        // zypper only resolves removals as root, even for a dry run
+        let output = self.executor.execute(&[PrivilegedOp::package("zypper", &["remove", "-y", "--dry-run", name])]).await?;
+        self.preview("uninstall", parse_dry_run_removals(&output)).await
+    }
+    
+    async fn simulate_purge(&self, name: &str) -> Result<RemovalPreview> {
+        // Same as uninstall, like `purge_package`
+        let mut preview = self.simulate_uninstall(name).await?;
+        preview.action = "purge".to_string();
+        Ok(preview)
+    }
+    
+    async fn simulate_autoremove(&self) -> Result<RemovalPreview> {
+        if super::is_mock_mode() {
+            log::info!("[MOCK] Nothing to remove for openSUSE");
+            return Ok(RemovalPreview::empty("autoremove"));
+        }
+        
+        let output = query_output("zypper", &["--no-refresh", "packages", "--unneeded"]).await?;
+        self.preview("autoremove", parse_unneeded(&output)).await
+    }
+    
+    async fn dependency_graph(&self) -> Result<DependencyGraph> {
+        let packages = self.get_installed_packages().await?;
+        if super::is_mock_mode() {
+            return Ok(DependencyGraph::new(&packages, &HashMap::new()));
+        }
+        
+        Ok(DependencyGraph::new(&packages, &rpm_dependencies().await?))
+    }
+    
     async fn clean_cache(&self) -> Result<CleanupResult> {
-        let result = privileged::run_privileged("zypper", &["clean", "--all"]).await;
+        let result = self.executor.execute(&[PrivilegedOp::clean_package_cache("zypper")]).await;
         
         Ok(CleanupResult {
             category: "zypper_cache".to_string(),
@@ -145,3 +506,49 @@ impl PackageManager for SuseAdapter {
         Ok((total, auto, size))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search() {
+        let summaries = "S  | Name    | Summary                  | Type\n---+---------+--------------------------+--------\ni+ | firefox | Mozilla Firefox Web Browser | package\n";
+        let details = "S  | Name    | Type    | Version   | Arch   | Repository\n---+---------+---------+-----------+--------+-----------\nv  | firefox | package | 126.0-1.1 | x86_64 | repo-oss\ni+ | firefox | package | 125.0-1.1 | x86_64 | (System Packages)\n";
+        let results = parse_search(summaries, details);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].installed);
+        assert_eq!(results[0].package.version, "126.0-1.1");
+        assert_eq!(results[0].repository.as_deref(), Some("repo-oss"));
+        assert_eq!(results[0].package.description, "Mozilla Firefox Web Browser");
+    }
+
+    #[test]
+    fn test_parse_dry_run_removals() {
+        let output = "Reading installed packages...\nResolving package dependencies...\n\nThe following 3 packages are going to be REMOVED:\n  vim vim-data\n  vim-data-common\n\n3 packages to remove.\nAfter the operation, 40.2 MiB will be freed.\n";
+        assert_eq!(parse_dry_run_removals(output), vec!["vim", "vim-data", "vim-data-common"]);
+        
+        let unneeded = "S | Repository | Name    | Version  | Arch\n--+------------+---------+----------+-------\ni | @System    | libfoo1 | 1.0-1.1  | x86_64\n";
+        assert_eq!(parse_unneeded(unneeded), vec!["libfoo1"]);
+    }
+
+    #[test]
+    fn test_parse_history() {
+        let log = "# 2024-01-01 10:00:00 bash-5.2.15-1.1.x86_64.rpm installed ok\n\
+                   2024-01-01 10:00:00|install|bash|5.2.15-1.1|x86_64||repo-oss|abc|\n\
+                   2024-01-10 12:00:00|command|root@host|'zypper' 'update'|\n\
+                   2024-01-10 12:00:02|install|bash|5.2.21-1.1|x86_64|root@host|repo-oss|def|\n\
+                   2024-01-10 12:00:02|install|vim|9.0.2-1.1|x86_64|root@host|repo-oss|ghi|\n\
+                   2024-01-11 08:00:00|command|root@host|'zypper' 'refresh'|\n\
+                   2024-01-11 09:00:00|command|root@host|'zypper' 'remove' 'vim'|\n\
+                   2024-01-11 09:00:01|remove |vim|9.0.2-1.1|x86_64|root@host|\n";
+        let transactions = parse_history(log);
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(transactions[0].action, "install");
+        assert_eq!(transactions[1].id, "2024-01-10 12:00:00");
+        assert_eq!(transactions[1].command.as_deref(), Some("zypper update"));
+        assert_eq!(transactions[1].version_changes(), vec![("bash", "5.2.15-1.1")]);
+        assert_eq!(transactions[1].installed(), vec!["vim"]);
+        assert_eq!(transactions[2].removed(), vec![("vim", "9.0.2-1.1")]);
+    }
+}
@@ -0,0 +1,5 @@
+// Headless Glance CLI - see `glance-cli --help`
+fn main() {
+    let args = std::env::args().skip(1).collect();
+    std::process::exit(app_lib::cli::run(args));
+}
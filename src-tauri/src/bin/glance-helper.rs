@@ -0,0 +1,5 @@
+// Glance root helper - started through pkexec by the desktop app, see `helper.rs`
+fn main() {
+    let args = std::env::args().skip(1).collect();
+    std::process::exit(app_lib::helper::run(args));
+}
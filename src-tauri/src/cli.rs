@@ -0,0 +1,875 @@
+//! Headless command-line interface (glance-cli)
+//! Drives the same module functions as the GUI so Glance can be scripted
+//! over SSH or from provisioning tools; `--json` switches to machine output
+
+use crate::adapters::{PackageOrigin, RemovalPreview};
+use crate::error::AppError;
+use crate::helper;
+use crate::modules::keyrings::KeyIssue;
+use crate::modules::mirrorlist::DEFAULT_MIRROR_COUNT;
+use crate::modules::repositories::MirrorInfo;
+use crate::modules::updates::{AutoUpdateConfig, RebootPolicy, UpdateSchedule};
+use crate::modules::{dns, hosts, processes, profile, services, tweaks};
+use crate::state::AppState;
+use crate::utils::privileged;
+use crate::utils::DistroFamily;
+use crate::utils::progress::{Progress, ProgressEvent};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// `println!` that tolerates a closed stdout (e.g. `glance-cli ... | head`)
+macro_rules! out {
+    ($($arg:tt)*) => {{
+        use std::io::Write;
+        let _ = writeln!(std::io::stdout(), $($arg)*);
+    }};
+}
+
+const USAGE: &str = "\
+Usage: glance-cli [--json] <command> [args]
+
+Commands:
+  cleaner list                           List cleanup categories and sizes
+  cleaner preview <category>             Show what cleaning a category would free
+  cleaner clean <category>...            Clean one or more categories
+
+  tweaks list                            Show tweak values and recommendations
+  tweaks apply <id> <value> [--persist|--runtime]
+  tweaks recommended [--persist]         Apply all recommended tweaks
+  tweaks persist <id> | unpersist <id>   Toggle boot persistence for a tweak
+  tweaks history                         List journaled tweak transactions
+  tweaks rollback <transaction-id>       Roll back one transaction
+  tweaks undo                            Roll back the latest transaction
+
+  dns status | providers                 Show current DNS / known providers
+  dns set <provider-id>                  Use a known provider
+  dns custom <primary> [secondary]       Use custom servers
+  dns reset                              Back to automatic (DHCP)
+
+  hosts sources | stats                  Show blocklist sources / statistics
+  hosts apply <source-id>...             Replace blocklists with the given sources
+  hosts clear                            Remove all blocklists
+
+  services list [query]                  List systemd services
+  services start|stop|restart|enable|disable <name>
+
+  packages list | stats                  Installed packages / statistics
+  packages orphans | leaves              Unneeded dependencies / packages nothing depends on
+  packages search <query> [--remote]     Search installed packages (or the repositories)
+  packages info <name>                   Version, origin, dependencies and files
+  packages install|remove|purge <name>
+  packages remove|purge <name> --dry-run Show everything the removal would take out
+  packages remove <name> --origin flatpak|snap
+  packages clean-unused flatpak|snap     Unused Flatpak runtimes / old snap revisions
+  packages upgradable                    Packages with a newer version available
+  packages upgrade [name]...             Upgrade the given packages (all if none)
+  packages autoremove [--dry-run]
+  packages history                       Past install/upgrade/remove transactions
+  packages undo <id>                     Reverse a transaction from the history
+
+  updates status                         Automatic update backend, settings and last run
+  updates enable [--all] [--weekly] [--reboot [HH:MM]]
+                                         Install security (or all) updates automatically
+  updates disable                        Stop automatic updates
+
+  mirrors test [--region CC]             Benchmark mirrors by latency, throughput and sync age
+  mirrors list                           Servers of the pacman mirrorlist (Arch)
+  mirrors generate [--region CC] [--count N]
+                                         Write the fastest mirrors to the mirrorlist
+
+  keys list                              APT keyrings, the repositories they sign and their issues
+  keys remove <keyring>                  Delete a keyring no repository or package uses
+  keys remove-key <fingerprint>          Delete an unused key from /etc/apt/trusted.gpg
+  keys migrate <sources-file> <line>     Move a repository's globally trusted key to signed-by
+
+  processes list [--top N]               Processes sorted by CPU usage
+  processes search <query>
+  processes kill <pid> [--force]
+
+  profile export [file]                  Write the system profile (stdout if no file)
+  profile import <file> [--dry-run]      Apply a profile (or only show the diff)
+
+Options:
+  --json                                 Print results as JSON
+  -h, --help                             Show this help
+  -V, --version                          Show version
+
+Environment:
+  GLANCE_ELEVATION=pkexec|sudo|doas      Privilege tool (default: pkexec in a
+                                         graphical session, sudo otherwise)";
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+enum CliError {
+    Usage(String),
+    App(AppError),
+}
+
+impl From<AppError> for CliError {
+    fn from(err: AppError) -> Self {
+        CliError::App(err)
+    }
+}
+
+type CliResult = std::result::Result<(), CliError>;
+
+fn usage(msg: &str) -> CliError {
+    CliError::Usage(msg.to_string())
+}
+
+/// Remove a boolean flag from the argument list, returning whether it was present
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|a| a != flag);
+    args.len() != before
+}
+
+/// Remove `--name value` from the argument list
+fn take_option(args: &mut Vec<String>, name: &str) -> std::result::Result<Option<String>, CliError> {
+    match args.iter().position(|a| a == name) {
+        Some(i) if i + 1 < args.len() => {
+            let value = args.remove(i + 1);
+            args.remove(i);
+            Ok(Some(value))
+        }
+        Some(_) => Err(usage(&format!("{} needs a value", name))),
+        None => Ok(None),
+    }
+}
+
+/// Get a required positional argument
+fn arg<'a>(args: &'a [String], index: usize, name: &str) -> std::result::Result<&'a str, CliError> {
+    args.get(index)
+        .map(|s| s.as_str())
+        .ok_or_else(|| usage(&format!("missing <{}>", name)))
+}
+
+/// Print a value as JSON or through the human-readable formatter
+fn emit<T: Serialize>(json: bool, value: &T, human: impl FnOnce(&T)) -> CliResult {
+    if json {
+        let text = serde_json::to_string_pretty(value)
+            .map_err(|e| AppError::System(format!("Failed to encode JSON: {}", e)))?;
+        out!("{}", text);
+    } else {
+        human(value);
+    }
+    Ok(())
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Progress of long operations on stderr, so stdout stays clean for pipes; silent with `--json`
+fn progress(json: bool) -> Progress {
+    if json {
+        return Progress::silent();
+    }
+    Progress::new(
+        "cli",
+        Some(Arc::new(|event: ProgressEvent| {
+            use std::io::Write;
+            let text = match (&event.line, event.done) {
+                (Some(line), _) => format!("    {}", line),
+                (None, false) => format!("==> {}", event.step),
+                (None, true) => return,
+            };
+            let _ = writeln!(std::io::stderr(), "{}", text);
+        })),
+    )
+}
+
+/// Turn a `{ success, message }` style action into a CLI outcome
+fn check_action(success: bool, message: &str) -> CliResult {
+    if success {
+        Ok(())
+    } else {
+        Err(CliError::App(AppError::CommandFailed(message.to_string())))
+    }
+}
+
+// ============================================================================
+// Subcommands
+// ============================================================================
+
+async fn run_cleaner(args: &[String], json: bool) -> CliResult {
+    let state = AppState::new();
+    let cleaner = state.cleaner();
+
+    match arg(args, 0, "action")? {
+        "list" => {
+            let categories = cleaner.categories().await?;
+            emit(json, &categories, |cats| {
+                for c in cats {
+                    let root = if c.requires_root { " (root)" } else { "" };
+                    out!("{:<16} {:>10} {:>8}  {}{}", c.id, format_bytes(c.size_bytes), c.file_count, c.name, root);
+                }
+            })
+        }
+        "preview" => {
+            let id = arg(args, 1, "category")?;
+            let result = cleaner.preview(id).await?;
+            emit(json, &result, |r| {
+                out!("{}: would free {} from {} files", r.category, format_bytes(r.bytes_freed), r.files_removed)
+            })
+        }
+        "clean" => {
+            if args.len() < 2 {
+                return Err(usage("missing <category>"));
+            }
+            let progress = progress(json);
+            let mut results = Vec::new();
+            for id in &args[1..] {
+                progress.step(format!("Cleaning {}", id), None);
+                results.push(progress.run(cleaner.clean(id)).await?);
+            }
+            emit(json, &results, |rs| {
+                for r in rs {
+                    out!("{}: {} ({})", r.category, r.message, format_bytes(r.bytes_freed));
+                }
+            })
+        }
+        other => Err(usage(&format!("unknown cleaner action '{}'", other))),
+    }
+}
+
+async fn run_tweaks(args: &[String], json: bool) -> CliResult {
+    let mut args = args.to_vec();
+    let persist = take_flag(&mut args, "--persist");
+    let runtime = take_flag(&mut args, "--runtime");
+    if persist && runtime {
+        return Err(usage("--persist and --runtime are mutually exclusive"));
+    }
+    let persist_choice = if persist { Some(true) } else if runtime { Some(false) } else { None };
+
+    match arg(&args, 0, "action")? {
+        "list" => {
+            let categories = tweaks::get_tweaks().await?;
+            emit(json, &categories, |cats| {
+                for c in cats {
+                    out!("{}", c.name);
+                    for t in &c.tweaks {
+                        let boot = if t.is_persisted { "persisted" } else { "runtime" };
+                        out!(
+                            "  {:<24} {:<14} (recommended {}) [{}]",
+                            t.id, t.current_value, t.recommended_value, boot
+                        );
+                    }
+                }
+            })
+        }
+        "apply" => {
+            let id = arg(&args, 1, "id")?;
+            let value = arg(&args, 2, "value")?;
+            let message = AppState::new().tweaks().apply(id.to_string(), value.to_string(), persist_choice).await?;
+            emit(json, &message, |m| out!("{}", m))
+        }
+        "recommended" => {
+            let results = AppState::new().tweaks().apply_all_recommended(Some(persist)).await?;
+            emit(json, &results, |rs| rs.iter().for_each(|r| out!("{}", r)))
+        }
+        action @ ("persist" | "unpersist") => {
+            let id = arg(&args, 1, "id")?;
+            let message = AppState::new().tweaks().set_persistence(id.to_string(), action == "persist").await?;
+            emit(json, &message, |m| out!("{}", m))
+        }
+        "history" => {
+            let history = tweaks::get_tweak_history().await?;
+            emit(json, &history, |txs| {
+                for t in txs {
+                    let state = if t.rolled_back { "rolled back" } else { "active" };
+                    out!("#{:<5} {:<12} {:<12} {}", t.id, t.timestamp, state, t.description);
+                }
+            })
+        }
+        "rollback" => {
+            let id = arg(&args, 1, "transaction-id")?
+                .parse::<u64>()
+                .map_err(|_| usage("transaction id must be a number"))?;
+            let message = AppState::new().tweaks().rollback(id).await?;
+            emit(json, &message, |m| out!("{}", m))
+        }
+        "undo" => {
+            let message = AppState::new().tweaks().undo_last().await?;
+            emit(json, &message, |m| out!("{}", m))
+        }
+        other => Err(usage(&format!("unknown tweaks action '{}'", other))),
+    }
+}
+
+async fn run_dns(args: &[String], json: bool) -> CliResult {
+    match arg(args, 0, "action")? {
+        "status" => {
+            let status = dns::get_current_dns().await?;
+            emit(json, &status, |s| {
+                out!("Servers:  {}", s.current_dns.join(" "));
+                out!("Provider: {}", s.active_provider.as_deref().unwrap_or("custom / automatic"));
+            })
+        }
+        "providers" => emit(json, &dns::get_dns_providers(), |providers| {
+            for p in providers {
+                out!("{:<22} {:<16} {:<16} {}", p.id, p.primary_dns, p.secondary_dns, p.name);
+            }
+        }),
+        "set" => {
+            let id = arg(args, 1, "provider-id")?;
+            AppState::new().dns().set_provider(id).await?;
+            emit(json, &format!("DNS set to {}", id), |m| out!("{}", m))
+        }
+        "custom" => {
+            let primary = arg(args, 1, "primary")?;
+            let secondary = args.get(2).map(|s| s.as_str()).unwrap_or_default();
+            AppState::new().dns().set_custom(primary, secondary).await?;
+            emit(json, &"Custom DNS set", |m| out!("{}", m))
+        }
+        "reset" => {
+            AppState::new().dns().reset().await?;
+            emit(json, &"DNS reset to automatic", |m| out!("{}", m))
+        }
+        other => Err(usage(&format!("unknown dns action '{}'", other))),
+    }
+}
+
+async fn run_hosts(args: &[String], json: bool) -> CliResult {
+    match arg(args, 0, "action")? {
+        "sources" => {
+            let sources = hosts::get_blocklist_sources().await?;
+            emit(json, &sources, |list| {
+                for s in list {
+                    let mark = if s.is_enabled { "x" } else { " " };
+                    out!("[{}] {:<20} {}", mark, s.id, s.name);
+                }
+            })
+        }
+        "stats" => {
+            let stats = hosts::get_adblock_stats().await?;
+            emit(json, &stats, |s| {
+                out!("Blocked domains: {}", s.total_blocked_domains);
+                out!("Active lists:    {}", s.active_blocklists.join(", "));
+                out!("Hosts size:      {}", format_bytes(s.hosts_file_size));
+            })
+        }
+        "apply" => {
+            if args.len() < 2 {
+                return Err(usage("missing <source-id>"));
+            }
+            let progress = progress(json);
+            let count = progress.run(AppState::new().hosts().install_blocklists(args[1..].to_vec(), &progress)).await?;
+            emit(json, &count, |c| out!("{} domains blocked", c))
+        }
+        "clear" => {
+            AppState::new().hosts().clear_blocklists().await?;
+            emit(json, &"Blocklists cleared", |m| out!("{}", m))
+        }
+        other => Err(usage(&format!("unknown hosts action '{}'", other))),
+    }
+}
+
+async fn run_services(args: &[String], json: bool) -> CliResult {
+    let action = arg(args, 0, "action")?;
+    if action == "list" {
+        let list = match args.get(1) {
+            Some(query) => services::search_services(query.clone()).await?,
+            None => services::get_services().await?,
+        };
+        return emit(json, &list, |svcs| {
+            for s in svcs {
+                let enabled = if s.is_enabled { "enabled" } else { "disabled" };
+                out!("{:<40} {:<10} {:<9} {}", s.name, s.active_state, enabled, s.description);
+            }
+        });
+    }
+
+    let name = arg(args, 1, "name")?.to_string();
+    let services = AppState::new().services();
+    let result = match action {
+        "start" => services.start(name).await?,
+        "stop" => services.stop(name).await?,
+        "restart" => services.restart(name).await?,
+        "enable" => services.enable(name).await?,
+        "disable" => services.disable(name).await?,
+        other => return Err(usage(&format!("unknown services action '{}'", other))),
+    };
+    emit(json, &result, |r| out!("{}: {}", r.name, r.message))?;
+    check_action(result.success, &result.message)
+}
+
+async fn run_packages(args: &[String], json: bool) -> CliResult {
+    let mut args = args.to_vec();
+    let remote = take_flag(&mut args, "--remote");
+    let dry_run = take_flag(&mut args, "--dry-run");
+    let origin = take_option(&mut args, "--origin")?
+        .map(|o| o.parse::<PackageOrigin>())
+        .transpose()?
+        .unwrap_or_default();
+    let pm = AppState::new().packages();
+
+    let print_packages = |list: &Vec<crate::adapters::PackageInfo>| {
+        for p in list {
+            let tag = match p.origin {
+                PackageOrigin::Native => "",
+                PackageOrigin::Flatpak => "[flatpak]",
+                PackageOrigin::Snap => "[snap]",
+            };
+            let line = format!("{:<40} {:<30} {:>10} {}", p.name, p.version, format_bytes(p.size_bytes), tag);
+            out!("{}", line.trim_end());
+        }
+    };
+    let print_preview = |preview: &RemovalPreview| {
+        for p in &preview.packages {
+            out!("{:<40} {:<30} {:>10}", p.name, p.version, format_bytes(p.size_bytes));
+        }
+        out!("{} packages would be removed, freeing {}", preview.packages.len(), format_bytes(preview.bytes_freed));
+    };
+
+    match arg(&args, 0, "action")? {
+        "list" => emit(json, &pm.list().await?, print_packages),
+        "search" if remote => {
+            let query = arg(&args, 1, "query")?;
+            emit(json, &pm.search_remote(query).await?, |list| {
+                for r in list {
+                    let mark = if r.installed { "[installed]" } else { "" };
+                    out!("{:<40} {:<30} {:<20} {}", r.package.name, r.package.version, r.repository.as_deref().unwrap_or(""), mark);
+                }
+            })
+        }
+        "search" => {
+            let query = arg(&args, 1, "query")?;
+            emit(json, &pm.search(query).await?, print_packages)
+        }
+        "stats" => {
+            let (total, auto, size) = pm.stats().await?;
+            let stats = serde_json::json!({ "total": total, "auto_installed": auto, "size_bytes": size });
+            emit(json, &stats, |_| {
+                out!("Package manager: {}", pm.manager_name());
+                out!("Installed:       {} ({} automatic)", total, auto);
+                out!("Total size:      {}", format_bytes(size));
+            })
+        }
+        "info" => {
+            let name = arg(&args, 1, "name")?;
+            emit(json, &pm.details(name).await?, |d| {
+                out!("Name:         {}", d.name);
+                out!("Version:      {}{}", d.version, if d.installed { " (installed)" } else { "" });
+                out!("Repository:   {}", d.repository.as_deref().unwrap_or("-"));
+                out!("Size:         {}", format_bytes(d.size_bytes));
+                out!("Description:  {}", d.description);
+                out!("Depends on:   {}", d.dependencies.join(", "));
+                out!("Required by:  {}", d.reverse_dependencies.join(", "));
+                out!("Files:        {}", d.files.len());
+            })
+        }
+        kind @ ("orphans" | "leaves") => {
+            let analysis = pm.analysis().await?;
+            let (selected, total): (Vec<_>, u64) = if kind == "orphans" {
+                (analysis.packages.into_iter().filter(|p| p.orphan).collect(), analysis.orphan_bytes)
+            } else {
+                let leaves: Vec<_> = analysis.packages.into_iter().filter(|p| p.leaf).collect();
+                let total = leaves.iter().map(|p| p.exclusive_size_bytes).sum();
+                (leaves, total)
+            };
+            emit(json, &selected, |list| {
+                for p in list {
+                    out!("{:<40} {:>10} {:>10} freed with private deps", p.name, format_bytes(p.size_bytes), format_bytes(p.exclusive_size_bytes));
+                }
+                out!("{} {} ({})", list.len(), kind, format_bytes(total));
+            })
+        }
+        "upgradable" => emit(json, &pm.list_upgradable().await?, |list| {
+            for p in list {
+                out!("{:<40} {:<25} -> {:<25} {}", p.name, p.current_version, p.new_version, p.repository.as_deref().unwrap_or(""));
+            }
+        }),
+        "upgrade" => {
+            let progress = progress(json);
+            let names = &args[1..];
+            let result = if names.is_empty() {
+                progress.run(pm.upgrade_all()).await?
+            } else {
+                progress.run(pm.upgrade(names)).await?
+            };
+            emit(json, &result, |r| out!("{}", r.message))?;
+            check_action(result.success, &result.message)
+        }
+        action @ ("remove" | "purge") if dry_run => {
+            if origin != PackageOrigin::Native {
+                return Err(usage("--dry-run only applies to native packages"));
+            }
+            let name = arg(&args, 1, "name")?;
+            let preview = match action {
+                "remove" => pm.simulate_uninstall(name).await?,
+                _ => pm.simulate_purge(name).await?,
+            };
+            emit(json, &preview, print_preview)
+        }
+        "autoremove" if dry_run => emit(json, &pm.simulate_autoremove().await?, print_preview),
+        action @ ("install" | "remove" | "purge") => {
+            let name = arg(&args, 1, "name")?;
+            let result = match action {
+                "install" => progress(json).run(pm.install(name)).await?,
+                "remove" => pm.uninstall(name, origin).await?,
+                _ => pm.purge(name).await?,
+            };
+            emit(json, &result, |r| out!("{}: {}", r.name, r.message))?;
+            check_action(result.success, &result.message)
+        }
+        "clean-unused" => {
+            let origin = arg(&args, 1, "origin")?.parse::<PackageOrigin>()?;
+            let result = progress(json).run(pm.clean_unused(origin)).await?;
+            emit(json, &result, |r| out!("{} ({} freed)", r.message, format_bytes(r.bytes_freed)))?;
+            check_action(result.success, &result.message)
+        }
+        "history" => emit(json, &pm.history().await?, |list| {
+            for t in list {
+                out!("{:<28} {:<10} {:>4} packages  {}", t.id, t.action, t.changes.len(), t.command.as_deref().unwrap_or(""));
+            }
+        }),
+        "undo" => {
+            let id = arg(&args, 1, "id")?;
+            let result = progress(json).run(pm.undo(id)).await?;
+            emit(json, &result, |r| out!("{}", r.message))?;
+            check_action(result.success, &result.message)
+        }
+        "autoremove" => {
+            let result = pm.autoremove().await?;
+            emit(json, &result, |r| out!("{}", r.message))?;
+            check_action(result.success, &result.message)
+        }
+        other => Err(usage(&format!("unknown packages action '{}'", other))),
+    }
+}
+
+async fn run_updates(args: &[String], json: bool) -> CliResult {
+    let mut args = args.to_vec();
+    let all = take_flag(&mut args, "--all");
+    let weekly = take_flag(&mut args, "--weekly");
+    let reboot = args.iter().position(|a| a == "--reboot").map(|i| {
+        args.remove(i);
+        (i < args.len() && !args[i].starts_with('-')).then(|| args.remove(i))
+    });
+    let updates = AppState::new().auto_updates();
+
+    match arg(&args, 0, "action")? {
+        "status" => {}
+        "enable" => {
+            let config = AutoUpdateConfig {
+                enabled: true,
+                security_only: !all,
+                reboot: if reboot.is_some() { RebootPolicy::WhenNeeded } else { RebootPolicy::Never },
+                reboot_time: reboot.flatten(),
+                schedule: if weekly { UpdateSchedule::Weekly } else { UpdateSchedule::Daily },
+            };
+            updates.configure(&config).await?;
+        }
+        "disable" => {
+            let current = updates.status().await?.config;
+            updates.configure(&AutoUpdateConfig { enabled: false, ..current }).await?;
+        }
+        other => return Err(usage(&format!("unknown updates action '{}'", other))),
+    }
+
+    let status = updates.status().await?;
+    emit(json, &status, |s| {
+        let c = &s.config;
+        let mode = if s.notify_only { "notify only" } else if c.security_only { "security updates" } else { "all updates" };
+        out!("Backend:   {}{}", s.backend, if s.installed { "" } else { " (not installed)" });
+        out!("Enabled:   {} ({}, {:?})", if c.enabled { "yes" } else { "no" }, mode, c.schedule);
+        if !s.notify_only {
+            let reboot = match (c.reboot, &c.reboot_time) {
+                (RebootPolicy::Never, _) => "never".to_string(),
+                (RebootPolicy::WhenNeeded, Some(time)) => format!("when needed, at {}", time),
+                (RebootPolicy::WhenNeeded, None) => "when needed".to_string(),
+            };
+            out!("Reboot:    {}", reboot);
+        }
+        out!("Last run:  {}", s.last_run.as_deref().unwrap_or("never"));
+        if let Some(result) = &s.last_result {
+            out!("Result:    {}", result);
+        }
+        if let Some(next) = &s.next_run {
+            out!("Next run:  {}", next);
+        }
+    })
+}
+
+/// Benchmark table, best first
+fn print_mirrors<'a>(mirrors: impl Iterator<Item = &'a MirrorInfo>) {
+    out!("{:<4} {:<28} {:>7} {:>10} {:>7} {:>6}  URI", "#", "NAME", "LATENCY", "SPEED", "BEHIND", "SCORE");
+    for (i, m) in mirrors.enumerate() {
+        let latency = m.latency_ms.map_or("-".to_string(), |ms| format!("{} ms", ms));
+        let speed = m.throughput_kbps.map_or("-".to_string(), |kbps| format!("{}/s", format_bytes(kbps * 1024)));
+        let behind = m.sync_lag_secs.map_or("-".to_string(), |secs| format!("{} h", secs / 3600));
+        let score = m.score.map_or("-".to_string(), |s| s.to_string());
+        let note = match (&m.error, m.outdated) {
+            (Some(error), _) => format!("  ({})", error),
+            (None, true) => "  (outdated)".to_string(),
+            (None, false) => String::new(),
+        };
+        out!("{:<4} {:<28} {:>7} {:>10} {:>7} {:>6}  {}{}", i + 1, m.name, latency, speed, behind, score, m.uri, note);
+    }
+}
+
+async fn run_mirrors(args: &[String], json: bool) -> CliResult {
+    let mut args = args.to_vec();
+    let region = take_option(&mut args, "--region")?.map(|r| r.to_uppercase());
+    let count = take_option(&mut args, "--count")?
+        .map(|n| n.parse::<usize>().map_err(|_| usage("--count needs a number")))
+        .transpose()?
+        .unwrap_or(DEFAULT_MIRROR_COUNT);
+    let state = AppState::new();
+    let is_arch = state.distro_family() == DistroFamily::Arch;
+    let mirrorlist = state.mirrorlist();
+    let action = arg(&args, 0, "action")?;
+
+    if !is_arch && matches!(action, "list" | "generate") {
+        return Err(AppError::NotSupported("The pacman mirrorlist only exists on Arch".to_string()).into());
+    }
+    match action {
+        "test" if is_arch => {
+            let ranked = mirrorlist.rank(region, &progress(json)).await?;
+            emit(json, &ranked, |list| print_mirrors(list.iter().map(|r| &r.mirror)))
+        }
+        "test" => {
+            let mirrors = state.repositories().test_all_mirrors(region, &progress(json)).await?;
+            emit(json, &mirrors, |list| print_mirrors(list.iter()))
+        }
+        "list" => emit(json, &mirrorlist.servers(), |servers| {
+            for s in servers {
+                out!("{} {:<5} {:<20} {}", if s.is_enabled { "*" } else { " " }, s.line_number, s.country.as_deref().unwrap_or(""), s.url);
+            }
+        }),
+        "generate" => {
+            let update = mirrorlist.generate(region, count, &progress(json)).await?;
+            emit(json, &update, |u| {
+                print_mirrors(u.mirrors.iter().map(|r| &r.mirror));
+                if let Some(path) = &u.backup_path {
+                    out!("Previous mirrorlist saved to {}", path);
+                }
+            })
+        }
+        other => Err(usage(&format!("unknown mirrors action '{}'", other))),
+    }
+}
+
+async fn run_keys(args: &[String], json: bool) -> CliResult {
+    let keyrings = AppState::new().keyrings();
+    let issues = |list: &[KeyIssue]| list.iter().map(|i| format!("{:?}", i)).collect::<Vec<_>>().join(", ");
+
+    match arg(args, 0, "action")? {
+        "list" => {
+            let inventory = keyrings.inventory().await?;
+            emit(json, &inventory, |inv| {
+                for k in &inv.keyrings {
+                    let owner = k.package.as_deref().map_or(String::new(), |p| format!(" [{}]", p));
+                    out!("{} {}{}  {}", if k.removable { "-" } else { " " }, k.path, owner, issues(&k.issues));
+                    for key in &k.keys {
+                        let uid = key.uids.first().map_or("", |u| u.as_str());
+                        let removable = if key.removable { "- " } else { "" };
+                        out!("      {}{} {}{}", removable, key.fingerprint, uid, if key.expired { " (expired)" } else { "" });
+                    }
+                    for repo in &k.used_by {
+                        out!("      ← {}", repo);
+                    }
+                }
+                out!("");
+                for r in inv.repositories.iter().filter(|r| !r.issues.is_empty()) {
+                    let migrate = if r.can_migrate { "  (migratable)" } else { "" };
+                    out!("{}:{}  {} {}  {}{}", r.file_path, r.line_number, r.uri, r.suite, issues(&r.issues), migrate);
+                }
+            })
+        }
+        "remove" => {
+            let message = keyrings.remove(arg(args, 1, "keyring")?).await?;
+            emit(json, &message, |m| out!("{}", m))
+        }
+        "remove-key" => {
+            let message = keyrings.remove_legacy_key(arg(args, 1, "fingerprint")?).await?;
+            emit(json, &message, |m| out!("{}", m))
+        }
+        "migrate" => {
+            let line = arg(args, 2, "line")?.parse::<usize>().map_err(|_| usage("line must be a number"))?;
+            let message = keyrings.migrate(arg(args, 1, "sources-file")?, line).await?;
+            emit(json, &message, |m| out!("{}", m))
+        }
+        other => Err(usage(&format!("unknown keys action '{}'", other))),
+    }
+}
+
+async fn run_processes(args: &[String], json: bool) -> CliResult {
+    let mut args = args.to_vec();
+    let force = take_flag(&mut args, "--force");
+    let top = take_option(&mut args, "--top")?
+        .map(|n| n.parse::<usize>().map_err(|_| usage("--top needs a number")))
+        .transpose()?;
+    let procs = AppState::new().processes();
+
+    let print_processes = |list: &Vec<processes::ProcessInfo>| {
+        for p in list {
+            out!("{:>7} {:>6.1}% {:>10}  {:<10} {}", p.pid, p.cpu_usage, format_bytes(p.memory_bytes), p.category, p.name);
+        }
+    };
+
+    match arg(&args, 0, "action")? {
+        "list" => {
+            let list = match top {
+                Some(limit) => procs.top(limit).await?,
+                None => procs.list().await?,
+            };
+            emit(json, &list, print_processes)
+        }
+        "search" => {
+            let query = arg(&args, 1, "query")?;
+            emit(json, &procs.search(query).await?, print_processes)
+        }
+        "kill" => {
+            let pid = arg(&args, 1, "pid")?
+                .parse::<u32>()
+                .map_err(|_| usage("pid must be a number"))?;
+            let result = procs.signal(pid, force).await?;
+            emit(json, &result, |r| out!("{}: {}", r.pid, r.message))
+        }
+        other => Err(usage(&format!("unknown processes action '{}'", other))),
+    }
+}
+
+async fn run_profile(args: &[String], json: bool) -> CliResult {
+    let mut args = args.to_vec();
+    let dry_run = take_flag(&mut args, "--dry-run");
+
+    match arg(&args, 0, "action")? {
+        "export" => {
+            let text = profile::export_profile().await?;
+            match args.get(1) {
+                Some(path) => {
+                    std::fs::write(path, &text).map_err(AppError::from)?;
+                    emit(json, &format!("Profile written to {}", path), |m| out!("{}", m))
+                }
+                None => {
+                    print!("{}", text);
+                    Ok(())
+                }
+            }
+        }
+        "import" => {
+            let path = arg(&args, 1, "file")?;
+            let text = std::fs::read_to_string(path).map_err(AppError::from)?;
+            let changes = if dry_run {
+                profile::preview_profile_import(text).await?
+            } else {
+                profile::apply_profile(&AppState::new(), &text).await?
+            };
+            emit(json, &changes, |list| {
+                if list.is_empty() {
+                    out!("Machine already matches the profile");
+                }
+                for c in list {
+                    let mark = if dry_run { "~" } else if c.applied { "✓" } else { "✗" };
+                    out!("{} {:<9} {:<30} {} → {}  {}", mark, c.section, c.item, c.current, c.desired, c.message);
+                }
+            })
+        }
+        other => Err(usage(&format!("unknown profile action '{}'", other))),
+    }
+}
+
+async fn dispatch(args: &[String], json: bool) -> CliResult {
+    let rest = &args[1..];
+    match args[0].as_str() {
+        "cleaner" => run_cleaner(rest, json).await,
+        "tweaks" => run_tweaks(rest, json).await,
+        "dns" => run_dns(rest, json).await,
+        "hosts" => run_hosts(rest, json).await,
+        "services" => run_services(rest, json).await,
+        "packages" => run_packages(rest, json).await,
+        "updates" => run_updates(rest, json).await,
+        "mirrors" => run_mirrors(rest, json).await,
+        "keys" => run_keys(rest, json).await,
+        "processes" => run_processes(rest, json).await,
+        "profile" => run_profile(rest, json).await,
+        other => Err(usage(&format!("unknown command '{}'", other))),
+    }
+}
+
+// ============================================================================
+// Entry Point
+// ============================================================================
+
+/// Run the CLI with the given arguments (without the program name), returning the exit code
+pub fn run(args: Vec<String>) -> i32 {
+    let mut args = args;
+    let json = take_flag(&mut args, "--json");
+
+    match args.first().map(|s| s.as_str()) {
+        // Elevated batch from `PkexecExecutor` (see `helper::run`)
+        Some(helper::ONCE_FLAG) => return helper::run(args),
+        None | Some("-h") | Some("--help") | Some("help") => {
+            out!("{}", USAGE);
+            return 0;
+        }
+        Some("-V") | Some("--version") => {
+            out!("glance-cli {}", env!("CARGO_PKG_VERSION"));
+            return 0;
+        }
+        _ => {}
+    }
+
+    // Simulation mode: record privileged calls and print them at the end
+    let recorder = std::env::var_os("FORCE_DISTRO")
+        .is_some()
+        .then(|| Arc::new(privileged::RecordingExecutor::new()));
+    let graphical = std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some();
+    if let Some(recorder) = &recorder {
+        privileged::set_executor(recorder.clone());
+    } else if !graphical && std::env::var_os("GLANCE_ELEVATION").is_none() {
+        // No polkit agent over SSH or on a bare console - elevate with sudo instead
+        privileged::set_executor(Arc::new(privileged::SudoExecutor::sudo()));
+    }
+
+    let runtime = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("error: failed to start async runtime: {}", e);
+            return 1;
+        }
+    };
+
+    let result = runtime.block_on(dispatch(&args, json));
+
+    if let Some(recorder) = recorder {
+        for call in recorder.calls() {
+            eprintln!("[MOCK EXEC] Would run: {}", call);
+        }
+    }
+
+    match result {
+        Ok(()) => 0,
+        Err(CliError::Usage(msg)) => {
+            eprintln!("error: {}\n\n{}", msg, USAGE);
+            2
+        }
+        Err(CliError::App(err)) => {
+            if json {
+                eprintln!("{}", serde_json::json!({ "error": err }));
+            } else {
+                eprintln!("error: {}", err);
+                if let Some(hint) = err.hint() {
+                    eprintln!("hint: {}", hint);
+                }
+            }
+            1
+        }
+    }
+}
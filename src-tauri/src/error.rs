@@ -1,8 +1,56 @@
 //! Custom error types for Glance
 //! Uses thiserror for ergonomic error definitions
+//! Serialized to the frontend as `{ code, message, hint?, context? }`
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+/// Longest stderr excerpt carried in an error
+const STDERR_EXCERPT_CHARS: usize = 500;
+
+/// stderr fragments of apt, dpkg, pacman, dnf and zypper when another process holds the lock
+const LOCK_MARKERS: &[&str] = &[
+    "Could not get lock",
+    "Unable to acquire the dpkg frontend lock",
+    "unable to lock database",
+    "Waiting for process with pid",
+    "System management is locked",
+];
+
+/// stderr fragments of a dpkg run that stopped half way and must be finished first
+const INTERRUPTED_MARKERS: &[&str] = &["dpkg was interrupted"];
+
+/// stderr fragments of download failures
+const NETWORK_MARKERS: &[&str] = &[
+    "Temporary failure resolving",
+    "Could not resolve",
+    "Failed to fetch",
+    "failed retrieving file",
+    "Network is unreachable",
+    "Cannot download",
+];
+
+/// The external command behind an error
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandContext {
+    pub command: String,
+    pub status: Option<i32>,
+    /// Last lines of stderr, trimmed to a readable size
+    pub stderr: String,
+}
+
+impl CommandContext {
+    pub fn new(command: impl Into<String>, status: Option<i32>, stderr: &str) -> Self {
+        let stderr = stderr.trim();
+        let skip = stderr.chars().count().saturating_sub(STDERR_EXCERPT_CHARS);
+        Self {
+            command: command.into(),
+            status,
+            stderr: stderr.chars().skip(skip).collect(),
+        }
+    }
+}
+
 /// Application-level errors
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -26,6 +74,88 @@ pub enum AppError {
 
     #[error("Network error: {0}")]
     Network(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Not supported: {0}")]
+    NotSupported(String),
+
+    /// Another package manager instance holds the database lock
+    #[error("Package manager is busy: {}", .0.stderr)]
+    PackageLocked(CommandContext),
+
+    /// A previous dpkg run stopped half way; `dpkg --configure -a` has to finish it
+    #[error("Package manager was interrupted: {}", .0.stderr)]
+    PackageInterrupted(CommandContext),
+
+    /// A command could not download what it needed
+    #[error("Network error: {}", .0.stderr)]
+    Download(CommandContext),
+
+    /// An external command exited unsuccessfully
+    #[error("{} failed: {}", .0.command, .0.stderr)]
+    Command(CommandContext),
+}
+
+impl AppError {
+    /// Classify a failed command by its stderr
+    pub fn from_command(command: impl Into<String>, status: Option<i32>, stderr: &str) -> Self {
+        let context = CommandContext::new(command, status, stderr);
+        if LOCK_MARKERS.iter().any(|m| stderr.contains(m)) {
+            AppError::PackageLocked(context)
+        } else if INTERRUPTED_MARKERS.iter().any(|m| stderr.contains(m)) {
+            AppError::PackageInterrupted(context)
+        } else if NETWORK_MARKERS.iter().any(|m| stderr.contains(m)) {
+            AppError::Download(context)
+        } else {
+            AppError::Command(context)
+        }
+    }
+
+    /// Stable machine-readable code; the frontend branches and localizes on this
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::System(_) => "system",
+            AppError::Io(_) => "io",
+            AppError::PermissionDenied(_) => "permission_denied",
+            AppError::CommandFailed(_) | AppError::Command(_) => "command_failed",
+            AppError::Timeout(_) => "timeout",
+            AppError::UserCancelled => "user_cancelled",
+            AppError::Network(_) | AppError::Download(_) => "network",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::NotSupported(_) => "not_supported",
+            AppError::PackageLocked(_) => "package_locked",
+            AppError::PackageInterrupted(_) => "package_interrupted",
+        }
+    }
+
+    /// What the user can do about it, when there is something
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            AppError::PermissionDenied(_) => Some("Glance refused or was not allowed to perform this change"),
+            AppError::Timeout(_) => Some("Respond to the authentication dialog, then try again"),
+            AppError::Network(_) | AppError::Download(_) => Some("Check your internet connection and try again"),
+            AppError::PackageLocked(_) => {
+                Some("Another package manager or update is running; wait for it to finish and try again")
+            }
+            AppError::PackageInterrupted(_) => {
+                Some("A previous installation was interrupted; run `sudo dpkg --configure -a`, then try again")
+            }
+            AppError::NotSupported(_) => Some("This feature is not available on your distribution"),
+            _ => None,
+        }
+    }
+
+    pub fn context(&self) -> Option<&CommandContext> {
+        match self {
+            AppError::PackageLocked(context)
+            | AppError::PackageInterrupted(context)
+            | AppError::Download(context)
+            | AppError::Command(context) => Some(context),
+            _ => None,
+        }
+    }
 }
 
 // Manual From implementation for std::io::Error
@@ -38,12 +168,99 @@ impl From<std::io::Error> for AppError {
 /// Result type alias for convenience
 pub type Result<T> = std::result::Result<T, AppError>;
 
+// ============================================================================
+// Wire Format
+// ============================================================================
+
+/// Serialized form of `AppError`, shared by Tauri commands and the root helper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub code: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<CommandContext>,
+}
+
+impl From<&AppError> for ErrorReport {
+    fn from(err: &AppError) -> Self {
+        Self {
+            code: err.code().to_string(),
+            message: err.to_string(),
+            hint: err.hint().map(|h| h.to_string()),
+            context: err.context().cloned(),
+        }
+    }
+}
+
+impl From<ErrorReport> for AppError {
+    fn from(report: ErrorReport) -> Self {
+        if let Some(context) = report.context {
+            return match report.code.as_str() {
+                "package_locked" => AppError::PackageLocked(context),
+                "package_interrupted" => AppError::PackageInterrupted(context),
+                "network" => AppError::Download(context),
+                _ => AppError::Command(context),
+            };
+        }
+
+        match report.code.as_str() {
+            "io" => AppError::Io(report.message),
+            "permission_denied" => AppError::PermissionDenied(report.message),
+            "command_failed" => AppError::CommandFailed(report.message),
+            "timeout" => AppError::Timeout(report.message),
+            "user_cancelled" => AppError::UserCancelled,
+            "network" => AppError::Network(report.message),
+            "invalid_input" => AppError::InvalidInput(report.message),
+            "not_supported" => AppError::NotSupported(report.message),
+            _ => AppError::System(report.message),
+        }
+    }
+}
+
 /// Convert AppError to a serializable format for Tauri
-impl serde::Serialize for AppError {
+impl Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        ErrorReport::from(self).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_errors_are_classified() {
+        let locked = AppError::from_command(
+            "apt-get",
+            Some(100),
+            "E: Could not get lock /var/lib/dpkg/lock-frontend. It is held by process 1234",
+        );
+        assert_eq!(locked.code(), "package_locked");
+        assert_eq!(locked.context().unwrap().status, Some(100));
+
+        let interrupted = AppError::from_command(
+            "apt-get",
+            Some(100),
+            "E: dpkg was interrupted, you must manually run 'dpkg --configure -a' to correct the problem.",
+        );
+        assert_eq!(interrupted.code(), "package_interrupted");
+
+        let offline = AppError::from_command("apt-get", Some(100), "W: Failed to fetch http://deb.debian.org/");
+        assert_eq!(offline.code(), "network");
+        assert_eq!(offline.context().unwrap().command, "apt-get");
+
+        let failed = AppError::from_command("pacman", Some(1), "error: target not found: foo");
+        let json = serde_json::to_value(&failed).unwrap();
+        assert_eq!(json["code"], "command_failed");
+        assert_eq!(json["context"]["command"], "pacman");
+        assert!(json.get("hint").is_none());
+
+        let roundtrip = AppError::from(serde_json::from_value::<ErrorReport>(json).unwrap());
+        assert_eq!(roundtrip.context(), failed.context());
     }
 }
@@ -0,0 +1,472 @@
+//! Root helper daemon
+//! Authorized once through pkexec, then serves `PrivilegedOp`s over a Unix socket
+//! Replaces one pkexec dialog per action (see `utils::privileged::PrivilegedExecutor`)
+//! With `--once` it runs a single batch read from stdin instead (see `PkexecExecutor`)
+
+use crate::error::{AppError, ErrorReport, Result};
+use crate::utils::privileged::{self, PrivilegedExecutor, PrivilegedOp};
+use crate::utils::progress::{self, Progress, ProgressEvent};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout, Duration, Instant};
+
+/// Root-owned directory holding one socket per desktop user
+const SOCKET_DIR: &str = "/run/glance";
+/// Installed helper locations, checked after the directory of the running binary
+const HELPER_PATHS: &[&str] = &["/usr/lib/glance/glance-helper", "/usr/libexec/glance-helper"];
+const HELPER_BINARY: &str = "glance-helper";
+/// The helper exits after this long without a request
+const IDLE_TIMEOUT_SECS: u64 = 600;
+/// ...and stops taking requests this long after pkexec started it, however busy it is
+const MAX_LIFETIME_SECS: u64 = 3600;
+/// A connected client must send each request within this time
+const READ_TIMEOUT_SECS: u64 = 10;
+/// polkit action checked afresh, with the user's agent, before adding package sources or keys
+const SOURCES_ACTION: &str = "com.github.WRVbit.glance.sources";
+/// Time allowed for the authentication dialog plus helper startup
+const STARTUP_TIMEOUT_SECS: u64 = 30;
+/// Run the `Vec<PrivilegedOp>` JSON on stdin and exit; accepted by every Glance binary
+pub const ONCE_FLAG: &str = "--once";
+
+
+// ============================================================================
+// Protocol (one JSON object per line)
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum HelperRequest {
+    Ping,
+    /// Validated and run in order, stopping at the first failure
+    Execute { ops: Vec<PrivilegedOp> },
+    Shutdown,
+}
+
+/// Output line of a running command, sent before the request's `HelperResponse`
+/// Closing the connection instead of waiting for the response cancels the request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelperLog {
+    pub log: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelperResponse {
+    pub success: bool,
+    pub output: String,
+    /// Set when `success` is false, so the client gets the original error back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorReport>,
+}
+
+/// Socket the helper serves for a given user
+pub fn socket_path(uid: u32) -> PathBuf {
+    Path::new(SOCKET_DIR).join(format!("helper-{}.sock", uid))
+}
+
+fn current_uid() -> u32 {
+    fs::metadata("/proc/self").map(|m| m.uid()).unwrap_or(0)
+}
+
+impl HelperResponse {
+    fn from_result(result: Result<String>) -> Self {
+        match result {
+            Ok(output) => Self { success: true, output, error: None },
+            Err(e) => Self {
+                success: false,
+                output: e.to_string(),
+                error: Some(ErrorReport::from(&e)),
+            },
+        }
+    }
+
+    /// Back to the original output or error
+    pub fn into_result(self) -> Result<String> {
+        match (self.success, self.error) {
+            (true, _) => Ok(self.output),
+            (false, Some(report)) => Err(AppError::from(report)),
+            (false, None) => Err(AppError::CommandFailed(self.output)),
+        }
+    }
+}
+
+// ============================================================================
+// Daemon (runs as root)
+// ============================================================================
+
+/// `pid,start-time,uid` of a connected process, as pkcheck identifies it without pid reuse races
+fn pkcheck_subject(pid: i32, uid: u32) -> Option<String> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // Fields after the parenthesised command name start at field 3 (state); start time is field 22
+    let start_time = stat.rsplit_once(')')?.1.split_whitespace().nth(19)?;
+    Some(format!("{},{},{}", pid, start_time, uid))
+}
+
+/// Ask polkit again, prompting through the user's agent, before a client adds package sources
+/// Being allowed to use the helper is not enough: any process of the user could be talking to it
+async fn authorize_sources(peer: Option<(i32, u32)>) -> Result<()> {
+    let subject = peer
+        .and_then(|(pid, uid)| pkcheck_subject(pid, uid))
+        .ok_or_else(|| AppError::PermissionDenied("Cannot identify the requesting process".to_string()))?;
+    let status = Command::new("pkcheck")
+        .args(["--action-id", SOURCES_ACTION, "--process", &subject, "--allow-user-interaction"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map_err(|e| AppError::CommandFailed(format!("Failed to run pkcheck: {}", e)))?;
+    // 0 authorized, 3 dialog dismissed; anything else is a refusal
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(3) => Err(AppError::UserCancelled),
+        _ => Err(AppError::PermissionDenied("Not authorized to add package sources".to_string())),
+    }
+}
+
+async fn execute(request: &HelperRequest, peer: Option<(i32, u32)>) -> Result<String> {
+    match request {
+        HelperRequest::Ping => Ok("pong".to_string()),
+        HelperRequest::Execute { ops } => {
+            if ops.iter().any(PrivilegedOp::adds_package_source) {
+                ops.iter().try_for_each(PrivilegedOp::validate)?;
+                authorize_sources(peer).await?;
+            }
+            privileged::execute_as_root(ops).await
+        }
+        HelperRequest::Shutdown => Ok("Shutting down".to_string()),
+    }
+}
+
+async fn send<T: Serialize>(write: &mut OwnedWriteHalf, message: &T) -> std::io::Result<()> {
+    let mut text = serde_json::to_string(message).unwrap_or_default();
+    text.push('\n');
+    write.write_all(text.as_bytes()).await
+}
+
+/// Run a request, streaming command output to the client as `HelperLog` lines
+/// Returns None when the client disconnects first; dropping the request kills its commands,
+/// except for package transactions, which always run to completion
+async fn execute_streaming(
+    request: &HelperRequest,
+    peer: Option<(i32, u32)>,
+    lines: &mut Lines<BufReader<OwnedReadHalf>>,
+    write: &mut OwnedWriteHalf,
+) -> Option<Result<String>> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let progress = Progress::new(
+        "helper",
+        Some(Arc::new(move |event: ProgressEvent| {
+            if let Some(log) = event.line {
+                let _ = tx.send(HelperLog { log });
+            }
+        })),
+    );
+
+    let interruptible = !matches!(request, HelperRequest::Execute { ops } if ops.iter().any(PrivilegedOp::is_transaction));
+    let run = progress.run(execute(request, peer));
+    tokio::pin!(run);
+    loop {
+        tokio::select! {
+            result = &mut run => {
+                while let Ok(log) = rx.try_recv() {
+                    send(write, &log).await.ok()?;
+                }
+                return Some(result);
+            }
+            Some(log) = rx.recv() => {
+                if send(write, &log).await.is_err() && interruptible {
+                    return None;
+                }
+            }
+            // Clients send nothing while waiting, so any read here means they went away
+            _ = lines.next_line(), if interruptible => return None,
+        }
+    }
+}
+
+/// Answer requests on one connection until it closes, goes quiet or `deadline` passes;
+/// returns false once asked to shut down
+async fn handle_connection(stream: UnixStream, peer: Option<(i32, u32)>, deadline: Instant) -> bool {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+
+    while Instant::now() < deadline {
+        // A client that connects and says nothing must not hold the helper
+        let Ok(Ok(Some(line))) = timeout(Duration::from_secs(READ_TIMEOUT_SECS), lines.next_line()).await else {
+            break;
+        };
+        let (response, shutdown) = match serde_json::from_str::<HelperRequest>(&line) {
+            Ok(request) => {
+                let shutdown = request == HelperRequest::Shutdown;
+                let Some(result) = execute_streaming(&request, peer, &mut lines, &mut write).await else {
+                    return true;
+                };
+                (HelperResponse::from_result(result), shutdown)
+            }
+            Err(e) => (
+                HelperResponse {
+                    success: false,
+                    output: format!("Malformed request: {}", e),
+                    error: None,
+                },
+                false,
+            ),
+        };
+
+        if send(&mut write, &response).await.is_err() || shutdown {
+            return !shutdown;
+        }
+    }
+    true
+}
+
+/// Serve `uid` until idle, told to shut down or `MAX_LIFETIME_SECS` after starting
+/// Requests are handled one at a time, so package transactions never overlap
+pub async fn serve(uid: u32) -> Result<()> {
+    let deadline = Instant::now() + Duration::from_secs(MAX_LIFETIME_SECS);
+    fs::create_dir_all(SOCKET_DIR)?;
+    fs::set_permissions(SOCKET_DIR, fs::Permissions::from_mode(0o755))?;
+
+    let path = socket_path(uid);
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    std::os::unix::fs::chown(&path, Some(uid), None)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+
+    while Instant::now() < deadline {
+        let idle = Duration::from_secs(IDLE_TIMEOUT_SECS).min(deadline - Instant::now());
+        let stream = match timeout(idle, listener.accept()).await {
+            Err(_) => break, // Idle
+            Ok(Ok((stream, _))) => stream,
+            Ok(Err(e)) => {
+                eprintln!("glance-helper: accept failed: {}", e);
+                continue;
+            }
+        };
+
+        // Only the user who authorized us (or root) may talk to the helper
+        let Ok(cred) = stream.peer_cred() else { continue };
+        if cred.uid() != uid && cred.uid() != 0 {
+            continue;
+        }
+
+        if !handle_connection(stream, cred.pid().map(|pid| (pid, cred.uid())), deadline).await {
+            break;
+        }
+    }
+
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+/// Print a message as one JSON line on stdout
+fn print_line<T: Serialize>(message: &T) {
+    use std::io::Write;
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", serde_json::to_string(message).unwrap_or_default());
+    let _ = stdout.flush();
+}
+
+/// `--once`: run the operations on stdin, printing `HelperLog` lines and then one `HelperResponse`
+async fn serve_once() {
+    let mut input = String::new();
+    let ops = match std::io::Read::read_to_string(&mut std::io::stdin(), &mut input) {
+        Ok(_) => serde_json::from_str::<Vec<PrivilegedOp>>(&input)
+            .map_err(|e| AppError::InvalidInput(format!("Malformed operations: {}", e))),
+        Err(e) => Err(AppError::from(e)),
+    };
+    let result = match ops {
+        Ok(ops) => {
+            let progress = Progress::new(
+                "helper",
+                Some(Arc::new(|event: ProgressEvent| {
+                    if let Some(log) = event.line {
+                        print_line(&HelperLog { log });
+                    }
+                })),
+            );
+            progress.run(privileged::execute_as_root(&ops)).await
+        }
+        Err(e) => Err(e),
+    };
+    print_line(&HelperResponse::from_result(result));
+}
+
+/// Entry point of the `glance-helper` binary, and of the other binaries when given `--once`
+/// The desktop user comes from PKEXEC_UID, or `--uid N` when started by root directly
+pub fn run(args: Vec<String>) -> i32 {
+    if current_uid() != 0 {
+        eprintln!("glance-helper must run as root (start it through pkexec)");
+        return 1;
+    }
+    let once = args.iter().any(|a| a == ONCE_FLAG);
+
+    let uid = match args.iter().position(|a| a == "--uid") {
+        _ if once => Some(0),
+        Some(i) => args.get(i + 1).and_then(|v| v.parse::<u32>().ok()),
+        None => std::env::var("PKEXEC_UID").ok().and_then(|v| v.parse::<u32>().ok()),
+    };
+    let Some(uid) = uid else {
+        eprintln!("glance-helper: unknown caller (no PKEXEC_UID and no --uid)");
+        return 2;
+    };
+
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("glance-helper: failed to start async runtime: {}", e);
+            return 1;
+        }
+    };
+
+    if once {
+        runtime.block_on(serve_once());
+        return 0;
+    }
+    match runtime.block_on(serve(uid)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("glance-helper: {}", e);
+            1
+        }
+    }
+}
+
+// ============================================================================
+// Client
+// ============================================================================
+
+/// Installed helper binary, if any
+pub fn find_helper() -> Option<PathBuf> {
+    let sibling = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(HELPER_BINARY)));
+
+    sibling
+        .into_iter()
+        .chain(HELPER_PATHS.iter().map(PathBuf::from))
+        .find(|path| path.is_file())
+}
+
+/// Sends commands and typed operations to the root helper, starting it through pkexec on first use
+pub struct HelperExecutor {
+    helper: PathBuf,
+    socket: PathBuf,
+    starting: tokio::sync::Mutex<()>,
+}
+
+impl HelperExecutor {
+    pub fn new(helper: PathBuf) -> Self {
+        Self {
+            helper,
+            socket: socket_path(current_uid()),
+            starting: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Start the helper (one authentication dialog) unless it is already running
+    async fn ensure_started(&self) -> Result<()> {
+        let _guard = self.starting.lock().await;
+        if UnixStream::connect(&self.socket).await.is_ok() {
+            return Ok(());
+        }
+
+        let mut child = Command::new("pkexec")
+            .arg(&self.helper)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| AppError::CommandFailed(format!("Failed to spawn pkexec: {}", e)))?;
+
+        for _ in 0..STARTUP_TIMEOUT_SECS * 5 {
+            sleep(Duration::from_millis(200)).await;
+            if UnixStream::connect(&self.socket).await.is_ok() {
+                return Ok(());
+            }
+            if let Ok(Some(status)) = child.try_wait() {
+                // pkexec exits 126 when the dialog is dismissed and 127 when not authorized
+                return Err(match status.code() {
+                    Some(126) | Some(127) => AppError::UserCancelled,
+                    _ => AppError::CommandFailed(format!("Root helper exited: {}", status)),
+                });
+            }
+        }
+
+        Err(AppError::Timeout("Authentication dialog timed out after 30 seconds".to_string()))
+    }
+
+    /// Send one request and wait for its response, forwarding output lines to the current `Progress`
+    /// Dropping the returned future closes the connection, which cancels the request
+    pub async fn call(&self, request: &HelperRequest) -> Result<String> {
+        self.ensure_started().await?;
+
+        let stream = UnixStream::connect(&self.socket)
+            .await
+            .map_err(|e| AppError::CommandFailed(format!("Root helper unavailable: {}", e)))?;
+        let (read, mut write) = stream.into_split();
+
+        let mut line = serde_json::to_string(request)
+            .map_err(|e| AppError::System(format!("Failed to encode request: {}", e)))?;
+        line.push('\n');
+        write.write_all(line.as_bytes()).await?;
+
+        let progress = progress::current();
+        let mut reader = BufReader::new(read);
+        let response: HelperResponse = loop {
+            let mut reply = String::new();
+            if reader.read_line(&mut reply).await? == 0 {
+                return Err(AppError::CommandFailed("Root helper closed the connection".to_string()));
+            }
+            if let Ok(HelperLog { log }) = serde_json::from_str(&reply) {
+                if let Some(progress) = &progress {
+                    progress.log(log);
+                }
+                continue;
+            }
+            break serde_json::from_str(&reply)
+                .map_err(|e| AppError::CommandFailed(format!("Invalid helper response: {}", e)))?;
+        };
+
+        response.into_result()
+    }
+}
+
+#[async_trait]
+impl PrivilegedExecutor for HelperExecutor {
+    fn name(&self) -> &'static str {
+        "helper"
+    }
+
+    async fn execute(&self, ops: &[PrivilegedOp]) -> Result<String> {
+        let _guard = ops.iter().any(PrivilegedOp::is_transaction).then(progress::uncancellable).transpose()?;
+        self.call(&HelperRequest::Execute { ops: ops.to_vec() }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_shape() {
+        let line = serde_json::to_string(&HelperRequest::Execute {
+            ops: vec![PrivilegedOp::sysctl("vm.swappiness", "10")],
+        })
+        .unwrap();
+        assert_eq!(
+            line,
+            r#"{"op":"execute","ops":[{"kind":"sysctl_set","key":"vm.swappiness","value":"10"}]}"#
+        );
+    }
+}
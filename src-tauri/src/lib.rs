@@ -3,12 +3,14 @@
 //! Supports: Debian/Ubuntu, Arch, Fedora, OpenSUSE
 
 pub mod adapters;
+pub mod cli;
+pub mod helper;
 mod error;
 mod modules;
 mod state;
 mod utils;
 
-use modules::{cleaner, dns, gaming, hosts, packages, processes, repositories, resources, services, startup, system_stats, tweaks};
+use modules::{cleaner, dns, gaming, hosts, keyrings, mirrorlist, packages, processes, profile, repositories, resources, services, startup, system_stats, tweaks, updates};
 use state::AppState;
 use utils::distro::DistroInfo;
 use utils::{DistroFamily, DesktopEnvironment};
@@ -29,11 +31,24 @@ fn get_distro_family(state: State<'_, AppState>) -> String {
 /// Get package manager name for UI display
 #[tauri::command]
 fn get_pm_name(state: State<'_, AppState>) -> String {
-    state.context.package_manager.name().to_string()
+    state.packages().manager_name().to_string()
+}
+
+/// Cancel a running operation and kill its child processes
+/// Returns false if it already finished; fails while a privileged step can't be interrupted
+#[tauri::command]
+fn cancel_operation(operation_id: String, state: State<'_, AppState>) -> Result<bool, error::AppError> {
+    state.operations.cancel(&operation_id)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // pkexec runs this binary with `--once` when the root helper is not installed
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some(helper::ONCE_FLAG) {
+        std::process::exit(helper::run(args));
+    }
+
     tauri::Builder::default()
         // Plugins
         .plugin(tauri_plugin_shell::init())
@@ -52,6 +67,8 @@ pub fn run() {
             get_distro_family,
             get_pm_name,
             packages::get_package_manager_name,
+            // Operations
+            cancel_operation,
             // System Stats
             system_stats::get_system_info,
             system_stats::get_cpu_stats,
@@ -72,6 +89,10 @@ pub fn run() {
             tweaks::apply_tweak,
             tweaks::apply_all_recommended,
             tweaks::get_device_info,
+            tweaks::set_tweak_persistence,
+            tweaks::get_tweak_history,
+            tweaks::rollback_tweak_transaction,
+            tweaks::undo_last_tweak,
             // Services
             services::get_services,
             services::start_service,
@@ -92,7 +113,20 @@ pub fn run() {
             packages::uninstall_package,
             packages::purge_package,
             packages::autoremove_packages,
+            packages::simulate_uninstall_package,
+            packages::simulate_purge_package,
+            packages::simulate_autoremove_packages,
             packages::get_package_stats,
+            packages::get_package_analysis,
+            packages::search_remote_packages,
+            packages::clean_unused_packages,
+            packages::get_package_history,
+            packages::undo_package_transaction,
+            packages::install_package,
+            packages::get_upgradable_packages,
+            packages::upgrade_packages,
+            packages::upgrade_all_packages,
+            packages::get_package_details,
             // Processes
             processes::get_processes,
             processes::get_top_processes,
@@ -105,6 +139,7 @@ pub fn run() {
             repositories::is_repositories_available,
             repositories::get_repositories,
             repositories::toggle_repository,
+            repositories::add_repository,
             repositories::delete_repository,
             repositories::add_ppa,
             repositories::remove_ppa,
@@ -113,6 +148,13 @@ pub fn run() {
             repositories::test_mirror_speed,
             repositories::test_all_mirrors,
             repositories::set_mirror,
+            mirrorlist::get_arch_mirrorlist,
+            mirrorlist::rank_arch_mirrors,
+            mirrorlist::generate_arch_mirrorlist,
+            keyrings::get_key_inventory,
+            keyrings::remove_keyring,
+            keyrings::remove_legacy_key,
+            keyrings::migrate_repository_key,
             repositories::apt_update,
             // apt-fast
             repositories::check_apt_fast,
@@ -140,6 +182,9 @@ pub fn run() {
             dns::set_dns_provider,
             dns::set_custom_dns,
             dns::reset_dns,
+            // Automatic Updates
+            updates::get_auto_update_status,
+            updates::set_auto_update_config,
             // Gaming Center
             gaming::get_gaming_status,
             gaming::get_gaming_packages,
@@ -153,6 +198,10 @@ pub fn run() {
             gaming::get_system_profile,
             gaming::get_gaming_checklist,
             gaming::one_touch_gaming_setup,
+            // Profiles
+            profile::export_profile,
+            profile::preview_profile_import,
+            profile::import_profile,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -2,13 +2,16 @@
 //! Handles cleanup of cache, logs, trash, etc. (async)
 //! Uses distro-agnostic paths via DistroContext
 
+use crate::adapters::{PackageSource, SnapAdapter};
 use crate::error::{AppError, Result};
 use crate::state::AppState;
-use crate::utils::privileged;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
+use crate::utils::DistroContext;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
-use tauri::State;
+use std::sync::Arc;
+use tauri::{AppHandle, State};
 
 // ============================================================================
 // Data Structures
@@ -120,11 +123,10 @@ fn get_dirs_size(paths: &[String]) -> (u64, u32) {
 // Cleanup Categories
 // ============================================================================
 
-/// Get all cleanup categories with their current sizes (async)
-#[tauri::command]
-pub async fn get_cleanup_categories(state: State<'_, AppState>) -> Result<Vec<CleanupCategory>> {
-    let pkg_cache_path = state.context.paths.package_cache.clone();
-    let pm_name = state.context.package_manager.name().to_string();
+/// Scan every cleanup category for the given distro context
+async fn scan_categories(context: &DistroContext) -> Result<Vec<CleanupCategory>> {
+    let pkg_cache_path = context.paths.package_cache.clone();
+    let pm_name = context.package_manager.name().to_string();
     
     let categories = tokio::task::spawn_blocking(move || {
         let home = home_dir();
@@ -429,29 +431,12 @@ fn get_old_logs_size_sync() -> u64 {
 // Cleanup Actions (All async)
 // ============================================================================
 
-/// Preview cleanup (dry run) - shows what would be deleted without actually deleting
-/// Returns the cleanup result with calculated size but no actual deletion
-#[tauri::command]
-pub async fn preview_cleanup(category_id: String, state: State<'_, AppState>) -> Result<CleanupResult> {
-    // Just get the category info - this is already a "dry run" calculation
-    let categories = get_cleanup_categories(state).await?;
-    
-    if let Some(cat) = categories.iter().find(|c| c.id == category_id) {
-        Ok(CleanupResult {
-            category: category_id,
-            success: true,
-            bytes_freed: cat.size_bytes,
-            files_removed: cat.file_count,
-            message: format!("Preview: Would free {} bytes from {} files", cat.size_bytes, cat.file_count),
-        })
-    } else {
-        Err(AppError::System(format!("Unknown category: {}", category_id)))
-    }
-}
-
-/// Clean a specific category (async with timeout for root ops)
-#[tauri::command]
-pub async fn clean_category(category_id: String, state: State<'_, AppState>) -> Result<CleanupResult> {
+/// Clean a single category for the given distro context
+async fn clean_with_context(
+    context: &DistroContext,
+    executor: &Arc<dyn PrivilegedExecutor>,
+    category_id: String,
+) -> Result<CleanupResult> {
     let home = home_dir();
 
     match category_id.as_str() {
@@ -632,7 +617,12 @@ pub async fn clean_category(category_id: String, state: State<'_, AppState>) ->
             }).await.unwrap();
 
             // Also try to clear /var/crash (may need root)
-            let _ = privileged::run_privileged_shell("rm -f /var/crash/*").await;
+            let _ = executor.execute(&[PrivilegedOp::RemoveFiles {
+                dir: "/var/crash".to_string(),
+                patterns: vec!["*".to_string()],
+                recursive: false,
+            }])
+            .await;
 
             Ok(CleanupResult {
                 category: "crash_reports".to_string(),
@@ -660,7 +650,7 @@ pub async fn clean_category(category_id: String, state: State<'_, AppState>) ->
         }
 
         "pkg_cache" | "apt_cache" => {
-            let result = state.context.package_manager.clean_cache().await;
+            let result = context.package_manager.clean_cache().await;
             
             match result {
                 Ok(cleanup) => Ok(CleanupResult {
@@ -676,17 +666,13 @@ pub async fn clean_category(category_id: String, state: State<'_, AppState>) ->
 
         "snap_cache" => {
             // Clean old snap revisions
-            let result = privileged::run_privileged_shell(
-                "snap list --all | awk '/disabled/{print $1, $3}' | while read snapname revision; do snap remove \"$snapname\" --revision=\"$revision\"; done"
-            ).await;
-
-            match result {
-                Ok(_) => Ok(CleanupResult {
+            match SnapAdapter::new(executor.clone()).clean_unused().await {
+                Ok(cleanup) => Ok(CleanupResult {
                     category: "snap_cache".to_string(),
-                    success: true,
-                    bytes_freed: 0,
-                    files_removed: 0,
-                    message: "Old snap revisions removed".to_string(),
+                    success: cleanup.success,
+                    bytes_freed: cleanup.bytes_freed,
+                    files_removed: cleanup.items_removed,
+                    message: cleanup.message,
                 }),
                 Err(AppError::UserCancelled) => Ok(CleanupResult {
                     category: "snap_cache".to_string(),
@@ -706,7 +692,7 @@ pub async fn clean_category(category_id: String, state: State<'_, AppState>) ->
         }
 
         "journal" => {
-            let result = privileged::run_privileged("journalctl", &["--vacuum-size=100M"]).await;
+            let result = executor.execute(&[PrivilegedOp::VacuumJournal { max_mb: 100 }]).await;
 
             match result {
                 Ok(output) => Ok(CleanupResult {
@@ -735,9 +721,12 @@ pub async fn clean_category(category_id: String, state: State<'_, AppState>) ->
         }
 
         "old_logs" => {
-            let result = privileged::run_privileged_shell(
-                "find /var/log -type f \\( -name '*.gz' -o -name '*.old' -o -name '*.1' \\) -delete"
-            ).await;
+            let result = executor.execute(&[PrivilegedOp::RemoveFiles {
+                dir: "/var/log".to_string(),
+                patterns: vec!["*.gz".to_string(), "*.old".to_string(), "*.1".to_string()],
+                recursive: true,
+            }])
+            .await;
 
             match result {
                 Ok(_) => Ok(CleanupResult {
@@ -765,7 +754,7 @@ pub async fn clean_category(category_id: String, state: State<'_, AppState>) ->
         }
 
         "old_kernels" => {
-            let result = state.context.package_manager.autoremove().await;
+            let result = context.package_manager.autoremove().await;
             
             match result {
                 Ok(action) => Ok(CleanupResult {
@@ -779,18 +768,124 @@ pub async fn clean_category(category_id: String, state: State<'_, AppState>) ->
             }
         }
 
-        _ => Err(AppError::System(format!(
+        _ => Err(AppError::InvalidInput(format!(
             "Unknown cleanup category: {}",
             category_id
         ))),
     }
 }
 
+// ============================================================================
+// Cleaner Service
+// ============================================================================
+
+/// Cleanup operations bound to the detected distro
+pub struct Cleaner<'a> {
+    context: &'a DistroContext,
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+impl<'a> Cleaner<'a> {
+    pub fn new(context: &'a DistroContext, executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { context, executor }
+    }
+
+    /// All cleanup categories with their current sizes
+    pub async fn categories(&self) -> Result<Vec<CleanupCategory>> {
+        scan_categories(self.context).await
+    }
+
+    /// Dry run - what cleaning a category would free, without deleting anything
+    pub async fn preview(&self, category_id: &str) -> Result<CleanupResult> {
+        // Just get the category info - this is already a "dry run" calculation
+        let categories = self.categories().await?;
+
+        if let Some(cat) = categories.iter().find(|c| c.id == category_id) {
+            Ok(CleanupResult {
+                category: category_id.to_string(),
+                success: true,
+                bytes_freed: cat.size_bytes,
+                files_removed: cat.file_count,
+                message: format!("Preview: Would free {} bytes from {} files", cat.size_bytes, cat.file_count),
+            })
+        } else {
+            Err(AppError::InvalidInput(format!("Unknown category: {}", category_id)))
+        }
+    }
+
+    /// Clean a single category (root operations go through pkexec)
+    pub async fn clean(&self, category_id: &str) -> Result<CleanupResult> {
+        clean_with_context(self.context, &self.executor, category_id.to_string()).await
+    }
+
+    /// Total reclaimable space across all categories
+    pub async fn total_reclaimable(&self) -> Result<u64> {
+        let categories = self.categories().await?;
+        Ok(categories.iter().map(|c| c.size_bytes).sum())
+    }
+
+    /// Clean the categories configured for auto-clean
+    pub async fn run_autoclean(&self) -> Result<String> {
+        let config = get_autoclean_schedule().await?;
+
+        if config.categories.is_empty() {
+            return Ok("No categories configured".to_string());
+        }
+
+        let mut cleaned = Vec::new();
+        for cat in &config.categories {
+            if let Ok(result) = self.clean(cat).await {
+                if result.success {
+                    cleaned.push(cat.clone());
+                }
+            }
+        }
+
+        Ok(format!("Cleaned {} categories", cleaned.len()))
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Get all cleanup categories with their current sizes (async)
+#[tauri::command]
+pub async fn get_cleanup_categories(state: State<'_, AppState>) -> Result<Vec<CleanupCategory>> {
+    state.cleaner().categories().await
+}
+
+/// Preview cleanup (dry run) - shows what would be deleted without actually deleting
+/// Returns the cleanup result with calculated size but no actual deletion
+#[tauri::command]
+pub async fn preview_cleanup(category_id: String, state: State<'_, AppState>) -> Result<CleanupResult> {
+    state.cleaner().preview(&category_id).await
+}
+
+/// Clean a specific category (async with timeout for root ops)
+/// Cancellable through `cancel_operation(operation_id)`
+#[tauri::command]
+pub async fn clean_category(
+    category_id: String,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<CleanupResult> {
+    let cleaner = state.cleaner();
+    state
+        .track(&app, operation_id, |progress| async move {
+            progress.step(format!("Cleaning {}", category_id), None);
+            let result = cleaner.clean(&category_id).await?;
+            progress.log(result.message.clone());
+            Ok(result)
+        })
+        .await
+}
+
 /// Get total reclaimable space (async)
 #[tauri::command]
 pub async fn get_total_reclaimable(state: State<'_, AppState>) -> Result<u64> {
-    let categories = get_cleanup_categories(state).await?;
-    Ok(categories.iter().map(|c| c.size_bytes).sum())
+    state.cleaner().total_reclaimable().await
 }
 
 // ============================================================================
@@ -1002,20 +1097,5 @@ pub async fn get_autoclean_status() -> Result<String> {
 /// Run auto-clean now (manual trigger)
 #[tauri::command]
 pub async fn run_autoclean_now(state: State<'_, AppState>) -> Result<String> {
-    let config = get_autoclean_schedule().await?;
-    
-    if config.categories.is_empty() {
-        return Ok("No categories configured".to_string());
-    }
-    
-    let mut cleaned = Vec::new();
-    for cat in &config.categories {
-        if let Ok(result) = clean_category(cat.clone(), state.clone()).await {
-            if result.success {
-                cleaned.push(cat.clone());
-            }
-        }
-    }
-    
-    Ok(format!("Cleaned {} categories", cleaned.len()))
+    state.cleaner().run_autoclean().await
 }
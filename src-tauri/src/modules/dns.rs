@@ -2,9 +2,12 @@
 //! Configure system DNS via /etc/systemd/resolved.conf
 
 use crate::error::{AppError, Result};
-use crate::utils::privileged;
+use crate::state::AppState;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp, RESOLVED_CONF_PATH};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::sync::Arc;
+use tauri::State;
 
 // ============================================================================
 // Data Structures
@@ -53,7 +56,25 @@ pub const DNS_PROVIDERS: &[(&str, &str, &str, &str, &str, &str)] = &[
     ("cleanbrowsing_family", "CleanBrowsing Family", "Family-friendly filtering", "185.228.168.168", "185.228.169.168", "family"),
 ];
 
-const RESOLVED_CONF_PATH: &str = "/etc/systemd/resolved.conf";
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+/// DNS servers pinned in resolved.conf (empty when DNS comes from DHCP)
+pub fn get_configured_dns() -> Vec<String> {
+    let content = fs::read_to_string(RESOLVED_CONF_PATH).unwrap_or_default();
+    content
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| line.starts_with("DNS="))
+        .map(|line| {
+            line.trim_start_matches("DNS=")
+                .split_whitespace()
+                .map(|s| s.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
 
 // ============================================================================
 // Tauri Commands
@@ -83,19 +104,7 @@ pub async fn get_current_dns() -> Result<DnsStatus> {
         let mut active_provider: Option<String> = None;
 
         // Try to read from resolved.conf first
-        if let Ok(content) = fs::read_to_string(RESOLVED_CONF_PATH) {
-            for line in content.lines() {
-                let trimmed = line.trim();
-                if trimmed.starts_with("DNS=") && !trimmed.starts_with('#') {
-                    let dns_value = trimmed.trim_start_matches("DNS=").trim();
-                    current_dns = dns_value
-                        .split_whitespace()
-                        .map(|s| s.to_string())
-                        .collect();
-                    break;
-                }
-            }
-        }
+        current_dns.extend(get_configured_dns());
 
         // If no DNS found in resolved.conf, try resolv.conf
         if current_dns.is_empty() {
@@ -137,67 +146,105 @@ pub async fn get_current_dns() -> Result<DnsStatus> {
 
 /// Set DNS using a provider ID
 #[tauri::command]
-pub async fn set_dns_provider(provider_id: String) -> Result<()> {
-    // Find the provider
-    let provider = DNS_PROVIDERS
-        .iter()
-        .find(|(id, _, _, _, _, _)| *id == provider_id)
-        .ok_or_else(|| AppError::System("Unknown DNS provider".to_string()))?;
-
-    let (_, _, _, primary, secondary, _) = provider;
-
-    apply_dns(primary, secondary).await
+pub async fn set_dns_provider(provider_id: String, state: State<'_, AppState>) -> Result<()> {
+    state.dns().set_provider(&provider_id).await
 }
 
 /// Set custom DNS servers
 #[tauri::command]
-pub async fn set_custom_dns(primary: String, secondary: String) -> Result<()> {
-    // Validate IP addresses (basic check)
-    if !is_valid_ip(&primary) {
-        return Err(AppError::System("Invalid primary DNS address".to_string()));
-    }
-    if !secondary.is_empty() && !is_valid_ip(&secondary) {
-        return Err(AppError::System("Invalid secondary DNS address".to_string()));
-    }
-
-    let sec = if secondary.is_empty() { "" } else { &secondary };
-    apply_dns(&primary, sec).await
+pub async fn set_custom_dns(primary: String, secondary: String, state: State<'_, AppState>) -> Result<()> {
+    state.dns().set_custom(&primary, &secondary).await
 }
 
 /// Reset DNS to DHCP (automatic)
 #[tauri::command]
-pub async fn reset_dns() -> Result<()> {
-    // Read current config
-    let content = fs::read_to_string(RESOLVED_CONF_PATH).unwrap_or_default();
+pub async fn reset_dns(state: State<'_, AppState>) -> Result<()> {
+    state.dns().reset().await
+}
 
-    // Comment out DNS line or remove it
-    let new_content: String = content
-        .lines()
-        .map(|line| {
-            let trimmed = line.trim();
-            if trimmed.starts_with("DNS=") && !trimmed.starts_with('#') {
-                format!("#{}", line)
-            } else {
-                line.to_string()
-            }
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
+// ============================================================================
+// DNS Service
+// ============================================================================
+
+/// Writes resolved.conf through the injected privileged executor
+#[derive(Clone)]
+pub struct Dns {
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+impl Dns {
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { executor }
+    }
+
+    /// Use a known provider's servers
+    pub async fn set_provider(&self, provider_id: &str) -> Result<()> {
+        // Find the provider
+        let provider = DNS_PROVIDERS
+            .iter()
+            .find(|(id, _, _, _, _, _)| *id == provider_id)
+            .ok_or_else(|| AppError::InvalidInput("Unknown DNS provider".to_string()))?;
+
+        let (_, _, _, primary, secondary, _) = provider;
+
+        self.apply_dns(primary, secondary).await
+    }
+
+    /// Use custom servers (secondary may be empty)
+    pub async fn set_custom(&self, primary: &str, secondary: &str) -> Result<()> {
+        // Validate IP addresses (basic check)
+        if !is_valid_ip(primary) {
+            return Err(AppError::InvalidInput("Invalid primary DNS address".to_string()));
+        }
+        if !secondary.is_empty() && !is_valid_ip(secondary) {
+            return Err(AppError::InvalidInput("Invalid secondary DNS address".to_string()));
+        }
+
+        self.apply_dns(primary, secondary).await
+    }
+
+    /// Back to DHCP-provided DNS
+    pub async fn reset(&self) -> Result<()> {
+        // Read current config
+        let content = fs::read_to_string(RESOLVED_CONF_PATH).unwrap_or_default();
 
-    let temp_path = std::env::temp_dir().join("glance_resolved.tmp");
-    fs::write(&temp_path, &new_content)
-        .map_err(|e| AppError::System(format!("Failed to write temp file: {}", e)))?;
+        // Comment out DNS line or remove it
+        let new_content: String = content
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim();
+                if trimmed.starts_with("DNS=") && !trimmed.starts_with('#') {
+                    format!("#{}", line)
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.write_resolved_conf(&new_content).await
+    }
+
+    async fn apply_dns(&self, primary: &str, secondary: &str) -> Result<()> {
+        // Read current config
+        let content = fs::read_to_string(RESOLVED_CONF_PATH).unwrap_or_else(|_| {
+            "[Resolve]\n".to_string()
+        });
 
-    let script = format!(
-        "cp '{}' '{}' && rm '{}' && systemctl restart systemd-resolved",
-        temp_path.to_string_lossy(),
-        RESOLVED_CONF_PATH,
-        temp_path.to_string_lossy()
-    );
+        self.write_resolved_conf(&render_resolved_conf(&content, primary, secondary)).await
+    }
 
-    privileged::run_privileged_shell(&script).await?;
+    /// Install new resolved.conf content and restart systemd-resolved
+    async fn write_resolved_conf(&self, content: &str) -> Result<()> {
+        self.executor
+            .execute(&[
+                PrivilegedOp::write_file(RESOLVED_CONF_PATH, content, 0o644),
+                PrivilegedOp::systemctl("restart", "systemd-resolved"),
+            ])
+            .await?;
 
-    Ok(())
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -208,12 +255,8 @@ fn is_valid_ip(ip: &str) -> bool {
     ip.parse::<std::net::IpAddr>().is_ok()
 }
 
-async fn apply_dns(primary: &str, secondary: &str) -> Result<()> {
-    // Read current config
-    let content = fs::read_to_string(RESOLVED_CONF_PATH).unwrap_or_else(|_| {
-        "[Resolve]\n".to_string()
-    });
-
+/// Set the DNS= line in resolved.conf content (adding [Resolve] and FallbackDNS if missing)
+fn render_resolved_conf(content: &str, primary: &str, secondary: &str) -> String {
     let dns_line = if secondary.is_empty() {
         format!("DNS={}", primary)
     } else {
@@ -260,20 +303,41 @@ async fn apply_dns(primary: &str, secondary: &str) -> Result<()> {
         new_lines.push("FallbackDNS=1.1.1.1 8.8.8.8 9.9.9.9".to_string());
     }
 
-    let new_content = new_lines.join("\n") + "\n";
-
-    let temp_path = std::env::temp_dir().join("glance_resolved.tmp");
-    fs::write(&temp_path, &new_content)
-        .map_err(|e| AppError::System(format!("Failed to write temp file: {}", e)))?;
+    new_lines.join("\n") + "\n"
+}
 
-    let script = format!(
-        "cp '{}' '{}' && rm '{}' && systemctl restart systemd-resolved",
-        temp_path.to_string_lossy(),
-        RESOLVED_CONF_PATH,
-        temp_path.to_string_lossy()
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::privileged::RecordingExecutor;
+
+    #[test]
+    fn test_apply_dns_writes_config_then_restarts_resolved() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let recorder = Arc::new(RecordingExecutor::new());
+        let dns = Dns::new(recorder.clone());
+
+        runtime.block_on(dns.set_provider("quad9")).unwrap();
+
+        let calls = recorder.calls();
+        assert_eq!(calls.len(), 2);
+        match &calls[0] {
+            PrivilegedOp::WriteFile { path, content, mode } => {
+                assert_eq!(path, RESOLVED_CONF_PATH);
+                assert!(content.contains("DNS=9.9.9.9 149.112.112.112"));
+                assert_eq!(*mode, 0o644);
+            }
+            other => panic!("unexpected call: {}", other),
+        }
+        assert_eq!(calls[1], PrivilegedOp::systemctl("restart", "systemd-resolved"));
+    }
 
-    privileged::run_privileged_shell(&script).await?;
+    #[test]
+    fn test_render_resolved_conf() {
+        let rendered = render_resolved_conf("[Resolve]\n#DNS=\n", "1.1.1.1", "");
+        assert_eq!(rendered, "[Resolve]\nDNS=1.1.1.1\nFallbackDNS=1.1.1.1 8.8.8.8 9.9.9.9\n");
 
-    Ok(())
+        let rendered = render_resolved_conf("", "1.1.1.1", "1.0.0.1");
+        assert!(rendered.starts_with("[Resolve]\nDNS=1.1.1.1 1.0.0.1\n"));
+    }
 }
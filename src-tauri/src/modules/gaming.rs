@@ -5,7 +5,14 @@
 use serde::{Deserialize, Serialize};
 use std::process::Command;
 use std::fs;
+use tauri::{AppHandle, State};
+use crate::adapters::PackageManager;
+use crate::error::{AppError, CommandContext, Result};
+use crate::state::AppState;
+use crate::utils::progress::Progress;
 use crate::utils::{DistroFamily, DistroInfo};
+use crate::utils::persistence::{PersistentSettings, OWNER_GAMING};
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
 
 // ============================================================================
 // GPU Detection & Status
@@ -94,38 +101,32 @@ fn detect_desktop_environment() -> String {
 }
 
 /// Set mouse acceleration based on Desktop Environment
-fn set_mouse_flat_profile() -> Result<(), String> {
+async fn set_mouse_flat_profile() -> Result<()> {
     let de = detect_desktop_environment();
     
     match de.as_str() {
         "gnome" | "cinnamon" => {
             // GNOME/Cinnamon use gsettings
-            Command::new("gsettings")
-                .args(["set", "org.gnome.desktop.peripherals.mouse", "accel-profile", "flat"])
-                .output()
-                .map_err(|e| e.to_string())?;
+            run_user_command("gsettings", &["set", "org.gnome.desktop.peripherals.mouse", "accel-profile", "flat"]).await?;
             Ok(())
         }
         "kde" => {
             // KDE Plasma uses kwriteconfig5
-            Command::new("kwriteconfig5")
-                .args(["--file", "kcminputrc", "--group", "Mouse", "--key", "XLbInptPointerAcceleration", "0"])
-                .output()
-                .map_err(|e| e.to_string())?;
+            run_user_command(
+                "kwriteconfig5",
+                &["--file", "kcminputrc", "--group", "Mouse", "--key", "XLbInptPointerAcceleration", "0"],
+            )
+            .await?;
             Ok(())
         }
         "xfce" | "mate" | "x11" => {
             // X11: Use xinput or libinput config
             // First try to find the mouse device
-            if let Ok(output) = Command::new("xinput").args(["list", "--short"]).output() {
-                let devices = String::from_utf8_lossy(&output.stdout);
-                for line in devices.lines() {
-                    if line.to_lowercase().contains("mouse") {
-                        if let Some(id) = line.split("id=").nth(1).and_then(|s| s.split_whitespace().next()) {
-                            let _ = Command::new("xinput")
-                                .args(["set-prop", id, "libinput Accel Profile Enabled", "0", "1"])
-                                .output();
-                        }
+            let devices = run_user_command("xinput", &["list", "--short"]).await?;
+            for line in devices.lines() {
+                if line.to_lowercase().contains("mouse") {
+                    if let Some(id) = line.split("id=").nth(1).and_then(|s| s.split_whitespace().next()) {
+                        run_user_command("xinput", &["set-prop", id, "libinput Accel Profile Enabled", "0", "1"]).await?;
                     }
                 }
             }
@@ -135,6 +136,60 @@ fn set_mouse_flat_profile() -> Result<(), String> {
     }
 }
 
+// ============================================================================
+// Command Helpers
+// ============================================================================
+
+/// Run a desktop-session command (gsettings, kwriteconfig5, xinput) as the current user
+async fn run_user_command(program: &str, args: &[&str]) -> Result<String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| AppError::CommandFailed(format!("Failed to run {}: {}", program, e)))?;
+    
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let argv = format!("{} {}", program, args.join(" "));
+        Err(AppError::from_command(argv, output.status.code(), &String::from_utf8_lossy(&output.stderr)))
+    }
+}
+
+/// Install packages one at a time so one missing package doesn't sink the rest
+/// Returns the packages that failed; a dismissed authentication dialog aborts
+async fn install_each(manager: &dyn PackageManager, packages: &[&str]) -> Result<Vec<String>> {
+    let mut failed = Vec::new();
+    for package in packages {
+        match manager.install_packages(&[package]).await {
+            Ok(_) => {}
+            Err(AppError::UserCancelled) => return Err(AppError::UserCancelled),
+            Err(e) => {
+                log::warn!("Failed to install {}: {}", package, e);
+                failed.push(package.to_string());
+            }
+        }
+    }
+    Ok(failed)
+}
+
+/// Enable the family's 32-bit repositories, then install the base 32-bit libraries
+async fn enable_32bit(manager: &dyn PackageManager, family: DistroFamily) -> Result<String> {
+    let enabled = manager.enable_multilib().await?;
+    manager.install_packages(MULTILIB_LIBS.for_family(family)).await?;
+    Ok(format!("{} ({})", enabled, family.display_name()))
+}
+
+/// Install an app from Flathub (system-wide), adding the remote first if needed
+async fn install_flatpak(executor: &dyn PrivilegedExecutor, app_id: &str) -> Result<String> {
+    executor.execute(&[
+        PrivilegedOp::add_flatpak_remote("flathub"),
+        PrivilegedOp::package("flatpak", &["install", "-y", "--noninteractive", "flathub", app_id]),
+    ])
+    .await
+}
+
 /// Detect GPU using lspci with fallback to /proc/driver
 fn detect_gpu_internal() -> Option<GpuInfo> {
     // Try lspci first
@@ -291,39 +346,27 @@ fn check_vulkan_support() -> bool {
         .unwrap_or(false)
 }
 
-fn check_multilib() -> bool {
+fn check_multilib(family: DistroFamily) -> bool {
     // Check for 32-bit library support
-    let dpkg_check = Command::new("dpkg")
-        .args(["--print-foreign-architectures"])
-        .output();
-    
-    if let Ok(o) = dpkg_check {
-        let archs = String::from_utf8_lossy(&o.stdout);
-        if archs.contains("i386") {
-            return true;
-        }
-    }
-    
-    // Arch: check for lib32
-    let pacman_check = Command::new("pacman")
-        .args(["-Q", "lib32-glibc"])
-        .output();
-    
-    if let Ok(o) = pacman_check {
-        if o.status.success() {
-            return true;
-        }
+    match family {
+        // Arch: lib32 packages come from [multilib]
+        DistroFamily::Arch => is_native_installed(family, "lib32-glibc"),
+        DistroFamily::Fedora => is_native_installed(family, "glibc.i686"),
+        DistroFamily::Suse => is_native_installed(family, "glibc-32bit"),
+        _ => Command::new("dpkg")
+            .args(["--print-foreign-architectures"])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).contains("i386"))
+            .unwrap_or(false),
     }
-    
-    false
 }
 
 /// Get full gaming status
 #[tauri::command]
 pub fn get_gaming_status() -> GamingStatus {
     let gpu = detect_gpu_internal();
-    let multilib_enabled = check_multilib();
     let distro_family = get_distro_family();
+    let multilib_enabled = check_multilib(distro_family);
     
     let mut issues: Vec<String> = Vec::new();
     let mut score = 100;
@@ -394,14 +437,107 @@ pub struct GamingPackage {
     pub category: String,        // "platform", "compatibility", "tools"
     pub installed: bool,
     pub recommended: bool,
-    pub install_method: String,  // "apt", "flatpak", "script"
+    pub install_method: String,  // Package manager ("apt", "pacman", "dnf", "zypper") or "flatpak"
     pub flatpak_id: Option<String>,
-    pub apt_package: Option<String>,
+    pub native_packages: NativePackages,
+}
+
+/// Package name in each distro family's repositories (None: not packaged there)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct NativePackages {
+    pub debian: Option<String>,
+    pub arch: Option<String>,
+    pub fedora: Option<String>,
+    pub suse: Option<String>,
+}
+
+impl NativePackages {
+    /// Same name on every family
+    fn all(name: &str) -> Self {
+        Self {
+            debian: Some(name.to_string()),
+            arch: Some(name.to_string()),
+            fedora: Some(name.to_string()),
+            suse: Some(name.to_string()),
+        }
+    }
+    
+    pub fn for_family(&self, family: DistroFamily) -> Option<&str> {
+        match family {
+            DistroFamily::Arch => self.arch.as_deref(),
+            DistroFamily::Fedora => self.fedora.as_deref(),
+            DistroFamily::Suse => self.suse.as_deref(),
+            _ => self.debian.as_deref(),
+        }
+    }
+}
+
+/// One install, spelled per distro family
+struct FamilyPackages {
+    debian: &'static [&'static str],
+    arch: &'static [&'static str],
+    fedora: &'static [&'static str],
+    suse: &'static [&'static str],
 }
 
-fn is_apt_installed(package: &str) -> bool {
-    Command::new("dpkg")
-        .args(["-s", package])
+impl FamilyPackages {
+    fn for_family(&self, family: DistroFamily) -> &'static [&'static str] {
+        match family {
+            DistroFamily::Arch => self.arch,
+            DistroFamily::Fedora => self.fedora,
+            DistroFamily::Suse => self.suse,
+            _ => self.debian,
+        }
+    }
+}
+
+/// Base 32-bit libraries Steam and Wine need
+const MULTILIB_LIBS: FamilyPackages = FamilyPackages {
+    debian: &["libc6:i386", "libstdc++6:i386"],
+    arch: &["lib32-glibc", "lib32-gcc-libs"],
+    fedora: &["glibc.i686", "libstdc++.i686"],
+    suse: &["glibc-32bit", "libstdc++6-32bit"],
+};
+
+/// Vulkan loader, 64 and 32-bit
+const VULKAN_LOADER: FamilyPackages = FamilyPackages {
+    debian: &["vulkan-tools", "libvulkan1", "libvulkan1:i386"],
+    arch: &["vulkan-tools", "vulkan-icd-loader", "lib32-vulkan-icd-loader"],
+    fedora: &["vulkan-tools", "vulkan-loader", "vulkan-loader.i686"],
+    suse: &["vulkan-tools", "libvulkan1", "libvulkan1-32bit"],
+};
+
+/// Mesa Vulkan drivers (RADV/ANV); Arch splits them per vendor
+const MESA_VULKAN: FamilyPackages = FamilyPackages {
+    debian: &["mesa-vulkan-drivers", "mesa-vulkan-drivers:i386"],
+    arch: &["vulkan-radeon", "lib32-vulkan-radeon", "vulkan-intel", "lib32-vulkan-intel"],
+    fedora: &["mesa-vulkan-drivers", "mesa-vulkan-drivers.i686"],
+    suse: &["libvulkan_radeon", "libvulkan_intel"],
+};
+
+const WINE_PACKAGES: FamilyPackages = FamilyPackages {
+    debian: &["wine", "winetricks", "libwine:i386"],
+    arch: &["wine", "winetricks"],
+    fedora: &["wine", "winetricks"],
+    suse: &["wine", "winetricks"],
+};
+
+const GAMEMODE_PACKAGES: FamilyPackages = FamilyPackages {
+    debian: &["gamemode", "libgamemode0:i386"],
+    arch: &["gamemode", "lib32-gamemode"],
+    fedora: &["gamemode", "gamemode.i686"],
+    suse: &["gamemode"],
+};
+
+/// Check a package in the family's package database
+fn is_native_installed(family: DistroFamily, package: &str) -> bool {
+    let (program, flag) = match family {
+        DistroFamily::Arch => ("pacman", "-Q"),
+        DistroFamily::Fedora | DistroFamily::Suse => ("rpm", "-q"),
+        _ => ("dpkg", "-s"),
+    };
+    Command::new(program)
+        .args([flag, package])
         .output()
         .map(|o| o.status.success())
         .unwrap_or(false)
@@ -418,7 +554,8 @@ fn is_flatpak_installed(app_id: &str) -> bool {
 /// Get all gaming packages (Tab 1: Essentials & Launchers)
 #[tauri::command]
 pub fn get_gaming_packages() -> Vec<GamingPackage> {
-    vec![
+    let family = get_distro_family();
+    let mut packages = vec![
         // === PLATFORMS ===
         GamingPackage {
             id: "steam".to_string(),
@@ -426,11 +563,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Valve's gaming platform. The #1 way to play games on Linux.".to_string(),
             icon: "🎮".to_string(),
             category: "platform".to_string(),
-            installed: is_apt_installed("steam") || is_flatpak_installed("com.valvesoftware.Steam"),
+            installed: false,
             recommended: true,
-            install_method: "apt".to_string(),
+            install_method: "native".to_string(),
             flatpak_id: Some("com.valvesoftware.Steam".to_string()),
-            apt_package: Some("steam".to_string()),
+            native_packages: NativePackages::all("steam"),
         },
         GamingPackage {
             id: "lutris".to_string(),
@@ -438,11 +575,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Play games from Epic, GOG, Ubisoft, and run Windows games.".to_string(),
             icon: "🍷".to_string(),
             category: "platform".to_string(),
-            installed: is_apt_installed("lutris") || is_flatpak_installed("net.lutris.Lutris"),
+            installed: false,
             recommended: true,
             install_method: "flatpak".to_string(),
             flatpak_id: Some("net.lutris.Lutris".to_string()),
-            apt_package: Some("lutris".to_string()),
+            native_packages: NativePackages::all("lutris"),
         },
         GamingPackage {
             id: "heroic".to_string(),
@@ -450,11 +587,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Open-source Epic Games & GOG launcher. Lightweight alternative.".to_string(),
             icon: "⚔️".to_string(),
             category: "platform".to_string(),
-            installed: is_flatpak_installed("com.heroicgameslauncher.hgl"),
+            installed: false,
             recommended: true,
             install_method: "flatpak".to_string(),
             flatpak_id: Some("com.heroicgameslauncher.hgl".to_string()),
-            apt_package: None,
+            native_packages: NativePackages::default(),
         },
         GamingPackage {
             id: "bottles".to_string(),
@@ -462,11 +599,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Run Windows .exe files easily. Great for standalone games.".to_string(),
             icon: "🍾".to_string(),
             category: "platform".to_string(),
-            installed: is_flatpak_installed("com.usebottles.bottles"),
+            installed: false,
             recommended: false,
             install_method: "flatpak".to_string(),
             flatpak_id: Some("com.usebottles.bottles".to_string()),
-            apt_package: None,
+            native_packages: NativePackages::default(),
         },
         GamingPackage {
             id: "prismlauncher".to_string(),
@@ -474,11 +611,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Minecraft launcher with multi-instance and mod support.".to_string(),
             icon: "🎲".to_string(),
             category: "platform".to_string(),
-            installed: is_flatpak_installed("org.prismlauncher.PrismLauncher"),
+            installed: false,
             recommended: false,
             install_method: "flatpak".to_string(),
             flatpak_id: Some("org.prismlauncher.PrismLauncher".to_string()),
-            apt_package: None,
+            native_packages: NativePackages::default(),
         },
         
         // === COMPATIBILITY TOOLS ===
@@ -488,11 +625,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Download GE-Proton & Wine-GE. Fixes cutscenes and compatibility.".to_string(),
             icon: "🔧".to_string(),
             category: "compatibility".to_string(),
-            installed: is_flatpak_installed("net.davidotek.pupgui2"),
+            installed: false,
             recommended: true,
             install_method: "flatpak".to_string(),
             flatpak_id: Some("net.davidotek.pupgui2".to_string()),
-            apt_package: None,
+            native_packages: NativePackages::default(),
         },
         GamingPackage {
             id: "protontricks".to_string(),
@@ -500,11 +637,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Apply Winetricks to Steam Proton games. Install fonts, DLLs.".to_string(),
             icon: "🪄".to_string(),
             category: "compatibility".to_string(),
-            installed: is_flatpak_installed("com.github.Matoking.protontricks"),
+            installed: false,
             recommended: true,
             install_method: "flatpak".to_string(),
             flatpak_id: Some("com.github.Matoking.protontricks".to_string()),
-            apt_package: None,
+            native_packages: NativePackages::default(),
         },
         GamingPackage {
             id: "winetricks".to_string(),
@@ -512,11 +649,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Install Windows libraries (vcrun, fonts, DirectX) for Wine.".to_string(),
             icon: "📦".to_string(),
             category: "compatibility".to_string(),
-            installed: is_apt_installed("winetricks"),
+            installed: false,
             recommended: true,
-            install_method: "apt".to_string(),
+            install_method: "native".to_string(),
             flatpak_id: None,
-            apt_package: Some("winetricks".to_string()),
+            native_packages: NativePackages::all("winetricks"),
         },
         
         // === PERFORMANCE TOOLS ===
@@ -526,11 +663,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "FPS overlay. Show FPS, CPU/GPU usage, temps while gaming.".to_string(),
             icon: "📊".to_string(),
             category: "tools".to_string(),
-            installed: is_apt_installed("mangohud"),
+            installed: false,
             recommended: true,
-            install_method: "apt".to_string(),
+            install_method: "native".to_string(),
             flatpak_id: None,
-            apt_package: Some("mangohud".to_string()),
+            native_packages: NativePackages::all("mangohud"),
         },
         GamingPackage {
             id: "goverlay".to_string(),
@@ -538,11 +675,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "GUI to configure MangoHud. Customize your overlay easily.".to_string(),
             icon: "🎨".to_string(),
             category: "tools".to_string(),
-            installed: is_flatpak_installed("io.github.benjamimgois.GOverlay"),
+            installed: false,
             recommended: false,
             install_method: "flatpak".to_string(),
             flatpak_id: Some("io.github.benjamimgois.GOverlay".to_string()),
-            apt_package: None,
+            native_packages: NativePackages::default(),
         },
         GamingPackage {
             id: "gamemode".to_string(),
@@ -550,11 +687,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Feral Interactive's optimizer. Auto CPU boost while gaming.".to_string(),
             icon: "⚡".to_string(),
             category: "tools".to_string(),
-            installed: is_apt_installed("gamemode"),
+            installed: false,
             recommended: true,
-            install_method: "apt".to_string(),
+            install_method: "native".to_string(),
             flatpak_id: None,
-            apt_package: Some("gamemode".to_string()),
+            native_packages: NativePackages::all("gamemode"),
         },
         GamingPackage {
             id: "gamescope".to_string(),
@@ -562,11 +699,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Valve's compositor. Force resolution, HDR, FSR upscaling.".to_string(),
             icon: "🖼️".to_string(),
             category: "tools".to_string(),
-            installed: is_apt_installed("gamescope"),
+            installed: false,
             recommended: false,
-            install_method: "apt".to_string(),
+            install_method: "native".to_string(),
             flatpak_id: None,
-            apt_package: Some("gamescope".to_string()),
+            native_packages: NativePackages::all("gamescope"),
         },
         
         // === STREAMING ===
@@ -576,11 +713,11 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Stream and record your games. Industry standard.".to_string(),
             icon: "📹".to_string(),
             category: "streaming".to_string(),
-            installed: is_flatpak_installed("com.obsproject.Studio"),
+            installed: false,
             recommended: false,
             install_method: "flatpak".to_string(),
             flatpak_id: Some("com.obsproject.Studio".to_string()),
-            apt_package: Some("obs-studio".to_string()),
+            native_packages: NativePackages::all("obs-studio"),
         },
         GamingPackage {
             id: "sunshine".to_string(),
@@ -588,13 +725,27 @@ pub fn get_gaming_packages() -> Vec<GamingPackage> {
             description: "Self-hosted game streaming. Use with Moonlight client.".to_string(),
             icon: "☀️".to_string(),
             category: "streaming".to_string(),
-            installed: is_flatpak_installed("dev.lizardbyte.app.Sunshine"),
+            installed: false,
             recommended: false,
             install_method: "flatpak".to_string(),
             flatpak_id: Some("dev.lizardbyte.app.Sunshine".to_string()),
-            apt_package: None,
+            native_packages: NativePackages::default(),
         },
-    ]
+    ];
+    
+    // Resolve against this distro: native packages fall back to Flatpak where not packaged
+    for pkg in &mut packages {
+        let native = pkg.native_packages.for_family(family);
+        pkg.installed = native.is_some_and(|name| is_native_installed(family, name))
+            || pkg.flatpak_id.as_deref().is_some_and(is_flatpak_installed);
+        let prefers_native = pkg.install_method == "native" || pkg.flatpak_id.is_none();
+        pkg.install_method = match native {
+            Some(_) if prefers_native => family.package_manager_name().to_string(),
+            _ => "flatpak".to_string(),
+        };
+    }
+    
+    packages
 }
 
 // ============================================================================
@@ -724,7 +875,7 @@ pub fn get_gaming_tweaks() -> Vec<GamingTweak> {
             min_value: None,
             max_value: None,
             value_type: "dropdown".to_string(),
-            is_optimal: governor == "performance" || is_apt_installed("gamemode"),
+            is_optimal: governor == "performance" || is_native_installed(get_distro_family(), "gamemode"),
             requires_reboot: false,
             risk_level: "safe".to_string(),
         },
@@ -783,258 +934,170 @@ pub fn get_gaming_tweaks() -> Vec<GamingTweak> {
 // Apply Actions
 // ============================================================================
 
+/// ESYNC/FSYNC open file limit
+const NOFILE_LIMIT: u32 = 1_048_576;
+
 /// Install a gaming package
 #[tauri::command]
-pub fn install_gaming_package(pkg_id: String) -> Result<String, String> {
-    let packages = get_gaming_packages();
+pub async fn install_gaming_package(pkg_id: String, state: State<'_, AppState>) -> Result<String> {
+    let family = state.context.family;
+    let manager = state.context.package_manager.clone();
+    let packages = tokio::task::spawn_blocking(get_gaming_packages).await.unwrap();
     let pkg = packages.iter().find(|p| p.id == pkg_id)
-        .ok_or_else(|| "Package not found".to_string())?;
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown gaming package: {}", pkg_id)))?;
     
     if pkg.install_method == "flatpak" {
         if let Some(ref flatpak_id) = pkg.flatpak_id {
-            // Ensure Flatpak and Flathub are available
-            Command::new("flatpak")
-                .args(["remote-add", "--if-not-exists", "flathub", "https://dl.flathub.org/repo/flathub.flatpakrepo"])
-                .output()
-                .ok();
-            
-            let output = Command::new("pkexec")
-                .args(["flatpak", "install", "-y", "flathub", flatpak_id])
-                .output()
-                .map_err(|e| e.to_string())?;
-            
-            if output.status.success() {
-                return Ok(format!("{} installed successfully!", pkg.name));
-            } else {
-                return Err(String::from_utf8_lossy(&output.stderr).to_string());
-            }
+            install_flatpak(state.executor.as_ref(), flatpak_id).await?;
+            return Ok(format!("{} installed successfully!", pkg.name));
         }
-    } else if pkg.install_method == "apt" {
-        if let Some(ref apt_pkg) = pkg.apt_package {
-            // Enable 32-bit for Steam
-            if pkg_id == "steam" {
-                Command::new("pkexec")
-                    .args(["dpkg", "--add-architecture", "i386"])
-                    .output()
-                    .ok();
-                
-                Command::new("pkexec")
-                    .args(["apt-get", "update"])
-                    .output()
-                    .ok();
-            }
-            
-            let output = Command::new("pkexec")
-                .args(["apt-get", "install", "-y", apt_pkg])
-                .output()
-                .map_err(|e| e.to_string())?;
-            
-            if output.status.success() {
-                return Ok(format!("{} installed successfully!", pkg.name));
-            } else {
-                return Err(String::from_utf8_lossy(&output.stderr).to_string());
-            }
+    } else if let Some(native) = pkg.native_packages.for_family(family) {
+        // Steam needs 32-bit libraries (and RPM Fusion on Fedora)
+        if pkg_id == "steam" {
+            enable_32bit(manager.as_ref(), family).await?;
         }
+        
+        manager.install_packages(&[native]).await?;
+        return Ok(format!("{} installed successfully!", pkg.name));
     }
     
-    Err("Could not determine install method".to_string())
+    Err(AppError::NotSupported(format!("No install method for {} on {}", pkg.name, family.display_name())))
 }
 
 /// Enable 32-bit multilib support
 #[tauri::command]
-pub fn enable_multilib() -> Result<String, String> {
-    // Ubuntu/Debian
-    let output = Command::new("pkexec")
-        .args(["dpkg", "--add-architecture", "i386"])
-        .output()
-        .map_err(|e| e.to_string())?;
-    
-    if output.status.success() {
-        Command::new("pkexec")
-            .args(["apt-get", "update"])
-            .output()
-            .ok();
-        
-        // Install 32-bit libs
-        Command::new("pkexec")
-            .args(["apt-get", "install", "-y", "libc6:i386", "libstdc++6:i386"])
-            .output()
-            .ok();
-        
-        return Ok("32-bit support enabled!".to_string());
-    }
-    
-    Err("Failed to enable 32-bit support".to_string())
+pub async fn enable_multilib(state: State<'_, AppState>) -> Result<String> {
+    let family = state.context.family;
+    enable_32bit(state.context.package_manager.as_ref(), family).await?;
+    Ok("32-bit support enabled!".to_string())
 }
 
 /// Install Vulkan packages
 #[tauri::command]
-pub fn install_vulkan_support() -> Result<String, String> {
-    Command::new("pkexec")
-        .args(["dpkg", "--add-architecture", "i386"])
-        .output()
-        .ok();
-    
-    Command::new("pkexec")
-        .args(["apt-get", "update"])
-        .output()
-        .ok();
-    
-    let packages = vec![
-        "vulkan-tools",
-        "libvulkan1",
-        "libvulkan1:i386",
-        "mesa-vulkan-drivers",
-        "mesa-vulkan-drivers:i386",
-    ];
-    
-    for pkg in packages {
-        Command::new("pkexec")
-            .args(["apt-get", "install", "-y", pkg])
-            .output()
-            .ok();
+pub async fn install_vulkan_support(state: State<'_, AppState>) -> Result<String> {
+    let family = state.context.family;
+    let manager = state.context.package_manager.as_ref();
+    manager.enable_multilib().await?;
+    
+    let packages: Vec<&str> = VULKAN_LOADER.for_family(family)
+        .iter()
+        .chain(MESA_VULKAN.for_family(family))
+        .copied()
+        .collect();
+    
+    let failed = install_each(manager, &packages).await?;
+    if !failed.is_empty() {
+        return Err(AppError::CommandFailed(format!("Failed to install: {}", failed.join(", "))));
     }
     
     Ok("Vulkan support installed!".to_string())
 }
 
+/// Recommended gaming sysctl values (persisted under the "gaming" owner)
+const GAMING_SYSCTL_TWEAKS: &[(&str, &str)] = &[
+    ("vm.max_map_count", "2147483642"),   // Prevent crashes in heavy games
+    ("vm.swappiness", "10"),              // Keep games in RAM
+    ("kernel.split_lock_mitigate", "0"),  // Fix stuttering in some games
+    ("net.ipv4.tcp_mtu_probing", "1"),    // Fix network issues (Uplay, etc.)
+];
+
+/// Persist the recommended sysctl values, then apply the ones this kernel has
+/// Returns the applied `key = value` pairs
+async fn apply_gaming_sysctls(executor: &dyn PrivilegedExecutor) -> Result<Vec<String>> {
+    let mut settings = PersistentSettings::load();
+    for (key, value) in GAMING_SYSCTL_TWEAKS {
+        settings.set_sysctl(OWNER_GAMING, key, value);
+    }
+    settings.save(executor).await?;
+    
+    // kernel.split_lock_mitigate only exists on newer kernels and some CPUs
+    let supported: Vec<_> = GAMING_SYSCTL_TWEAKS
+        .iter()
+        .filter(|(key, _)| read_sysctl(key).is_some())
+        .collect();
+    let ops: Vec<_> = supported.iter().map(|(key, value)| PrivilegedOp::sysctl(key, value)).collect();
+    executor.execute(&ops).await?;
+    
+    Ok(supported.iter().map(|(key, value)| format!("{} = {}", key, value)).collect())
+}
+
 /// Apply a gaming tweak
 #[tauri::command]
-pub fn apply_gaming_tweak(tweak_id: String, value: String) -> Result<String, String> {
+pub async fn apply_gaming_tweak(tweak_id: String, value: String, state: State<'_, AppState>) -> Result<String> {
+    let executor = state.executor.as_ref();
     match tweak_id.as_str() {
         "vm.max_map_count" | "vm.swappiness" | "kernel.split_lock_mitigate" | "net.ipv4.tcp_mtu_probing" => {
-            // Write to sysctl.d for persistence
-            let content = format!("# Game Optimizer Tweak\n{}={}\n", tweak_id, value);
+            let op = PrivilegedOp::sysctl(&tweak_id, &value);
+            op.validate()?;
             
-            let output = Command::new("pkexec")
-                .args(["bash", "-c", &format!(
-                    "echo '{}' > /etc/sysctl.d/90-game-optimizer.conf && sysctl -w {}={}",
-                    content, tweak_id, value
-                )])
-                .output()
-                .map_err(|e| e.to_string())?;
+            // Persist through the shared Glance sysctl drop-in
+            let mut settings = PersistentSettings::load();
+            settings.set_sysctl(OWNER_GAMING, &tweak_id, &value);
+            settings.save(executor).await?;
             
-            if output.status.success() {
-                Ok(format!("{} set to {}", tweak_id, value))
-            } else {
-                Err(String::from_utf8_lossy(&output.stderr).to_string())
-            }
+            executor.execute(&[op]).await?;
+            Ok(format!("{} set to {}", tweak_id, value))
         }
         "nofile_limit" => {
             // Write to limits.d
-            let content = "# Game Optimizer - ESYNC/FSYNC\n* hard nofile 1048576\n* soft nofile 1048576\n";
-            
-            let output = Command::new("pkexec")
-                .args(["bash", "-c", &format!(
-                    "echo '{}' > /etc/security/limits.d/90-game-limits.conf",
-                    content
-                )])
-                .output()
-                .map_err(|e| e.to_string())?;
-            
-            if output.status.success() {
-                Ok("ESYNC/FSYNC limit set. Please log out and back in.".to_string())
-            } else {
-                Err(String::from_utf8_lossy(&output.stderr).to_string())
-            }
+            executor.execute(&[PrivilegedOp::SetNofileLimit { limit: NOFILE_LIMIT }]).await?;
+            Ok("ESYNC/FSYNC limit set. Please log out and back in.".to_string())
         }
         "mouse_accel" => {
             // Use gsettings for GNOME
             let profile = if value == "disable" { "flat" } else { "default" };
             
-            let output = Command::new("gsettings")
-                .args(["set", "org.gnome.desktop.peripherals.mouse", "accel-profile", profile])
-                .output()
-                .map_err(|e| e.to_string())?;
-            
-            if output.status.success() {
-                Ok(format!("Mouse acceleration set to {}", profile))
-            } else {
-                Err(String::from_utf8_lossy(&output.stderr).to_string())
-            }
+            run_user_command("gsettings", &["set", "org.gnome.desktop.peripherals.mouse", "accel-profile", profile]).await?;
+            Ok(format!("Mouse acceleration set to {}", profile))
         }
-        _ => Err("Unknown tweak".to_string())
+        _ => Err(AppError::InvalidInput(format!("Unknown gaming tweak: {}", tweak_id)))
     }
 }
 
 /// Apply all recommended tweaks at once
+/// Lists what was applied; fails on the first privileged error
 #[tauri::command]
-pub fn apply_all_gaming_tweaks() -> Result<Vec<String>, String> {
-    let mut applied = Vec::new();
-    
+pub async fn apply_all_gaming_tweaks(state: State<'_, AppState>) -> Result<Vec<String>> {
+    let executor = state.executor.as_ref();
     // Sysctl tweaks
-    let sysctl_content = r#"# Game Optimizer - All Recommended Tweaks
-# Applied by Glance Gaming Center
-vm.max_map_count=2147483642
-vm.swappiness=10
-kernel.split_lock_mitigate=0
-net.ipv4.tcp_mtu_probing=1
-"#;
-    
-    let output = Command::new("pkexec")
-        .args(["bash", "-c", &format!(
-            "echo '{}' > /etc/sysctl.d/90-game-optimizer.conf && sysctl --system",
-            sysctl_content
-        )])
-        .output()
-        .map_err(|e| e.to_string())?;
-    
-    if output.status.success() {
-        applied.push("vm.max_map_count = 2147483642".to_string());
-        applied.push("vm.swappiness = 10".to_string());
-        applied.push("kernel.split_lock_mitigate = 0".to_string());
-        applied.push("net.ipv4.tcp_mtu_probing = 1".to_string());
-    }
+    let mut applied = apply_gaming_sysctls(executor).await?;
     
     // ESYNC/FSYNC limit
-    let limits_content = "# Game Optimizer - ESYNC/FSYNC\n* hard nofile 1048576\n* soft nofile 1048576\n";
-    
-    Command::new("pkexec")
-        .args(["bash", "-c", &format!(
-            "echo '{}' > /etc/security/limits.d/90-game-limits.conf",
-            limits_content
-        )])
-        .output()
-        .ok();
-    
-    applied.push("nofile limit = 1048576".to_string());
-    
-    // Mouse acceleration
-    Command::new("gsettings")
-        .args(["set", "org.gnome.desktop.peripherals.mouse", "accel-profile", "flat"])
-        .output()
-        .ok();
+    executor.execute(&[PrivilegedOp::SetNofileLimit { limit: NOFILE_LIMIT }]).await?;
+    applied.push(format!("nofile limit = {}", NOFILE_LIMIT));
     
-    applied.push("Mouse acceleration = disabled".to_string());
+    // Mouse acceleration (no GNOME session is not an error)
+    match run_user_command("gsettings", &["set", "org.gnome.desktop.peripherals.mouse", "accel-profile", "flat"]).await {
+        Ok(_) => applied.push("Mouse acceleration = disabled".to_string()),
+        Err(e) => log::warn!("Mouse acceleration not changed: {}", e),
+    }
     
     Ok(applied)
 }
 
 /// Reset all gaming tweaks
 #[tauri::command]
-pub fn reset_gaming_tweaks() -> Result<String, String> {
-    // Remove our config files
-    Command::new("pkexec")
-        .args(["rm", "-f", "/etc/sysctl.d/90-game-optimizer.conf"])
-        .output()
-        .ok();
-    
-    Command::new("pkexec")
-        .args(["rm", "-f", "/etc/security/limits.d/90-game-limits.conf"])
-        .output()
-        .ok();
-    
-    // Reload sysctl defaults
-    Command::new("pkexec")
-        .args(["sysctl", "--system"])
-        .output()
-        .ok();
+pub async fn reset_gaming_tweaks(state: State<'_, AppState>) -> Result<String> {
+    let executor = state.executor.as_ref();
+    // Drop gaming keys from the shared drop-in (general tweaks stay)
+    let mut settings = PersistentSettings::load();
+    let restore = settings.remove_owner(OWNER_GAMING);
+    settings.save(executor).await?;
+    
+    // Remove the limits file and put back what tweaks still want, or the values from before
+    let mut ops = vec![PrivilegedOp::ResetNofileLimit];
+    ops.extend(
+        restore
+            .iter()
+            .filter(|(key, _)| read_sysctl(key).is_some())
+            .map(|(key, value)| PrivilegedOp::sysctl(key, value)),
+    );
+    executor.execute(&ops).await?;
     
     // Reset mouse acceleration
-    Command::new("gsettings")
-        .args(["reset", "org.gnome.desktop.peripherals.mouse", "accel-profile"])
-        .output()
-        .ok();
+    if let Err(e) = run_user_command("gsettings", &["reset", "org.gnome.desktop.peripherals.mouse", "accel-profile"]).await {
+        log::warn!("Mouse acceleration not reset: {}", e);
+    }
     
     Ok("All gaming tweaks reset to system defaults.".to_string())
 }
@@ -1198,7 +1261,8 @@ pub fn get_gaming_checklist() -> GamingChecklist {
     let mut missing = Vec::new();
     
     // 1. Multilib (32-bit)
-    let multilib_ok = check_multilib();
+    let family = get_distro_family();
+    let multilib_ok = check_multilib(family);
     if !multilib_ok {
         missing.push("32-bit architecture not enabled".to_string());
     }
@@ -1239,7 +1303,7 @@ pub fn get_gaming_checklist() -> GamingChecklist {
     }
     
     // 6. Gamemode
-    let gamemode_ok = is_apt_installed("gamemode");
+    let gamemode_ok = is_native_installed(family, "gamemode");
     if !gamemode_ok {
         missing.push("GameMode not installed".to_string());
     }
@@ -1261,21 +1325,159 @@ pub fn get_gaming_checklist() -> GamingChecklist {
 /// ONE-TOUCH Gaming Setup - Semua Layer sekaligus!
 /// Ini fungsi utama yang user minta - 1 klik langsung gaming ready
 /// Uses DistroFamily adapter for proper multi-distro support
+/// Reports each layer as a progress step; cancellable through `cancel_operation(operation_id)`
 #[tauri::command]
-pub fn one_touch_gaming_setup() -> Result<Vec<String>, String> {
-    let mut steps_done = Vec::new();
+pub async fn one_touch_gaming_setup(
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>> {
+    let family = state.context.family;
+    let manager = state.context.package_manager.clone();
+    let executor = state.executor.clone();
+    state
+        .track(&app, operation_id, |progress| async move {
+            run_one_touch_setup(&progress, manager.as_ref(), executor.as_ref(), family).await
+        })
+        .await
+}
+
+/// Step list of the one-touch setup, mirrored to the progress channel as it grows
+struct SetupLog<'a> {
+    progress: &'a Progress,
+    lines: Vec<String>,
+    failures: usize,
+}
+
+impl SetupLog<'_> {
+    fn push(&mut self, line: String) {
+        if !line.is_empty() {
+            self.progress.log(line.clone());
+        }
+        self.lines.push(line);
+    }
+
+    /// Log `done` if the step succeeded, the error otherwise; returns whether it succeeded
+    /// A dismissed authentication dialog aborts the whole setup
+    fn record<T>(&mut self, what: &str, result: Result<T>, done: String) -> Result<bool> {
+        match result {
+            Ok(_) => {
+                self.push(done);
+                Ok(true)
+            }
+            Err(AppError::UserCancelled) => Err(AppError::UserCancelled),
+            Err(e) => {
+                self.failures += 1;
+                self.push(format!("❌ {} failed: {}", what, e));
+                Ok(false)
+            }
+        }
+    }
+
+    /// Like `record`, for an `install_each` result
+    fn record_installs(&mut self, what: &str, result: Result<Vec<String>>, done: String) -> Result<bool> {
+        match result {
+            Ok(failed) if !failed.is_empty() => {
+                self.failures += 1;
+                self.push(format!("❌ {} failed: could not install {}", what, failed.join(", ")));
+                Ok(false)
+            }
+            result => self.record(what, result, done),
+        }
+    }
+}
+
+/// GPU driver packages for a vendor and distro, with the success line
+fn driver_packages(gpu_vendor: &str, family: DistroFamily, nvidia_driver: &str) -> Option<(Vec<String>, String)> {
+    let (packages, done): (Vec<&str>, &str) = match (gpu_vendor, family) {
+        // Arch: nvidia-dkms + lib32
+        ("nvidia", DistroFamily::Arch) => {
+            (vec!["nvidia-dkms", "nvidia-utils", "lib32-nvidia-utils"], "✓ NVIDIA drivers installed (Arch)")
+        }
+        // Fedora: Use RPM Fusion
+        ("nvidia", DistroFamily::Fedora) => {
+            (vec!["akmod-nvidia", "xorg-x11-drv-nvidia-cuda"], "✓ NVIDIA drivers installed (Fedora)")
+        }
+        ("nvidia", DistroFamily::Suse) => {
+            (vec!["nvidia-video-G06", "nvidia-gl-G06"], "✓ NVIDIA drivers installed (openSUSE)")
+        }
+        // Debian/Ubuntu: Detected driver version
+        ("nvidia", _) => {
+            let gl_package = nvidia_driver.replace("nvidia-driver-", "libnvidia-gl-") + ":i386";
+            return Some((
+                vec![nvidia_driver.to_string(), gl_package, "nvidia-settings".to_string()],
+                format!("✓ {} installed (Debian)", nvidia_driver),
+            ));
+        }
+        ("amd", DistroFamily::Arch) => {
+            (vec!["vulkan-radeon", "lib32-vulkan-radeon", "mesa", "lib32-mesa"], "✓ AMD Mesa (RADV) installed (Arch)")
+        }
+        ("amd", DistroFamily::Fedora) => (
+            vec!["mesa-vulkan-drivers", "mesa-vulkan-drivers.i686", "mesa-dri-drivers.i686"],
+            "✓ AMD Mesa (RADV) installed (Fedora)",
+        ),
+        ("amd", DistroFamily::Suse) => (
+            vec!["Mesa-vulkan-drivers", "Mesa-libva-drivers", "Mesa-32bit"],
+            "✓ AMD Mesa (RADV) installed (openSUSE)",
+        ),
+        ("amd", _) => (
+            vec!["mesa-vulkan-drivers", "mesa-vulkan-drivers:i386", "libgl1-mesa-dri:i386"],
+            "✓ AMD Mesa (RADV) installed (Debian)",
+        ),
+        ("intel", DistroFamily::Arch) => (
+            vec!["vulkan-intel", "lib32-vulkan-intel", "intel-media-driver"],
+            "✓ Intel Mesa (ANV) installed (Arch)",
+        ),
+        ("intel", DistroFamily::Fedora) => (
+            vec!["mesa-vulkan-drivers", "mesa-vulkan-drivers.i686", "mesa-dri-drivers.i686"],
+            "✓ Intel Mesa (ANV) installed (Fedora)",
+        ),
+        ("intel", DistroFamily::Suse) => (
+            vec!["Mesa-vulkan-drivers", "Mesa-vulkan-drivers-32bit", "Mesa-32bit"],
+            "✓ Intel Mesa (ANV) installed (openSUSE)",
+        ),
+        ("intel", _) => (
+            vec!["mesa-vulkan-drivers", "mesa-vulkan-drivers:i386", "intel-media-va-driver"],
+            "✓ Intel Mesa (ANV) installed (Debian)",
+        ),
+        _ => return None,
+    };
+    Some((packages.iter().map(|p| p.to_string()).collect(), done.to_string()))
+}
+
+async fn run_one_touch_setup(
+    progress: &Progress,
+    manager: &dyn PackageManager,
+    executor: &dyn PrivilegedExecutor,
+    distro_family: DistroFamily,
+) -> Result<Vec<String>> {
+    let mut steps_done = SetupLog { progress, lines: Vec::new(), failures: 0 };
+    progress.step("Pre-flight checks", Some(0));
     
     // ========================================
     // PRE-FLIGHT CHECKS (Fail-Fast)
     // ========================================
     
+    let (locked, gpu, nvidia_driver) = tokio::task::spawn_blocking(move || {
+        let gpu = detect_gpu_internal();
+        let nvidia_driver = match (&gpu, distro_family) {
+            (Some(gpu), DistroFamily::Debian) if gpu.vendor == "nvidia" => detect_nvidia_driver_package(),
+            _ => String::new(),
+        };
+        (is_package_manager_locked(), gpu, nvidia_driver)
+    })
+    .await
+    .map_err(|e| AppError::System(format!("Task failed: {}", e)))?;
+
     // Check if package manager is locked
-    if is_package_manager_locked() {
-        return Err("❌ Package manager is locked! Close other update processes (Software Center, apt, dnf, pacman) and try again.".to_string());
+    if locked {
+        return Err(AppError::PackageLocked(CommandContext::new(
+            distro_family.package_manager_name(),
+            None,
+            "Close other update processes (Software Center, apt, dnf, pacman) and try again.",
+        )));
     }
-    
-    let distro_family = get_distro_family();
-    let gpu = detect_gpu_internal();
+
     let gpu_vendor = gpu.as_ref().map(|g| g.vendor.as_str()).unwrap_or("unknown");
     
     steps_done.push(format!("🖥️ Detected: {} distro, {} GPU", distro_family.display_name(), gpu_vendor.to_uppercase()));
@@ -1285,153 +1487,36 @@ pub fn one_touch_gaming_setup() -> Result<Vec<String>, String> {
     // LAYER 1: Driver & Arsitektur
     // ========================================
     
+    progress.step("Layer 1: Drivers & 32-bit support", Some(5));
+    
     // 1a. Enable 32-bit architecture (distro-specific)
     steps_done.push("🔧 Enabling 32-bit architecture...".to_string());
-    
-    match distro_family {
-        DistroFamily::Arch => {
-            // Arch: Uncomment [multilib] in pacman.conf
-            let _ = Command::new("pkexec")
-                .args(["bash", "-c", "sed -i '/\\[multilib\\]/,/Include/s/^#//' /etc/pacman.conf && pacman -Sy"])
-                .output();
-            steps_done.push("✓ Multilib enabled (Arch)".to_string());
-        }
-        DistroFamily::Fedora => {
-            // Fedora: Usually multilib is enabled, but ensure glibc.i686
-            let _ = Command::new("pkexec")
-                .args(["dnf", "install", "-y", "glibc.i686"])
-                .output();
-            steps_done.push("✓ 32-bit glibc installed (Fedora)".to_string());
-        }
-        DistroFamily::Suse => {
-            // openSUSE: Similar to Fedora
-            let _ = Command::new("pkexec")
-                .args(["zypper", "install", "-y", "glibc-32bit"])
-                .output();
-            steps_done.push("✓ 32-bit glibc installed (openSUSE)".to_string());
-        }
-        _ => {
-            // Debian/Ubuntu
-            let _ = Command::new("pkexec")
-                .args(["dpkg", "--add-architecture", "i386"])
-                .output();
-            let _ = Command::new("pkexec")
-                .args(["apt-get", "update"])
-                .output();
-            steps_done.push("✓ 32-bit (i386) enabled (Debian)".to_string());
-        }
-    }
+    let result = enable_32bit(manager, distro_family).await;
+    let done = result.as_ref().map(|line| format!("✓ {}", line)).unwrap_or_default();
+    let multilib_ok = steps_done.record("32-bit support", result, done)?;
     
     // 1b. GPU-specific drivers
     steps_done.push(format!("🔧 Installing {} drivers...", gpu_vendor.to_uppercase()));
+    progress.step(format!("Layer 1: Installing {} drivers", gpu_vendor.to_uppercase()), Some(15));
     
-    // Layer 1 success flag for fail-fast
-    let mut layer1_ok = true;
-    
-    match (gpu_vendor, distro_family) {
-        ("nvidia", DistroFamily::Arch) => {
-            // Arch: nvidia-dkms + lib32
-            for pkg in ["nvidia-dkms", "nvidia-utils", "lib32-nvidia-utils"] {
-                let result = Command::new("pkexec")
-                    .args(["pacman", "-S", "--noconfirm", pkg])
-                    .output();
-                if result.is_err() { layer1_ok = false; }
-            }
-            steps_done.push("✓ NVIDIA drivers installed (Arch)".to_string());
-        }
-        ("nvidia", DistroFamily::Fedora) => {
-            // Fedora: Use RPM Fusion
-            for pkg in ["akmod-nvidia", "xorg-x11-drv-nvidia-cuda"] {
-                let result = Command::new("pkexec")
-                    .args(["dnf", "install", "-y", pkg])
-                    .output();
-                if result.is_err() { layer1_ok = false; }
-            }
-            steps_done.push("✓ NVIDIA drivers installed (Fedora)".to_string());
-        }
-        ("nvidia", DistroFamily::Suse) => {
-            // openSUSE: Use opi nvidia
-            let result = Command::new("pkexec")
-                .args(["zypper", "install", "-y", "nvidia-video-G06", "nvidia-gl-G06"])
-                .output();
-            if result.is_err() { layer1_ok = false; }
-            steps_done.push("✓ NVIDIA drivers installed (openSUSE)".to_string());
-        }
-        ("nvidia", _) => {
-            // Debian/Ubuntu: Detect available driver version
-            let driver_pkg = detect_nvidia_driver_package();
-            let pkgs = vec![
-                driver_pkg.clone(),
-                driver_pkg.replace("nvidia-driver-", "libnvidia-gl-") + ":i386",
-                "nvidia-settings".to_string(),
-            ];
-            for pkg in pkgs {
-                let result = Command::new("pkexec")
-                    .args(["apt-get", "install", "-y", &pkg])
-                    .output();
-                if result.is_err() { layer1_ok = false; }
-            }
-            steps_done.push(format!("✓ {} installed (Debian)", driver_pkg));
-        }
-        ("amd", DistroFamily::Arch) => {
-            for pkg in ["vulkan-radeon", "lib32-vulkan-radeon", "mesa", "lib32-mesa"] {
-                let _ = Command::new("pkexec")
-                    .args(["pacman", "-S", "--noconfirm", pkg])
-                    .output();
-            }
-            steps_done.push("✓ AMD Mesa (RADV) installed (Arch)".to_string());
-        }
-        ("amd", DistroFamily::Fedora) => {
-            for pkg in ["mesa-vulkan-drivers", "mesa-vulkan-drivers.i686", "mesa-dri-drivers.i686"] {
-                let _ = Command::new("pkexec")
-                    .args(["dnf", "install", "-y", pkg])
-                    .output();
-            }
-            steps_done.push("✓ AMD Mesa (RADV) installed (Fedora)".to_string());
-        }
-        ("amd", DistroFamily::Suse) => {
-            for pkg in ["Mesa-vulkan-drivers", "Mesa-libva-drivers", "Mesa-32bit"] {
-                let _ = Command::new("pkexec")
-                    .args(["zypper", "install", "-y", pkg])
-                    .output();
-            }
-            steps_done.push("✓ AMD Mesa (RADV) installed (openSUSE)".to_string());
-        }
-        ("amd", _) => {
-            for pkg in ["mesa-vulkan-drivers", "mesa-vulkan-drivers:i386", "libgl1-mesa-dri:i386"] {
-                let _ = Command::new("pkexec")
-                    .args(["apt-get", "install", "-y", pkg])
-                    .output();
-            }
-            steps_done.push("✓ AMD Mesa (RADV) installed (Debian)".to_string());
+    let drivers_ok = match driver_packages(gpu_vendor, distro_family, &nvidia_driver) {
+        Some((packages, done)) => {
+            let packages: Vec<&str> = packages.iter().map(|p| p.as_str()).collect();
+            let result = install_each(manager, &packages).await;
+            steps_done.record_installs("GPU driver install", result, done)?
         }
-        ("intel", DistroFamily::Arch) => {
-            for pkg in ["vulkan-intel", "lib32-vulkan-intel", "intel-media-driver"] {
-                let _ = Command::new("pkexec")
-                    .args(["pacman", "-S", "--noconfirm", pkg])
-                    .output();
-            }
-            steps_done.push("✓ Intel Mesa (ANV) installed (Arch)".to_string());
-        }
-        ("intel", _) => {
-            for pkg in ["mesa-vulkan-drivers", "mesa-vulkan-drivers:i386", "intel-media-va-driver"] {
-                let _ = Command::new("pkexec")
-                    .args(["apt-get", "install", "-y", pkg])
-                    .output();
-            }
-            steps_done.push("✓ Intel Mesa (ANV) installed (Debian)".to_string());
-        }
-        _ => {
+        None => {
             steps_done.push("⚠ Unknown GPU - skipping driver install".to_string());
+            true
         }
-    }
+    };
     
     // FAIL-FAST: If Layer 1 (Driver) failed, stop here
-    if !layer1_ok {
+    if !multilib_ok || !drivers_ok {
         steps_done.push("".to_string());
         steps_done.push("❌ LAYER 1 FAILED! GPU driver installation had errors.".to_string());
         steps_done.push("   Please fix driver issues before continuing.".to_string());
-        return Err(steps_done.join("\n"));
+        return Err(AppError::CommandFailed(steps_done.lines.join("\n")));
     }
     
     steps_done.push("".to_string());
@@ -1441,148 +1526,88 @@ pub fn one_touch_gaming_setup() -> Result<Vec<String>, String> {
     // LAYER 2: Compatibility (Wine/Vulkan)
     // ========================================
     
+    progress.step("Layer 2: Vulkan, Wine & GameMode", Some(40));
+    
     steps_done.push("🔧 Installing Vulkan loader & Wine dependencies...".to_string());
-    let vulkan_pkgs = vec![
-        "vulkan-tools",
-        "libvulkan1",
-        "libvulkan1:i386",
-    ];
-    for pkg in vulkan_pkgs {
-        Command::new("pkexec")
-            .args(["apt-get", "install", "-y", pkg])
-            .output()
-            .ok();
-    }
-    steps_done.push("✓ Vulkan loader installed".to_string());
+    let result = install_each(manager, VULKAN_LOADER.for_family(distro_family)).await;
+    steps_done.record_installs("Vulkan loader", result, "✓ Vulkan loader installed".to_string())?;
     
     // Wine dependencies
-    let wine_deps = vec![
-        "wine",
-        "winetricks",
-        "libwine:i386",
-    ];
-    for pkg in wine_deps {
-        Command::new("pkexec")
-            .args(["apt-get", "install", "-y", pkg])
-            .output()
-            .ok();
-    }
-    steps_done.push("✓ Wine dependencies installed".to_string());
+    let result = install_each(manager, WINE_PACKAGES.for_family(distro_family)).await;
+    steps_done.record_installs("Wine", result, "✓ Wine dependencies installed".to_string())?;
     
     // GameMode (Layer 2.5)
     steps_done.push("🔧 Installing GameMode...".to_string());
-    Command::new("pkexec")
-        .args(["apt-get", "install", "-y", "gamemode", "libgamemode0:i386"])
-        .output()
-        .ok();
-    steps_done.push("✓ GameMode installed (auto CPU boost)".to_string());
+    let result = install_each(manager, GAMEMODE_PACKAGES.for_family(distro_family)).await;
+    steps_done.record_installs("GameMode", result, "✓ GameMode installed (auto CPU boost)".to_string())?;
     
     // ========================================
     // LAYER 3: System Tweaks
     // ========================================
     
-    steps_done.push("🔧 Applying kernel tweaks...".to_string());
+    progress.step("Layer 3: System tweaks", Some(65));
     
-    // Sysctl tweaks (vm.max_map_count, swappiness, etc.)
-    let sysctl_content = r#"# Glance Gaming Center - One-Touch Setup
-# Layer 3: Kernel Tweaks for Gaming
-
-# Prevent crashes in heavy games (Hogwarts, CS2, DayZ)
-vm.max_map_count=2147483642
-
-# Keep games in RAM (don't swap aggressively)
-vm.swappiness=10
-
-# Fix stuttering in some games
-kernel.split_lock_mitigate=0
-
-# Fix network issues (Uplay, etc.)
-net.ipv4.tcp_mtu_probing=1
-"#;
+    steps_done.push("🔧 Applying kernel tweaks...".to_string());
     
-    Command::new("pkexec")
-        .args(["bash", "-c", &format!(
-            "echo '{}' > /etc/sysctl.d/99-gaming.conf && sysctl --system",
-            sysctl_content
-        )])
-        .output()
-        .ok();
-    steps_done.push("✓ vm.max_map_count = 2147483642".to_string());
-    steps_done.push("✓ vm.swappiness = 10".to_string());
+    // Sysctl tweaks (vm.max_map_count, swappiness, etc.) via the shared drop-in
+    match apply_gaming_sysctls(executor).await {
+        Ok(applied) => {
+            for line in applied {
+                steps_done.push(format!("✓ {}", line));
+            }
+        }
+        Err(e) => {
+            steps_done.record::<()>("Kernel tweaks", Err(e), String::new())?;
+        }
+    }
     
     // File descriptor limits (ESYNC/FSYNC)
-    let limits_content = r#"# Glance Gaming Center - ESYNC/FSYNC
-# Layer 3: High file descriptor limit for Wine/Proton
-
-* hard nofile 1048576
-* soft nofile 1048576
-"#;
-    
-    Command::new("pkexec")
-        .args(["bash", "-c", &format!(
-            "echo '{}' > /etc/security/limits.d/99-gaming.conf",
-            limits_content
-        )])
-        .output()
-        .ok();
-    steps_done.push("✓ ESYNC/FSYNC limit = 1048576".to_string());
+    let result = executor.execute(&[PrivilegedOp::SetNofileLimit { limit: NOFILE_LIMIT }]).await;
+    steps_done.record("ESYNC/FSYNC limit", result, format!("✓ ESYNC/FSYNC limit = {}", NOFILE_LIMIT))?;
     
     // Mouse acceleration off (for FPS games) - DE-aware
     let de = detect_desktop_environment();
-    let _ = set_mouse_flat_profile();
-    steps_done.push(format!("✓ Mouse acceleration disabled ({})", de));
+    let result = set_mouse_flat_profile().await;
+    steps_done.record("Mouse acceleration", result, format!("✓ Mouse acceleration disabled ({})", de))?;
     
     // ========================================
     // LAYER 4: Essential Apps
     // ========================================
     
+    progress.step("Layer 4: Steam & gaming apps", Some(75));
+    
     steps_done.push("🔧 Installing Steam...".to_string());
-    Command::new("pkexec")
-        .args(["apt-get", "install", "-y", "steam"])
-        .output()
-        .ok();
-    steps_done.push("✓ Steam installed".to_string());
+    let result = install_each(manager, &["steam"]).await;
+    steps_done.record_installs("Steam", result, "✓ Steam installed".to_string())?;
     
     // MangoHud for FPS overlay
-    Command::new("pkexec")
-        .args(["apt-get", "install", "-y", "mangohud"])
-        .output()
-        .ok();
-    steps_done.push("✓ MangoHud installed (FPS overlay)".to_string());
+    let result = install_each(manager, &["mangohud"]).await;
+    steps_done.record_installs("MangoHud", result, "✓ MangoHud installed (FPS overlay)".to_string())?;
     
     // Flatpak setup for ProtonUp-Qt and Heroic
-    Command::new("pkexec")
-        .args(["apt-get", "install", "-y", "flatpak"])
-        .output()
-        .ok();
-    
-    Command::new("flatpak")
-        .args(["remote-add", "--if-not-exists", "flathub", "https://dl.flathub.org/repo/flathub.flatpakrepo"])
-        .output()
-        .ok();
-    
-    // ProtonUp-Qt (for GE-Proton)
-    Command::new("flatpak")
-        .args(["install", "-y", "flathub", "net.davidotek.pupgui2"])
-        .output()
-        .ok();
-    steps_done.push("✓ ProtonUp-Qt installed (download GE-Proton)".to_string());
-    
-    // Heroic Games Launcher
-    Command::new("flatpak")
-        .args(["install", "-y", "flathub", "com.heroicgameslauncher.hgl"])
-        .output()
-        .ok();
-    steps_done.push("✓ Heroic Games Launcher installed".to_string());
+    progress.step("Layer 4: Flatpak apps", Some(90));
+    let result = install_each(manager, &["flatpak"]).await;
+    if steps_done.record_installs("Flatpak", result, "✓ Flatpak installed".to_string())? {
+        // ProtonUp-Qt (for GE-Proton)
+        let result = install_flatpak(executor, "net.davidotek.pupgui2").await;
+        steps_done.record("ProtonUp-Qt", result, "✓ ProtonUp-Qt installed (download GE-Proton)".to_string())?;
+        
+        // Heroic Games Launcher
+        let result = install_flatpak(executor, "com.heroicgameslauncher.hgl").await;
+        steps_done.record("Heroic", result, "✓ Heroic Games Launcher installed".to_string())?;
+    }
     
     // ========================================
     // DONE!
     // ========================================
     
     steps_done.push("".to_string());
-    steps_done.push("🎮 ONE-TOUCH SETUP COMPLETE!".to_string());
+    if steps_done.failures == 0 {
+        steps_done.push("🎮 ONE-TOUCH SETUP COMPLETE!".to_string());
+    } else {
+        steps_done.push(format!("⚠ Setup finished with {} failed step(s) - see ❌ above.", steps_done.failures));
+    }
     steps_done.push("🔄 Please REBOOT for all changes to take effect.".to_string());
     
-    Ok(steps_done)
+    Ok(steps_done.lines)
 }
-
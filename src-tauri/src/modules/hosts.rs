@@ -3,12 +3,14 @@
 //! Optimized for large files using temp files and streaming
 
 use crate::error::{AppError, Result};
-use crate::utils::privileged;
+use crate::state::AppState;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp, HOSTS_PATH};
+use crate::utils::progress::Progress;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{AppHandle, State};
 use tokio::time::Duration;
 
 // ============================================================================
@@ -94,7 +96,6 @@ pub const BLOCKLIST_SOURCES: &[(&str, &str, &str, &str)] = &[
     ),
 ];
 
-const HOSTS_PATH: &str = "/etc/hosts";
 const BLOCKLIST_MARKER_START: &str = "# === GLANCE ADBLOCK START ===";
 const BLOCKLIST_MARKER_END: &str = "# === GLANCE ADBLOCK END ===";
 
@@ -102,24 +103,6 @@ const BLOCKLIST_MARKER_END: &str = "# === GLANCE ADBLOCK END ===";
 // Helper Functions
 // ============================================================================
 
-/// Create a temporary file with content
-fn create_temp_file(content: &str) -> Result<PathBuf> {
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
-
-    let temp_path = std::env::temp_dir().join(format!("glance_hosts_{}.tmp", timestamp));
-
-    let mut file = fs::File::create(&temp_path)
-        .map_err(|e| AppError::System(format!("Failed to create temp file: {}", e)))?;
-
-    file.write_all(content.as_bytes())
-        .map_err(|e| AppError::System(format!("Failed to write temp file: {}", e)))?;
-
-    Ok(temp_path)
-}
-
 /// Get the base hosts content (without blocklist section)
 fn get_base_hosts_content() -> Result<String> {
     let content = fs::read_to_string(HOSTS_PATH)
@@ -189,6 +172,166 @@ fn parse_blocklist_entries(content: &str) -> Vec<String> {
     entries
 }
 
+// ============================================================================
+// Hosts File Service
+// ============================================================================
+
+/// Writes blocklists and backups of the hosts file through the privileged executor
+pub struct Hosts {
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+impl Hosts {
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { executor }
+    }
+
+    /// Download the selected blocklists and write them into the hosts file
+    /// Returns the number of blocked domains
+    pub async fn install_blocklists(&self, source_ids: Vec<String>, progress: &Progress) -> Result<usize> {
+        if source_ids.is_empty() {
+            return Err(AppError::InvalidInput("No blocklists selected".to_string()));
+        }
+
+        // Get URLs for selected sources
+        let selected_sources: Vec<(&str, &str, &str)> = BLOCKLIST_SOURCES
+            .iter()
+            .filter(|(id, _, _, _)| source_ids.contains(&id.to_string()))
+            .map(|(id, name, url, _)| (*id, *name, *url))
+            .collect();
+
+        if selected_sources.is_empty() {
+            return Err(AppError::System("No valid blocklists found".to_string()));
+        }
+
+        // Download all blocklists
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|e| AppError::Network(format!("Failed to create HTTP client: {}", e)))?;
+
+        let mut all_entries: Vec<String> = Vec::new();
+        let mut source_markers: Vec<String> = Vec::new();
+
+        let source_count = selected_sources.len();
+        for (i, (_id, name, url)) in selected_sources.iter().enumerate() {
+            progress.step(format!("Downloading {}", name), Some((i * 80 / source_count) as u8));
+            let response = client
+                .get(*url)
+                .send()
+                .await
+                .map_err(|e| AppError::Network(format!("Failed to download {}: {}", name, e)))?;
+
+            if !response.status().is_success() {
+                progress.log(format!("{}: HTTP {}, skipped", name, response.status()));
+                continue; // Skip failed downloads
+            }
+
+            let content = response
+                .text()
+                .await
+                .map_err(|e| AppError::Network(format!("Failed to read {}: {}", name, e)))?;
+
+            let entries = parse_blocklist_entries(&content);
+            progress.log(format!("{}: {} entries", name, entries.len()));
+            source_markers.push(format!("# Source: {} ({} entries)", url, entries.len()));
+            all_entries.extend(entries);
+        }
+
+        if all_entries.is_empty() {
+            return Err(AppError::System("No valid entries found in blocklists".to_string()));
+        }
+
+        // Deduplicate entries
+        progress.step("Merging blocklists", Some(80));
+        let mut seen = HashSet::new();
+        let unique_entries: Vec<String> = all_entries
+            .into_iter()
+            .filter(|e| {
+                let hostname = e.split_whitespace().nth(1).unwrap_or("");
+                seen.insert(hostname.to_string())
+            })
+            .collect();
+
+        let total_count = unique_entries.len();
+
+        // Build the blocklist section
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let blocklist_section = format!(
+            "{}\n# Applied: {} (unix timestamp)\n# Total blocked: {} domains\n{}\n{}\n{}\n",
+            BLOCKLIST_MARKER_START,
+            timestamp,
+            total_count,
+            source_markers.join("\n"),
+            unique_entries.join("\n"),
+            BLOCKLIST_MARKER_END
+        );
+
+        // Get base hosts and append blocklist
+        let base_content = tokio::task::spawn_blocking(get_base_hosts_content)
+            .await
+            .unwrap()?;
+
+        let new_content = format!("{}\n\n{}", base_content.trim(), blocklist_section);
+
+        progress.step(format!("Writing {} domains to {}", total_count, HOSTS_PATH), Some(90));
+        self.executor.execute(&[PrivilegedOp::write_file(HOSTS_PATH, &new_content, 0o644)]).await?;
+
+        Ok(total_count)
+    }
+
+    /// Remove the Glance blocklist section from the hosts file
+    pub async fn clear_blocklists(&self) -> Result<()> {
+        let base_content = tokio::task::spawn_blocking(get_base_hosts_content)
+            .await
+            .unwrap()?;
+
+        let new_content = format!("{}\n", base_content.trim());
+
+        self.executor.execute(&[PrivilegedOp::write_file(HOSTS_PATH, &new_content, 0o644)]).await?;
+
+        Ok(())
+    }
+
+    /// Copy the hosts file to a timestamped backup, returning its path
+    pub async fn backup(&self) -> Result<String> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let backup_path = format!("/etc/hosts.backup.{}", timestamp);
+
+        self.executor.execute(&[PrivilegedOp::CopyFile {
+            from: HOSTS_PATH.to_string(),
+            to: backup_path.clone(),
+        }])
+        .await?;
+
+        Ok(backup_path)
+    }
+
+    /// Restore the hosts file from one of its backups
+    pub async fn restore(&self, backup_path: String) -> Result<()> {
+        // Validate path
+        if !backup_path.starts_with("/etc/hosts.backup.") {
+            return Err(AppError::PermissionDenied("Invalid backup path".to_string()));
+        }
+
+        self.executor.execute(&[PrivilegedOp::CopyFile {
+            from: backup_path,
+            to: HOSTS_PATH.to_string(),
+        }])
+        .await?;
+
+        Ok(())
+    }
+}
+
 // ============================================================================
 // Tauri Commands
 // ============================================================================
@@ -273,148 +416,33 @@ pub async fn get_adblock_stats() -> Result<AdBlockStats> {
     Ok(stats)
 }
 
-/// Apply selected blocklists
+/// Apply selected blocklists, reporting download progress
+/// Cancellable through `cancel_operation(operation_id)`
 #[tauri::command]
-pub async fn apply_blocklists(source_ids: Vec<String>) -> Result<usize> {
-    if source_ids.is_empty() {
-        return Err(AppError::System("No blocklists selected".to_string()));
-    }
-
-    // Get URLs for selected sources
-    let selected_sources: Vec<(&str, &str, &str)> = BLOCKLIST_SOURCES
-        .iter()
-        .filter(|(id, _, _, _)| source_ids.contains(&id.to_string()))
-        .map(|(id, name, url, _)| (*id, *name, *url))
-        .collect();
-
-    if selected_sources.is_empty() {
-        return Err(AppError::System("No valid blocklists found".to_string()));
-    }
-
-    // Download all blocklists
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(60))
-        .build()
-        .map_err(|e| AppError::Network(format!("Failed to create HTTP client: {}", e)))?;
-
-    let mut all_entries: Vec<String> = Vec::new();
-    let mut source_markers: Vec<String> = Vec::new();
-
-    for (_id, name, url) in &selected_sources {
-        let response = client
-            .get(*url)
-            .send()
-            .await
-            .map_err(|e| AppError::Network(format!("Failed to download {}: {}", name, e)))?;
-
-        if !response.status().is_success() {
-            continue; // Skip failed downloads
-        }
-
-        let content = response
-            .text()
-            .await
-            .map_err(|e| AppError::Network(format!("Failed to read {}: {}", name, e)))?;
-
-        let entries = parse_blocklist_entries(&content);
-        source_markers.push(format!("# Source: {} ({} entries)", url, entries.len()));
-        all_entries.extend(entries);
-    }
-
-    if all_entries.is_empty() {
-        return Err(AppError::System("No valid entries found in blocklists".to_string()));
-    }
-
-    // Deduplicate entries
-    let mut seen = HashSet::new();
-    let unique_entries: Vec<String> = all_entries
-        .into_iter()
-        .filter(|e| {
-            let hostname = e.split_whitespace().nth(1).unwrap_or("");
-            seen.insert(hostname.to_string())
+pub async fn apply_blocklists(
+    source_ids: Vec<String>,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<usize> {
+    let hosts = state.hosts();
+    state
+        .track(&app, operation_id, |progress| async move {
+            hosts.install_blocklists(source_ids, &progress).await
         })
-        .collect();
-
-    let total_count = unique_entries.len();
-
-    // Build the blocklist section
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
-    
-    let blocklist_section = format!(
-        "{}\n# Applied: {} (unix timestamp)\n# Total blocked: {} domains\n{}\n{}\n{}\n",
-        BLOCKLIST_MARKER_START,
-        timestamp,
-        total_count,
-        source_markers.join("\n"),
-        unique_entries.join("\n"),
-        BLOCKLIST_MARKER_END
-    );
-
-    // Get base hosts and append blocklist
-    let base_content = tokio::task::spawn_blocking(get_base_hosts_content)
-        .await
-        .unwrap()?;
-
-    let new_content = format!("{}\n\n{}", base_content.trim(), blocklist_section);
-
-    // Write via temp file
-    let temp_path = tokio::task::spawn_blocking(move || create_temp_file(&new_content))
         .await
-        .unwrap()?;
-
-    let script = format!(
-        "cat '{}' > '{}' && rm '{}'",
-        temp_path.to_string_lossy(),
-        HOSTS_PATH,
-        temp_path.to_string_lossy()
-    );
-
-    privileged::run_privileged_shell(&script).await?;
-
-    Ok(total_count)
 }
 
 /// Clear all blocklists from hosts file
 #[tauri::command]
-pub async fn clear_blocklists() -> Result<()> {
-    let base_content = tokio::task::spawn_blocking(get_base_hosts_content)
-        .await
-        .unwrap()?;
-
-    let new_content = format!("{}\n", base_content.trim());
-
-    let temp_path = tokio::task::spawn_blocking(move || create_temp_file(&new_content))
-        .await
-        .unwrap()?;
-
-    let script = format!(
-        "cat '{}' > '{}' && rm '{}'",
-        temp_path.to_string_lossy(),
-        HOSTS_PATH,
-        temp_path.to_string_lossy()
-    );
-
-    privileged::run_privileged_shell(&script).await?;
-
-    Ok(())
+pub async fn clear_blocklists(state: State<'_, AppState>) -> Result<()> {
+    state.hosts().clear_blocklists().await
 }
 
 /// Backup hosts file
 #[tauri::command]
-pub async fn backup_hosts() -> Result<String> {
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
-
-    let backup_path = format!("/etc/hosts.backup.{}", timestamp);
-
-    privileged::run_privileged("cp", &[HOSTS_PATH, &backup_path]).await?;
-
-    Ok(backup_path)
+pub async fn backup_hosts(state: State<'_, AppState>) -> Result<String> {
+    state.hosts().backup().await
 }
 
 /// List available backups
@@ -442,13 +470,6 @@ pub async fn list_hosts_backups() -> Result<Vec<String>> {
 
 /// Restore hosts from backup
 #[tauri::command]
-pub async fn restore_hosts(backup_path: String) -> Result<()> {
-    // Validate path
-    if !backup_path.starts_with("/etc/hosts.backup.") {
-        return Err(AppError::PermissionDenied("Invalid backup path".to_string()));
-    }
-
-    privileged::run_privileged("cp", &[&backup_path, HOSTS_PATH]).await?;
-
-    Ok(())
+pub async fn restore_hosts(backup_path: String, state: State<'_, AppState>) -> Result<()> {
+    state.hosts().restore(backup_path).await
 }
@@ -0,0 +1,827 @@
+//! APT signing keys module
+//! Inventory of the keyrings APT trusts and the repositories each key signs, with cleanup:
+//! removing unused keyrings or legacy keys and moving globally trusted keys to per-repository `signed-by`
+
+use crate::adapters::query_output;
+use crate::error::{AppError, Result};
+use crate::modules::repositories::{self, Repository};
+use crate::state::AppState;
+use crate::utils::deb822;
+use crate::utils::distro::DistroFamily;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tauri::State;
+
+/// Keyring `apt-key add` writes to; every key in it is trusted for every repository
+const LEGACY_KEYRING: &str = "/etc/apt/trusted.gpg";
+/// Keyrings trusted for every repository
+const TRUSTED_PARTS_DIR: &str = "/etc/apt/trusted.gpg.d";
+/// Where keys for `signed-by` belong (Debian 12+, Ubuntu 22.04+)
+const LOCAL_KEYRINGS_DIR: &str = "/etc/apt/keyrings";
+/// Keyrings shipped by packages, and older `signed-by` keys
+const SHARED_KEYRINGS_DIR: &str = "/usr/share/keyrings";
+/// Downloaded Release files, named after their URL
+const APT_LISTS_DIR: &str = "/var/lib/apt/lists";
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyringLocation {
+    /// /etc/apt/trusted.gpg
+    Legacy,
+    /// /etc/apt/trusted.gpg.d
+    TrustedParts,
+    /// /etc/apt/keyrings
+    Local,
+    /// /usr/share/keyrings
+    Shared,
+}
+
+impl KeyringLocation {
+    /// Trusted for every repository, whether a repository names it or not
+    fn is_global(self) -> bool {
+        matches!(self, Self::Legacy | Self::TrustedParts)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyIssue {
+    /// Every key has expired (or, for a repository, its signing key has)
+    Expired,
+    /// No repository is signed by any of its keys, and none that might be is left unchecked
+    Unused,
+    /// Added with the deprecated `apt-key add`
+    LegacyAptKey,
+    /// Trusted for every repository instead of only the one it belongs to
+    GlobalTrust,
+    /// The repository's `signed-by` keyring does not exist
+    MissingKeyring,
+    /// None of the trusted keys signed the repository's Release file
+    UnknownSigner,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningKey {
+    pub fingerprint: String,
+    pub uids: Vec<String>,
+    /// Unix times
+    pub created: Option<u64>,
+    pub expires: Option<u64>,
+    pub expired: bool,
+    /// Subkey fingerprints; Release files are often signed by a subkey
+    pub subkeys: Vec<String>,
+    /// A repository's Release file is signed by this key
+    pub used: bool,
+    /// Can be deleted on its own from /etc/apt/trusted.gpg
+    pub removable: bool,
+}
+
+impl SigningKey {
+    /// Whether this key (or one of its subkeys) made a signature
+    fn signed(&self, signer: &Signer) -> bool {
+        std::iter::once(&self.fingerprint).chain(&self.subkeys).any(|fpr| match &signer.fingerprint {
+            Some(signer_fpr) => fpr.eq_ignore_ascii_case(signer_fpr),
+            None => fpr.to_uppercase().ends_with(&signer.key_id.to_uppercase()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keyring {
+    pub path: String,
+    pub location: KeyringLocation,
+    /// Package that ships the file; such keyrings are removed with the package
+    pub package: Option<String>,
+    pub keys: Vec<SigningKey>,
+    /// "uri suite" of every repository checked against this keyring
+    pub used_by: Vec<String>,
+    /// A repository that trusts it has no Release signature to check (not fetched yet),
+    /// or its keys could not be read, so it may be in use without showing up in `used_by`
+    pub usage_unknown: bool,
+    pub issues: Vec<KeyIssue>,
+    pub removable: bool,
+}
+
+/// An APT repository, enabled or not, and the key its Release file is checked with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepositoryKey {
+    pub file_path: String,
+    pub line_number: usize,
+    pub uri: String,
+    pub suite: String,
+    pub enabled: bool,
+    pub signed_by: Option<String>,
+    /// Keyring holding the key that signed the Release file
+    pub keyring: Option<String>,
+    /// Primary fingerprint of that key
+    pub fingerprint: Option<String>,
+    pub issues: Vec<KeyIssue>,
+    /// The key can be moved out of global trust into a `signed-by` keyring (enabled repositories only)
+    pub can_migrate: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyInventory {
+    pub keyrings: Vec<Keyring>,
+    pub repositories: Vec<RepositoryKey>,
+}
+
+/// The issuer of a Release file signature
+#[derive(Debug, Clone, PartialEq)]
+struct Signer {
+    key_id: String,
+    fingerprint: Option<String>,
+}
+
+// ============================================================================
+// gpg Output Parsing
+// ============================================================================
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Keys from `gpg --show-keys --with-colons --fixed-list-mode`
+fn parse_keys(colons: &str, now: u64) -> Vec<SigningKey> {
+    let mut keys: Vec<SigningKey> = Vec::new();
+    let mut in_subkey = false;
+
+    for line in colons.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        let field = |i: usize| fields.get(i).copied().unwrap_or_default();
+        match field(0) {
+            "pub" => {
+                let expires = field(6).parse().ok();
+                keys.push(SigningKey {
+                    fingerprint: String::new(),
+                    uids: Vec::new(),
+                    created: field(5).parse().ok(),
+                    expires,
+                    expired: field(1) == "e" || field(1) == "r" || expires.is_some_and(|t| t <= now),
+                    subkeys: Vec::new(),
+                    used: false,
+                    removable: false,
+                });
+                in_subkey = false;
+            }
+            "sub" => in_subkey = true,
+            "fpr" => {
+                let Some(key) = keys.last_mut() else { continue };
+                if in_subkey {
+                    key.subkeys.push(field(9).to_string());
+                } else {
+                    key.fingerprint = field(9).to_string();
+                }
+            }
+            "uid" => {
+                if let Some(key) = keys.last_mut() {
+                    key.uids.push(field(9).replace("\\x3a", ":"));
+                }
+            }
+            _ => {}
+        }
+    }
+    keys
+}
+
+/// Issuers of the signatures in `gpgv --status-fd 1` output against an empty keyring
+fn parse_signers(status: &str) -> Vec<Signer> {
+    status
+        .lines()
+        .filter_map(|line| line.strip_prefix("[GNUPG:] ERRSIG "))
+        .filter_map(|rest| {
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            Some(Signer {
+                key_id: fields.first()?.to_string(),
+                fingerprint: fields.get(6).filter(|f| f.len() >= 40).map(|f| f.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Owning package of each path in `dpkg-query -S` output ("pkg[, pkg]: /path")
+fn parse_owners(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with("diversion "))
+        .filter_map(|line| line.split_once(": "))
+        .map(|(packages, path)| (path.trim().to_string(), packages.split(',').next().unwrap_or(packages).trim().to_string()))
+        .collect()
+}
+
+/// Name apt stores a downloaded file under in /var/lib/apt/lists
+fn lists_file_name(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.chars()
+        .map(|c| match c {
+            '/' => "_".to_string(),
+            c if "\\|{}[]<>\"^~_=!@#$%&*".contains(c) => format!("%{:02x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// URL of a suite's InRelease, Release or Release.gpg; flat repositories end their suite with '/'
+fn release_file_url(uri: &str, suite: &str, file: &str) -> String {
+    let uri = uri.trim_end_matches('/');
+    if suite.ends_with('/') {
+        format!("{}/{}{}", uri, suite.trim_start_matches("./"), file)
+    } else {
+        format!("{}/dists/{}/{}", uri, suite, file)
+    }
+}
+
+// ============================================================================
+// Sources Editing
+// ============================================================================
+
+/// Add `signed-by` to the one-line entry or deb822 stanza at `line_number`
+fn set_signed_by(content: &str, is_deb822: bool, line_number: usize, keyring: &str) -> Option<String> {
+    if is_deb822 {
+        return deb822::set_field(content, line_number, "Signed-By", keyring);
+    }
+
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let line = lines.get_mut(line_number.checked_sub(1)?)?;
+    // A commented-out entry would become `# [signed-by=...] deb ...`, which no longer re-enables
+    if line.trim_start().starts_with('#') {
+        return None;
+    }
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let (repo_type, rest) = line.trim_start().split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    let new_line = match rest.strip_prefix('[') {
+        Some(options) => {
+            let (options, tail) = options.split_once(']')?;
+            if options.contains("signed-by=") {
+                return None;
+            }
+            format!("{}{} [{} signed-by={}]{}", indent, repo_type, options.trim(), keyring, tail)
+        }
+        None => format!("{}{} [signed-by={}] {}", indent, repo_type, keyring, rest),
+    };
+    *line = new_line;
+    Some(lines.join("\n") + "\n")
+}
+
+/// File name for a key exported from the legacy keyring: the repository host and the key's long ID,
+/// so the many PPAs on one Launchpad host each get their own file
+fn exported_key_name(uri: &str, fingerprint: &str) -> String {
+    let host = uri.split("://").nth(1).and_then(|rest| rest.split(['/', ':']).next()).unwrap_or("repository");
+    let name: String = host.chars().filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-')).collect();
+    let key_id = &fingerprint[fingerprint.len().saturating_sub(16)..];
+    format!("{}-{}.asc", if name.is_empty() { "repository" } else { &name }, key_id.to_ascii_lowercase())
+}
+
+// ============================================================================
+// Keyring Service
+// ============================================================================
+
+/// Inventory and cleanup of APT signing keys (Debian family only)
+pub struct Keyrings {
+    family: DistroFamily,
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+impl Keyrings {
+    pub fn new(family: DistroFamily, executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { family, executor }
+    }
+
+    fn ensure_apt(&self) -> Result<()> {
+        if self.family != DistroFamily::Debian {
+            return Err(AppError::NotSupported("Signing key management is only available for APT".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Every keyring APT may use, the repositories each one verifies, and what needs attention
+    pub async fn inventory(&self) -> Result<KeyInventory> {
+        self.ensure_apt()?;
+        let now = now();
+
+        let mut keyrings = Vec::new();
+        if Path::new(LEGACY_KEYRING).exists() {
+            keyrings.push((LEGACY_KEYRING.to_string(), KeyringLocation::Legacy));
+        }
+        for (dir, location) in [
+            (TRUSTED_PARTS_DIR, KeyringLocation::TrustedParts),
+            (LOCAL_KEYRINGS_DIR, KeyringLocation::Local),
+            (SHARED_KEYRINGS_DIR, KeyringLocation::Shared),
+        ] {
+            let mut paths: Vec<String> = fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|e| e == "gpg" || e == "asc"))
+                .map(|p| p.to_string_lossy().to_string())
+                .collect();
+            paths.sort();
+            keyrings.extend(paths.into_iter().map(|p| (p, location)));
+        }
+
+        let paths: Vec<&str> = keyrings.iter().map(|(p, _)| p.as_str()).collect();
+        let owners = if paths.is_empty() {
+            HashMap::new()
+        } else {
+            parse_owners(&query_output("dpkg-query", &[&["-S"], paths.as_slice()].concat()).await.unwrap_or_default())
+        };
+
+        let mut inventory = KeyInventory { keyrings: Vec::new(), repositories: Vec::new() };
+        for (path, location) in keyrings {
+            let colons = gpg("gpg", &["--show-keys", "--with-colons", "--fixed-list-mode", &path]).await?;
+            let keys = parse_keys(&colons, now);
+            inventory.keyrings.push(Keyring {
+                package: owners.get(&path).cloned(),
+                // Unreadable keys could be signing anything
+                usage_unknown: keys.is_empty(),
+                keys,
+                path,
+                location,
+                used_by: Vec::new(),
+                issues: Vec::new(),
+                removable: false,
+            });
+        }
+
+        // Disabled repositories count too: their keys are needed the moment they are re-enabled
+        for repo in repositories::list_apt_repositories() {
+            let signers = release_signers(&repo).await?;
+            inventory.repositories.push(match_repository(&repo, &signers, &mut inventory.keyrings));
+        }
+
+        for keyring in &mut inventory.keyrings {
+            rate_keyring(keyring);
+        }
+        Ok(inventory)
+    }
+
+    /// Delete a keyring no repository uses and no package ships
+    /// (trusted.gpg itself is never deleted, only its keys, see `remove_legacy_key`)
+    pub async fn remove(&self, path: &str) -> Result<String> {
+        let inventory = self.inventory().await?;
+        let keyring = inventory
+            .keyrings
+            .iter()
+            .find(|k| k.path == path)
+            .ok_or_else(|| AppError::InvalidInput(format!("{} is not an APT keyring", path)))?;
+
+        if let Some(package) = &keyring.package {
+            return Err(AppError::InvalidInput(format!("{} belongs to the {} package; remove the package instead", path, package)));
+        }
+        if keyring.location == KeyringLocation::Legacy {
+            return Err(AppError::InvalidInput(format!("{} is not deleted as a whole; remove its unused keys instead", path)));
+        }
+        if !keyring.used_by.is_empty() {
+            return Err(AppError::InvalidInput(format!("{} is still used by {}", path, keyring.used_by.join(", "))));
+        }
+        if !keyring.removable {
+            return Err(AppError::InvalidInput(format!("{} may still be used by a repository that has not been fetched", path)));
+        }
+
+        self.executor.execute(&[PrivilegedOp::remove_file(path)]).await?;
+        Ok(format!("Removed {}", path))
+    }
+
+    /// Delete one key no repository uses from /etc/apt/trusted.gpg
+    pub async fn remove_legacy_key(&self, fingerprint: &str) -> Result<String> {
+        let inventory = self.inventory().await?;
+        let key = inventory
+            .keyrings
+            .iter()
+            .filter(|k| k.location == KeyringLocation::Legacy)
+            .flat_map(|k| &k.keys)
+            .find(|key| key.fingerprint.eq_ignore_ascii_case(fingerprint))
+            .ok_or_else(|| AppError::InvalidInput(format!("{} is not in {}", fingerprint, LEGACY_KEYRING)))?;
+        if !key.removable {
+            return Err(AppError::InvalidInput(format!("{} may still sign a repository", fingerprint)));
+        }
+
+        self.executor.execute(&[PrivilegedOp::delete_legacy_key(&key.fingerprint)]).await?;
+        Ok(format!("Removed {} from {}", key.fingerprint, LEGACY_KEYRING))
+    }
+
+    /// Give a repository that relies on a globally trusted key its own `signed-by` keyring,
+    /// dropping the global copy (the trusted.gpg.d file, or the key in trusted.gpg) once nothing else relies on it
+    pub async fn migrate(&self, file_path: &str, line_number: usize) -> Result<String> {
+        let inventory = self.inventory().await?;
+        let repo = inventory
+            .repositories
+            .iter()
+            .find(|r| r.file_path == file_path && r.line_number == line_number)
+            .ok_or_else(|| AppError::InvalidInput("Unknown repository".to_string()))?;
+        if !repo.enabled {
+            return Err(AppError::InvalidInput(format!("Enable {} {} before moving its key", repo.uri, repo.suite)));
+        }
+        if !repo.can_migrate {
+            return Err(AppError::InvalidInput(format!(
+                "{} {} does not use a globally trusted key that can be moved",
+                repo.uri, repo.suite
+            )));
+        }
+        let (Some(keyring_path), Some(fingerprint)) = (&repo.keyring, &repo.fingerprint) else {
+            return Err(AppError::InvalidInput("The repository's signing key is unknown".to_string()));
+        };
+        let keyring = inventory.keyrings.iter().find(|k| &k.path == keyring_path).expect("matched keyring is listed");
+
+        let dir = if Path::new(LOCAL_KEYRINGS_DIR).is_dir() { LOCAL_KEYRINGS_DIR } else { SHARED_KEYRINGS_DIR };
+        let mut ops = Vec::new();
+        let target = match keyring.location {
+            // A drop-in keyring can be used as it is
+            KeyringLocation::TrustedParts => {
+                let name = Path::new(keyring_path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                let target = format!("{}/{}", dir, name);
+                ops.push(PrivilegedOp::CopyFile { from: keyring_path.clone(), to: target.clone() });
+                target
+            }
+            // trusted.gpg holds many keys; root exports just this one (once, if repositories share it)
+            _ => {
+                let target = format!("{}/{}", dir, exported_key_name(&repo.uri, fingerprint));
+                if !Path::new(&target).exists() {
+                    ops.push(PrivilegedOp::migrate_key(fingerprint, &target));
+                }
+                target
+            }
+        };
+
+        let content = fs::read_to_string(file_path)?;
+        let new_content = set_signed_by(&content, file_path.ends_with(".sources"), line_number, &target)
+            .ok_or_else(|| AppError::InvalidInput(format!("Cannot add signed-by to {}:{}", file_path, line_number)))?;
+        ops.push(PrivilegedOp::write_repo_file(file_path, &new_content));
+
+        // A repository that was never fetched might rely on it too
+        let still_needed = keyring.usage_unknown
+            || inventory.repositories.iter().any(|r| {
+                let other = !(r.file_path == file_path && r.line_number == line_number);
+                let names_it = r.signed_by.as_deref().is_some_and(|s| s.split(',').any(|p| p.trim() == keyring_path));
+                let same_key = r.keyring.as_ref() == Some(keyring_path)
+                    && (keyring.location != KeyringLocation::Legacy || r.fingerprint.as_ref() == Some(fingerprint));
+                other && (names_it || same_key)
+            });
+        if keyring.package.is_none() && !still_needed {
+            ops.push(match keyring.location {
+                KeyringLocation::Legacy => PrivilegedOp::delete_legacy_key(fingerprint),
+                _ => PrivilegedOp::remove_file(keyring_path),
+            });
+        }
+
+        self.executor.execute(&ops).await?;
+        Ok(format!("{} {} now uses signed-by={}", repo.uri, repo.suite, target))
+    }
+}
+
+/// A fresh gpg home for a single run, deleted when dropped
+struct GnupgHome(PathBuf);
+
+impl GnupgHome {
+    /// Create a private directory under $XDG_RUNTIME_DIR (or the temp dir), refusing one
+    /// that already exists or does not end up owned by us with mode 0700
+    fn create() -> Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let base = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_dir())
+            .unwrap_or_else(std::env::temp_dir);
+        let path = base.join(format!("glance-gnupg-{}-{}", std::process::id(), COUNT.fetch_add(1, Ordering::Relaxed)));
+
+        fs::DirBuilder::new().mode(0o700).create(&path)?;
+        let home = Self(path);
+        let meta = fs::symlink_metadata(&home.0)?;
+        let uid = fs::metadata("/proc/self")?.uid();
+        if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o777 != 0o700 {
+            return Err(AppError::PermissionDenied(format!("{} has the wrong owner or mode", home.0.display())));
+        }
+        Ok(home)
+    }
+}
+
+impl Drop for GnupgHome {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run gpg or gpgv with a private throwaway home, so reading keys never touches the user's keyrings
+async fn gpg(program: &str, args: &[&str]) -> Result<String> {
+    let home = GnupgHome::create()?;
+    let home_dir = home.0.to_string_lossy().to_string();
+    query_output(program, &[&["--homedir", home_dir.as_str()], args].concat()).await
+}
+
+/// Issuers of the downloaded InRelease, or Release.gpg, files of a repository (every URI and suite)
+async fn release_signers(repo: &Repository) -> Result<Vec<Signer>> {
+    let mut signers = Vec::new();
+    for uri in repo.uri.split_whitespace() {
+        for suite in repo.suite.split_whitespace() {
+            let path = |file: &str| {
+                Path::new(APT_LISTS_DIR).join(lists_file_name(&release_file_url(uri, suite, file))).to_string_lossy().to_string()
+            };
+            let (in_release, signature, release) = (path("InRelease"), path("Release.gpg"), path("Release"));
+            let mut checks: Vec<Vec<&str>> = Vec::new();
+            if Path::new(&in_release).exists() {
+                checks.push(vec![&in_release]);
+            }
+            // A detached signature is checked against its Release file
+            if Path::new(&signature).exists() && Path::new(&release).exists() {
+                checks.push(vec![&signature, &release]);
+            }
+            for files in checks {
+                let status = gpg("gpgv", &[&["--status-fd", "1", "--keyring", "/dev/null"], files.as_slice()].concat()).await?;
+                for signer in parse_signers(&status) {
+                    if !signers.contains(&signer) {
+                        signers.push(signer);
+                    }
+                }
+            }
+        }
+    }
+    Ok(signers)
+}
+
+/// Find the keyring a repository is verified with, recording the use on the keyring
+fn match_repository(repo: &Repository, signers: &[Signer], keyrings: &mut [Keyring]) -> RepositoryKey {
+    let mut entry = RepositoryKey {
+        file_path: repo.file_path.clone(),
+        line_number: repo.line_number,
+        uri: repo.uri.clone(),
+        suite: repo.suite.clone(),
+        enabled: repo.is_enabled,
+        signed_by: repo.signed_by.clone(),
+        keyring: None,
+        fingerprint: None,
+        issues: Vec::new(),
+        can_migrate: false,
+    };
+    let label = format!("{} {}", repo.uri, repo.suite);
+
+    // `signed-by` names keyring files, or fingerprints looked up in the global keyrings;
+    // an inline key block needs nothing on disk
+    let candidates: Vec<usize> = match repo.signed_by.as_deref() {
+        Some(inline) if inline.contains("BEGIN PGP") => return entry,
+        Some(signed_by) if signed_by.starts_with('/') => {
+            let paths: Vec<&str> = signed_by.split(',').map(str::trim).collect();
+            if paths.iter().any(|p| !Path::new(p).exists()) {
+                entry.issues.push(KeyIssue::MissingKeyring);
+            }
+            keyrings.iter().enumerate().filter(|(_, k)| paths.contains(&k.path.as_str())).map(|(i, _)| i).collect()
+        }
+        Some(_) => keyrings.iter().enumerate().filter(|(_, k)| k.location.is_global()).map(|(i, _)| i).collect(),
+        None => {
+            entry.issues.push(KeyIssue::GlobalTrust);
+            keyrings.iter().enumerate().filter(|(_, k)| k.location.is_global()).map(|(i, _)| i).collect()
+        }
+    };
+
+    let found = candidates.iter().find_map(|&i| {
+        let key = keyrings[i].keys.iter().position(|key| signers.iter().any(|s| key.signed(s)))?;
+        Some((i, key))
+    });
+    match found {
+        Some((i, key)) => {
+            let keyring = &mut keyrings[i];
+            keyring.used_by.push(label);
+            keyring.keys[key].used = true;
+            let (fingerprint, expired) = (keyring.keys[key].fingerprint.clone(), keyring.keys[key].expired);
+            if keyring.location == KeyringLocation::Legacy {
+                entry.issues.push(KeyIssue::LegacyAptKey);
+            }
+            if expired {
+                entry.issues.push(KeyIssue::Expired);
+            }
+            entry.can_migrate = repo.is_enabled && repo.signed_by.is_none() && keyring.package.is_none();
+            entry.keyring = Some(keyring.path.clone());
+            entry.fingerprint = Some(fingerprint);
+        }
+        None => {
+            // A named keyring counts as used even before the first `apt update`
+            match repo.signed_by.as_deref() {
+                Some(signed_by) if signed_by.starts_with('/') => {
+                    for keyring in keyrings.iter_mut().filter(|k| signed_by.split(',').any(|p| p.trim() == k.path)) {
+                        keyring.used_by.push(label.clone());
+                    }
+                }
+                // Without a signature there is no telling which global key it relies on
+                _ if signers.is_empty() => {
+                    for &i in &candidates {
+                        keyrings[i].usage_unknown = true;
+                    }
+                }
+                _ => {}
+            }
+            if !signers.is_empty() {
+                entry.issues.push(KeyIssue::UnknownSigner);
+            }
+        }
+    }
+    entry
+}
+
+/// Flag what needs attention and whether the keyring may be deleted
+fn rate_keyring(keyring: &mut Keyring) {
+    if !keyring.keys.is_empty() && keyring.keys.iter().all(|k| k.expired) {
+        keyring.issues.push(KeyIssue::Expired);
+    }
+    // Keyrings shipped by packages (the distro's own archive keys) are not stale leftovers
+    let unused = keyring.used_by.is_empty() && keyring.package.is_none() && !keyring.usage_unknown;
+    if unused {
+        keyring.issues.push(KeyIssue::Unused);
+    }
+    match keyring.location {
+        KeyringLocation::Legacy => keyring.issues.push(KeyIssue::LegacyAptKey),
+        KeyringLocation::TrustedParts if keyring.package.is_none() && !keyring.used_by.is_empty() => {
+            keyring.issues.push(KeyIssue::GlobalTrust)
+        }
+        _ => {}
+    }
+    // trusted.gpg is emptied one key at a time, never deleted
+    if keyring.location == KeyringLocation::Legacy {
+        for key in &mut keyring.keys {
+            key.removable = !key.used && !keyring.usage_unknown;
+        }
+    } else {
+        keyring.removable = unused;
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// APT keyrings, the repositories they verify, and their issues
+#[tauri::command]
+pub async fn get_key_inventory(state: State<'_, AppState>) -> Result<KeyInventory> {
+    state.keyrings().inventory().await
+}
+
+/// Delete an unused keyring
+#[tauri::command]
+pub async fn remove_keyring(path: String, state: State<'_, AppState>) -> Result<String> {
+    state.keyrings().remove(&path).await
+}
+
+/// Delete an unused key from /etc/apt/trusted.gpg
+#[tauri::command]
+pub async fn remove_legacy_key(fingerprint: String, state: State<'_, AppState>) -> Result<String> {
+    state.keyrings().remove_legacy_key(&fingerprint).await
+}
+
+/// Move a repository's globally trusted key to a `signed-by` keyring
+#[tauri::command]
+pub async fn migrate_repository_key(file_path: String, line_number: usize, state: State<'_, AppState>) -> Result<String> {
+    state.keyrings().migrate(&file_path, line_number).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_and_signers() {
+        let colons = "\
+pub:-:2048:1:2F59B5F99B1BE0B4:1464022864:1700000000::-:::scESC::::::23::0:
+fpr:::::::::6F71F525282841EEDAF851B42F59B5F99B1BE0B4:
+uid:-::::1768408735::45B5::NSolid <nsolid-gpg@nodesource.com>::::::::::0:
+sub:-:2048:1:7E9656125E955B26:1464022864::::::e::::::23:
+fpr:::::::::0FA5ECC8C0CA58863C0AC5867E9656125E955B26:
+pub:-:255:22:F8D2585B8783D481:1674492243:1926780243::-:::scSC:::::ed25519:::0:
+fpr:::::::::4D64FEC119C2029067D6E791F8D2585B8783D481:
+uid:-::::1674492243::F511::Debian Stable Release Key (12/bookworm) <debian-release@lists.debian.org>::::::::::0:
+";
+        let keys = parse_keys(colons, 1_800_000_000);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].subkeys, ["0FA5ECC8C0CA58863C0AC5867E9656125E955B26"]);
+        assert_eq!(keys[0].uids, ["NSolid <nsolid-gpg@nodesource.com>"]);
+        assert!(keys[0].expired && !keys[1].expired);
+
+        let status = "[GNUPG:] NEWSIG\n\
+                      [GNUPG:] ERRSIG 7E9656125E955B26 1 8 01 1777553095 9 0FA5ECC8C0CA58863C0AC5867E9656125E955B26\n\
+                      [GNUPG:] NO_PUBKEY 7E9656125E955B26\n\
+                      [GNUPG:] ERRSIG F8D2585B8783D481 22 8 01 1778929222 9 -\n";
+        let signers = parse_signers(status);
+        assert_eq!(signers[1], Signer { key_id: "F8D2585B8783D481".to_string(), fingerprint: None });
+        assert!(keys[0].signed(&signers[0]) && keys[1].signed(&signers[1]) && !keys[1].signed(&signers[0]));
+
+        assert_eq!(
+            lists_file_name(&release_file_url("https://deb.nodesource.com/node_20.x/", "nodistro", "InRelease")),
+            "deb.nodesource.com_node%5f20.x_dists_nodistro_InRelease"
+        );
+        assert_eq!(release_file_url("https://repo.example/debian", "./", "Release.gpg"), "https://repo.example/debian/Release.gpg");
+        let owners = parse_owners("debian-archive-keyring: /usr/share/keyrings/debian-archive-keyring.gpg\n");
+        assert_eq!(owners["/usr/share/keyrings/debian-archive-keyring.gpg"], "debian-archive-keyring");
+    }
+
+    #[test]
+    fn test_set_signed_by() {
+        let list = "# Cloud SDK\ndeb https://packages.cloud.google.com/apt cloud-sdk main\ndeb [arch=amd64] https://repo.example/apt stable main\n";
+        let edited = set_signed_by(list, false, 2, "/etc/apt/keyrings/cloud.google.gpg").unwrap();
+        assert!(edited.contains("\ndeb [signed-by=/etc/apt/keyrings/cloud.google.gpg] https://packages.cloud.google.com/apt cloud-sdk main\n"));
+        let edited = set_signed_by(&edited, false, 3, "/etc/apt/keyrings/repo.asc").unwrap();
+        assert!(edited.ends_with("deb [arch=amd64 signed-by=/etc/apt/keyrings/repo.asc] https://repo.example/apt stable main\n"));
+        assert_eq!(set_signed_by(&edited, false, 3, "/x.gpg"), None);
+
+        let sources = "Types: deb\nURIs: https://repo.example/apt\nSuites: stable\nComponents: main\n";
+        let edited = set_signed_by(sources, true, 1, "/etc/apt/keyrings/repo.asc").unwrap();
+        assert!(edited.ends_with("Components: main\nSigned-By: /etc/apt/keyrings/repo.asc\n"));
+        assert_eq!(
+            exported_key_name("http://ppa.launchpad.net/graphics-drivers/ppa/ubuntu", "2388FF3BE10A76F638F80723FCAE110B1118213C"),
+            "ppa.launchpad.net-fcae110b1118213c.asc"
+        );
+
+        // Disabled entries are left alone rather than turned into an unparsable comment
+        let disabled = "# deb https://x.example/ stable main\n";
+        assert_eq!(set_signed_by(disabled, false, 1, "/etc/apt/keyrings/x.asc"), None);
+    }
+
+    #[test]
+    fn test_unfetched_repositories_keep_global_keys() {
+        let key = |fingerprint: &str| SigningKey {
+            fingerprint: fingerprint.to_string(),
+            uids: Vec::new(),
+            created: None,
+            expires: None,
+            expired: false,
+            subkeys: Vec::new(),
+            used: false,
+            removable: false,
+        };
+        let keyring = |path: &str, location, fingerprint: &str| Keyring {
+            path: path.to_string(),
+            location,
+            package: None,
+            keys: vec![key(fingerprint)],
+            used_by: Vec::new(),
+            usage_unknown: false,
+            issues: Vec::new(),
+            removable: false,
+        };
+        let repo = |uri: &str, signed_by: Option<&str>| Repository {
+            file_path: "/etc/apt/sources.list".to_string(),
+            line_number: 1,
+            repo_type: "deb".to_string(),
+            uri: uri.to_string(),
+            suite: "stable".to_string(),
+            name: None,
+            components: vec!["main".to_string()],
+            is_enabled: false,
+            is_ppa: false,
+            raw_line: String::new(),
+            ppa_name: None,
+            signed_by: signed_by.map(str::to_string),
+        };
+        let signed = "6F71F525282841EEDAF851B42F59B5F99B1BE0B4";
+        let signer = Signer { key_id: "2F59B5F99B1BE0B4".to_string(), fingerprint: None };
+        let mut keyrings = vec![
+            keyring(LEGACY_KEYRING, KeyringLocation::Legacy, signed),
+            keyring("/etc/apt/trusted.gpg.d/old.gpg", KeyringLocation::TrustedParts, "4D64FEC119C2029067D6E791F8D2585B8783D481"),
+            keyring("/etc/apt/keyrings/local.gpg", KeyringLocation::Local, "0FA5ECC8C0CA58863C0AC5867E9656125E955B26"),
+        ];
+        keyrings[0].keys.push(key("0000000000000000000000000000000000000001"));
+
+        // A disabled repository still marks its signer as used
+        match_repository(&repo("https://a.example", None), &[signer], &mut keyrings);
+        match_repository(&repo("https://b.example", Some("/etc/apt/keyrings/local.gpg")), &[], &mut keyrings);
+        let mut rated = keyrings.clone();
+        rated.iter_mut().for_each(rate_keyring);
+        assert!(rated[0].keys[0].used && !rated[0].keys[0].removable && rated[0].keys[1].removable);
+        assert!(!rated[0].removable);
+        assert!(rated[1].removable && rated[1].issues.contains(&KeyIssue::Unused));
+        assert!(!rated[2].removable);
+
+        // A global-trust repository that was never fetched could be signed by any global key
+        match_repository(&repo("https://c.example", None), &[], &mut keyrings);
+        keyrings.iter_mut().for_each(rate_keyring);
+        assert!(!keyrings[0].keys[1].removable);
+        assert!(!keyrings[1].removable && !keyrings[1].issues.contains(&KeyIssue::Unused));
+
+        // Keys gpg could not read are never taken for unused ones
+        let mut unreadable = keyring("/etc/apt/trusted.gpg.d/unreadable.gpg", KeyringLocation::TrustedParts, "");
+        unreadable.keys.clear();
+        unreadable.usage_unknown = true;
+        rate_keyring(&mut unreadable);
+        assert!(!unreadable.removable && unreadable.issues.is_empty());
+    }
+
+    #[test]
+    fn test_gnupg_home_is_private_and_per_call() {
+        let (first, second) = (GnupgHome::create().unwrap(), GnupgHome::create().unwrap());
+        assert_ne!(first.0, second.0);
+        assert_eq!(fs::metadata(&first.0).unwrap().mode() & 0o777, 0o700);
+        let path = first.0.clone();
+        drop(first);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,456 @@
+//! Pacman mirrorlist module
+//! Reflector-like ranking of Arch mirrors by latency and download throughput, and
+//! generation of /etc/pacman.d/mirrorlist with a backup of the previous one
+//! Glance writes its ranking as a block at the top and keeps the rest of the file, with
+//! servers it disabled commented out, so the full list stays available as candidates
+
+use crate::error::{AppError, Result};
+use crate::modules::repositories::{self, benchmark_mirror, mirror_client, BenchmarkTarget, MirrorInfo};
+use crate::state::AppState;
+use crate::utils::distro::DistroFamily;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
+use crate::utils::progress::Progress;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+
+pub const MIRRORLIST_PATH: &str = "/etc/pacman.d/mirrorlist";
+/// Copy of the mirrorlist before the last change (only one is kept)
+pub const MIRRORLIST_BACKUP_PATH: &str = "/etc/pacman.d/mirrorlist.backup";
+/// First and last lines of the block `generate` writes
+const GENERATED_HEADER: &str =
+    "##\n## Arch Linux repository mirrorlist\n## Generated by Glance, ranked by latency and download throughput\n##\n";
+const GENERATED_END: &str = "## End of Glance ranking";
+const BENCHMARK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Commented-out servers of the region taken from the current mirrorlist as extra candidates
+const MAX_LISTED_CANDIDATES: usize = 30;
+/// Servers written by `generate` unless asked otherwise
+pub const DEFAULT_MIRROR_COUNT: usize = 10;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// A `Server =` line of the mirrorlist, commented out or not
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MirrorlistServer {
+    /// As written, e.g. `https://mirrors.kernel.org/archlinux/$repo/os/$arch`
+    pub url: String,
+    /// Mirror root without the `$repo/os/$arch` part
+    pub base: String,
+    /// Last `## Country` heading above the server
+    pub country: Option<String>,
+    pub is_enabled: bool,
+    pub line_number: usize,
+}
+
+/// A candidate mirror (its `uri` is the mirror root) and its benchmark
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankedMirror {
+    #[serde(flatten)]
+    pub mirror: MirrorInfo,
+    /// Enabled in the current mirrorlist
+    pub in_mirrorlist: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorlistUpdate {
+    /// Copy of the previous mirrorlist, if there was one
+    pub backup_path: Option<String>,
+    /// Servers written, in order
+    pub mirrors: Vec<RankedMirror>,
+}
+
+impl RankedMirror {
+    /// Unmeasured entry for a mirror root, named from the known mirror table when possible
+    fn new(base: &str, country: Option<&str>) -> Self {
+        let known = repositories::get_mirrors_for_family(None, &DistroFamily::Arch)
+            .into_iter()
+            .find(|m| m.uri == base);
+        let host = base.split("://").nth(1).and_then(|rest| rest.split('/').next()).unwrap_or(base);
+        let mirror = known.unwrap_or_else(|| MirrorInfo {
+            name: host.to_string(),
+            uri: base.to_string(),
+            country: country.unwrap_or_default().to_string(),
+            ..Default::default()
+        });
+        Self { mirror, in_mirrorlist: false }
+    }
+}
+
+// ============================================================================
+// Mirrorlist Parsing and Rendering
+// ============================================================================
+
+/// Mirror root of a `Server =` value
+fn server_base(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    url.strip_suffix("/$repo/os/$arch").unwrap_or(url).to_string()
+}
+
+fn server_url(base: &str) -> String {
+    format!("{}/$repo/os/$arch", base)
+}
+
+/// All servers of a mirrorlist, with the country heading they appear under
+pub fn parse_mirrorlist(content: &str) -> Vec<MirrorlistServer> {
+    let mut servers = Vec::new();
+    let mut country: Option<String> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some(heading) = trimmed.strip_prefix("##") {
+            let heading = heading.trim();
+            if !heading.is_empty() {
+                country = Some(heading.to_string());
+            }
+            continue;
+        }
+        let (is_enabled, entry) = match trimmed.strip_prefix('#') {
+            Some(rest) => (false, rest.trim()),
+            None => (true, trimmed),
+        };
+        let Some((key, value)) = entry.split_once('=') else { continue };
+        if key.trim() != "Server" {
+            continue;
+        }
+        servers.push(MirrorlistServer {
+            url: value.trim().to_string(),
+            base: server_base(value),
+            country: country.clone(),
+            is_enabled,
+            line_number: i + 1,
+        });
+    }
+    servers
+}
+
+/// `mirrors` in order, grouped under country headings as they change, followed by the
+/// `previous` mirrorlist (minus an earlier Glance block) with its servers commented out
+fn render_mirrorlist(mirrors: &[RankedMirror], previous: &str) -> String {
+    let mut content = String::from(GENERATED_HEADER);
+    let mut country = None;
+    for RankedMirror { mirror, .. } in mirrors {
+        if country != Some(&mirror.country) {
+            content.push_str(&format!("\n## {}\n", if mirror.country.is_empty() { "Other" } else { &mirror.country }));
+            country = Some(&mirror.country);
+        }
+        content.push_str(&format!("Server = {}\n", server_url(&mirror.uri)));
+    }
+    content.push_str(&format!("\n{}\n", GENERATED_END));
+
+    let rest = previous
+        .strip_prefix(GENERATED_HEADER)
+        .and_then(|block| block.split_once(&format!("\n{}\n", GENERATED_END)))
+        .map_or(previous, |(_, rest)| rest)
+        .trim_start_matches('\n');
+    if !rest.is_empty() {
+        content.push('\n');
+    }
+    for line in rest.lines() {
+        match parse_mirrorlist(line).first() {
+            Some(server) if server.is_enabled => content.push_str(&format!("#{}\n", line.trim_start())),
+            _ => content.push_str(&format!("{}\n", line)),
+        }
+    }
+    content
+}
+
+/// `base` as the first enabled server, with every other line kept (an enabled duplicate of it aside)
+fn insert_primary(content: &str, base: &str) -> String {
+    let servers = parse_mirrorlist(content);
+    let first = servers.iter().find(|s| s.is_enabled).map(|s| s.line_number);
+    let primary = format!("Server = {}", server_url(base));
+
+    let mut lines = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if Some(i + 1) == first {
+            lines.push(primary.as_str());
+        }
+        let duplicate = servers.iter().any(|s| s.line_number == i + 1 && s.is_enabled && s.base == base);
+        if !duplicate {
+            lines.push(line);
+        }
+    }
+    if first.is_none() {
+        if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+            lines.push("");
+        }
+        lines.push(&primary);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Accept a mirror root or a full `Server =` URL and return the root
+fn validate_base(uri: &str) -> Result<String> {
+    let base = server_base(uri);
+    let valid = (base.starts_with("https://") || base.starts_with("http://"))
+        && base.len() > "https://".len()
+        && !base.chars().any(|c| c.is_whitespace() || c.is_control() || c == '#');
+    if !valid {
+        return Err(AppError::InvalidInput(format!("Invalid mirror URL: {}", uri)));
+    }
+    Ok(base)
+}
+
+// ============================================================================
+// Ranking
+// ============================================================================
+
+/// Known mirrors of the region, enabled servers of the mirrorlist, and its commented-out
+/// servers listed under the region's country
+fn candidates(region: Option<&str>, servers: &[MirrorlistServer]) -> Vec<RankedMirror> {
+    let mut mirrors: Vec<RankedMirror> = repositories::get_mirrors_for_family(region.map(str::to_string), &DistroFamily::Arch)
+        .into_iter()
+        .map(|mirror| RankedMirror { mirror, in_mirrorlist: false })
+        .collect();
+    let country = region.and_then(repositories::region_name);
+
+    let listed = servers.iter().filter(|s| s.is_enabled).chain(
+        servers
+            .iter()
+            .filter(|s| !s.is_enabled && country.is_some() && s.country.as_deref() == country)
+            .take(MAX_LISTED_CANDIDATES),
+    );
+    for server in listed {
+        match mirrors.iter_mut().find(|m| m.mirror.uri == server.base) {
+            Some(mirror) => mirror.in_mirrorlist |= server.is_enabled,
+            None => {
+                let mut mirror = RankedMirror::new(&server.base, server.country.as_deref());
+                mirror.in_mirrorlist = server.is_enabled;
+                mirrors.push(mirror);
+            }
+        }
+    }
+    mirrors
+}
+
+/// Rate mirrors against the freshest one (Arch has no main archive to compare with) and
+/// sort them best first
+fn rank(mirrors: &mut [RankedMirror]) {
+    let newest = mirrors.iter().filter_map(|m| m.mirror.synced_at).max();
+    for ranked in mirrors.iter_mut() {
+        ranked.mirror.rate(newest);
+    }
+    mirrors.sort_by_key(|m| m.mirror.rank_key());
+}
+
+// ============================================================================
+// Mirrorlist Service
+// ============================================================================
+
+/// Ranks Arch mirrors and rewrites the pacman mirrorlist
+pub struct Mirrorlist {
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+impl Mirrorlist {
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { executor }
+    }
+
+    /// Servers of the current mirrorlist, commented-out ones included
+    pub fn servers(&self) -> Vec<MirrorlistServer> {
+        parse_mirrorlist(&fs::read_to_string(MIRRORLIST_PATH).unwrap_or_default())
+    }
+
+    /// Benchmark the candidate mirrors for a region in parallel, best first
+    /// Each finished benchmark is reported to `progress`
+    pub async fn rank(&self, region: Option<String>, progress: &Progress) -> Result<Vec<RankedMirror>> {
+        let region = region.filter(|r| r != "ALL");
+        let mirrors = candidates(region.as_deref(), &self.servers());
+        let target = BenchmarkTarget::arch();
+        let client = mirror_client(BENCHMARK_TIMEOUT)?;
+        let total = mirrors.len().max(1);
+        let tested = AtomicUsize::new(0);
+        progress.step(format!("Benchmarking {} mirrors", mirrors.len()), Some(0));
+
+        let benchmarks = mirrors.into_iter().map(|ranked| {
+            let (client, target, tested) = (&client, &target, &tested);
+            async move {
+                let mirror = benchmark_mirror(client, target, ranked.mirror).await;
+                let done = tested.fetch_add(1, Ordering::Relaxed) + 1;
+                progress.step(format!("Benchmarked {} of {} mirrors", done, total), Some((done * 100 / total) as u8));
+                progress.log(mirror.summary());
+                RankedMirror { mirror, ..ranked }
+            }
+        });
+        let mut mirrors = join_all(benchmarks).await;
+
+        rank(&mut mirrors);
+        Ok(mirrors)
+    }
+
+    /// Rank mirrors and write the best `count` up-to-date ones to the mirrorlist
+    pub async fn generate(&self, region: Option<String>, count: usize, progress: &Progress) -> Result<MirrorlistUpdate> {
+        let ranked = self.rank(region, progress).await?;
+        let best: Vec<RankedMirror> = ranked
+            .into_iter()
+            .filter(|m| m.mirror.score.is_some() && !m.mirror.outdated)
+            .take(count.max(1))
+            .collect();
+        if best.is_empty() {
+            return Err(AppError::Network("No mirror could be reached".to_string()));
+        }
+
+        progress.step(format!("Writing {} mirrors to {}", best.len(), MIRRORLIST_PATH), None);
+        for ranked in &best {
+            validate_base(&ranked.mirror.uri)?;
+        }
+        let current = fs::read_to_string(MIRRORLIST_PATH).unwrap_or_default();
+        let backup_path = self.write(render_mirrorlist(&best, &current)).await?;
+        Ok(MirrorlistUpdate { backup_path, mirrors: best })
+    }
+
+    /// Put `uri` first, leaving the rest of the mirrorlist as it is
+    pub async fn set_primary(&self, uri: &str) -> Result<String> {
+        let base = validate_base(uri)?;
+        let current = fs::read_to_string(MIRRORLIST_PATH).unwrap_or_default();
+        self.write(insert_primary(&current, &base)).await?;
+        Ok(format!("Mirror changed to {}", base))
+    }
+
+    /// Back up the current mirrorlist over the previous backup and replace it, returning the backup path
+    async fn write(&self, content: String) -> Result<Option<String>> {
+        let backup_path = Path::new(MIRRORLIST_PATH)
+            .exists()
+            .then(|| MIRRORLIST_BACKUP_PATH.to_string());
+
+        let mut ops = Vec::new();
+        if let Some(path) = &backup_path {
+            ops.push(PrivilegedOp::CopyFile { from: MIRRORLIST_PATH.to_string(), to: path.clone() });
+        }
+        ops.push(PrivilegedOp::WriteMirrorlist { content });
+        self.executor.execute(&ops).await?;
+
+        Ok(backup_path)
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Servers of the current pacman mirrorlist
+#[tauri::command]
+pub fn get_arch_mirrorlist(state: State<'_, AppState>) -> Vec<MirrorlistServer> {
+    state.mirrorlist().servers()
+}
+
+/// Benchmark Arch mirrors for a region, best first
+#[tauri::command]
+pub async fn rank_arch_mirrors(
+    region: Option<String>,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<RankedMirror>> {
+    let mirrorlist = state.mirrorlist();
+    state
+        .track(&app, operation_id, |progress| async move { mirrorlist.rank(region, &progress).await })
+        .await
+}
+
+/// Rank Arch mirrors and write the fastest ones to the mirrorlist
+#[tauri::command]
+pub async fn generate_arch_mirrorlist(
+    region: Option<String>,
+    count: Option<usize>,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<MirrorlistUpdate> {
+    let mirrorlist = state.mirrorlist();
+    let count = count.unwrap_or(DEFAULT_MIRROR_COUNT);
+    state
+        .track(&app, operation_id, |progress| async move { mirrorlist.generate(region, count, &progress).await })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::repositories::MAX_SYNC_LAG_SECS;
+
+    const MIRRORLIST: &str = "\
+##
+## Arch Linux repository mirrorlist
+##
+
+## Germany
+#Server = https://ftp.fau.de/archlinux/$repo/os/$arch
+Server = https://mirror.example.de/arch/$repo/os/$arch
+
+## United States
+Server = https://mirrors.kernel.org/archlinux/$repo/os/$arch
+#Server=http://mirror.example.com/archlinux/
+";
+
+    #[test]
+    fn test_mirrorlist_ranking() {
+        let servers = parse_mirrorlist(MIRRORLIST);
+        assert_eq!(servers.len(), 4);
+        assert_eq!(servers[1].base, "https://mirror.example.de/arch");
+        assert_eq!((servers[1].is_enabled, servers[1].line_number), (true, 7));
+        assert_eq!(servers[3].base, "http://mirror.example.com/archlinux");
+        assert_eq!(servers[3].country.as_deref(), Some("United States"));
+
+        // Known German mirrors, both enabled servers, and the commented-out FAU one only once
+        let mirrors = candidates(Some("DE"), &servers);
+        assert_eq!(mirrors.iter().filter(|m| m.mirror.uri == "https://ftp.fau.de/archlinux").count(), 1);
+        let kernel = mirrors.iter().find(|m| m.mirror.uri == "https://mirrors.kernel.org/archlinux").unwrap();
+        assert!(kernel.in_mirrorlist);
+        assert_eq!(kernel.mirror.name, "Kernel.org");
+        assert!(!mirrors.iter().any(|m| m.mirror.uri == "http://mirror.example.com/archlinux"));
+
+        let measured = |uri: &str, throughput_kbps: Option<u64>, synced_at: Option<u64>| {
+            let mut ranked = RankedMirror::new(uri, Some("Germany"));
+            ranked.mirror.latency_ms = throughput_kbps.map(|_| 0);
+            ranked.mirror.throughput_kbps = throughput_kbps;
+            ranked.mirror.synced_at = synced_at;
+            ranked
+        };
+        let mut ranked = vec![
+            measured("https://a.example", Some(5_000), Some(1_000)),
+            measured("https://b.example", None, None),
+            measured("https://c.example", Some(900), Some(1_000 + MAX_SYNC_LAG_SECS + 1)),
+            measured("https://d.example", Some(2_000), Some(1_000 + 2 * MAX_SYNC_LAG_SECS)),
+        ];
+        rank(&mut ranked);
+        let order: Vec<&str> = ranked.iter().map(|m| m.mirror.uri.as_str()).collect();
+        assert_eq!(order, ["https://d.example", "https://c.example", "https://a.example", "https://b.example"]);
+        assert!(ranked[2].mirror.outdated && !ranked[1].mirror.outdated);
+
+        let content = render_mirrorlist(&ranked[..2], "");
+        assert!(content.ends_with("\n## Germany\nServer = https://d.example/$repo/os/$arch\nServer = https://c.example/$repo/os/$arch\n\n## End of Glance ranking\n"));
+        assert_eq!(parse_mirrorlist(&content).len(), 2);
+        assert!(validate_base("https://x.example/$repo/os/$arch\nServer = evil").is_err());
+    }
+
+    #[test]
+    fn test_mirrorlist_writes_keep_the_rest_of_the_file() {
+        let primary = insert_primary(MIRRORLIST, "https://mirrors.kernel.org/archlinux");
+        let servers = parse_mirrorlist(&primary);
+        assert_eq!(primary.lines().count(), MIRRORLIST.lines().count());
+        assert_eq!((servers[1].base.as_str(), servers[1].is_enabled), ("https://mirrors.kernel.org/archlinux", true));
+        assert_eq!(servers.iter().filter(|s| s.is_enabled).count(), 2);
+        assert!(primary.contains("#Server = https://ftp.fau.de/archlinux/$repo/os/$arch\n"));
+
+        // The previous servers stay as (commented-out) candidates, and a second run replaces
+        // the Glance block instead of stacking another one on top
+        let ranked = [RankedMirror::new("https://d.example", Some("Germany"))];
+        let first = render_mirrorlist(&ranked, MIRRORLIST);
+        let second = render_mirrorlist(&ranked, &first);
+        assert_eq!(first, second);
+        let servers = parse_mirrorlist(&second);
+        assert_eq!(servers.len(), 5);
+        assert_eq!(servers.iter().filter(|s| s.is_enabled).count(), 1);
+        assert!(second.contains("\n## United States\n#Server = https://mirrors.kernel.org/archlinux/$repo/os/$arch\n"));
+    }
+}
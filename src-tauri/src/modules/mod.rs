@@ -4,13 +4,17 @@ pub mod cleaner;
 pub mod dns;
 pub mod gaming;
 pub mod hosts;
+pub mod keyrings;
+pub mod mirrorlist;
 pub mod packages;
 pub mod processes;
+pub mod profile;
 pub mod repositories;
 pub mod resources;
 pub mod services;
 pub mod startup;
 pub mod system_stats;
 pub mod tweaks;
+pub mod updates;
 
 
@@ -1,16 +1,346 @@
 //! Package management module
-//! Lists and uninstalls packages with categorization (async)
-//! Now uses distro-agnostic PackageManager trait
+//! Lists, installs, upgrades and uninstalls packages with categorization (async)
+//! Now uses distro-agnostic PackageManager trait, merged with Flatpak and Snap packages
 
-use crate::adapters::{PackageInfo, PackageAction};
+use crate::adapters::{CleanupResult, PackageInfo, PackageAction, PackageDetails, PackageAnalysis, PackageManager, PackageOrigin, PackageSource, PackageTransaction, RemovalPreview, SearchResult, UpgradablePackage};
 use crate::error::{AppError, Result};
 use crate::state::AppState;
-use tauri::State;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, State};
 
 // Re-export types for frontend
 pub use crate::adapters::PackageInfo as PackageInfoExport;
 pub use crate::adapters::PackageAction as PackageActionExport;
 
+/// Validate a package name before handing it to the package manager (prevent injection)
+pub fn validate_package_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('-') || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '.' || c == '+' || c == ':' || c == '_') {
+        return Err(AppError::PermissionDenied(
+            "Invalid package name".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Longest repository search query accepted
+const MAX_SEARCH_CHARS: usize = 100;
+
+/// Validate a free-text search query; unlike package names it may contain spaces
+pub fn validate_search_term(query: &str) -> Result<()> {
+    if query.is_empty()
+        || query.starts_with('-')
+        || query.chars().count() > MAX_SEARCH_CHARS
+        || query.chars().any(char::is_control)
+    {
+        return Err(AppError::InvalidInput("Invalid search query".to_string()));
+    }
+    Ok(())
+}
+
+/// Validate a Flatpak ref as Glance lists them: `ID` for apps, `ID//BRANCH` for runtimes
+pub fn validate_flatpak_ref(reference: &str) -> Result<()> {
+    match reference.split_once("//") {
+        Some((id, branch)) => {
+            validate_package_name(id)?;
+            validate_package_name(branch)
+        }
+        None => validate_package_name(reference),
+    }
+}
+
+// ============================================================================
+// Search Cache
+// ============================================================================
+
+/// Remote search results stay fresh this long (repositories change slowly)
+const SEARCH_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+const SEARCH_CACHE_ENTRIES: usize = 32;
+
+/// Remote search results by query, shared through `AppState`; cleared whenever
+/// Glance installs or removes packages so the `installed` flags stay right
+#[derive(Default)]
+pub struct SearchCache {
+    entries: Mutex<HashMap<String, (Instant, Vec<SearchResult>)>>,
+}
+
+impl SearchCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get(&self, query: &str) -> Option<Vec<SearchResult>> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(query)
+            .filter(|(at, _)| at.elapsed() < SEARCH_CACHE_TTL)
+            .map(|(_, results)| results.clone())
+    }
+
+    fn insert(&self, query: &str, results: Vec<SearchResult>) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (at, _)| at.elapsed() < SEARCH_CACHE_TTL);
+        if entries.len() >= SEARCH_CACHE_ENTRIES {
+            let oldest = entries.iter().min_by_key(|(_, (at, _))| *at).map(|(q, _)| q.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(query.to_string(), (Instant::now(), results));
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+// ============================================================================
+// Installed Package Index
+// ============================================================================
+
+/// Modification times of the package database files (None for missing ones)
+type DatabaseStamp = Vec<Option<SystemTime>>;
+
+fn database_stamp(paths: &[&str]) -> DatabaseStamp {
+    paths
+        .iter()
+        .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Installed native packages, shared through `AppState` and reused until the package
+/// database changes; listing thousands of packages takes seconds on some backends
+/// A change refreshes only what the backend can: pacman re-reads the `desc` files that changed,
+/// dpkg re-parses its status file in-process, and the rpm backends (no per-package files to
+/// compare) list everything again through `rpm -qa`
+#[derive(Default)]
+pub struct PackageIndex {
+    entry: Mutex<Option<(DatabaseStamp, Vec<PackageInfo>)>>,
+}
+
+impl PackageIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn installed(&self, manager: &dyn PackageManager) -> Result<Vec<PackageInfo>> {
+        let stamp = database_stamp(manager.database_paths());
+        if let Some((cached_stamp, packages)) = &*self.entry.lock().unwrap() {
+            if *cached_stamp == stamp {
+                return Ok(packages.clone());
+            }
+        }
+
+        let packages = manager.get_installed_packages().await?;
+        // Without a database to watch there is no telling when the list goes stale
+        if stamp.iter().any(Option::is_some) {
+            *self.entry.lock().unwrap() = Some((stamp, packages.clone()));
+        }
+        Ok(packages)
+    }
+}
+
+// ============================================================================
+// Package Service
+// ============================================================================
+
+/// Package operations on top of the distro's PackageManager adapter and the Flatpak/Snap sources
+#[derive(Clone)]
+pub struct Packages {
+    manager: Arc<dyn PackageManager>,
+    sources: Vec<Arc<dyn PackageSource>>,
+    index: Arc<PackageIndex>,
+    search_cache: Arc<SearchCache>,
+}
+
+impl Packages {
+    pub fn new(
+        manager: Arc<dyn PackageManager>,
+        sources: Vec<Arc<dyn PackageSource>>,
+        index: Arc<PackageIndex>,
+        search_cache: Arc<SearchCache>,
+    ) -> Self {
+        Self { manager, sources, index, search_cache }
+    }
+
+    /// The source of non-native packages, if it is installed here
+    async fn source(&self, origin: PackageOrigin) -> Result<&Arc<dyn PackageSource>> {
+        for source in self.sources.iter().filter(|s| s.origin() == origin) {
+            if source.is_available().await {
+                return Ok(source);
+            }
+        }
+        Err(AppError::InvalidInput(format!("{:?} packages are not available on this system", origin)))
+    }
+
+    /// Packages of every available Flatpak/Snap source; a failing source is logged and skipped
+    async fn source_packages(&self) -> Vec<PackageInfo> {
+        let mut packages = Vec::new();
+        for source in &self.sources {
+            if !source.is_available().await {
+                continue;
+            }
+            match source.get_installed_packages().await {
+                Ok(list) => packages.extend(list),
+                Err(e) => log::warn!("Failed to list {:?} packages: {}", source.origin(), e),
+            }
+        }
+        packages
+    }
+
+    /// Package manager name (apt, pacman, dnf, zypper)
+    pub fn manager_name(&self) -> &'static str {
+        self.manager.name()
+    }
+
+    /// Native packages followed by Flatpak and Snap packages, each tagged with its origin
+    pub async fn list(&self) -> Result<Vec<PackageInfo>> {
+        let mut packages = self.index.installed(self.manager.as_ref()).await?;
+        packages.extend(self.source_packages().await);
+        Ok(packages)
+    }
+
+    /// Installed packages whose name or description contains `query`
+    pub async fn search(&self, query: &str) -> Result<Vec<PackageInfo>> {
+        let query = query.to_lowercase();
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|p| p.name.to_lowercase().contains(&query) || p.description.to_lowercase().contains(&query))
+            .collect())
+    }
+
+    /// Search the repositories; results are cached per query for a few minutes
+    pub async fn search_remote(&self, query: &str) -> Result<Vec<SearchResult>> {
+        let query = query.trim().to_lowercase();
+        validate_search_term(&query)?;
+        if let Some(results) = self.search_cache.get(&query) {
+            return Ok(results);
+        }
+        let results = self.manager.search_remote(&query).await?;
+        self.search_cache.insert(&query, results.clone());
+        Ok(results)
+    }
+
+    pub async fn install(&self, name: &str) -> Result<PackageAction> {
+        validate_package_name(name)?;
+        let result = self.manager.install_package(name).await;
+        self.search_cache.clear();
+        result
+    }
+
+    pub async fn list_upgradable(&self) -> Result<Vec<UpgradablePackage>> {
+        self.manager.list_upgradable().await
+    }
+
+    /// Upgrade the given packages in one transaction
+    pub async fn upgrade(&self, names: &[String]) -> Result<PackageAction> {
+        if names.is_empty() {
+            return Err(AppError::InvalidInput("No packages to upgrade".to_string()));
+        }
+        names.iter().try_for_each(|name| validate_package_name(name))?;
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let result = self.manager.upgrade_packages(&names).await;
+        self.search_cache.clear();
+        result
+    }
+
+    pub async fn upgrade_all(&self) -> Result<PackageAction> {
+        let result = self.manager.upgrade_all().await;
+        self.search_cache.clear();
+        result
+    }
+
+    pub async fn details(&self, name: &str) -> Result<PackageDetails> {
+        validate_package_name(name)?;
+        self.manager.package_details(name).await
+    }
+
+    pub async fn uninstall(&self, name: &str, origin: PackageOrigin) -> Result<PackageAction> {
+        match origin {
+            PackageOrigin::Native => {
+                validate_package_name(name)?;
+                let result = self.manager.uninstall_package(name).await;
+                self.search_cache.clear();
+                result
+            }
+            PackageOrigin::Flatpak => {
+                validate_flatpak_ref(name)?;
+                self.source(origin).await?.uninstall_package(name).await
+            }
+            PackageOrigin::Snap => {
+                validate_package_name(name)?;
+                self.source(origin).await?.uninstall_package(name).await
+            }
+        }
+    }
+
+    /// Remove unused Flatpak runtimes or disabled snap revisions
+    pub async fn clean_unused(&self, origin: PackageOrigin) -> Result<CleanupResult> {
+        if origin == PackageOrigin::Native {
+            return Err(AppError::InvalidInput("Use autoremove for native packages".to_string()));
+        }
+        self.source(origin).await?.clean_unused().await
+    }
+
+    pub async fn purge(&self, name: &str) -> Result<PackageAction> {
+        validate_package_name(name)?;
+        let result = self.manager.purge_package(name).await;
+        self.search_cache.clear();
+        result
+    }
+
+    pub async fn autoremove(&self) -> Result<PackageAction> {
+        let result = self.manager.autoremove().await;
+        self.search_cache.clear();
+        result
+    }
+
+    /// Everything `uninstall` would remove for a native package
+    pub async fn simulate_uninstall(&self, name: &str) -> Result<RemovalPreview> {
+        validate_package_name(name)?;
+        self.manager.simulate_uninstall(name).await
+    }
+
+    /// Everything `purge` would remove
+    pub async fn simulate_purge(&self, name: &str) -> Result<RemovalPreview> {
+        validate_package_name(name)?;
+        self.manager.simulate_purge(name).await
+    }
+
+    /// Everything `autoremove` would remove
+    pub async fn simulate_autoremove(&self) -> Result<RemovalPreview> {
+        self.manager.simulate_autoremove().await
+    }
+
+    /// Past transactions from the package manager's log, newest first
+    pub async fn history(&self) -> Result<Vec<PackageTransaction>> {
+        self.manager.transaction_history().await
+    }
+
+    /// Reverse a transaction listed by `history`
+    pub async fn undo(&self, id: &str) -> Result<PackageAction> {
+        let result = self.manager.undo_transaction(id).await;
+        self.search_cache.clear();
+        result
+    }
+
+    /// (total, auto-installed, total size)
+    pub async fn stats(&self) -> Result<(usize, usize, u64)> {
+        let packages = self.index.installed(self.manager.as_ref()).await?;
+        let auto = packages.iter().filter(|p| p.is_auto).count();
+        let size = packages.iter().map(|p| p.size_bytes).sum();
+        Ok((packages.len(), auto, size))
+    }
+
+    /// Orphans, leaves, reverse-dependency counts and exclusive sizes of native packages
+    pub async fn analysis(&self) -> Result<PackageAnalysis> {
+        Ok(self.manager.dependency_graph().await?.analyze())
+    }
+}
+
 // ============================================================================
 // Tauri Commands (All async, use PackageManager adapter)
 // ============================================================================
@@ -18,55 +348,180 @@ pub use crate::adapters::PackageAction as PackageActionExport;
 /// Get all installed packages (async, distro-agnostic)
 #[tauri::command]
 pub async fn get_packages(state: State<'_, AppState>) -> Result<Vec<PackageInfo>> {
-    state.context.package_manager.get_installed_packages().await
+    state.packages().list().await
 }
 
 /// Search packages by name (async)
 #[tauri::command]
 pub async fn search_packages(query: String, state: State<'_, AppState>) -> Result<Vec<PackageInfo>> {
-    state.context.package_manager.search_packages(&query).await
+    state.packages().search(&query).await
 }
 
-/// Uninstall a package (requires auth, async with timeout)
+/// Install a package (requires auth); output streams as progress of `operation_id`
 #[tauri::command]
-pub async fn uninstall_package(name: String, state: State<'_, AppState>) -> Result<PackageAction> {
-    // Validate package name (prevent injection)
-    if !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '.' || c == '+' || c == ':' || c == '_') {
-        return Err(AppError::PermissionDenied(
-            "Invalid package name".to_string(),
-        ));
-    }
+pub async fn install_package(
+    name: String,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<PackageAction> {
+    let packages = state.packages();
+    state
+        .track(&app, operation_id, |progress| async move {
+            progress.step(format!("Installing {}", name), None);
+            packages.install(&name).await
+        })
+        .await
+}
+
+/// List installed packages with a newer version available (refresh repositories first)
+#[tauri::command]
+pub async fn get_upgradable_packages(state: State<'_, AppState>) -> Result<Vec<UpgradablePackage>> {
+    state.packages().list_upgradable().await
+}
+
+/// Upgrade selected packages (requires auth)
+#[tauri::command]
+pub async fn upgrade_packages(
+    names: Vec<String>,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<PackageAction> {
+    let packages = state.packages();
+    state
+        .track(&app, operation_id, |progress| async move {
+            progress.step(format!("Upgrading {} packages", names.len()), None);
+            packages.upgrade(&names).await
+        })
+        .await
+}
+
+/// Upgrade the whole system (requires auth)
+#[tauri::command]
+pub async fn upgrade_all_packages(
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<PackageAction> {
+    let packages = state.packages();
+    state
+        .track(&app, operation_id, |progress| async move {
+            progress.step("Upgrading system", None);
+            packages.upgrade_all().await
+        })
+        .await
+}
+
+/// Version, repository, dependencies, reverse dependencies and files of a package
+#[tauri::command]
+pub async fn get_package_details(name: String, state: State<'_, AppState>) -> Result<PackageDetails> {
+    state.packages().details(&name).await
+}
+
+/// Search the distro repositories (cached), marking installed packages
+#[tauri::command]
+pub async fn search_remote_packages(query: String, state: State<'_, AppState>) -> Result<Vec<SearchResult>> {
+    state.packages().search_remote(&query).await
+}
+
+/// Uninstall a package (requires auth, async with timeout); `origin` defaults to native
+#[tauri::command]
+pub async fn uninstall_package(
+    name: String,
+    origin: Option<PackageOrigin>,
+    state: State<'_, AppState>,
+) -> Result<PackageAction> {
+    state.packages().uninstall(&name, origin.unwrap_or_default()).await
+}
 
-    state.context.package_manager.uninstall_package(&name).await
+/// Remove unused Flatpak runtimes or disabled snap revisions (may require auth)
+#[tauri::command]
+pub async fn clean_unused_packages(
+    origin: PackageOrigin,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<CleanupResult> {
+    let packages = state.packages();
+    state
+        .track(&app, operation_id, |progress| async move {
+            progress.step(format!("Removing unused {:?} packages", origin), None);
+            packages.clean_unused(origin).await
+        })
+        .await
 }
 
 /// Purge a package (remove with config files, async with timeout)
 #[tauri::command]
 pub async fn purge_package(name: String, state: State<'_, AppState>) -> Result<PackageAction> {
-    // Validate package name
-    if !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '.' || c == '+' || c == ':' || c == '_') {
-        return Err(AppError::PermissionDenied(
-            "Invalid package name".to_string(),
-        ));
-    }
-
-    state.context.package_manager.purge_package(&name).await
+    state.packages().purge(&name).await
 }
 
 /// Remove unused dependencies (async with timeout)
 #[tauri::command]
 pub async fn autoremove_packages(state: State<'_, AppState>) -> Result<PackageAction> {
-    state.context.package_manager.autoremove().await
+    state.packages().autoremove().await
+}
+
+/// Dry run of `uninstall_package` for a native package: every package it would remove
+/// (dependents included) and the bytes freed, for the UI to confirm
+#[tauri::command]
+pub async fn simulate_uninstall_package(name: String, state: State<'_, AppState>) -> Result<RemovalPreview> {
+    state.packages().simulate_uninstall(&name).await
+}
+
+/// Dry run of `purge_package`
+#[tauri::command]
+pub async fn simulate_purge_package(name: String, state: State<'_, AppState>) -> Result<RemovalPreview> {
+    state.packages().simulate_purge(&name).await
+}
+
+/// Dry run of `autoremove_packages`
+#[tauri::command]
+pub async fn simulate_autoremove_packages(state: State<'_, AppState>) -> Result<RemovalPreview> {
+    state.packages().simulate_autoremove().await
+}
+
+/// Install, upgrade and removal history from the package manager's log
+#[tauri::command]
+pub async fn get_package_history(state: State<'_, AppState>) -> Result<Vec<PackageTransaction>> {
+    state.packages().history().await
+}
+
+/// Undo a past transaction (requires auth): removes what it installed and reinstalls
+/// what it removed; dnf and pacman (from its cache) also restore replaced versions
+#[tauri::command]
+pub async fn undo_package_transaction(
+    id: String,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<PackageAction> {
+    let packages = state.packages();
+    state
+        .track(&app, operation_id, |progress| async move {
+            progress.step(format!("Undoing transaction {}", id), None);
+            packages.undo(&id).await
+        })
+        .await
 }
 
 /// Get package count statistics (async)
 #[tauri::command]
 pub async fn get_package_stats(state: State<'_, AppState>) -> Result<(usize, usize, u64)> {
-    state.context.package_manager.get_stats().await
+    state.packages().stats().await
+}
+
+/// Dependency analysis of native packages: orphans (auto-installed and no longer needed), leaves
+/// (explicitly installed and needed by nothing) and what removing each package would free
+#[tauri::command]
+pub async fn get_package_analysis(state: State<'_, AppState>) -> Result<PackageAnalysis> {
+    state.packages().analysis().await
 }
 
 /// Get package manager name for this distro
 #[tauri::command]
 pub fn get_package_manager_name(state: State<'_, AppState>) -> String {
-    state.context.package_manager.name().to_string()
+    state.packages().manager_name().to_string()
 }
@@ -4,7 +4,8 @@
 use crate::error::{AppError, Result};
 use crate::state::AppState;
 use serde::{Deserialize, Serialize};
-use sysinfo::{ProcessStatus, ProcessesToUpdate, Signal};
+use std::sync::{Arc, Mutex};
+use sysinfo::{ProcessStatus, ProcessesToUpdate, Signal, System};
 use tauri::State;
 
 // ============================================================================
@@ -104,211 +105,255 @@ fn status_to_string(status: ProcessStatus) -> String {
 }
 
 // ============================================================================
-// Tauri Commands (All async)
+// Process Service
 // ============================================================================
 
-/// Get all running processes (async)
-#[tauri::command]
-pub async fn get_processes(state: State<'_, AppState>) -> Result<Vec<ProcessInfo>> {
-    let sys = state.sys.clone();
-    
-    let processes = tokio::task::spawn_blocking(move || {
-        let mut sys = sys.lock().unwrap();
-
-        // Refresh process list
-        sys.refresh_processes(ProcessesToUpdate::All, true);
-
-        let mut processes: Vec<ProcessInfo> = sys
-            .processes()
-            .iter()
-            .map(|(pid, process)| {
-                let name = process.name().to_string_lossy().to_string();
-                let command = process.cmd().iter().map(|s| s.to_string_lossy().to_string()).collect::<Vec<_>>().join(" ");
-                let user = process
-                    .user_id()
-                    .map(|uid| uid.to_string())
-                    .unwrap_or_else(|| "unknown".to_string());
-                
-                let (category, is_killable) = detect_process_category(&name, &command, &user);
-                
-                ProcessInfo {
-                    pid: pid.as_u32(),
-                    name,
-                    cpu_usage: process.cpu_usage(),
-                    memory_bytes: process.memory(),
-                    status: status_to_string(process.status()),
-                    user,
-                    command,
-                    category,
-                    is_killable,
-                }
+/// Process operations over the shared sysinfo handle
+#[derive(Clone)]
+pub struct Processes {
+    sys: Arc<Mutex<System>>,
+}
+
+impl Processes {
+    pub fn new(sys: Arc<Mutex<System>>) -> Self {
+        Self { sys }
+    }
+
+    /// List all processes, sorted by CPU usage
+    pub async fn list(&self) -> Result<Vec<ProcessInfo>> {
+        let sys = self.sys.clone();
+
+        let processes = tokio::task::spawn_blocking(move || {
+            let mut sys = sys.lock().unwrap();
+
+            // Refresh process list
+            sys.refresh_processes(ProcessesToUpdate::All, true);
+
+            let mut processes: Vec<ProcessInfo> = sys
+                .processes()
+                .iter()
+                .map(|(pid, process)| {
+                    let name = process.name().to_string_lossy().to_string();
+                    let command = process.cmd().iter().map(|s| s.to_string_lossy().to_string()).collect::<Vec<_>>().join(" ");
+                    let user = process
+                        .user_id()
+                        .map(|uid| uid.to_string())
+                        .unwrap_or_else(|| "unknown".to_string());
+                    
+                    let (category, is_killable) = detect_process_category(&name, &command, &user);
+                    
+                    ProcessInfo {
+                        pid: pid.as_u32(),
+                        name,
+                        cpu_usage: process.cpu_usage(),
+                        memory_bytes: process.memory(),
+                        status: status_to_string(process.status()),
+                        user,
+                        command,
+                        category,
+                        is_killable,
+                    }
+                })
+                .collect();
+
+            // Sort by CPU usage (descending)
+            processes.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
+
+            processes
+        }).await.unwrap();
+
+        Ok(processes)
+    }
+
+    /// Top `limit` processes by CPU usage
+    pub async fn top(&self, limit: usize) -> Result<Vec<ProcessInfo>> {
+        let mut processes = self.list().await?;
+        processes.truncate(limit);
+        Ok(processes)
+    }
+
+    /// Processes whose name or command line contains `query`
+    pub async fn search(&self, query: &str) -> Result<Vec<ProcessInfo>> {
+        let query_lower = query.to_lowercase();
+
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|p| {
+                p.name.to_lowercase().contains(&query_lower)
+                    || p.command.to_lowercase().contains(&query_lower)
             })
-            .collect();
+            .collect())
+    }
 
-        // Sort by CPU usage (descending)
-        processes.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
+    /// Send SIGTERM (or SIGKILL when `force`) to a process
+    pub async fn signal(&self, pid: u32, force: bool) -> Result<ProcessAction> {
+        let sys = self.sys.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let sys = sys.lock().unwrap();
+            let pid_obj = sysinfo::Pid::from_u32(pid);
+            let (signal, action, message) = if force {
+                (Signal::Kill, "force_kill", "Process killed")
+            } else {
+                (Signal::Term, "kill", "Process terminated")
+            };
+
+            if let Some(process) = sys.process(pid_obj) {
+                if process.kill_with(signal).is_some() {
+                    return Ok(ProcessAction {
+                        pid,
+                        action: action.to_string(),
+                        success: true,
+                        message: message.to_string(),
+                    });
+                }
+            }
 
-        processes
-    }).await.unwrap();
+            // Process doesn't exist or couldn't be killed
+            Err(AppError::CommandFailed(format!(
+                "Failed to {} process {}",
+                action.replace('_', " "),
+                pid
+            )))
+        }).await.unwrap()
+    }
+
+    /// Number of processes known to the last refresh
+    pub async fn count(&self) -> Result<usize> {
+        let sys = self.sys.clone();
+        
+        let count = tokio::task::spawn_blocking(move || {
+            let sys = sys.lock().unwrap();
+            sys.processes().len()
+        }).await.unwrap();
+
+        Ok(count)
+    }
+
+    /// Terminate all killable app processes to free RAM
+    pub async fn bulk_terminate_apps(&self) -> Result<ProcessAction> {
+        let sys = self.sys.clone();
+        
+        let result = tokio::task::spawn_blocking(move || {
+            let mut sys = sys.lock().unwrap();
+            sys.refresh_processes(ProcessesToUpdate::All, true);
+            
+            let mut killed_count = 0;
+            let mut failed_count = 0;
+            let mut total_memory_freed: u64 = 0;
+            
+            // Get all killable processes
+            let killable_pids: Vec<(sysinfo::Pid, u64)> = sys
+                .processes()
+                .iter()
+                .filter_map(|(pid, process)| {
+                    let name = process.name().to_string_lossy().to_string();
+                    let command = process.cmd().iter().map(|s| s.to_string_lossy().to_string()).collect::<Vec<_>>().join(" ");
+                    let user = process.user_id().map(|uid| uid.to_string()).unwrap_or_else(|| "unknown".to_string());
+                    
+                    let (category, is_killable) = detect_process_category(&name, &command, &user);
+                    
+                    // Only kill Apps, Browser, Media categories (not System, Kernel, Desktop)
+                    if is_killable && (category == "Apps" || category == "Browser" || category == "Media") {
+                        Some((*pid, process.memory()))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            
+            // Kill each process
+            for (pid, memory) in killable_pids {
+                if let Some(process) = sys.process(pid) {
+                    if process.kill_with(Signal::Term).is_some() {
+                        killed_count += 1;
+                        total_memory_freed += memory;
+                    } else {
+                        failed_count += 1;
+                    }
+                }
+            }
+            
+            let freed_mb = total_memory_freed / (1024 * 1024);
+            
+            ProcessAction {
+                pid: 0,
+                action: "bulk_terminate".to_string(),
+                success: killed_count > 0,
+                message: format!(
+                    "Terminated {} app processes (~{} MB RAM freed). {} failed.",
+                    killed_count, freed_mb, failed_count
+                ),
+            }
+        }).await.unwrap();
 
-    Ok(processes)
+        Ok(result)
+    }
+}
+
+// ============================================================================
+// Tauri Commands (All async)
+// ============================================================================
+
+/// Get all running processes (async)
+#[tauri::command]
+pub async fn get_processes(state: State<'_, AppState>) -> Result<Vec<ProcessInfo>> {
+    state.processes().list().await
 }
 
 /// Get top processes by CPU usage (async)
 #[tauri::command]
 pub async fn get_top_processes(state: State<'_, AppState>, limit: usize) -> Result<Vec<ProcessInfo>> {
-    let mut processes = get_processes(state).await?;
-    processes.truncate(limit);
-    Ok(processes)
+    state.processes().top(limit).await
 }
 
 /// Search processes by name (async)
 #[tauri::command]
 pub async fn search_processes(state: State<'_, AppState>, query: String) -> Result<Vec<ProcessInfo>> {
-    let all_processes = get_processes(state).await?;
-    let query_lower = query.to_lowercase();
-
-    let filtered: Vec<ProcessInfo> = all_processes
-        .into_iter()
-        .filter(|p| {
-            p.name.to_lowercase().contains(&query_lower)
-                || p.command.to_lowercase().contains(&query_lower)
-        })
-        .collect();
-
-    Ok(filtered)
+    state.processes().search(&query).await
 }
 
 /// Kill a process by PID (async)
 #[tauri::command]
 pub async fn kill_process(state: State<'_, AppState>, pid: u32) -> Result<ProcessAction> {
-    let sys = state.sys.clone();
-    
-    let result = tokio::task::spawn_blocking(move || {
-        let sys = sys.lock().unwrap();
-        let pid_obj = sysinfo::Pid::from_u32(pid);
-
-        if let Some(process) = sys.process(pid_obj) {
-            // Try SIGTERM first
-            if process.kill_with(Signal::Term).is_some() {
-                return Ok(ProcessAction {
-                    pid,
-                    action: "kill".to_string(),
-                    success: true,
-                    message: "Process terminated".to_string(),
-                });
-            }
-        }
-
-        // Process doesn't exist or couldn't be killed
-        Err(AppError::CommandFailed(format!(
-            "Failed to kill process {}",
-            pid
-        )))
-    }).await.unwrap();
-
-    result
+    state.processes().signal(pid, false).await
 }
 
 /// Force kill a process by PID (SIGKILL) - async
 #[tauri::command]
 pub async fn force_kill_process(state: State<'_, AppState>, pid: u32) -> Result<ProcessAction> {
-    let sys = state.sys.clone();
-    
-    let result = tokio::task::spawn_blocking(move || {
-        let sys = sys.lock().unwrap();
-        let pid_obj = sysinfo::Pid::from_u32(pid);
-
-        if let Some(process) = sys.process(pid_obj) {
-            if process.kill_with(Signal::Kill).is_some() {
-                return Ok(ProcessAction {
-                    pid,
-                    action: "force_kill".to_string(),
-                    success: true,
-                    message: "Process killed".to_string(),
-                });
-            }
-        }
-
-        Err(AppError::CommandFailed(format!(
-            "Failed to force kill process {}",
-            pid
-        )))
-    }).await.unwrap();
-
-    result
+    state.processes().signal(pid, true).await
 }
 
 /// Get process count (async)
 #[tauri::command]
 pub async fn get_process_count(state: State<'_, AppState>) -> Result<usize> {
-    let sys = state.sys.clone();
-    
-    let count = tokio::task::spawn_blocking(move || {
-        let sys = sys.lock().unwrap();
-        sys.processes().len()
-    }).await.unwrap();
-
-    Ok(count)
+    state.processes().count().await
 }
 
 /// Bulk terminate all killable app processes to free RAM (async)
 #[tauri::command]
 pub async fn bulk_terminate_apps(state: State<'_, AppState>) -> Result<ProcessAction> {
-    let sys = state.sys.clone();
-    
-    let result = tokio::task::spawn_blocking(move || {
-        let mut sys = sys.lock().unwrap();
-        sys.refresh_processes(ProcessesToUpdate::All, true);
-        
-        let mut killed_count = 0;
-        let mut failed_count = 0;
-        let mut total_memory_freed: u64 = 0;
-        
-        // Get all killable processes
-        let killable_pids: Vec<(sysinfo::Pid, u64)> = sys
-            .processes()
-            .iter()
-            .filter_map(|(pid, process)| {
-                let name = process.name().to_string_lossy().to_string();
-                let command = process.cmd().iter().map(|s| s.to_string_lossy().to_string()).collect::<Vec<_>>().join(" ");
-                let user = process.user_id().map(|uid| uid.to_string()).unwrap_or_else(|| "unknown".to_string());
-                
-                let (category, is_killable) = detect_process_category(&name, &command, &user);
-                
-                // Only kill Apps, Browser, Media categories (not System, Kernel, Desktop)
-                if is_killable && (category == "Apps" || category == "Browser" || category == "Media") {
-                    Some((*pid, process.memory()))
-                } else {
-                    None
-                }
-            })
-            .collect();
-        
-        // Kill each process
-        for (pid, memory) in killable_pids {
-            if let Some(process) = sys.process(pid) {
-                if process.kill_with(Signal::Term).is_some() {
-                    killed_count += 1;
-                    total_memory_freed += memory;
-                } else {
-                    failed_count += 1;
-                }
-            }
-        }
-        
-        let freed_mb = total_memory_freed / (1024 * 1024);
-        
-        ProcessAction {
-            pid: 0,
-            action: "bulk_terminate".to_string(),
-            success: killed_count > 0,
-            message: format!(
-                "Terminated {} app processes (~{} MB RAM freed). {} failed.",
-                killed_count, freed_mb, failed_count
-            ),
-        }
-    }).await.unwrap();
-
-    Ok(result)
+    state.processes().bulk_terminate_apps().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_runs_without_tauri() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let processes = Processes::new(Arc::new(Mutex::new(System::new())));
+
+        let own_pid = std::process::id();
+        let all = runtime.block_on(processes.list()).unwrap();
+        assert!(all.iter().any(|p| p.pid == own_pid));
+        assert!(all.windows(2).all(|w| w[0].cpu_usage >= w[1].cpu_usage));
+
+        let top = runtime.block_on(processes.top(1)).unwrap();
+        assert_eq!(top.len(), 1);
+    }
 }
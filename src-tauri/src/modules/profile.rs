@@ -0,0 +1,447 @@
+//! System profile module
+//! Export the machine's Glance configuration as a versioned TOML profile
+//! and replay it elsewhere by applying only the differences
+
+use crate::error::{AppError, Result};
+use crate::modules::{dns, hosts, services, startup, tweaks};
+use crate::state::AppState;
+use crate::utils::progress::{self, Progress};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use tauri::State;
+
+/// Current profile format version
+const PROFILE_VERSION: u32 = 1;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GlanceProfile {
+    pub version: u32,
+    #[serde(default)]
+    pub glance_version: String,
+    /// tweak id -> value (as shown by get_tweaks)
+    #[serde(default)]
+    pub tweaks: BTreeMap<String, String>,
+    #[serde(default)]
+    pub dns: DnsProfile,
+    #[serde(default)]
+    pub hosts: HostsProfile,
+    #[serde(default)]
+    pub services: ServicesProfile,
+    #[serde(default)]
+    pub startup: Vec<StartupProfileEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DnsProfile {
+    #[serde(default)]
+    pub mode: String, // "auto", "provider", "custom"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub servers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HostsProfile {
+    /// Enabled blocklist source ids
+    #[serde(default)]
+    pub blocklists: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServicesProfile {
+    #[serde(default)]
+    pub enabled: Vec<String>,
+    #[serde(default)]
+    pub disabled: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartupProfileEntry {
+    pub name: String,
+    #[serde(default)]
+    pub exec: String,
+    #[serde(default)]
+    pub comment: String,
+    pub enabled: bool,
+}
+
+/// One difference between a profile and this machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileChange {
+    pub section: String, // "tweaks", "dns", "hosts", "services", "startup"
+    pub item: String,
+    pub current: String,
+    pub desired: String,
+    pub applied: bool,
+    pub message: String,
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn change(section: &str, item: &str, current: &str, desired: &str) -> ProfileChange {
+    ProfileChange {
+        section: section.to_string(),
+        item: item.to_string(),
+        current: current.to_string(),
+        desired: desired.to_string(),
+        applied: false,
+        message: String::new(),
+    }
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "enabled"
+    } else {
+        "disabled"
+    }
+}
+
+/// Parse and version-check a profile
+fn parse_profile(profile_toml: &str) -> Result<GlanceProfile> {
+    let profile: GlanceProfile = toml::from_str(profile_toml)
+        .map_err(|e| AppError::InvalidInput(format!("Invalid profile: {}", e)))?;
+
+    if profile.version == 0 || profile.version > PROFILE_VERSION {
+        return Err(AppError::InvalidInput(format!(
+            "Unsupported profile version {} (this Glance reads up to {})",
+            profile.version, PROFILE_VERSION
+        )));
+    }
+    Ok(profile)
+}
+
+/// Capture the current machine state as a profile
+async fn capture_profile() -> Result<GlanceProfile> {
+    let tweaks = tweaks::get_tweaks()
+        .await?
+        .into_iter()
+        .flat_map(|c| c.tweaks)
+        .filter(|t| t.current_value != "unknown")
+        .map(|t| (t.id, t.current_value))
+        .collect();
+
+    let configured = dns::get_configured_dns();
+    let dns_status = dns::get_current_dns().await?;
+    let dns = if configured.is_empty() {
+        DnsProfile {
+            mode: "auto".to_string(),
+            ..Default::default()
+        }
+    } else if let Some(provider) = dns_status.active_provider {
+        DnsProfile {
+            mode: "provider".to_string(),
+            provider: Some(provider),
+            servers: Vec::new(),
+        }
+    } else {
+        DnsProfile {
+            mode: "custom".to_string(),
+            provider: None,
+            servers: configured,
+        }
+    };
+
+    let blocklists = hosts::get_blocklist_sources()
+        .await?
+        .into_iter()
+        .filter(|s| s.is_enabled)
+        .map(|s| s.id)
+        .collect();
+
+    let mut service_profile = ServicesProfile::default();
+    for service in services::get_services().await? {
+        if service.load_state != "loaded" {
+            continue;
+        }
+        if service.is_enabled {
+            service_profile.enabled.push(service.name);
+        } else {
+            service_profile.disabled.push(service.name);
+        }
+    }
+
+    let startup = startup::get_startup_apps()
+        .await?
+        .into_iter()
+        .map(|app| StartupProfileEntry {
+            name: app.name,
+            exec: app.exec,
+            comment: app.comment,
+            enabled: app.is_enabled,
+        })
+        .collect();
+
+    Ok(GlanceProfile {
+        version: PROFILE_VERSION,
+        glance_version: env!("CARGO_PKG_VERSION").to_string(),
+        tweaks,
+        dns,
+        hosts: HostsProfile { blocklists },
+        services: service_profile,
+        startup,
+    })
+}
+
+/// Compute what would change to make this machine match `desired`
+async fn diff_against_machine(desired: &GlanceProfile) -> Result<Vec<ProfileChange>> {
+    let current = capture_profile().await?;
+    let mut changes = Vec::new();
+
+    // Tweaks
+    for (id, value) in &desired.tweaks {
+        match current.tweaks.get(id) {
+            Some(now) if now == value => {}
+            Some(now) => changes.push(change("tweaks", id, now, value)),
+            None => {
+                let mut c = change("tweaks", id, "unavailable", value);
+                c.message = "Tweak not available on this machine".to_string();
+                changes.push(c);
+            }
+        }
+    }
+
+    // DNS
+    let dns_differs = match desired.dns.mode.as_str() {
+        "auto" => current.dns.mode != "auto",
+        "provider" => current.dns.provider != desired.dns.provider,
+        "custom" => current.dns.servers != desired.dns.servers,
+        _ => false,
+    };
+    if dns_differs {
+        let describe = |d: &DnsProfile| match d.mode.as_str() {
+            "provider" => d.provider.clone().unwrap_or_default(),
+            "custom" => d.servers.join(" "),
+            _ => "automatic".to_string(),
+        };
+        changes.push(change("dns", "resolver", &describe(&current.dns), &describe(&desired.dns)));
+    }
+
+    // Hosts blocklists (applied as one set)
+    let now: BTreeSet<&String> = current.hosts.blocklists.iter().collect();
+    let want: BTreeSet<&String> = desired.hosts.blocklists.iter().collect();
+    if now != want {
+        changes.push(change(
+            "hosts",
+            "blocklists",
+            &current.hosts.blocklists.join(", "),
+            &desired.hosts.blocklists.join(", "),
+        ));
+    }
+
+    // Services
+    let installed: BTreeMap<&String, bool> = current
+        .services
+        .enabled
+        .iter()
+        .map(|s| (s, true))
+        .chain(current.services.disabled.iter().map(|s| (s, false)))
+        .collect();
+    let wanted = desired
+        .services
+        .enabled
+        .iter()
+        .map(|s| (s, true))
+        .chain(desired.services.disabled.iter().map(|s| (s, false)));
+    for (name, enable) in wanted {
+        match installed.get(name) {
+            Some(now) if *now == enable => {}
+            Some(now) => changes.push(change("services", name, on_off(*now), on_off(enable))),
+            None => {
+                let mut c = change("services", name, "not installed", on_off(enable));
+                c.message = "Service not installed on this machine".to_string();
+                changes.push(c);
+            }
+        }
+    }
+
+    // Startup entries (matched by name)
+    for entry in &desired.startup {
+        match current.startup.iter().find(|a| a.name == entry.name) {
+            Some(app) if app.enabled == entry.enabled => {}
+            Some(app) => changes.push(change("startup", &entry.name, on_off(app.enabled), on_off(entry.enabled))),
+            None => changes.push(new_startup_change(entry)),
+        }
+    }
+
+    Ok(changes)
+}
+
+/// A startup entry that would be created; the preview shows the command it runs at login
+fn new_startup_change(entry: &StartupProfileEntry) -> ProfileChange {
+    let desired = format!("{}, runs `{}`", on_off(entry.enabled), entry.exec);
+    change("startup", &entry.name, "missing", &desired)
+}
+
+/// Apply one difference, recording the outcome on the change itself
+async fn apply_change(state: &AppState, change: &mut ProfileChange, desired: &GlanceProfile) {
+    if !change.message.is_empty() {
+        // Diff already flagged this item as not applicable here
+        return;
+    }
+
+    let result: Result<String> = match change.section.as_str() {
+        "tweaks" => state.tweaks().apply(change.item.clone(), change.desired.clone(), None).await,
+        "dns" => match desired.dns.mode.as_str() {
+            "provider" => state
+                .dns()
+                .set_provider(desired.dns.provider.as_deref().unwrap_or_default())
+                .await
+                .map(|_| "DNS provider set".to_string()),
+            "custom" => state
+                .dns()
+                .set_custom(
+                    desired.dns.servers.first().map(|s| s.as_str()).unwrap_or_default(),
+                    desired.dns.servers.get(1).map(|s| s.as_str()).unwrap_or_default(),
+                )
+                .await
+                .map(|_| "Custom DNS set".to_string()),
+            _ => state.dns().reset().await.map(|_| "DNS reset to automatic".to_string()),
+        },
+        "hosts" => {
+            if desired.hosts.blocklists.is_empty() {
+                state.hosts().clear_blocklists().await.map(|_| "Blocklists cleared".to_string())
+            } else {
+                let progress = progress::current().unwrap_or_else(Progress::silent);
+                state
+                    .hosts()
+                    .install_blocklists(desired.hosts.blocklists.clone(), &progress)
+                    .await
+                    .map(|count| format!("{} domains blocked", count))
+            }
+        }
+        "services" => {
+            let action = if change.desired == "enabled" {
+                state.services().enable(change.item.clone()).await
+            } else {
+                state.services().disable(change.item.clone()).await
+            };
+            match action {
+                Ok(a) if a.success => Ok(a.message),
+                Ok(a) => Err(AppError::CommandFailed(a.message)),
+                Err(e) => Err(e),
+            }
+        }
+        "startup" => apply_startup_change(change, desired).await,
+        other => Err(AppError::InvalidInput(format!("Unknown profile section: {}", other))),
+    };
+
+    match result {
+        Ok(message) => {
+            change.applied = true;
+            change.message = message;
+        }
+        Err(e) => change.message = e.to_string(),
+    }
+}
+
+async fn apply_startup_change(change: &ProfileChange, desired: &GlanceProfile) -> Result<String> {
+    let entry = desired
+        .startup
+        .iter()
+        .find(|e| e.name == change.item)
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown startup entry: {}", change.item)))?;
+
+    let file_path = match startup::get_startup_apps()
+        .await?
+        .into_iter()
+        .find(|a| a.name == entry.name)
+    {
+        Some(app) => app.file_path,
+        None => {
+            if entry.exec.is_empty() {
+                return Err(AppError::System("Startup entry has no command".to_string()));
+            }
+            startup::add_startup_app(entry.name.clone(), entry.exec.clone(), entry.comment.clone()).await?
+        }
+    };
+
+    if entry.enabled {
+        startup::enable_startup_app(file_path).await?;
+    } else {
+        startup::disable_startup_app(file_path).await?;
+    }
+    Ok(format!("{} {}", entry.name, on_off(entry.enabled)))
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Export this machine's configuration as a TOML profile
+#[tauri::command]
+pub async fn export_profile() -> Result<String> {
+    let profile = capture_profile().await?;
+    toml::to_string_pretty(&profile).map_err(|e| AppError::System(format!("Failed to serialize profile: {}", e)))
+}
+
+/// Show what importing a profile would change, without applying anything
+#[tauri::command]
+pub async fn preview_profile_import(profile_toml: String) -> Result<Vec<ProfileChange>> {
+    let profile = parse_profile(&profile_toml)?;
+    diff_against_machine(&profile).await
+}
+
+/// Import a profile, applying only the differences
+#[tauri::command]
+pub async fn import_profile(profile_toml: String, state: State<'_, AppState>) -> Result<Vec<ProfileChange>> {
+    apply_profile(&state, &profile_toml).await
+}
+
+/// Apply a TOML profile, returning every difference with its outcome
+pub async fn apply_profile(state: &AppState, profile_toml: &str) -> Result<Vec<ProfileChange>> {
+    let profile = parse_profile(profile_toml)?;
+    let mut changes = diff_against_machine(&profile).await?;
+
+    for change in changes.iter_mut() {
+        apply_change(state, change, &profile).await;
+    }
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_roundtrip() {
+        let mut profile = GlanceProfile {
+            version: PROFILE_VERSION,
+            ..Default::default()
+        };
+        profile.tweaks.insert("swappiness".to_string(), "10".to_string());
+        profile.dns = DnsProfile {
+            mode: "provider".to_string(),
+            provider: Some("cloudflare".to_string()),
+            servers: Vec::new(),
+        };
+        profile.startup.push(StartupProfileEntry {
+            name: "Syncthing".to_string(),
+            exec: "syncthing serve".to_string(),
+            comment: String::new(),
+            enabled: true,
+        });
+
+        let text = toml::to_string_pretty(&profile).unwrap();
+        let parsed = parse_profile(&text).unwrap();
+
+        assert_eq!(parsed.tweaks.get("swappiness").map(String::as_str), Some("10"));
+        assert_eq!(parsed.dns.provider.as_deref(), Some("cloudflare"));
+        assert_eq!(parsed.startup.len(), 1);
+        assert!(new_startup_change(&parsed.startup[0]).desired.contains("syncthing serve"));
+    }
+
+    #[test]
+    fn test_rejects_newer_version() {
+        assert!(parse_profile("version = 99\n").is_err());
+        assert!(parse_profile("version = 1\n").is_ok());
+    }
+}
@@ -1,28 +1,27 @@
-//! APT Repository Manager module - Enhanced
-//! Manages sources.list and PPAs with region detection and apt-fast support
-//! NOTE: This module is only available on Debian/Ubuntu based systems
+//! Repository Manager module - Enhanced
+//! Manages APT sources (one-line and deb822) and PPAs, dnf and zypper `.repo` files and pacman.conf repositories,
+//! with region detection and apt-fast support
 
 use crate::error::{AppError, Result};
 use crate::state::AppState;
-use crate::utils::distro::DistroFamily;
-use crate::utils::privileged;
+use crate::utils::distro::{DistroFamily, DistroInfo};
+use crate::utils::{deb822, ini};
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
+use crate::utils::progress::Progress;
 use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
-use tauri::State;
-use tokio::time::{timeout, Duration};
+use tauri::{AppHandle, State};
+use tokio::time::Duration;
 
 // ============================================================================
 // Feature Availability Check
 // ============================================================================
 
-/// Check if this module is available (APT-based distros only)
-fn is_apt_based(family: &DistroFamily) -> bool {
-    matches!(family, DistroFamily::Debian)
-}
-
 /// Check if we're in mock mode
 fn is_mock_mode() -> bool {
     std::env::var("FORCE_DISTRO").is_ok()
@@ -36,23 +35,37 @@ fn is_mock_mode() -> bool {
 pub struct Repository {
     pub file_path: String,
     pub line_number: usize,
-    pub repo_type: String,      // deb or deb-src
+    pub repo_type: String,      // deb, deb-src, rpm-md, yast2 or pacman
     pub uri: String,
-    pub suite: String,          // noble, noble-updates, etc.
+    pub suite: String,          // noble, noble-updates, etc. (repo id on dnf, zypper and pacman)
+    pub name: Option<String>,   // Display name of dnf and zypper repos
     pub components: Vec<String>, // main, restricted, universe, multiverse
     pub is_enabled: bool,
     pub is_ppa: bool,
     pub raw_line: String,
-    pub ppa_name: Option<String>, // For PPA: "ppa:user/repo"
+    pub ppa_name: Option<String>, // "ppa:user/repo", or "copr:owner/project" for COPR
+    pub signed_by: Option<String>, // APT keyring path or fingerprint (or an inline key)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MirrorInfo {
     pub name: String,
     pub uri: String,
     pub country: String,
     pub country_code: String,
+    /// Benchmark results, `None` until tested
     pub latency_ms: Option<u64>,
+    pub throughput_kbps: Option<u64>,
+    pub http_status: Option<u16>,
+    /// Unix time the mirror's index was generated (Release `Date:`, repomd revision, Arch `lastsync`)
+    pub synced_at: Option<u64>,
+    /// How far the index is behind the main archive (or the freshest mirror)
+    pub sync_lag_secs: Option<u64>,
+    /// More than a day behind
+    pub outdated: bool,
+    /// Combined latency, throughput and freshness; higher is better
+    pub score: Option<u64>,
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -406,6 +419,13 @@ fn parse_repo_line(line: &str, file_path: &str, line_number: usize) -> Option<Re
         (1, parts[1].to_string())
     };
     
+    // Options like [arch=amd64 signed-by=/usr/share/keyrings/foo.gpg]
+    let signed_by = parts[1..uri_idx]
+        .iter()
+        .map(|o| o.trim_matches(|c| c == '[' || c == ']'))
+        .find_map(|o| o.strip_prefix("signed-by="))
+        .map(|s| s.to_string());
+    
     if uri_idx + 2 > parts.len() {
         return None;
     }
@@ -413,17 +433,7 @@ fn parse_repo_line(line: &str, file_path: &str, line_number: usize) -> Option<Re
     let suite = parts[uri_idx + 1].to_string();
     let components: Vec<String> = parts[uri_idx + 2..].iter().map(|s| s.to_string()).collect();
     
-    let is_ppa = uri.contains("ppa.launchpad.net") || uri.contains("ppa.launchpadcontent.net");
-    
-    // Extract PPA name from URI
-    let ppa_name = if is_ppa {
-        // Format: https://ppa.launchpadcontent.net/user/repo/ubuntu
-        uri.split('/').skip(3).take(2).collect::<Vec<&str>>().join("/")
-            .split("/ubuntu").next()
-            .map(|s| format!("ppa:{}", s))
-    } else {
-        None
-    };
+    let ppa_name = ppa_name(&uri);
     
     Some(Repository {
         file_path: file_path.to_string(),
@@ -431,14 +441,62 @@ fn parse_repo_line(line: &str, file_path: &str, line_number: usize) -> Option<Re
         repo_type,
         uri,
         suite,
+        name: None,
         components,
         is_enabled,
-        is_ppa,
+        is_ppa: ppa_name.is_some(),
         raw_line: line.to_string(),
         ppa_name,
+        signed_by,
     })
 }
 
+/// "ppa:user/repo" for a Launchpad PPA URI
+fn ppa_name(uri: &str) -> Option<String> {
+    if !uri.contains("ppa.launchpad.net") && !uri.contains("ppa.launchpadcontent.net") {
+        return None;
+    }
+    // Format: https://ppa.launchpadcontent.net/user/repo/ubuntu
+    uri.split('/').skip(3).take(2).collect::<Vec<&str>>().join("/")
+        .split("/ubuntu").next()
+        .map(|s| format!("ppa:{}", s))
+}
+
+/// Parse a deb822 `.sources` file; each stanza is one repository, which may cover several
+/// types, URIs and suites
+fn parse_deb822_file(content: &str, file_path: &str) -> Vec<Repository> {
+    let lines: Vec<&str> = content.lines().collect();
+    deb822::parse(content)
+        .into_iter()
+        .filter(|stanza| stanza.get("Types").is_some() && stanza.get("URIs").is_some())
+        .map(|stanza| {
+            let uri = stanza.values("URIs").join(" ");
+            let ppa_name = stanza.values("URIs").into_iter().find_map(ppa_name);
+            Repository {
+                file_path: file_path.to_string(),
+                line_number: stanza.line,
+                repo_type: stanza.values("Types").join(" "),
+                suite: stanza.values("Suites").join(" "),
+                name: None,
+                components: stanza.values("Components").iter().map(|c| c.to_string()).collect(),
+                is_enabled: stanza.get("Enabled").map_or(true, |e| !e.eq_ignore_ascii_case("no")),
+                is_ppa: ppa_name.is_some(),
+                raw_line: lines[stanza.line - 1].to_string(),
+                ppa_name,
+                signed_by: stanza.get("Signed-By").map(|s| s.trim().to_string()),
+                uri,
+            }
+        })
+        .collect()
+}
+
+/// Flip `Enabled` of the stanza starting on `line_number`
+fn toggle_deb822_repo(content: &str, line_number: usize) -> Option<String> {
+    let stanza = deb822::parse(content).into_iter().find(|s| s.line == line_number)?;
+    let enabled = stanza.get("Enabled").map_or(true, |e| !e.eq_ignore_ascii_case("no"));
+    deb822::set_field(content, line_number, "Enabled", if enabled { "no" } else { "yes" })
+}
+
 /// Parse all repositories from a file
 fn parse_sources_file(path: &Path) -> Vec<Repository> {
     let mut repos = Vec::new();
@@ -454,6 +512,329 @@ fn parse_sources_file(path: &Path) -> Vec<Repository> {
     repos
 }
 
+/// sources.list and every `.list` and deb822 `.sources` file in sources.list.d
+pub(crate) fn list_apt_repositories() -> Vec<Repository> {
+    let mut all_repos = Vec::new();
+    
+    let main_sources = Path::new("/etc/apt/sources.list");
+    if main_sources.exists() {
+        all_repos.extend(parse_sources_file(main_sources));
+    }
+    
+    let sources_d = Path::new("/etc/apt/sources.list.d");
+    if sources_d.exists() {
+        if let Ok(entries) = fs::read_dir(sources_d) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                match path.extension().and_then(|e| e.to_str()) {
+                    Some("list") => all_repos.extend(parse_sources_file(&path)),
+                    Some("sources") => {
+                        let content = fs::read_to_string(&path).unwrap_or_default();
+                        all_repos.extend(parse_deb822_file(&content, &path.to_string_lossy()));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    
+    all_repos
+}
+
+/// Comment out or uncomment one sources.list line
+fn toggle_apt_line(content: &str, line_number: usize) -> Option<String> {
+    let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+    let line = lines.get_mut(line_number.checked_sub(1)?)?;
+    
+    *line = if line.trim().starts_with('#') {
+        line.trim_start_matches('#').trim_start().to_string()
+    } else {
+        format!("# {}", line)
+    };
+    
+    Some(lines.join("\n") + "\n")
+}
+
+// ============================================================================
+// dnf / zypper Repositories (.repo INI files)
+// ============================================================================
+
+const YUM_REPOS_DIR: &str = "/etc/yum.repos.d";
+const ZYPP_REPOS_DIR: &str = "/etc/zypp/repos.d";
+const COPR_HOST: &str = "copr.fedorainfracloud.org";
+
+fn is_truthy(value: &str) -> bool {
+    matches!(value.to_lowercase().as_str(), "1" | "yes" | "true" | "on")
+}
+
+/// "copr:owner/project" from a COPR repo id (`copr:<host>:<owner>:<project>`) or baseurl
+fn copr_name(id: &str, uri: &str) -> Option<String> {
+    let from_id = id.strip_prefix("copr:").and_then(|rest| {
+        let parts: Vec<&str> = rest.split(':').collect();
+        (parts.len() == 3).then(|| format!("{}/{}", parts[1], parts[2]))
+    });
+    let from_uri = || {
+        uri.split_once(&format!("{}/results/", COPR_HOST))
+            .map(|(_, path)| path.split('/').take(2).collect::<Vec<_>>().join("/"))
+    };
+    from_id.or_else(from_uri).map(|name| format!("copr:{}", name))
+}
+
+/// Parse a dnf or zypper `.repo` file; each `[id]` section is one repository
+fn parse_ini_repo_file(content: &str, file_path: &str) -> Vec<Repository> {
+    let lines: Vec<&str> = content.lines().collect();
+    ini::sections(content)
+        .into_iter()
+        .map(|section| {
+            let uri = ["baseurl", "metalink", "mirrorlist"]
+                .iter()
+                .find_map(|key| section.get(key))
+                .unwrap_or_default()
+                .to_string();
+            let copr = copr_name(&section.name, &uri);
+            Repository {
+                file_path: file_path.to_string(),
+                line_number: section.line,
+                repo_type: section.get("type").unwrap_or("rpm-md").to_string(),
+                uri,
+                suite: section.name.clone(),
+                name: section.get("name").map(|n| n.to_string()),
+                components: Vec::new(),
+                // dnf treats a missing `enabled` as enabled
+                is_enabled: section.get("enabled").map_or(true, is_truthy),
+                is_ppa: copr.is_some(),
+                raw_line: lines[section.line - 1].to_string(),
+                ppa_name: copr,
+                signed_by: None,
+            }
+        })
+        .collect()
+}
+
+/// Every `.repo` file in a repos.d directory, by file name
+fn list_ini_repositories(dir: &Path) -> Vec<Repository> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    files.retain(|path| path.extension().is_some_and(|e| e == "repo"));
+    files.sort();
+    
+    files
+        .iter()
+        .filter_map(|path| {
+            let content = fs::read_to_string(path).ok()?;
+            Some(parse_ini_repo_file(&content, &path.to_string_lossy()))
+        })
+        .flatten()
+        .collect()
+}
+
+/// Flip `enabled` of the section whose header is on `line_number`
+fn toggle_ini_repo(content: &str, line_number: usize) -> Option<String> {
+    let section = ini::sections(content).into_iter().find(|s| s.line == line_number)?;
+    let enabled = section.get("enabled").map_or(true, is_truthy);
+    let value = if enabled { "0" } else { "1" };
+    Some(ini::set_values(content, &section.name, &[("enabled", value.to_string())]))
+}
+
+/// `<id> <url>` of a repository to add
+fn parse_repo_spec(spec: &str) -> Result<(&str, &str)> {
+    let (id, uri) = spec
+        .split_once(char::is_whitespace)
+        .map(|(id, uri)| (id, uri.trim()))
+        .ok_or_else(|| AppError::InvalidInput("Use <id> <url> to add a repository".to_string()))?;
+    
+    let valid_id = !id.starts_with(['-', '.'])
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !valid_id {
+        return Err(AppError::InvalidInput(format!("Invalid repository id: {}", id)));
+    }
+    let valid_uri = ["https://", "http://", "ftp://", "file:///"].iter().any(|p| uri.starts_with(p))
+        && !uri.chars().any(|c| c.is_whitespace() || c.is_control());
+    if !valid_uri {
+        return Err(AppError::InvalidInput(format!("Invalid repository URL: {}", uri)));
+    }
+    
+    Ok((id, uri))
+}
+
+/// Repo id and `.repo` content for `copr:owner/project`, as `dnf copr enable` writes it
+fn render_copr_repo(spec: &str) -> Result<(String, String)> {
+    let (owner, project) = spec
+        .strip_prefix("copr:")
+        .and_then(|name| name.split_once('/'))
+        .filter(|(owner, project)| {
+            let valid = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+            valid(owner.trim_start_matches('@')) && valid(project)
+        })
+        .ok_or_else(|| AppError::InvalidInput("Invalid COPR format. Use copr:owner/project".to_string()))?;
+    
+    // Group projects (@group) use a "group_" prefix in the repo id
+    let id = format!("copr:{}:{}:{}", COPR_HOST, owner.replace('@', "group_"), project);
+    let chroot = if Path::new("/etc/fedora-release").exists() { "fedora" } else { "epel" };
+    let results = format!("https://download.{}/results/{}/{}", COPR_HOST, owner, project);
+    let content = format!(
+        "[{id}]\n\
+         name=Copr repo for {project} owned by {owner}\n\
+         baseurl={results}/{chroot}-$releasever-$basearch/\n\
+         type=rpm-md\n\
+         skip_if_unavailable=True\n\
+         gpgcheck=1\n\
+         gpgkey={results}/pubkey.gpg\n\
+         repo_gpgcheck=0\n\
+         enabled=1\n\
+         enabled_metadata=1\n"
+    );
+    
+    Ok((id, content))
+}
+
+// ============================================================================
+// pacman.conf Repositories
+// ============================================================================
+
+const PACMAN_CONF: &str = "/etc/pacman.conf";
+
+/// A `[repo]` section of pacman.conf; commented-out sections (`#[multilib]`) are disabled repos
+#[derive(Debug, Clone, PartialEq)]
+struct PacmanSection {
+    name: String,
+    /// 1-based line of the header
+    line: usize,
+    /// 0-based index of the first line after the section
+    end: usize,
+    enabled: bool,
+    server: Option<String>,
+    include: Option<String>,
+}
+
+/// Sections are the header plus the `Key = value` lines right below it (commented or not),
+/// up to the first blank or free-text line
+fn pacman_sections(content: &str) -> Vec<PacmanSection> {
+    let mut sections: Vec<PacmanSection> = Vec::new();
+    let mut open = false;
+    
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        let (commented, body) = match trimmed.strip_prefix('#') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, trimmed),
+        };
+        
+        if let Some(name) = body.strip_prefix('[').and_then(|b| b.strip_suffix(']')) {
+            let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+            open = valid;
+            if valid {
+                sections.push(PacmanSection {
+                    name: name.to_string(),
+                    line: i + 1,
+                    end: i + 1,
+                    enabled: !commented,
+                    server: None,
+                    include: None,
+                });
+            }
+            continue;
+        }
+        
+        let entry = body
+            .split_once('=')
+            .map(|(k, v)| (k.trim(), v.trim()))
+            .filter(|(k, _)| !k.is_empty() && k.chars().all(|c| c.is_ascii_alphanumeric()));
+        match (entry, sections.last_mut()) {
+            (Some((key, value)), Some(section)) if open => {
+                section.end = i + 1;
+                // Inside an enabled section, commented keys are switched off
+                if commented && section.enabled {
+                    continue;
+                }
+                match key {
+                    "Server" if section.server.is_none() => section.server = Some(value.to_string()),
+                    "Include" if section.include.is_none() => section.include = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+            _ => open = false,
+        }
+    }
+    
+    sections
+}
+
+/// First active `Server` of a mirrorlist, with `$repo` filled in
+fn first_mirror(include: &str, repo: &str) -> Option<String> {
+    fs::read_to_string(include)
+        .ok()?
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Server"))
+        .find_map(|rest| rest.trim_start().strip_prefix('='))
+        .map(|server| server.trim().replace("$repo", repo))
+}
+
+fn parse_pacman_conf(content: &str) -> Vec<Repository> {
+    let lines: Vec<&str> = content.lines().collect();
+    pacman_sections(content)
+        .into_iter()
+        .filter(|s| s.name != "options" && (s.server.is_some() || s.include.is_some()))
+        .map(|section| Repository {
+            file_path: PACMAN_CONF.to_string(),
+            line_number: section.line,
+            repo_type: "pacman".to_string(),
+            uri: section
+                .server
+                .clone()
+                .or_else(|| section.include.as_deref().and_then(|path| first_mirror(path, &section.name)))
+                .or(section.include.clone())
+                .unwrap_or_default(),
+            suite: section.name.clone(),
+            name: None,
+            components: Vec::new(),
+            is_enabled: section.enabled,
+            is_ppa: false,
+            raw_line: lines[section.line - 1].to_string(),
+            ppa_name: None,
+            signed_by: None,
+        })
+        .collect()
+}
+
+fn list_pacman_repositories() -> Vec<Repository> {
+    parse_pacman_conf(&fs::read_to_string(PACMAN_CONF).unwrap_or_default())
+}
+
+/// Comment out (or uncomment) the header and keys of the section on `line_number`
+fn toggle_pacman_repo(content: &str, line_number: usize) -> Option<String> {
+    let section = pacman_sections(content).into_iter().find(|s| s.line == line_number && s.name != "options")?;
+    let lines: Vec<String> = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i + 1 < section.line || i >= section.end {
+                line.to_string()
+            } else if section.enabled {
+                format!("#{}", line)
+            } else {
+                line.replacen('#', "", 1)
+            }
+        })
+        .collect();
+    Some(lines.join("\n") + "\n")
+}
+
+/// Drop the section on `line_number` and the blank line after it
+fn remove_pacman_repo(content: &str, line_number: usize) -> Option<String> {
+    let section = pacman_sections(content).into_iter().find(|s| s.line == line_number && s.name != "options")?;
+    let lines: Vec<&str> = content.lines().collect();
+    let end = if lines.get(section.end).is_some_and(|l| l.trim().is_empty()) { section.end + 1 } else { section.end };
+    let kept: Vec<&str> = lines[..section.line - 1].iter().chain(&lines[end..]).copied().collect();
+    Some(kept.join("\n") + "\n")
+}
+
+/// Country name of a region code
+pub(crate) fn region_name(code: &str) -> Option<&'static str> {
+    AVAILABLE_REGIONS.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+}
+
 /// Detect system region from locale
 fn detect_region() -> (String, String) {
     // Try multiple sources
@@ -469,144 +850,597 @@ fn detect_region() -> (String, String) {
         .unwrap_or("US")
         .to_uppercase();
     
-    let name = AVAILABLE_REGIONS
-        .iter()
-        .find(|(c, _)| *c == code)
-        .map(|(_, n)| n.to_string())
-        .unwrap_or_else(|| "United States".to_string());
+    let name = region_name(&code).unwrap_or("United States").to_string();
     
     (code, name)
 }
 
 // ============================================================================
-// Tauri Commands (All async)
+// Mirror Benchmarks
 // ============================================================================
 
-/// Check if repositories feature is available on this distro
-#[tauri::command]
-pub fn is_repositories_available(state: State<'_, AppState>) -> bool {
-    is_apt_based(&state.context.family)
+/// Bytes of the index file downloaded from each mirror
+const BENCHMARK_BYTES: usize = 256 * 1024;
+const BENCHMARK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Mirrors further behind than this are flagged and ranked after all others
+pub(crate) const MAX_SYNC_LAG_SECS: u64 = 24 * 3600;
+/// Sync lag that halves a mirror's score
+const SYNC_LAG_HALF_SCORE_SECS: u64 = 6 * 3600;
+
+const UBUNTU_ARCHIVE: &str = "http://archive.ubuntu.com/ubuntu";
+const FEDORA_ARCHIVE: &str = "https://dl.fedoraproject.org/pub/fedora/linux";
+const OPENSUSE_ARCHIVE: &str = "https://download.opensuse.org";
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// One timed download of the start of a file on a mirror
+pub(crate) struct Sample {
+    pub status: u16,
+    /// Time until the response headers arrived
+    pub latency_ms: u64,
+    /// Body bytes read, at most the cap
+    pub body: Vec<u8>,
+    /// Body transfer rate in KiB/s
+    pub throughput_kbps: u64,
 }
 
-/// Get all APT repositories
-#[tauri::command]
-pub async fn get_repositories(state: State<'_, AppState>) -> Result<Vec<Repository>> {
-    // Check if feature is available
-    if !is_apt_based(&state.context.family) {
-        if is_mock_mode() {
-            log::info!("[MOCK] Returning empty repositories for non-APT distro");
+/// The file a distro's mirrors are benchmarked on
+pub(crate) struct BenchmarkTarget {
+    /// Index file relative to the mirror root; one that changes with every sync
+    pub path: String,
+    /// File holding the sync time when the index does not carry one (Arch `lastsync`)
+    pub sync_path: Option<&'static str>,
+    /// Main archive the sync time is compared with; the freshest mirror otherwise
+    pub reference: Option<&'static str>,
+}
+
+impl BenchmarkTarget {
+    /// Arch: the core database, with the sync time from `lastsync`
+    pub fn arch() -> Self {
+        Self { path: "core/os/x86_64/core.db".to_string(), sync_path: Some("lastsync"), reference: None }
+    }
+
+    fn for_distro(distro: &DistroInfo) -> Result<Self> {
+        let target = match distro.family {
+            DistroFamily::Arch => Self::arch(),
+            // `-updates` since the release pocket itself never changes after release
+            DistroFamily::Debian | DistroFamily::Unknown => Self {
+                path: format!("dists/{}-updates/Release", ubuntu_codename(distro)?),
+                sync_path: None,
+                reference: Some(UBUNTU_ARCHIVE),
+            },
+            DistroFamily::Fedora => Self {
+                path: format!("updates/{}/Everything/x86_64/repodata/repomd.xml", distro.version),
+                sync_path: None,
+                reference: Some(FEDORA_ARCHIVE),
+            },
+            DistroFamily::Suse => Self {
+                path: if distro.id.contains("tumbleweed") {
+                    "tumbleweed/repo/oss/repodata/repomd.xml".to_string()
+                } else {
+                    format!("update/leap/{}/oss/repodata/repomd.xml", distro.version)
+                },
+                sync_path: None,
+                reference: Some(OPENSUSE_ARCHIVE),
+            },
+        };
+        Ok(target)
+    }
+}
+
+/// Ubuntu release the mirror table applies to: the suite of the Ubuntu archive in the
+/// sources (right on derivatives like Mint), else the os-release codename
+fn ubuntu_codename(distro: &DistroInfo) -> Result<String> {
+    list_apt_repositories()
+        .iter()
+        .find(|r| r.is_enabled && r.repo_type == "deb" && r.uri.contains("ubuntu") && !r.is_ppa)
+        .and_then(|r| r.suite.split('-').next().map(str::to_string))
+        .or_else(|| Some(distro.version_codename.clone()).filter(|c| !c.is_empty()))
+        .ok_or_else(|| AppError::NotSupported("Cannot tell which Ubuntu release to benchmark mirrors for".to_string()))
+}
+
+/// HTTP client for mirror probes; `timeout` covers the whole download
+pub(crate) fn mirror_client(timeout: Duration) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| AppError::Network(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// Download up to `max_bytes` of `url`, timing the response and the transfer
+pub(crate) async fn fetch_sample(client: &reqwest::Client, url: &str, max_bytes: usize) -> Result<Sample> {
+    let network_error = |e: reqwest::Error| {
+        if e.is_timeout() {
+            AppError::Timeout(format!("{} timed out", url))
+        } else {
+            AppError::Network(format!("Mirror unreachable: {}", e))
+        }
+    };
+
+    let start = Instant::now();
+    let mut response = client.get(url).send().await.map_err(network_error)?;
+    let latency = start.elapsed();
+    let status = response.status().as_u16();
+
+    let mut body = Vec::new();
+    while body.len() < max_bytes {
+        match response.chunk().await.map_err(network_error)? {
+            Some(chunk) => body.extend_from_slice(&chunk),
+            None => break,
         }
-        return Ok(Vec::new());
     }
-    
-    let repos = tokio::task::spawn_blocking(|| {
-        let mut all_repos = Vec::new();
+    body.truncate(max_bytes);
+
+    let transfer_ms = (start.elapsed() - latency).as_millis().max(1) as u64;
+    Ok(Sample {
+        status,
+        latency_ms: latency.as_millis() as u64,
+        throughput_kbps: body.len() as u64 * 1000 / 1024 / transfer_ms,
+        body,
+    })
+}
+
+/// Unix time of an RFC 2822 date as written in Release files ("Sat, 17 Oct 2026 10:00:00 UTC")
+fn parse_release_date(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace().skip_while(|p| p.ends_with(','));
+    let day: i64 = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+    let time: Vec<i64> = parts.next()?.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let [hours, minutes, seconds] = time[..] else { return None };
+
+    // Days since the epoch from the civil date, with March as the first month of the year
+    let (y, m) = if month <= 2 { (year - 1, month + 9) } else { (year, month - 3) };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u64::try_from(days * 86_400 + hours * 3600 + minutes * 60 + seconds).ok()
+}
+
+/// Generation time of an index: Arch `lastsync`, repomd `<revision>` or Release `Date:`
+fn index_time(body: &[u8]) -> Option<u64> {
+    let text = String::from_utf8_lossy(body);
+    let text = text.trim();
+    if let Ok(time) = text.parse() {
+        return Some(time);
+    }
+    if let Some((_, rest)) = text.split_once("<revision>") {
+        return rest.split('<').next()?.trim().parse().ok();
+    }
+    text.lines().find_map(|l| l.strip_prefix("Date:")).and_then(parse_release_date)
+}
+
+/// `repodata/...-primary.xml.*` location in a repomd.xml
+fn repomd_primary(body: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(body);
+    let (_, data) = text.split_once("<data type=\"primary\">")?;
+    let (_, location) = data.split_once("<location href=\"")?;
+    location.split('"').next().map(str::to_string)
+}
+
+/// Sync time of the target's index on `root`
+async fn sync_time(client: &reqwest::Client, target: &BenchmarkTarget, root: &str) -> Option<u64> {
+    let path = target.sync_path.map_or(target.path.as_str(), |p| p);
+    let sample = fetch_sample(client, &format!("{}/{}", root, path), BENCHMARK_BYTES).await.ok()?;
+    (sample.status == 200).then(|| index_time(&sample.body)).flatten()
+}
+
+/// Sync time of the main archive, if the target has one
+async fn reference_time(client: &reqwest::Client, target: &BenchmarkTarget) -> Option<u64> {
+    sync_time(client, target, target.reference?).await
+}
+
+/// Download the target's index from a mirror, recording status, latency, throughput and
+/// sync time; failures end up in `error`
+pub(crate) async fn benchmark_mirror(client: &reqwest::Client, target: &BenchmarkTarget, mut mirror: MirrorInfo) -> MirrorInfo {
+    let index = match fetch_sample(client, &format!("{}/{}", mirror.uri, target.path), BENCHMARK_BYTES).await {
+        Ok(sample) => sample,
+        Err(e) => {
+            mirror.error = Some(e.to_string());
+            return mirror;
+        }
+    };
+    mirror.http_status = Some(index.status);
+    if index.status != 200 {
+        mirror.error = Some(format!("HTTP {} for {}", index.status, target.path));
+        return mirror;
+    }
+    mirror.latency_ms = Some(index.latency_ms);
+    mirror.throughput_kbps = Some(index.throughput_kbps);
+    mirror.synced_at = index_time(&index.body);
+
+    // repomd.xml is a few KiB; time the primary metadata it points to instead
+    if let (Some(href), Some((dir, _))) = (repomd_primary(&index.body), target.path.rsplit_once("/repodata/")) {
+        if let Ok(sample) = fetch_sample(client, &format!("{}/{}/{}", mirror.uri, dir, href), BENCHMARK_BYTES).await {
+            if sample.status == 200 {
+                mirror.throughput_kbps = Some(sample.throughput_kbps);
+            }
+        }
+    }
+    if target.sync_path.is_some() {
+        mirror.synced_at = sync_time(client, target, &mirror.uri).await;
+    }
+    mirror
+}
+
+impl MirrorInfo {
+    /// Work out the sync lag against `reference` and the combined score: throughput discounted
+    /// by latency (so a mirror that answers fast but trickles loses to one that streams fast),
+    /// then by how far behind the mirror is
+    pub(crate) fn rate(&mut self, reference: Option<u64>) {
+        let (Some(latency), Some(throughput)) = (self.latency_ms, self.throughput_kbps) else { return };
+        self.sync_lag_secs = self.synced_at.zip(reference).map(|(synced, newest)| newest.saturating_sub(synced));
+        self.outdated = self.sync_lag_secs.is_some_and(|lag| lag > MAX_SYNC_LAG_SECS);
+
+        let speed = throughput * 1000 / (1000 + latency);
+        let lag = self.sync_lag_secs.unwrap_or(0);
+        self.score = Some(speed * SYNC_LAG_HALF_SCORE_SECS / (SYNC_LAG_HALF_SCORE_SECS + lag));
+    }
+
+    /// Sort key: measured and up to date by score, then outdated, then unreachable
+    pub(crate) fn rank_key(&self) -> (bool, bool, std::cmp::Reverse<Option<u64>>) {
+        (self.score.is_none(), self.outdated, std::cmp::Reverse(self.score))
+    }
+
+    /// One-line result for progress logs
+    pub(crate) fn summary(&self) -> String {
+        match (&self.error, self.latency_ms, self.throughput_kbps) {
+            (Some(error), _, _) => format!("{}: {}", self.uri, error),
+            (None, Some(ms), Some(kbps)) => format!("{}: {} ms, {} KiB/s", self.uri, ms, kbps),
+            _ => format!("{}: not tested", self.uri),
+        }
+    }
+}
+
+/// Rate every mirror against the main archive (or the freshest mirror) and sort best first
+pub(crate) fn rank_mirrors(mirrors: &mut [MirrorInfo], reference: Option<u64>) {
+    let reference = reference.or_else(|| mirrors.iter().filter_map(|m| m.synced_at).max());
+    for mirror in mirrors.iter_mut() {
+        mirror.rate(reference);
+    }
+    mirrors.sort_by_key(MirrorInfo::rank_key);
+}
+
+// ============================================================================
+// Repository Service
+// ============================================================================
+
+/// Repository and mirror operations for a distro
+#[derive(Clone)]
+pub struct Repositories {
+    distro: DistroInfo,
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+impl Repositories {
+    pub fn new(distro: DistroInfo, executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { distro, executor }
+    }
+
+    /// Whether repository management applies to this distro
+    pub fn is_available(&self) -> bool {
+        self.distro.family != DistroFamily::Unknown
+    }
+
+    /// All configured repositories, enabled or not (empty on unknown distros)
+    pub async fn list(&self) -> Result<Vec<Repository>> {
+        // Check if feature is available
+        if !self.is_available() {
+            if is_mock_mode() {
+                log::info!("[MOCK] Returning empty repositories for unknown distro");
+            }
+            return Ok(Vec::new());
+        }
+        
+        let family = self.distro.family;
+        let repos = tokio::task::spawn_blocking(move || match family {
+            DistroFamily::Fedora => list_ini_repositories(Path::new(YUM_REPOS_DIR)),
+            DistroFamily::Suse => list_ini_repositories(Path::new(ZYPP_REPOS_DIR)),
+            DistroFamily::Arch => list_pacman_repositories(),
+            _ => list_apt_repositories(),
+        }).await.map_err(|e| AppError::System(format!("Task failed: {}", e)))?;
+        
+        Ok(repos)
+    }
+
+    /// Enable or disable the repository at `line_number` of `file_path`
+    pub async fn toggle(&self, file_path: &str, line_number: usize) -> Result<()> {
+        let content = fs::read_to_string(file_path)?;
+        let new_content = match self.distro.family {
+            DistroFamily::Fedora | DistroFamily::Suse => toggle_ini_repo(&content, line_number),
+            DistroFamily::Arch => toggle_pacman_repo(&content, line_number),
+            _ if file_path.ends_with(".sources") => toggle_deb822_repo(&content, line_number),
+            _ => toggle_apt_line(&content, line_number),
+        }
+        .ok_or_else(|| AppError::InvalidInput("Invalid line number".to_string()))?;
+        
+        self.executor.execute(&[PrivilegedOp::write_repo_file(file_path, &new_content)]).await?;
+        
+        Ok(())
+    }
+
+    /// Delete a repository file, or only the repository at `line_number`
+    /// (one-line APT files get every line commented out instead)
+    pub async fn delete(&self, file_path: &str, is_whole_file: bool, line_number: Option<usize>) -> Result<String> {
+        if self.distro.family == DistroFamily::Arch && (is_whole_file || file_path != PACMAN_CONF) {
+            return Err(AppError::InvalidInput("Arch repositories live in pacman.conf".to_string()));
+        }
+        if is_whole_file {
+            // Delete the entire file (PPAs, COPRs, third-party .repo files)
+            self.executor.execute(&[PrivilegedOp::remove_file(file_path)]).await?;
+            return Ok(format!("Deleted {}", file_path));
+        }
+        
+        let content = fs::read_to_string(file_path)?;
+        let op = match (self.distro.family, line_number) {
+            (DistroFamily::Fedora | DistroFamily::Suse | DistroFamily::Arch, Some(line)) => {
+                let new_content = if self.distro.family == DistroFamily::Arch {
+                    remove_pacman_repo(&content, line)
+                } else {
+                    ini::remove_section(&content, line)
+                }
+                .ok_or_else(|| AppError::InvalidInput("Invalid line number".to_string()))?;
+                
+                if self.distro.family != DistroFamily::Arch && ini::sections(&new_content).is_empty() {
+                    PrivilegedOp::remove_file(file_path)
+                } else {
+                    PrivilegedOp::write_repo_file(file_path, &new_content)
+                }
+            }
+            (DistroFamily::Fedora | DistroFamily::Suse | DistroFamily::Arch, None) => {
+                return Err(AppError::InvalidInput("Missing repository line number".to_string()));
+            }
+            (_, Some(line)) if file_path.ends_with(".sources") => {
+                let new_content = deb822::remove_stanza(&content, line)
+                    .ok_or_else(|| AppError::InvalidInput("Invalid line number".to_string()))?;
+                if deb822::parse(&new_content).is_empty() {
+                    PrivilegedOp::remove_file(file_path)
+                } else {
+                    PrivilegedOp::write_repo_file(file_path, &new_content)
+                }
+            }
+            (_, None) if file_path.ends_with(".sources") => {
+                // Disable every stanza
+                let new_content = deb822::parse(&content)
+                    .iter()
+                    .rev()
+                    .try_fold(content.clone(), |text, stanza| deb822::set_field(&text, stanza.line, "Enabled", "no"))
+                    .ok_or_else(|| AppError::InvalidInput(format!("Cannot parse {}", file_path)))?;
+                PrivilegedOp::write_repo_file(file_path, &new_content)
+            }
+            _ => {
+                // Just disable the lines (comment them out)
+                let new_content: String = content.lines()
+                    .map(|line| {
+                        if !line.trim().starts_with('#') && line.contains("deb") {
+                            format!("# {}", line)
+                        } else {
+                            line.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n") + "\n";
+                PrivilegedOp::write_repo_file(file_path, &new_content)
+            }
+        };
         
-        let main_sources = Path::new("/etc/apt/sources.list");
-        if main_sources.exists() {
-            all_repos.extend(parse_sources_file(main_sources));
+        self.executor.execute(&[op]).await?;
+        Ok("Repository removed".to_string())
+    }
+
+    /// Add a repository from `ppa:user/repo` (Debian), `copr:owner/project` (Fedora)
+    /// or `<id> <url>` (Fedora, openSUSE, Arch)
+    pub async fn add(&self, spec: &str) -> Result<String> {
+        let spec = spec.trim();
+        if self.distro.family == DistroFamily::Debian {
+            return self.add_ppa(spec).await;
         }
         
-        let sources_d = Path::new("/etc/apt/sources.list.d");
-        if sources_d.exists() {
-            if let Ok(entries) = fs::read_dir(sources_d) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.extension().map(|e| e == "list").unwrap_or(false) {
-                        all_repos.extend(parse_sources_file(&path));
+        let (path, content) = match self.distro.family {
+            DistroFamily::Fedora if spec.starts_with("copr:") => {
+                let (id, content) = render_copr_repo(spec)?;
+                (format!("{}/_{}.repo", YUM_REPOS_DIR, id), content)
+            }
+            DistroFamily::Fedora | DistroFamily::Suse | DistroFamily::Arch => {
+                let (id, uri) = parse_repo_spec(spec)?;
+                match self.distro.family {
+                    DistroFamily::Fedora => (
+                        format!("{}/{}.repo", YUM_REPOS_DIR, id),
+                        format!("[{}]\nname={}\nbaseurl={}\nenabled=1\ngpgcheck=1\n", id, id, uri),
+                    ),
+                    DistroFamily::Suse => (
+                        format!("{}/{}.repo", ZYPP_REPOS_DIR, id),
+                        format!("[{}]\nname={}\nenabled=1\nautorefresh=1\nbaseurl={}\ntype=rpm-md\ngpgcheck=1\n", id, id, uri),
+                    ),
+                    _ => {
+                        let current = fs::read_to_string(PACMAN_CONF)?;
+                        if id == "options" || pacman_sections(&current).iter().any(|s| s.name == id) {
+                            return Err(AppError::InvalidInput(format!("Repository {} already exists", id)));
+                        }
+                        let separator = if current.ends_with("\n\n") || current.is_empty() { "" } else if current.ends_with('\n') { "\n" } else { "\n\n" };
+                        (PACMAN_CONF.to_string(), format!("{}{}[{}]\nServer = {}\n", current, separator, id, uri))
                     }
                 }
             }
+            _ => return Err(AppError::NotSupported("Repositories are not supported on this distribution".to_string())),
+        };
+        
+        if path != PACMAN_CONF && Path::new(&path).exists() {
+            return Err(AppError::InvalidInput(format!("{} already exists", path)));
         }
+        self.executor.execute(&[PrivilegedOp::write_repo_file(&path, &content)]).await?;
         
-        all_repos
-    }).await.map_err(|e| AppError::System(format!("Task failed: {}", e)))?;
-    
-    Ok(repos)
-}
+        Ok(format!("Added repository to {}", path))
+    }
 
-/// Delete a repository file or line
-#[tauri::command]
-pub async fn delete_repository(file_path: String, is_whole_file: bool) -> Result<String> {
-    if is_whole_file {
-        // Delete the entire .list file (for PPAs)
-        let script = format!("rm -f '{}'", file_path);
-        privileged::run_privileged_shell(&script).await?;
-        Ok(format!("Deleted {}", file_path))
-    } else {
-        // Just disable the line (comment it out)
-        let content = fs::read_to_string(&file_path)?;
-        let lines: Vec<&str> = content.lines().collect();
-        let new_content: String = lines.iter()
-            .map(|line| {
-                if !line.trim().starts_with('#') && line.contains("deb") {
-                    format!("# {}", line)
-                } else {
-                    line.to_string()
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n") + "\n";
+    /// Add a Launchpad PPA (`ppa:user/repo`)
+    pub async fn add_ppa(&self, ppa: &str) -> Result<String> {
+        self.executor.execute(&[PrivilegedOp::add_ppa(ppa)]).await
+    }
+
+    /// Remove a Launchpad PPA
+    pub async fn remove_ppa(&self, ppa: &str) -> Result<String> {
+        self.executor.execute(&[PrivilegedOp::remove_ppa(ppa)]).await
+    }
+
+    /// Point known APT mirror URIs at `new_uri`
+    /// Rewrites sources.list and the URIs of deb822 `.sources` files
+    pub async fn set_apt_mirror(&self, new_uri: &str) -> Result<String> {
+        let mut files = vec![Path::new("/etc/apt/sources.list").to_path_buf()];
+        if let Ok(entries) = fs::read_dir("/etc/apt/sources.list.d") {
+            files.extend(entries.flatten().map(|e| e.path()).filter(|p| p.extension().is_some_and(|e| e == "sources")));
+        }
+        
+        let mut ops = Vec::new();
+        for path in &files {
+            let Ok(content) = fs::read_to_string(path) else { continue };
+            let new_content = if path.extension().is_some_and(|e| e == "sources") {
+                replace_deb822_mirror(&content, new_uri)
+            } else {
+                replace_mirror(&content, new_uri)
+            };
+            if new_content != content {
+                ops.push(PrivilegedOp::write_repo_file(&path.to_string_lossy(), &new_content));
+            }
+        }
+        
+        if ops.is_empty() {
+            return Ok("No changes needed".to_string());
+        }
+        
+        self.executor.execute(&ops).await?;
         
-        let script = format!(
-            "echo '{}' | tee '{}' > /dev/null",
-            new_content.replace("'", "'\\''"),
-            file_path
-        );
-        privileged::run_privileged_shell(&script).await?;
-        Ok("Repository disabled".to_string())
+        Ok(format!("Mirror changed to {}", new_uri))
+    }
+
+    /// Install apt-fast from its PPA
+    pub async fn install_apt_fast(&self) -> Result<String> {
+        self.executor
+            .execute(&[
+                PrivilegedOp::add_ppa("ppa:apt-fast/stable"),
+                PrivilegedOp::refresh_repositories("apt-get"),
+                PrivilegedOp::package("apt-get", &["install", "-y", "apt-fast", "aria2"]),
+            ])
+            .await
+    }
+
+    /// Configure apt-fast max connections
+    pub async fn configure_apt_fast(&self, max_connections: u32) -> Result<String> {
+        self.executor.execute(&[PrivilegedOp::ConfigureAptFast { max_connections }]).await?;
+        
+        Ok(format!("apt-fast configured with {} connections", max_connections))
+    }
+
+    /// Refresh the APT package lists, with apt-fast if it is installed
+    pub async fn apt_update(&self, progress: &Progress) -> Result<String> {
+        let status = check_apt_fast();
+        let program = if status.installed { "apt-fast" } else { "apt-get" };
+
+        progress.step(format!("Refreshing package lists ({})", program), None);
+        self.executor.execute(&[PrivilegedOp::refresh_repositories(program)]).await
+    }
+
+    /// Known mirrors for this distro, optionally filtered by region
+    pub fn mirrors(&self, region: Option<String>) -> Vec<MirrorInfo> {
+        get_mirrors_for_family(region, &self.distro.family)
+    }
+
+    /// Benchmark mirrors for a region in parallel, best first
+    /// Each finished benchmark is reported to `progress`
+    pub async fn test_all_mirrors(&self, region: Option<String>, progress: &Progress) -> Result<Vec<MirrorInfo>> {
+        let mirrors = self.mirrors(region);
+        let target = BenchmarkTarget::for_distro(&self.distro)?;
+        let client = mirror_client(BENCHMARK_TIMEOUT)?;
+        let total = mirrors.len().max(1);
+        let tested = AtomicUsize::new(0);
+        progress.step(format!("Benchmarking {} mirrors on {}", mirrors.len(), target.path), Some(0));
+
+        let benchmarks = join_all(mirrors.into_iter().map(|mirror| {
+            let (client, target, tested) = (&client, &target, &tested);
+            async move {
+                let mirror = benchmark_mirror(client, target, mirror).await;
+                let done = tested.fetch_add(1, Ordering::Relaxed) + 1;
+                progress.step(format!("Tested {} of {} mirrors", done, total), Some((done * 100 / total) as u8));
+                progress.log(mirror.summary());
+                mirror
+            }
+        }));
+        let (reference, mut mirrors) = futures::join!(reference_time(&client, &target), benchmarks);
+
+        rank_mirrors(&mut mirrors, reference);
+        Ok(mirrors)
     }
+
+    /// Benchmark one mirror against the main archive
+    pub async fn test_mirror(&self, uri: &str) -> Result<MirrorInfo> {
+        let uri = uri.trim_end_matches('/');
+        let mirror = self
+            .mirrors(None)
+            .into_iter()
+            .find(|m| m.uri == uri)
+            .unwrap_or_else(|| MirrorInfo { name: uri.to_string(), uri: uri.to_string(), ..Default::default() });
+        let target = BenchmarkTarget::for_distro(&self.distro)?;
+        let client = mirror_client(BENCHMARK_TIMEOUT)?;
+
+        let (reference, mut mirror) = futures::join!(reference_time(&client, &target), benchmark_mirror(&client, &target, mirror));
+        mirror.rate(reference);
+        Ok(mirror)
+    }
+}
+
+// ============================================================================
+// Tauri Commands (All async)
+// ============================================================================
+
+/// Check if repositories feature is available on this distro
+#[tauri::command]
+pub fn is_repositories_available(state: State<'_, AppState>) -> bool {
+    state.repositories().is_available()
+}
+
+/// Get all repositories of this distro
+#[tauri::command]
+pub async fn get_repositories(state: State<'_, AppState>) -> Result<Vec<Repository>> {
+    state.repositories().list().await
+}
+
+/// Delete a repository file, or the repository at a line of it
+#[tauri::command]
+pub async fn delete_repository(
+    file_path: String,
+    is_whole_file: bool,
+    line_number: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<String> {
+    state.repositories().delete(&file_path, is_whole_file, line_number).await
 }
 
 /// Toggle repository enabled/disabled
 #[tauri::command]
-pub async fn toggle_repository(file_path: String, line_number: usize) -> Result<()> {
-    let content = fs::read_to_string(&file_path)?;
-    let lines: Vec<&str> = content.lines().collect();
-    
-    if line_number == 0 || line_number > lines.len() {
-        return Err(AppError::System("Invalid line number".to_string()));
-    }
-    
-    let mut new_lines: Vec<String> = lines.iter().map(|s| s.to_string()).collect();
-    let line = &new_lines[line_number - 1];
-    
-    if line.trim().starts_with('#') {
-        new_lines[line_number - 1] = line.trim_start_matches('#').trim_start().to_string();
-    } else {
-        new_lines[line_number - 1] = format!("# {}", line);
-    }
-    
-    let new_content = new_lines.join("\n") + "\n";
-    
-    let script = format!(
-        "echo '{}' | tee '{}' > /dev/null",
-        new_content.replace("'", "'\\''"),
-        file_path
-    );
-    privileged::run_privileged_shell(&script).await?;
-    
-    Ok(())
+pub async fn toggle_repository(file_path: String, line_number: usize, state: State<'_, AppState>) -> Result<()> {
+    state.repositories().toggle(&file_path, line_number).await
+}
+
+/// Add a repository (PPA, COPR or `<id> <url>`)
+#[tauri::command]
+pub async fn add_repository(spec: String, state: State<'_, AppState>) -> Result<String> {
+    state.repositories().add(&spec).await
 }
 
 /// Add a PPA
 #[tauri::command]
-pub async fn add_ppa(ppa: String) -> Result<String> {
-    if !ppa.starts_with("ppa:") {
-        return Err(AppError::System("Invalid PPA format. Use ppa:user/repo".to_string()));
-    }
-    
-    privileged::run_privileged("add-apt-repository", &["-y", &ppa]).await
+pub async fn add_ppa(ppa: String, state: State<'_, AppState>) -> Result<String> {
+    state.repositories().add_ppa(&ppa).await
 }
 
 /// Remove a PPA
 #[tauri::command]
-pub async fn remove_ppa(ppa: String) -> Result<String> {
-    if !ppa.starts_with("ppa:") {
-        return Err(AppError::System("Invalid PPA format".to_string()));
-    }
-    
-    privileged::run_privileged("add-apt-repository", &["-r", "-y", &ppa]).await
+pub async fn remove_ppa(ppa: String, state: State<'_, AppState>) -> Result<String> {
+    state.repositories().remove_ppa(&ppa).await
 }
 
 /// Get region info
@@ -625,11 +1459,11 @@ pub fn get_region_info() -> RegionInfo {
 /// Now supports multi-distro: Debian/Ubuntu, Arch, Fedora, openSUSE
 #[tauri::command]
 pub fn get_mirrors(region: Option<String>, state: State<'_, AppState>) -> Vec<MirrorInfo> {
-    get_mirrors_for_family(region, &state.context.family)
+    state.repositories().mirrors(region)
 }
 
 /// Internal helper function to get mirrors for a specific distro family
-fn get_mirrors_for_family(region: Option<String>, family: &DistroFamily) -> Vec<MirrorInfo> {
+pub(crate) fn get_mirrors_for_family(region: Option<String>, family: &DistroFamily) -> Vec<MirrorInfo> {
     // Select mirror list based on distro family
     let mirrors: &[(&str, &str, &str, &str)] = match family {
         DistroFamily::Debian => UBUNTU_MIRRORS,
@@ -649,99 +1483,81 @@ fn get_mirrors_for_family(region: Option<String>, family: &DistroFamily) -> Vec<
             uri: uri.to_string(),
             country: country.to_string(),
             country_code: code.to_string(),
-            latency_ms: None,
+            ..Default::default()
         })
         .collect()
 }
 
-/// Test a single mirror speed
+/// Benchmark a single mirror
 #[tauri::command]
-pub async fn test_mirror_speed(uri: String) -> Result<u64> {
-    let start = Instant::now();
-    
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()
-        .map_err(|e| AppError::Network(format!("Failed to create HTTP client: {}", e)))?;
-    
-    let result = timeout(
-        Duration::from_secs(5),
-        client.head(&uri).send()
-    ).await;
-    
-    match result {
-        Ok(Ok(_)) => Ok(start.elapsed().as_millis() as u64),
-        Ok(Err(e)) => Err(AppError::Network(format!("Mirror unreachable: {}", e))),
-        Err(_) => Err(AppError::Timeout("Mirror test timed out".to_string())),
-    }
+pub async fn test_mirror_speed(uri: String, state: State<'_, AppState>) -> Result<MirrorInfo> {
+    state.repositories().test_mirror(&uri).await
 }
 
 /// Test mirrors for a region in parallel
 #[tauri::command]
-pub async fn test_all_mirrors(region: Option<String>, state: State<'_, AppState>) -> Result<Vec<MirrorInfo>> {
-    // Extract family before async block to avoid lifetime issues
-    let family = state.context.family;
-    
-    // Get mirrors using extracted family
-    let mut mirrors: Vec<MirrorInfo> = get_mirrors_for_family(region, &family);
-    
-    // Collect URIs for parallel testing
-    let uris: Vec<String> = mirrors.iter().map(|m| m.uri.clone()).collect();
-    
-    let test_futures: Vec<_> = uris.into_iter().map(|uri| {
-        async move {
-            test_mirror_speed(uri).await.ok()
-        }
-    }).collect();
-    
-    let results = join_all(test_futures).await;
-    
-    for (mirror, latency) in mirrors.iter_mut().zip(results) {
-        mirror.latency_ms = latency;
-    }
-    
-    mirrors.sort_by(|a, b| {
-        match (a.latency_ms, b.latency_ms) {
-            (Some(a_ms), Some(b_ms)) => a_ms.cmp(&b_ms),
-            (Some(_), None) => std::cmp::Ordering::Less,
-            (None, Some(_)) => std::cmp::Ordering::Greater,
-            (None, None) => std::cmp::Ordering::Equal,
-        }
-    });
-    
-    Ok(mirrors)
+pub async fn test_all_mirrors(
+    region: Option<String>,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<MirrorInfo>> {
+    let repositories = state.repositories();
+    state
+        .track(&app, operation_id, |progress| async move {
+            repositories.test_all_mirrors(region, &progress).await
+        })
+        .await
 }
 
 /// Set the fastest mirror as primary
+/// Rewrites known mirror URIs in sources.list and in the URIs of deb822 `.sources` files;
+/// on Arch the mirror goes first in the pacman mirrorlist
 #[tauri::command]
-pub async fn set_mirror(new_uri: String) -> Result<String> {
-    let sources_path = "/etc/apt/sources.list";
-    let content = fs::read_to_string(sources_path)?;
-    
-    // Replace all known mirror patterns
-    let mut new_content = content.clone();
-    for (_, uri, _, _) in UBUNTU_MIRRORS {
-        new_content = new_content.replace(*uri, &new_uri);
+pub async fn set_mirror(new_uri: String, state: State<'_, AppState>) -> Result<String> {
+    if state.distro_family() == DistroFamily::Arch {
+        return state.mirrorlist().set_primary(&new_uri).await;
     }
-    
-    if new_content == content {
-        return Ok("No changes needed".to_string());
+    state.repositories().set_apt_mirror(&new_uri).await
+}
+
+/// Replace all known mirror patterns
+fn replace_mirror(text: &str, new_uri: &str) -> String {
+    let mut new_text = text.to_string();
+    for (_, uri, _, _) in UBUNTU_MIRRORS {
+        new_text = new_text.replace(*uri, new_uri);
     }
-    
-    let script = format!(
-        "echo '{}' | tee '{}' > /dev/null",
-        new_content.replace("'", "'\\''"),
-        sources_path
-    );
-    privileged::run_privileged_shell(&script).await?;
-    
-    Ok(format!("Mirror changed to {}", new_uri))
+    new_text
+}
+
+/// Replace known mirrors in the URIs fields only, leaving comments alone
+fn replace_deb822_mirror(content: &str, new_uri: &str) -> String {
+    deb822::parse(content).iter().fold(content.to_string(), |text, stanza| {
+        let uris = stanza.get("URIs").unwrap_or_default();
+        let new_uris = replace_mirror(uris, new_uri);
+        if new_uris == uris {
+            return text;
+        }
+        // Stanzas keep their line numbers since URIs stays on one line
+        deb822::set_field(&text, stanza.line, "URIs", &new_uris).unwrap_or(text)
+    })
 }
 
 // ============================================================================
 // apt-fast Integration
 // ============================================================================
 
+pub const APT_FAST_CONF: &str = "/etc/apt-fast.conf";
+
+/// apt-fast.conf for `max_connections` parallel aria2 downloads
+pub(crate) fn render_apt_fast_conf(max_connections: u32) -> String {
+    format!(r#"# apt-fast configuration
+_APTMGR=apt-get
+_MAXNUM={}
+_DOWNLOADER='aria2c --no-conf -c -j ${{_MAXNUM}} -x ${{_MAXNUM}} -s ${{_MAXNUM}} --min-split-size=1M --stream-piece-selector=default -i ${{DLLIST}} --connect-timeout=600 --timeout=600 -m0 --header "Accept: */*"'
+"#, max_connections)
+}
+
 /// Check if apt-fast is installed
 #[tauri::command]
 pub fn check_apt_fast() -> AptFastStatus {
@@ -758,7 +1574,7 @@ pub fn check_apt_fast() -> AptFastStatus {
         .unwrap_or(false);
     
     // Read max connections from config if exists
-    let max_connections = fs::read_to_string("/etc/apt-fast.conf")
+    let max_connections = fs::read_to_string(APT_FAST_CONF)
         .ok()
         .and_then(|content| {
             content.lines()
@@ -777,44 +1593,146 @@ pub fn check_apt_fast() -> AptFastStatus {
 
 /// Install apt-fast
 #[tauri::command]
-pub async fn install_apt_fast() -> Result<String> {
-    // Add PPA and install
-    let script = r#"
-        add-apt-repository -y ppa:apt-fast/stable && 
-        apt-get update && 
-        DEBIAN_FRONTEND=noninteractive apt-get install -y apt-fast aria2
-    "#;
-    
-    privileged::run_privileged_shell(script).await
+pub async fn install_apt_fast(state: State<'_, AppState>) -> Result<String> {
+    state.repositories().install_apt_fast().await
 }
 
 /// Configure apt-fast max connections
 #[tauri::command]
-pub async fn configure_apt_fast(max_connections: u32) -> Result<String> {
-    let config_content = format!(r#"# apt-fast configuration
-_APTMGR=apt-get
-_MAXNUM={}
-_DOWNLOADER='aria2c --no-conf -c -j ${{_MAXNUM}} -x ${{_MAXNUM}} -s ${{_MAXNUM}} --min-split-size=1M --stream-piece-selector=default -i ${{DLLIST}} --connect-timeout=600 --timeout=600 -m0 --header "Accept: */*"'
-"#, max_connections);
-    
-    let script = format!(
-        "echo '{}' | tee /etc/apt-fast.conf > /dev/null",
-        config_content
-    );
-    
-    privileged::run_privileged_shell(&script).await?;
-    
-    Ok(format!("apt-fast configured with {} connections", max_connections))
+pub async fn configure_apt_fast(max_connections: u32, state: State<'_, AppState>) -> Result<String> {
+    state.repositories().configure_apt_fast(max_connections).await
 }
 
-/// Run apt update (with apt-fast if available)
+/// Run apt update (with apt-fast if available), streaming its output
 #[tauri::command]
-pub async fn apt_update() -> Result<String> {
-    let status = check_apt_fast();
-    
-    if status.installed {
-        privileged::run_privileged("apt-fast", &["update"]).await
-    } else {
-        privileged::run_privileged("apt-get", &["update"]).await
+pub async fn apt_update(
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String> {
+    let repositories = state.repositories();
+    state
+        .track(&app, operation_id, |progress| async move { repositories.apt_update(&progress).await })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deb822_sources() {
+        let content = "Types: deb\n\
+                       # Mirror picked by the installer\n\
+                       URIs: http://archive.ubuntu.com/ubuntu/\n\
+                       Suites: noble noble-updates\n\
+                       Components: main restricted universe\n\
+                       Signed-By: /usr/share/keyrings/ubuntu-archive-keyring.gpg\n\n\
+                       Types: deb\n\
+                       URIs: https://ppa.launchpadcontent.net/fish-shell/release-3/ubuntu/\n\
+                       Suites: noble\n\
+                       Components: main\n\
+                       Enabled: no\n";
+        let repos = parse_deb822_file(content, "/etc/apt/sources.list.d/ubuntu.sources");
+        assert_eq!(repos.len(), 2);
+        assert_eq!(repos[0].suite, "noble noble-updates");
+        assert_eq!(repos[0].signed_by.as_deref(), Some("/usr/share/keyrings/ubuntu-archive-keyring.gpg"));
+        assert!(repos[0].is_enabled && !repos[1].is_enabled);
+        assert_eq!(repos[1].line_number, 8);
+        assert_eq!(repos[1].ppa_name.as_deref(), Some("ppa:fish-shell/release-3"));
+
+        let toggled = toggle_deb822_repo(content, 8).unwrap();
+        assert!(parse_deb822_file(&toggled, "")[1].is_enabled);
+
+        let switched = replace_deb822_mirror(content, "http://mirror.kakao.com/ubuntu");
+        assert!(switched.starts_with("Types: deb\n# Mirror picked by the installer\nURIs: http://mirror.kakao.com/ubuntu/\n"));
+
+        let line = "deb [arch=amd64 signed-by=/etc/apt/keyrings/docker.asc] https://download.docker.com/linux/ubuntu noble stable";
+        let repo = parse_repo_line(line, "/etc/apt/sources.list.d/docker.list", 1).unwrap();
+        assert_eq!(repo.signed_by.as_deref(), Some("/etc/apt/keyrings/docker.asc"));
+        assert_eq!(repo.uri, "https://download.docker.com/linux/ubuntu");
+    }
+
+    #[test]
+    fn test_parse_ini_repo_file() {
+        let content = "[fedora]\nname=Fedora $releasever - $basearch\n\
+                       metalink=https://mirrors.fedoraproject.org/metalink?repo=fedora-$releasever&arch=$basearch\n\
+                       enabled=1\n\n\
+                       [copr:copr.fedorainfracloud.org:atim:starship]\nname=Copr repo for starship owned by atim\n\
+                       baseurl=https://download.copr.fedorainfracloud.org/results/atim/starship/fedora-$releasever-$basearch/\n\
+                       enabled=0\n";
+        let repos = parse_ini_repo_file(content, "/etc/yum.repos.d/test.repo");
+        assert_eq!(repos.len(), 2);
+        assert!(repos[0].is_enabled && !repos[0].is_ppa);
+        assert!(repos[0].uri.starts_with("https://mirrors.fedoraproject.org/metalink"));
+        assert_eq!(repos[1].line_number, 6);
+        assert_eq!(repos[1].ppa_name.as_deref(), Some("copr:atim/starship"));
+
+        let toggled = toggle_ini_repo(content, 6).unwrap();
+        assert!(parse_ini_repo_file(&toggled, "")[1].is_enabled);
+
+        let (id, rendered) = render_copr_repo("copr:@python/python3.12").unwrap();
+        assert_eq!(id, "copr:copr.fedorainfracloud.org:group_python:python3.12");
+        assert_eq!(parse_ini_repo_file(&rendered, "")[0].ppa_name.as_deref(), Some("copr:group_python/python3.12"));
+        assert!(render_copr_repo("copr:owner").is_err());
+        assert!(parse_repo_spec("extra https://example.com/repo; rm").is_err());
+    }
+
+    #[test]
+    fn test_pacman_conf_repos() {
+        let content = "[options]\nHoldPkg = pacman glibc\n\n\
+                       #[core-testing]\n#Include = /etc/pacman.d/mirrorlist\n\n\
+                       [core]\nInclude = /nonexistent/mirrorlist\n\n\
+                       # An example of a custom package repository.\n\
+                       [custom]\nSigLevel = Optional TrustAll\nServer = file:///home/custompkgs\n";
+        let repos = parse_pacman_conf(content);
+        let names: Vec<&str> = repos.iter().map(|r| r.suite.as_str()).collect();
+        assert_eq!(names, ["core-testing", "core", "custom"]);
+        assert!(!repos[0].is_enabled && repos[1].is_enabled);
+        assert_eq!(repos[1].uri, "/nonexistent/mirrorlist");
+        assert_eq!(repos[2].uri, "file:///home/custompkgs");
+
+        let enabled = toggle_pacman_repo(content, 4).unwrap();
+        assert!(enabled.contains("\n[core-testing]\nInclude = /etc/pacman.d/mirrorlist\n"));
+        assert_eq!(toggle_pacman_repo(&enabled, 4).unwrap(), content);
+        assert_eq!(toggle_pacman_repo(content, 1), None);
+
+        let removed = remove_pacman_repo(content, 7).unwrap();
+        assert!(removed.contains("#Include = /etc/pacman.d/mirrorlist\n\n# An example"));
+    }
+
+    #[test]
+    fn test_mirror_benchmark_scoring() {
+        let release = b"Origin: Ubuntu\nLabel: Ubuntu\nSuite: noble-updates\nDate: Sat, 17 Oct 2026 10:00:00 UTC\n";
+        assert_eq!(index_time(release), Some(1_792_231_200));
+        assert_eq!(parse_release_date("29 Feb 2024 23:59:59 +0000"), Some(1_709_251_199));
+        assert_eq!(index_time(b"1792231200\n"), Some(1_792_231_200));
+
+        let repomd = br#"<repomd><revision>1792231200</revision>
+  <data type="filelists"><location href="repodata/aa-filelists.xml.zst"/></data>
+  <data type="primary"><checksum>x</checksum><location href="repodata/bb-primary.xml.zst"/></data></repomd>"#;
+        assert_eq!(index_time(repomd), Some(1_792_231_200));
+        assert_eq!(repomd_primary(repomd).as_deref(), Some("repodata/bb-primary.xml.zst"));
+
+        // Fast but two days stale loses to a slower mirror that is up to date
+        let tested = |uri: &str, latency_ms: u64, throughput_kbps: u64, lag: u64| MirrorInfo {
+            uri: uri.to_string(),
+            latency_ms: Some(latency_ms),
+            throughput_kbps: Some(throughput_kbps),
+            synced_at: Some(1_792_231_200 - lag),
+            ..Default::default()
+        };
+        let mut mirrors = vec![
+            MirrorInfo { uri: "down".to_string(), error: Some("HTTP 404".to_string()), ..Default::default() },
+            tested("stale", 20, 20_000, 2 * 86_400),
+            tested("fresh", 150, 4_000, 3_600),
+            tested("faster", 30, 8_000, 3_600),
+        ];
+        rank_mirrors(&mut mirrors, Some(1_792_231_200));
+        let order: Vec<&str> = mirrors.iter().map(|m| m.uri.as_str()).collect();
+        assert_eq!(order, ["faster", "fresh", "stale", "down"]);
+        assert!(mirrors[2].outdated);
+        assert_eq!(mirrors[1].sync_lag_secs, Some(3_600));
+        assert_eq!(mirrors[3].score, None);
     }
 }
@@ -7,8 +7,8 @@ use crate::state::AppState;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::fs;
-use std::sync::Mutex;
-use sysinfo::{CpuRefreshKind, MemoryRefreshKind, Networks};
+use std::sync::{Arc, Mutex};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, Networks, System};
 use tauri::State;
 
 // ============================================================================
@@ -217,71 +217,106 @@ fn detect_gpu() -> Option<GpuInfo> {
 }
 
 // ============================================================================
-// Tauri Commands (All non-blocking)
+// Resource Service
 // ============================================================================
 
-/// Get current resource snapshot with enhanced data
-#[tauri::command]
-pub async fn get_resource_snapshot(app_state: State<'_, AppState>) -> Result<ResourceSnapshot> {
-    let sys = app_state.sys.clone();
+/// Resource sampling from the shared sysinfo handle
+#[derive(Clone)]
+pub struct Resources {
+    sys: Arc<Mutex<System>>,
+}
 
-    let snapshot = tokio::task::spawn_blocking(move || {
-        let mut sys = sys.lock().unwrap();
+impl Resources {
+    pub fn new(sys: Arc<Mutex<System>>) -> Self {
+        Self { sys }
+    }
 
-        sys.refresh_cpu_specifics(CpuRefreshKind::nothing().with_cpu_usage());
-        sys.refresh_memory_specifics(MemoryRefreshKind::everything());
+    /// Sample CPU, memory, network and disk counters
+    pub async fn snapshot(&self) -> Result<ResourceSnapshot> {
+        let sys = self.sys.clone();
+
+        let snapshot = tokio::task::spawn_blocking(move || {
+            let mut sys = sys.lock().unwrap();
+
+            sys.refresh_cpu_specifics(CpuRefreshKind::nothing().with_cpu_usage());
+            sys.refresh_memory_specifics(MemoryRefreshKind::everything());
+
+            let per_core: Vec<f32> = sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
+            let cpu_percent: f32 = if per_core.is_empty() {
+                0.0
+            } else {
+                per_core.iter().sum::<f32>() / per_core.len() as f32
+            };
+
+            let networks = Networks::new_with_refreshed_list();
+            let (net_rx, net_tx) = networks
+                .iter()
+                .filter(|(name, _)| !name.starts_with("lo") && !name.starts_with("docker"))
+                .fold((0u64, 0u64), |(rx, tx), (_, data)| {
+                    (rx + data.total_received(), tx + data.total_transmitted())
+                });
 
-        let per_core: Vec<f32> = sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
-        let cpu_percent: f32 = if per_core.is_empty() {
-            0.0
-        } else {
-            per_core.iter().sum::<f32>() / per_core.len() as f32
-        };
+            let (disk_read, disk_write) = read_disk_io();
+
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+
+            // Calculate cached memory
+            let total = sys.total_memory();
+            let used = sys.used_memory();
+            let available = sys.available_memory();
+            let cached = if total > used + available {
+                total - used - available
+            } else {
+                0
+            };
+
+            ResourceSnapshot {
+                timestamp,
+                cpu_percent,
+                per_core_percent: per_core,
+                ram_used_bytes: used,
+                ram_total_bytes: total,
+                ram_cached_bytes: cached,
+                swap_used_bytes: sys.used_swap(),
+                swap_total_bytes: sys.total_swap(),
+                net_rx_bytes: net_rx,
+                net_tx_bytes: net_tx,
+                disk_read_bytes: disk_read,
+                disk_write_bytes: disk_write,
+            }
+        })
+        .await
+        .unwrap();
 
-        let networks = Networks::new_with_refreshed_list();
-        let (net_rx, net_tx) = networks
-            .iter()
-            .filter(|(name, _)| !name.starts_with("lo") && !name.starts_with("docker"))
-            .fold((0u64, 0u64), |(rx, tx), (_, data)| {
-                (rx + data.total_received(), tx + data.total_transmitted())
-            });
-
-        let (disk_read, disk_write) = read_disk_io();
-
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
-
-        // Calculate cached memory
-        let total = sys.total_memory();
-        let used = sys.used_memory();
-        let available = sys.available_memory();
-        let cached = if total > used + available {
-            total - used - available
-        } else {
-            0
-        };
+        Ok(snapshot)
+    }
 
-        ResourceSnapshot {
-            timestamp,
-            cpu_percent,
-            per_core_percent: per_core,
-            ram_used_bytes: used,
-            ram_total_bytes: total,
-            ram_cached_bytes: cached,
-            swap_used_bytes: sys.used_swap(),
-            swap_total_bytes: sys.total_swap(),
-            net_rx_bytes: net_rx,
-            net_tx_bytes: net_tx,
-            disk_read_bytes: disk_read,
-            disk_write_bytes: disk_write,
-        }
-    })
-    .await
-    .unwrap();
+    /// Per-core CPU usage
+    pub async fn per_core_usage(&self) -> Result<Vec<f32>> {
+        let sys = self.sys.clone();
+        let per_core = tokio::task::spawn_blocking(move || {
+            let mut sys = sys.lock().unwrap();
+            sys.refresh_cpu_specifics(CpuRefreshKind::nothing().with_cpu_usage());
+            sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect::<Vec<f32>>()
+        })
+        .await
+        .unwrap();
+
+        Ok(per_core)
+    }
+}
+
+// ============================================================================
+// Tauri Commands (All non-blocking)
+// ============================================================================
 
-    Ok(snapshot)
+/// Get current resource snapshot with enhanced data
+#[tauri::command]
+pub async fn get_resource_snapshot(app_state: State<'_, AppState>) -> Result<ResourceSnapshot> {
+    app_state.resources().snapshot().await
 }
 
 /// Get resource history with calculated speeds
@@ -361,16 +396,7 @@ pub fn clear_resource_history(history_state: State<ResourceHistoryState>) -> Res
 /// Get per-core CPU usage
 #[tauri::command]
 pub async fn get_per_core_usage(app_state: State<'_, AppState>) -> Result<Vec<f32>> {
-    let sys = app_state.sys.clone();
-    let per_core = tokio::task::spawn_blocking(move || {
-        let mut sys = sys.lock().unwrap();
-        sys.refresh_cpu_specifics(CpuRefreshKind::nothing().with_cpu_usage());
-        sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect::<Vec<f32>>()
-    })
-    .await
-    .unwrap();
-
-    Ok(per_core)
+    app_state.resources().per_core_usage().await
 }
 
 /// Get GPU information
@@ -2,8 +2,11 @@
 //! Lists and manages system services with categorization (async)
 
 use crate::error::{AppError, Result};
-use crate::utils::privileged;
+use crate::state::AppState;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
 use tokio::process::Command;
 
 // ============================================================================
@@ -107,6 +110,59 @@ fn detect_category(name: &str, description: &str) -> String {
     }
 }
 
+// ============================================================================
+// Service Control
+// ============================================================================
+
+/// Starts, stops, enables and disables systemd units through the privileged executor
+pub struct Services {
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+impl Services {
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { executor }
+    }
+
+    pub async fn start(&self, name: String) -> Result<ServiceAction> {
+        self.control("start", name, "Service started successfully").await
+    }
+
+    pub async fn stop(&self, name: String) -> Result<ServiceAction> {
+        self.control("stop", name, "Service stopped successfully").await
+    }
+
+    pub async fn restart(&self, name: String) -> Result<ServiceAction> {
+        self.control("restart", name, "Service restarted successfully").await
+    }
+
+    pub async fn enable(&self, name: String) -> Result<ServiceAction> {
+        self.control("enable", name, "Service enabled successfully").await
+    }
+
+    pub async fn disable(&self, name: String) -> Result<ServiceAction> {
+        self.control("disable", name, "Service disabled successfully").await
+    }
+
+    /// Run one systemctl action; a dismissed dialog or a timeout is reported, not raised
+    async fn control(&self, action: &str, name: String, done: &str) -> Result<ServiceAction> {
+        let result = self.executor.execute(&[PrivilegedOp::systemctl(action, &name)]).await;
+
+        let (success, message) = match result {
+            Ok(_) => (true, done.to_string()),
+            Err(AppError::UserCancelled) => (false, "Operation cancelled by user".to_string()),
+            Err(AppError::Timeout(msg)) => (false, msg),
+            Err(e) => return Err(e),
+        };
+        Ok(ServiceAction {
+            name,
+            action: action.to_string(),
+            success,
+            message,
+        })
+    }
+}
+
 // ============================================================================
 // Tauri Commands (All async)
 // ============================================================================
@@ -128,8 +184,10 @@ pub async fn get_services() -> Result<Vec<ServiceInfo>> {
         .map_err(|e| AppError::CommandFailed(format!("Failed to run systemctl: {}", e)))?;
 
     if !output.status.success() {
-        return Err(AppError::CommandFailed(
-            String::from_utf8_lossy(&output.stderr).to_string(),
+        return Err(AppError::from_command(
+            "systemctl list-units",
+            output.status.code(),
+            &String::from_utf8_lossy(&output.stderr),
         ));
     }
 
@@ -187,142 +245,32 @@ async fn check_enabled_async(name: &str) -> bool {
 
 /// Start a service (requires auth, async with timeout)
 #[tauri::command]
-pub async fn start_service(name: String) -> Result<ServiceAction> {
-    let result = privileged::run_privileged("systemctl", &["start", &name]).await;
-
-    match result {
-        Ok(_) => Ok(ServiceAction {
-            name,
-            action: "start".to_string(),
-            success: true,
-            message: "Service started successfully".to_string(),
-        }),
-        Err(AppError::UserCancelled) => Ok(ServiceAction {
-            name,
-            action: "start".to_string(),
-            success: false,
-            message: "Operation cancelled by user".to_string(),
-        }),
-        Err(AppError::Timeout(msg)) => Ok(ServiceAction {
-            name,
-            action: "start".to_string(),
-            success: false,
-            message: msg,
-        }),
-        Err(e) => Err(e),
-    }
+pub async fn start_service(name: String, state: State<'_, AppState>) -> Result<ServiceAction> {
+    state.services().start(name).await
 }
 
 /// Stop a service (requires auth, async with timeout)
 #[tauri::command]
-pub async fn stop_service(name: String) -> Result<ServiceAction> {
-    let result = privileged::run_privileged("systemctl", &["stop", &name]).await;
-
-    match result {
-        Ok(_) => Ok(ServiceAction {
-            name,
-            action: "stop".to_string(),
-            success: true,
-            message: "Service stopped successfully".to_string(),
-        }),
-        Err(AppError::UserCancelled) => Ok(ServiceAction {
-            name,
-            action: "stop".to_string(),
-            success: false,
-            message: "Operation cancelled by user".to_string(),
-        }),
-        Err(AppError::Timeout(msg)) => Ok(ServiceAction {
-            name,
-            action: "stop".to_string(),
-            success: false,
-            message: msg,
-        }),
-        Err(e) => Err(e),
-    }
+pub async fn stop_service(name: String, state: State<'_, AppState>) -> Result<ServiceAction> {
+    state.services().stop(name).await
 }
 
 /// Restart a service (requires auth, async with timeout)
 #[tauri::command]
-pub async fn restart_service(name: String) -> Result<ServiceAction> {
-    let result = privileged::run_privileged("systemctl", &["restart", &name]).await;
-
-    match result {
-        Ok(_) => Ok(ServiceAction {
-            name,
-            action: "restart".to_string(),
-            success: true,
-            message: "Service restarted successfully".to_string(),
-        }),
-        Err(AppError::UserCancelled) => Ok(ServiceAction {
-            name,
-            action: "restart".to_string(),
-            success: false,
-            message: "Operation cancelled by user".to_string(),
-        }),
-        Err(AppError::Timeout(msg)) => Ok(ServiceAction {
-            name,
-            action: "restart".to_string(),
-            success: false,
-            message: msg,
-        }),
-        Err(e) => Err(e),
-    }
+pub async fn restart_service(name: String, state: State<'_, AppState>) -> Result<ServiceAction> {
+    state.services().restart(name).await
 }
 
 /// Enable a service (requires auth, async with timeout)
 #[tauri::command]
-pub async fn enable_service(name: String) -> Result<ServiceAction> {
-    let result = privileged::run_privileged("systemctl", &["enable", &name]).await;
-
-    match result {
-        Ok(_) => Ok(ServiceAction {
-            name,
-            action: "enable".to_string(),
-            success: true,
-            message: "Service enabled successfully".to_string(),
-        }),
-        Err(AppError::UserCancelled) => Ok(ServiceAction {
-            name,
-            action: "enable".to_string(),
-            success: false,
-            message: "Operation cancelled by user".to_string(),
-        }),
-        Err(AppError::Timeout(msg)) => Ok(ServiceAction {
-            name,
-            action: "enable".to_string(),
-            success: false,
-            message: msg,
-        }),
-        Err(e) => Err(e),
-    }
+pub async fn enable_service(name: String, state: State<'_, AppState>) -> Result<ServiceAction> {
+    state.services().enable(name).await
 }
 
 /// Disable a service (requires auth, async with timeout)
 #[tauri::command]
-pub async fn disable_service(name: String) -> Result<ServiceAction> {
-    let result = privileged::run_privileged("systemctl", &["disable", &name]).await;
-
-    match result {
-        Ok(_) => Ok(ServiceAction {
-            name,
-            action: "disable".to_string(),
-            success: true,
-            message: "Service disabled successfully".to_string(),
-        }),
-        Err(AppError::UserCancelled) => Ok(ServiceAction {
-            name,
-            action: "disable".to_string(),
-            success: false,
-            message: "Operation cancelled by user".to_string(),
-        }),
-        Err(AppError::Timeout(msg)) => Ok(ServiceAction {
-            name,
-            action: "disable".to_string(),
-            success: false,
-            message: msg,
-        }),
-        Err(e) => Err(e),
-    }
+pub async fn disable_service(name: String, state: State<'_, AppState>) -> Result<ServiceAction> {
+    state.services().disable(name).await
 }
 
 /// Search services by name (async)
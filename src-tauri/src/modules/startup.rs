@@ -165,7 +165,7 @@ pub async fn enable_startup_app(file_path: String) -> Result<()> {
             // System app - remove user override if exists
             let file_name = path
                 .file_name()
-                .ok_or_else(|| AppError::System("Invalid path".to_string()))?;
+                .ok_or_else(|| AppError::InvalidInput("Invalid path".to_string()))?;
             let user_override = user_autostart_dir().join(file_name);
             if user_override.exists() {
                 fs::remove_file(user_override)?;
@@ -193,7 +193,7 @@ pub async fn disable_startup_app(file_path: String) -> Result<()> {
             // System app - create user override
             let file_name = path
                 .file_name()
-                .ok_or_else(|| AppError::System("Invalid path".to_string()))?;
+                .ok_or_else(|| AppError::InvalidInput("Invalid path".to_string()))?;
 
             // Ensure user autostart dir exists
             let user_dir = user_autostart_dir();
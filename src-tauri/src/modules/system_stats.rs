@@ -4,6 +4,7 @@
 use crate::error::Result;
 use crate::state::AppState;
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
 use sysinfo::{CpuRefreshKind, Disks, MemoryRefreshKind, Networks, System};
 use tauri::State;
 
@@ -63,6 +64,93 @@ pub struct NetworkStats {
     pub tx_packets: u64,
 }
 
+// ============================================================================
+// System Stats Service
+// ============================================================================
+
+/// Live CPU and memory readings from the shared sysinfo handle
+#[derive(Clone)]
+pub struct SystemStats {
+    sys: Arc<Mutex<System>>,
+}
+
+impl SystemStats {
+    pub fn new(sys: Arc<Mutex<System>>) -> Self {
+        Self { sys }
+    }
+
+    /// CPU usage since the previous refresh (no sleep)
+    pub async fn cpu(&self) -> Result<CpuStats> {
+        // Clone Arc to pass to blocking thread
+        let sys = self.sys.clone();
+    
+        let stats = tokio::task::spawn_blocking(move || {
+            let mut sys = sys.lock().unwrap();
+
+            // Refresh only CPU usage - no sleep needed
+            sys.refresh_cpu_specifics(CpuRefreshKind::nothing().with_cpu_usage());
+
+            let per_core: Vec<f32> = sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
+            let usage_percent = if per_core.is_empty() {
+                0.0
+            } else {
+                per_core.iter().sum::<f32>() / per_core.len() as f32
+            };
+
+            CpuStats {
+                usage_percent,
+                per_core,
+                frequency_mhz: sys.cpus().first().map(|c| c.frequency()).unwrap_or(0),
+                core_count: sys.cpus().len(),
+            }
+        }).await.unwrap();
+
+        Ok(stats)
+    }
+
+    /// Memory and swap usage
+    pub async fn memory(&self) -> Result<MemoryStats> {
+        // Clone Arc to pass to blocking thread
+        let sys = self.sys.clone();
+    
+        let stats = tokio::task::spawn_blocking(move || {
+            let mut sys = sys.lock().unwrap();
+
+            // Selective refresh - only memory
+            sys.refresh_memory_specifics(MemoryRefreshKind::everything());
+
+            let total = sys.total_memory();
+            let used = sys.used_memory();
+            let available = sys.available_memory();
+
+            // Calculate cached (total - used - available gives us cached/buffers)
+            let cached = if total > used + available {
+                total - used - available
+            } else {
+                0
+            };
+
+            let usage_percent = if total > 0 {
+                (used as f32 / total as f32) * 100.0
+            } else {
+                0.0
+            };
+
+            MemoryStats {
+                total_bytes: total,
+                used_bytes: used,
+                available_bytes: available,
+                cached_bytes: cached,
+                swap_total_bytes: sys.total_swap(),
+                swap_used_bytes: sys.used_swap(),
+                usage_percent,
+            }
+        }).await.unwrap();
+
+        Ok(stats)
+    }
+}
+
 // ============================================================================
 // Tauri Commands (All async, non-blocking)
 // ============================================================================
@@ -97,74 +185,13 @@ pub async fn get_system_info() -> Result<SystemInfo> {
 /// For accurate readings, frontend should poll every 1-2 seconds
 #[tauri::command]
 pub async fn get_cpu_stats(state: State<'_, AppState>) -> Result<CpuStats> {
-    // Clone Arc to pass to blocking thread
-    let sys = state.sys.clone();
-    
-    let stats = tokio::task::spawn_blocking(move || {
-        let mut sys = sys.lock().unwrap();
-
-        // Refresh only CPU usage - no sleep needed
-        sys.refresh_cpu_specifics(CpuRefreshKind::nothing().with_cpu_usage());
-
-        let per_core: Vec<f32> = sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
-        let usage_percent = if per_core.is_empty() {
-            0.0
-        } else {
-            per_core.iter().sum::<f32>() / per_core.len() as f32
-        };
-
-        CpuStats {
-            usage_percent,
-            per_core,
-            frequency_mhz: sys.cpus().first().map(|c| c.frequency()).unwrap_or(0),
-            core_count: sys.cpus().len(),
-        }
-    }).await.unwrap();
-
-    Ok(stats)
+    state.system_stats().cpu().await
 }
 
 /// Get memory statistics
 #[tauri::command]
 pub async fn get_memory_stats(state: State<'_, AppState>) -> Result<MemoryStats> {
-    // Clone Arc to pass to blocking thread
-    let sys = state.sys.clone();
-    
-    let stats = tokio::task::spawn_blocking(move || {
-        let mut sys = sys.lock().unwrap();
-
-        // Selective refresh - only memory
-        sys.refresh_memory_specifics(MemoryRefreshKind::everything());
-
-        let total = sys.total_memory();
-        let used = sys.used_memory();
-        let available = sys.available_memory();
-
-        // Calculate cached (total - used - available gives us cached/buffers)
-        let cached = if total > used + available {
-            total - used - available
-        } else {
-            0
-        };
-
-        let usage_percent = if total > 0 {
-            (used as f32 / total as f32) * 100.0
-        } else {
-            0.0
-        };
-
-        MemoryStats {
-            total_bytes: total,
-            used_bytes: used,
-            available_bytes: available,
-            cached_bytes: cached,
-            swap_total_bytes: sys.total_swap(),
-            swap_used_bytes: sys.used_swap(),
-            usage_percent,
-        }
-    }).await.unwrap();
-
-    Ok(stats)
+    state.system_stats().memory().await
 }
 
 /// Get disk statistics
@@ -3,9 +3,15 @@
 //! Features: sliders with ranges, device tier detection, TCP algorithm selection
 
 use crate::error::{AppError, Result};
-use crate::utils::privileged;
+use crate::state::AppState;
+use crate::utils::persistence::{PersistentSettings, OWNER_TWEAKS};
+use crate::utils::privileged::{check_sysfs, PrivilegedExecutor, PrivilegedOp};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::State;
 
 // ============================================================================
 // Data Structures
@@ -27,6 +33,7 @@ pub struct Tweak {
     pub max_value: Option<i32>,
     pub options: Option<Vec<String>>, // For dropdown/selector
     pub tweak_type: String, // "slider", "selector", "preset"
+    pub is_persisted: bool, // restored at boot via Glance drop-ins
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -164,6 +171,315 @@ fn get_recommended(tier: &str, low: &str, mid: &str, high: &str) -> String {
     }
 }
 
+/// Map a sysctl-backed tweak id to its kernel key
+fn sysctl_key_for(tweak_id: &str) -> Option<&'static str> {
+    match tweak_id {
+        "swappiness" => Some("vm.swappiness"),
+        "vfs_cache_pressure" => Some("vm.vfs_cache_pressure"),
+        "dirty_ratio" => Some("vm.dirty_ratio"),
+        "dirty_background_ratio" => Some("vm.dirty_background_ratio"),
+        "tcp_congestion" => Some("net.ipv4.tcp_congestion_control"),
+        "tcp_fastopen" => Some("net.ipv4.tcp_fastopen"),
+        "tcp_mtu_probing" => Some("net.ipv4.tcp_mtu_probing"),
+        "rmem_max" => Some("net.core.rmem_max"),
+        "wmem_max" => Some("net.core.wmem_max"),
+        _ => None,
+    }
+}
+
+/// Get the scaling_governor file of every CPU that exposes one
+fn get_governor_paths() -> Vec<String> {
+    let mut paths: Vec<String> = fs::read_dir("/sys/devices/system/cpu")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    name.starts_with("cpu") && name[3..].chars().all(|c| c.is_ascii_digit()) && name.len() > 3
+                })
+                .map(|e| format!("{}/cpufreq/scaling_governor", e.path().display()))
+                .filter(|p| Path::new(p).exists())
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+/// Write a governor to every CPU
+fn governor_ops(governor: &str) -> Vec<PrivilegedOp> {
+    get_governor_paths()
+        .iter()
+        .map(|path| PrivilegedOp::sysfs(path, governor))
+        .collect()
+}
+
+/// Write the I/O scheduler of a block device
+fn scheduler_op(device: &str, scheduler: &str) -> PrivilegedOp {
+    PrivilegedOp::sysfs(&format!("/sys/block/{}/queue/scheduler", device), scheduler)
+}
+
+const ZRAM_DEVICE: &str = "/dev/zram0";
+const ZRAM_SYSFS: &str = "/sys/block/zram0";
+
+/// Set up zram0 with 50% of RAM, preferring zstd compression
+/// Returns no operations when zram0 is already active
+fn zram_setup_ops() -> Vec<PrivilegedOp> {
+    let disksize = read_sys_value(&format!("{}/disksize", ZRAM_SYSFS));
+    if disksize != "unknown" && disksize != "0" {
+        return Vec::new();
+    }
+
+    let ram_bytes = fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|content| {
+            content
+                .lines()
+                .find(|l| l.starts_with("MemTotal:"))
+                .and_then(|l| l.split_whitespace().nth(1))
+                .and_then(|kb| kb.parse::<u64>().ok())
+        })
+        .unwrap_or(0)
+        * 1024;
+
+    // comp_algorithm lists what the kernel offers, e.g. "lzo [lz4] zstd" (unknown before modprobe)
+    let algorithms = read_sys_value(&format!("{}/comp_algorithm", ZRAM_SYSFS));
+    let algorithm = if algorithms == "unknown" || algorithms.contains("zstd") { "zstd" } else { "lz4" };
+
+    vec![
+        PrivilegedOp::sysfs(&format!("{}/comp_algorithm", ZRAM_SYSFS), algorithm),
+        PrivilegedOp::sysfs(&format!("{}/disksize", ZRAM_SYSFS), &(ram_bytes / 2).to_string()),
+        PrivilegedOp::EnableSwap { device: ZRAM_DEVICE.to_string(), priority: 100 },
+    ]
+}
+
+/// Whether zram0 is in use as swap; the device can exist without being switched on
+fn zram_swap_active() -> bool {
+    fs::read_to_string("/proc/swaps")
+        .map(|swaps| swaps.lines().any(|l| l.split_whitespace().next() == Some(ZRAM_DEVICE)))
+        .unwrap_or(false)
+}
+
+/// Operations that switch zram0 on (module + setup) or off
+fn zram_ops(enabled: bool) -> Vec<PrivilegedOp> {
+    if enabled {
+        let mut ops = vec![PrivilegedOp::load_module("zram", &["num_devices=1"])];
+        ops.extend(zram_setup_ops());
+        ops
+    } else {
+        vec![
+            PrivilegedOp::DisableSwap { device: ZRAM_DEVICE.to_string() },
+            PrivilegedOp::sysfs(&format!("{}/reset", ZRAM_SYSFS), "1"),
+            PrivilegedOp::UnloadModule { name: "zram".to_string() },
+        ]
+    }
+}
+
+/// Load the zram module, then size and enable zram0 as swap
+async fn enable_zram(executor: &dyn PrivilegedExecutor) -> Result<()> {
+    executor.execute(&zram_ops(true)).await?;
+    Ok(())
+}
+
+/// Best-effort teardown of zram0 and the module
+async fn disable_zram(executor: &dyn PrivilegedExecutor) {
+    for op in zram_ops(false) {
+        let _ = executor.execute(&[op]).await;
+    }
+}
+
+// ============================================================================
+// Tweak Service
+// ============================================================================
+
+/// Applies, persists and rolls back tweaks through the privileged executor
+pub struct Tweaks {
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+impl Tweaks {
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { executor }
+    }
+
+    /// Apply a specific tweak (async with timeout)
+    /// Prior values are snapshotted into the tweak journal so the change can be rolled back.
+    /// `persist` chooses between boot-persistent and runtime-only; when omitted an
+    /// already persisted tweak stays persisted with the new value.
+    pub async fn apply(&self, tweak_id: String, value: String, persist: Option<bool>) -> Result<String> {
+        // Resolve the boot side first so an unsupported choice fails before anything changes
+        let mut settings = PersistentSettings::load();
+        let was_persisted = is_tweak_persisted(&settings, &tweak_id);
+        let persist = persist.unwrap_or(was_persisted);
+        if persist || was_persisted {
+            update_persisted(&mut settings, &tweak_id, persist.then_some(value.as_str()))?;
+        }
+
+        let changes = snapshot_tweak(&tweak_id, &value);
+        let message = apply_tweak_value(self.executor.as_ref(), &tweak_id, &value).await?;
+        record_transaction(&format!("{} → {}", tweak_id, value), changes);
+
+        if persist || was_persisted {
+            settings.save(self.executor.as_ref()).await?;
+        }
+
+        Ok(message)
+    }
+
+    /// Switch a tweak between boot-persistent and runtime-only, keeping its live value
+    pub async fn set_persistence(&self, tweak_id: String, persist: bool) -> Result<String> {
+        let current = current_tweak_value(&tweak_id);
+        if persist && current == "unknown" {
+            return Err(AppError::System(format!("Cannot read current value of {}", tweak_id)));
+        }
+
+        let mut settings = PersistentSettings::load();
+        update_persisted(&mut settings, &tweak_id, persist.then_some(current.as_str()))?;
+        settings.save(self.executor.as_ref()).await?;
+
+        if persist {
+            Ok(format!("{} will be restored at boot ({})", tweak_id, current))
+        } else {
+            Ok(format!("{} is now runtime-only", tweak_id))
+        }
+    }
+
+    /// Apply all recommended tweaks at once (async)
+    /// Everything that succeeds is journaled as a single transaction
+    pub async fn apply_all_recommended(&self, persist: Option<bool>) -> Result<Vec<String>> {
+        let mut results = Vec::new();
+        let mut changes = Vec::new();
+        let (tier, _) = get_device_tier();
+
+        // Memory optimizations based on tier
+        let swap_val = get_recommended(&tier, "30", "10", "5");
+        let vfs_val = get_recommended(&tier, "100", "50", "30");
+        let dirty_val = get_recommended(&tier, "20", "10", "5");
+        let dirty_bg_val = get_recommended(&tier, "10", "5", "3");
+
+        let memory_tweaks = [
+            ("vm.swappiness", swap_val.as_str()),
+            ("vm.vfs_cache_pressure", vfs_val.as_str()),
+            ("vm.dirty_ratio", dirty_val.as_str()),
+            ("vm.dirty_background_ratio", dirty_bg_val.as_str()),
+        ];
+
+        for (key, value) in memory_tweaks {
+            let change = snapshot_sysctl(key, value);
+            if self.executor.execute(&[PrivilegedOp::sysctl(key, value)]).await.is_ok() {
+                results.push(format!("✓ {}", key));
+                changes.extend(change);
+            }
+        }
+
+        // Network optimizations
+        let _ = self.executor.execute(&[PrivilegedOp::load_module("tcp_bbr", &[])]).await;
+        let network_tweaks = [
+            ("net.ipv4.tcp_congestion_control", "bbr"),
+            ("net.ipv4.tcp_fastopen", "3"),
+            ("net.ipv4.tcp_mtu_probing", "1"),
+            ("net.core.rmem_max", "16777216"),
+            ("net.core.wmem_max", "16777216"),
+        ];
+
+        for (key, value) in network_tweaks {
+            let change = snapshot_sysctl(key, value);
+            if self.executor.execute(&[PrivilegedOp::sysctl(key, value)]).await.is_ok() {
+                results.push(format!("✓ {}", key));
+                changes.extend(change);
+            }
+        }
+
+        // CPU Governor - performance
+        let governor_changes = snapshot_governor("performance");
+        if self.executor.execute(&governor_ops("performance")).await.is_ok() {
+            results.push("✓ CPU Governor".to_string());
+            changes.extend(governor_changes);
+        }
+
+        // I/O Scheduler - auto-detect best
+        let device = get_main_block_device();
+        let disk_type = get_disk_type(&device);
+        let io_val = match disk_type.as_str() {
+            "nvme" => "none",
+            "ssd" => "mq-deadline",
+            _ => "mq-deadline",
+        };
+        let io_change = snapshot_scheduler(&device, io_val);
+        if self.executor.execute(&[scheduler_op(&device, io_val)]).await.is_ok() {
+            results.push(format!("✓ I/O Scheduler ({})", io_val));
+            changes.extend(io_change);
+        }
+
+        if persist.unwrap_or(false) {
+            let mut settings = PersistentSettings::load();
+            for change in &changes {
+                // The live value already changed, so remember the journaled one
+                if change.kind == "sysctl" {
+                    settings.record_default(&change.target, &change.previous_value);
+                }
+                persist_change(&mut settings, change, &change.new_value);
+            }
+            settings.save(self.executor.as_ref()).await?;
+            results.push("✓ Persisted for next boot".to_string());
+        }
+
+        record_transaction("Apply all recommended", changes);
+
+        Ok(results)
+    }
+
+    /// Roll a transaction back to the values recorded before it was applied
+    pub async fn rollback(&self, transaction_id: u64) -> Result<String> {
+        let mut journal = load_journal();
+        let index = journal
+            .iter()
+            .position(|t| t.id == transaction_id)
+            .ok_or_else(|| AppError::InvalidInput(format!("Unknown transaction: {}", transaction_id)))?;
+
+        if journal[index].rolled_back {
+            return Err(AppError::System(format!(
+                "Transaction {} was already rolled back",
+                transaction_id
+            )));
+        }
+
+        // Refuse if a newer, still-active transaction touched the same settings
+        let targets: HashSet<&str> = journal[index].changes.iter().map(|c| c.target.as_str()).collect();
+        let conflict = journal[index + 1..]
+            .iter()
+            .filter(|t| !t.rolled_back)
+            .find(|t| t.changes.iter().any(|c| targets.contains(c.target.as_str())));
+        if let Some(newer) = conflict {
+            return Err(AppError::System(format!(
+                "Transaction {} changed the same settings later; roll it back first",
+                newer.id
+            )));
+        }
+
+        run_rollback(self.executor.as_ref(), &journal[index].changes).await?;
+
+        journal[index].rolled_back = true;
+        let description = journal[index].description.clone();
+        save_journal(&journal)?;
+
+        restore_persisted(self.executor.as_ref(), &journal[index].changes).await?;
+
+        Ok(format!("Rolled back: {}", description))
+    }
+
+    /// Undo the most recent transaction that is still active
+    pub async fn undo_last(&self) -> Result<String> {
+        let last = load_journal()
+            .into_iter()
+            .rev()
+            .find(|t| !t.rolled_back)
+            .ok_or_else(|| AppError::System("Nothing to undo".to_string()))?;
+
+        self.rollback(last.id).await
+    }
+}
+
 // ============================================================================
 // Tauri Commands (All async)
 // ============================================================================
@@ -197,6 +513,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
         let available_governors = get_available_governors();
         let available_tcp = get_available_tcp_algos();
         let available_io = get_available_schedulers(&block_device);
+        let persisted = PersistentSettings::load();
         
         let mut categories = Vec::new();
 
@@ -207,7 +524,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
         let dirty_bg_ratio = read_sys_value("/proc/sys/vm/dirty_background_ratio");
         
         // Check ZRAM status
-        let zram_enabled = std::path::Path::new("/sys/block/zram0").exists();
+        let zram_enabled = zram_swap_active();
         let zram_status = if zram_enabled { "enabled" } else { "disabled" };
 
         let swap_rec = get_recommended(&tier, "30", "10", "5");
@@ -234,6 +551,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                     max_value: Some(100),
                     options: None,
                     tweak_type: "slider".to_string(),
+                    is_persisted: persisted.has_sysctl("vm.swappiness"),
                 },
                 Tweak {
                     id: "vfs_cache_pressure".to_string(),
@@ -249,6 +567,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                     max_value: Some(200),
                     options: None,
                     tweak_type: "slider".to_string(),
+                    is_persisted: persisted.has_sysctl("vm.vfs_cache_pressure"),
                 },
                 Tweak {
                     id: "dirty_ratio".to_string(),
@@ -264,6 +583,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                     max_value: Some(50),
                     options: None,
                     tweak_type: "slider".to_string(),
+                    is_persisted: persisted.has_sysctl("vm.dirty_ratio"),
                 },
                 Tweak {
                     id: "dirty_background_ratio".to_string(),
@@ -279,6 +599,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                     max_value: Some(25),
                     options: None,
                     tweak_type: "slider".to_string(),
+                    is_persisted: persisted.has_sysctl("vm.dirty_background_ratio"),
                 },
                 Tweak {
                     id: "zram".to_string(),
@@ -294,6 +615,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                     max_value: None,
                     options: Some(vec!["disabled".to_string(), "enabled".to_string()]),
                     tweak_type: "toggle".to_string(),
+                    is_persisted: false,
                 },
             ],
         });
@@ -324,6 +646,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                     max_value: None,
                     options: Some(available_tcp),
                     tweak_type: "selector".to_string(),
+                    is_persisted: persisted.has_sysctl("net.ipv4.tcp_congestion_control"),
                 },
                 Tweak {
                     id: "tcp_fastopen".to_string(),
@@ -339,6 +662,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                     max_value: Some(3),
                     options: None,
                     tweak_type: "slider".to_string(),
+                    is_persisted: persisted.has_sysctl("net.ipv4.tcp_fastopen"),
                 },
                 Tweak {
                     id: "tcp_mtu_probing".to_string(),
@@ -354,6 +678,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                     max_value: Some(2),
                     options: None,
                     tweak_type: "slider".to_string(),
+                    is_persisted: persisted.has_sysctl("net.ipv4.tcp_mtu_probing"),
                 },
                 Tweak {
                     id: "rmem_max".to_string(),
@@ -374,6 +699,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                         "33554432".to_string(),  // 32MB
                     ]),
                     tweak_type: "selector".to_string(),
+                    is_persisted: persisted.has_sysctl("net.core.rmem_max"),
                 },
                 Tweak {
                     id: "wmem_max".to_string(),
@@ -394,6 +720,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                         "33554432".to_string(),
                     ]),
                     tweak_type: "selector".to_string(),
+                    is_persisted: persisted.has_sysctl("net.core.wmem_max"),
                 },
             ],
         });
@@ -419,6 +746,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                 max_value: None,
                 options: Some(available_governors),
                 tweak_type: "preset".to_string(), // Special type for 3-button preset
+                is_persisted: persisted.cpu_governor.is_some(),
             }],
         });
 
@@ -451,6 +779,7 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
                 max_value: None,
                 options: Some(available_io),
                 tweak_type: "selector".to_string(),
+                is_persisted: persisted.io_schedulers.contains_key(&block_device),
             }],
         });
 
@@ -461,152 +790,453 @@ pub async fn get_tweaks() -> Result<Vec<TweakCategory>> {
 }
 
 /// Apply a specific tweak (async with timeout)
+/// `persist` chooses between boot-persistent and runtime-only
+#[tauri::command]
+pub async fn apply_tweak(
+    tweak_id: String,
+    value: String,
+    persist: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<String> {
+    state.tweaks().apply(tweak_id, value, persist).await
+}
+
+/// Switch a tweak between boot-persistent and runtime-only, keeping its live value
 #[tauri::command]
-pub async fn apply_tweak(tweak_id: String, value: String) -> Result<String> {
-    match tweak_id.as_str() {
+pub async fn set_tweak_persistence(tweak_id: String, persist: bool, state: State<'_, AppState>) -> Result<String> {
+    state.tweaks().set_persistence(tweak_id, persist).await
+}
+
+/// Write a tweak value to the live system
+async fn apply_tweak_value(executor: &dyn PrivilegedExecutor, tweak_id: &str, value: &str) -> Result<String> {
+    match tweak_id {
         // Sysctl tweaks (memory, network)
         "swappiness" | "vfs_cache_pressure" | "dirty_ratio" | "dirty_background_ratio"
         | "tcp_congestion" | "tcp_fastopen" | "tcp_mtu_probing" | "rmem_max" | "wmem_max" => {
-            let key = match tweak_id.as_str() {
-                "swappiness" => "vm.swappiness",
-                "vfs_cache_pressure" => "vm.vfs_cache_pressure",
-                "dirty_ratio" => "vm.dirty_ratio",
-                "dirty_background_ratio" => "vm.dirty_background_ratio",
-                "tcp_congestion" => "net.ipv4.tcp_congestion_control",
-                "tcp_fastopen" => "net.ipv4.tcp_fastopen",
-                "tcp_mtu_probing" => "net.ipv4.tcp_mtu_probing",
-                "rmem_max" => "net.core.rmem_max",
-                "wmem_max" => "net.core.wmem_max",
-                _ => return Err(AppError::System("Unknown sysctl key".to_string())),
-            };
+            let key = sysctl_key_for(tweak_id)
+                .ok_or_else(|| AppError::InvalidInput("Unknown sysctl key".to_string()))?;
 
             // For BBR, we need to load the module first
             if tweak_id == "tcp_congestion" && value == "bbr" {
-                let _ = privileged::run_privileged_shell("modprobe tcp_bbr").await;
+                let _ = executor.execute(&[PrivilegedOp::load_module("tcp_bbr", &[])]).await;
             }
 
-            privileged::run_privileged("sysctl", &["-w", &format!("{}={}", key, value)]).await?;
+            executor.execute(&[PrivilegedOp::sysctl(key, value)]).await?;
             Ok(format!("{} set to {}", key, value))
         }
 
         // CPU Governor
         "cpu_governor" => {
-            let script = format!(
-                "for gov in /sys/devices/system/cpu/cpu*/cpufreq/scaling_governor; do echo {} > \"$gov\"; done",
-                value
-            );
-            privileged::run_privileged_shell(&script).await?;
+            executor.execute(&governor_ops(value)).await?;
             Ok(format!("CPU governor set to {}", value))
         }
 
         // I/O Scheduler
         "io_scheduler" => {
             let device = get_main_block_device();
-            let script = format!(
-                "echo {} > /sys/block/{}/queue/scheduler",
-                value, device
-            );
-            privileged::run_privileged_shell(&script).await?;
+            executor.execute(&[scheduler_op(&device, value)]).await?;
             Ok(format!("I/O scheduler set to {}", value))
         }
 
         // ZRAM Compressed Swap
         "zram" => {
             if value == "enabled" {
-                // Enable ZRAM with 50% of RAM using zstd compression
-                let script = r#"
-                    # Load zram module
-                    modprobe zram num_devices=1
-                    
-                    # Get RAM size and calculate 50%
-                    RAM_KB=$(grep MemTotal /proc/meminfo | awk '{print $2}')
-                    ZRAM_SIZE=$((RAM_KB * 1024 / 2))
-                    
-                    # Setup zram0 if not already active
-                    if [ ! -e /sys/block/zram0/disksize ] || [ "$(cat /sys/block/zram0/disksize)" = "0" ]; then
-                        echo zstd > /sys/block/zram0/comp_algorithm 2>/dev/null || echo lz4 > /sys/block/zram0/comp_algorithm
-                        echo $ZRAM_SIZE > /sys/block/zram0/disksize
-                        mkswap /dev/zram0
-                        swapon -p 100 /dev/zram0
-                    fi
-                "#;
-                privileged::run_privileged_shell(script).await?;
+                enable_zram(executor).await?;
                 Ok("ZRAM enabled with 50% of RAM using zstd compression".to_string())
             } else {
-                // Disable ZRAM
-                let script = r#"
-                    swapoff /dev/zram0 2>/dev/null
-                    echo 1 > /sys/block/zram0/reset 2>/dev/null
-                    rmmod zram 2>/dev/null || true
-                "#;
-                privileged::run_privileged_shell(script).await?;
+                disable_zram(executor).await;
                 Ok("ZRAM disabled".to_string())
             }
         }
 
-        _ => Err(AppError::System(format!("Unknown tweak: {}", tweak_id))),
+        _ => Err(AppError::InvalidInput(format!("Unknown tweak: {}", tweak_id))),
     }
 }
 
 /// Apply all recommended tweaks at once (async)
 #[tauri::command]
-pub async fn apply_all_recommended() -> Result<Vec<String>> {
-    let mut results = Vec::new();
-    let (tier, _) = get_device_tier();
-
-    // Memory optimizations based on tier
-    let swap_val = get_recommended(&tier, "30", "10", "5");
-    let vfs_val = get_recommended(&tier, "100", "50", "30");
-    let dirty_val = get_recommended(&tier, "20", "10", "5");
-    let dirty_bg_val = get_recommended(&tier, "10", "5", "3");
-
-    let memory_tweaks = [
-        ("vm.swappiness", swap_val.as_str()),
-        ("vm.vfs_cache_pressure", vfs_val.as_str()),
-        ("vm.dirty_ratio", dirty_val.as_str()),
-        ("vm.dirty_background_ratio", dirty_bg_val.as_str()),
-    ];
-
-    for (key, value) in memory_tweaks {
-        if privileged::run_privileged("sysctl", &["-w", &format!("{}={}", key, value)]).await.is_ok() {
-            results.push(format!("✓ {}", key));
+pub async fn apply_all_recommended(persist: Option<bool>, state: State<'_, AppState>) -> Result<Vec<String>> {
+    state.tweaks().apply_all_recommended(persist).await
+}
+
+// ============================================================================
+// Boot Persistence
+// ============================================================================
+
+/// Whether a tweak is currently restored at boot
+fn is_tweak_persisted(settings: &PersistentSettings, tweak_id: &str) -> bool {
+    if let Some(key) = sysctl_key_for(tweak_id) {
+        return settings.has_sysctl(key);
+    }
+    match tweak_id {
+        "cpu_governor" => settings.cpu_governor.is_some(),
+        "io_scheduler" => settings.io_schedulers.contains_key(&get_main_block_device()),
+        _ => false,
+    }
+}
+
+/// Read the live value of a tweak
+fn current_tweak_value(tweak_id: &str) -> String {
+    if let Some(key) = sysctl_key_for(tweak_id) {
+        return read_sys_value(&format!("/proc/sys/{}", key.replace('.', "/")));
+    }
+    match tweak_id {
+        "cpu_governor" => read_sys_value("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor"),
+        "io_scheduler" => get_io_scheduler(&get_main_block_device()),
+        _ => "unknown".to_string(),
+    }
+}
+
+/// Set (Some) or clear (None) the persisted value of a tweak
+fn update_persisted(settings: &mut PersistentSettings, tweak_id: &str, value: Option<&str>) -> Result<()> {
+    if let Some(key) = sysctl_key_for(tweak_id) {
+        match value {
+            Some(v) => settings.set_sysctl(OWNER_TWEAKS, key, v),
+            // Runtime-only keeps the live value, so there is nothing to restore
+            None => {
+                settings.remove_sysctl(OWNER_TWEAKS, key);
+            }
         }
+        return Ok(());
     }
 
-    // Network optimizations
-    let _ = privileged::run_privileged_shell("modprobe tcp_bbr").await;
-    let network_tweaks = [
-        ("net.ipv4.tcp_congestion_control", "bbr"),
-        ("net.ipv4.tcp_fastopen", "3"),
-        ("net.ipv4.tcp_mtu_probing", "1"),
-        ("net.core.rmem_max", "16777216"),
-        ("net.core.wmem_max", "16777216"),
-    ];
+    match tweak_id {
+        "cpu_governor" => settings.cpu_governor = value.map(|v| v.to_string()),
+        "io_scheduler" => {
+            let device = get_main_block_device();
+            match value {
+                Some(v) => settings.io_schedulers.insert(device, v.to_string()),
+                None => settings.io_schedulers.remove(&device),
+            };
+        }
+        "zram" => {
+            return Err(AppError::NotSupported(
+                "ZRAM is runtime-only; use zram-generator for a persistent setup".to_string(),
+            ))
+        }
+        _ => return Err(AppError::InvalidInput(format!("Unknown tweak: {}", tweak_id))),
+    }
+    Ok(())
+}
 
-    for (key, value) in network_tweaks {
-        if privileged::run_privileged("sysctl", &["-w", &format!("{}={}", key, value)]).await.is_ok() {
-            results.push(format!("✓ {}", key));
+/// Persist a journaled change at the given value
+fn persist_change(settings: &mut PersistentSettings, change: &TweakChange, value: &str) {
+    match change.kind.as_str() {
+        "sysctl" => settings.set_sysctl(OWNER_TWEAKS, &change.target, value),
+        "sysfs" if change.target.ends_with("/scaling_governor") => {
+            settings.cpu_governor = Some(value.to_string());
+        }
+        "sysfs" => {
+            if let Some(device) = scheduler_device(&change.target) {
+                settings.io_schedulers.insert(device, value.to_string());
+            }
         }
+        _ => {}
+    }
+}
+
+/// Extract "sda" from /sys/block/sda/queue/scheduler
+fn scheduler_device(path: &str) -> Option<String> {
+    path.strip_prefix("/sys/block/")?
+        .strip_suffix("/queue/scheduler")
+        .map(|d| d.to_string())
+}
+
+/// After a rollback, make persisted entries follow the restored values
+async fn restore_persisted(executor: &dyn PrivilegedExecutor, changes: &[TweakChange]) -> Result<()> {
+    let mut settings = PersistentSettings::load();
+    let before = settings.clone();
+
+    for change in changes.iter().rev() {
+        let persisted = match change.kind.as_str() {
+            "sysctl" => settings.has_sysctl(&change.target),
+            "sysfs" if change.target.ends_with("/scaling_governor") => settings.cpu_governor.is_some(),
+            "sysfs" => scheduler_device(&change.target)
+                .map(|d| settings.io_schedulers.contains_key(&d))
+                .unwrap_or(false),
+            _ => false,
+        };
+        if !persisted {
+            continue;
+        }
+
+        if change.kind == "sysctl" {
+            // Keep the current owner, only the value in effect follows the rollback
+            if let Some(entry) = settings.sysctl.get_mut(&change.target).and_then(|stack| stack.last_mut()) {
+                entry.value = change.previous_value.clone();
+            }
+        } else {
+            persist_change(&mut settings, change, &change.previous_value);
+        }
+    }
+
+    if settings != before {
+        settings.save(executor).await?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Tweak Journal (snapshot & rollback)
+// ============================================================================
+
+/// Maximum number of transactions kept in the journal
+const JOURNAL_MAX_ENTRIES: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TweakChange {
+    pub kind: String, // "sysctl", "sysfs", "zram"
+    pub target: String, // sysctl key, sysfs path or "zram0"
+    pub previous_value: String,
+    pub new_value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TweakTransaction {
+    pub id: u64,
+    pub timestamp: u64, // unix seconds
+    pub description: String,
+    pub changes: Vec<TweakChange>,
+    pub rolled_back: bool,
+}
+
+/// Path of the persisted journal
+fn journal_path() -> String {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/home".to_string());
+    format!("{}/.config/glance/tweak_journal.json", home)
+}
+
+/// Load the journal (oldest transaction first)
+fn load_journal() -> Vec<TweakTransaction> {
+    fs::read_to_string(journal_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Save the journal via a temp file + rename so a crash never leaves it half-written
+fn save_journal(journal: &[TweakTransaction]) -> Result<()> {
+    let path = journal_path();
+    if let Some(parent) = Path::new(&path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let json = serde_json::to_string_pretty(journal)
+        .map_err(|e| AppError::System(e.to_string()))?;
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+/// Append a transaction to the journal (no-op when nothing changed)
+fn record_transaction(description: &str, changes: Vec<TweakChange>) {
+    if changes.is_empty() {
+        return;
+    }
+
+    let mut journal = load_journal();
+    let id = journal.last().map(|t| t.id + 1).unwrap_or(1);
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    journal.push(TweakTransaction {
+        id,
+        timestamp,
+        description: description.to_string(),
+        changes,
+        rolled_back: false,
+    });
+
+    if journal.len() > JOURNAL_MAX_ENTRIES {
+        let excess = journal.len() - JOURNAL_MAX_ENTRIES;
+        journal.drain(..excess);
     }
 
-    // CPU Governor - performance
-    let governor_script = "for gov in /sys/devices/system/cpu/cpu*/cpufreq/scaling_governor; do echo performance > \"$gov\"; done";
-    if privileged::run_privileged_shell(governor_script).await.is_ok() {
-        results.push("✓ CPU Governor".to_string());
+    if let Err(e) = save_journal(&journal) {
+        log::warn!("Failed to write tweak journal: {}", e);
     }
+}
 
-    // I/O Scheduler - auto-detect best
-    let device = get_main_block_device();
-    let disk_type = get_disk_type(&device);
-    let io_val = match disk_type.as_str() {
-        "nvme" => "none",
-        "ssd" => "mq-deadline",
-        _ => "mq-deadline",
+/// Snapshot a sysctl key before writing it
+fn snapshot_sysctl(key: &str, new_value: &str) -> Option<TweakChange> {
+    let previous = read_sys_value(&format!("/proc/sys/{}", key.replace('.', "/")));
+    if previous == "unknown" {
+        return None;
+    }
+    Some(TweakChange {
+        kind: "sysctl".to_string(),
+        target: key.to_string(),
+        previous_value: previous,
+        new_value: new_value.to_string(),
+    })
+}
+
+/// Snapshot every CPU's governor before writing it
+fn snapshot_governor(new_value: &str) -> Vec<TweakChange> {
+    get_governor_paths()
+        .into_iter()
+        .map(|path| TweakChange {
+            kind: "sysfs".to_string(),
+            previous_value: read_sys_value(&path),
+            target: path,
+            new_value: new_value.to_string(),
+        })
+        .filter(|c| c.previous_value != "unknown")
+        .collect()
+}
+
+/// Snapshot the active I/O scheduler of a block device
+fn snapshot_scheduler(device: &str, new_value: &str) -> Option<TweakChange> {
+    let previous = get_io_scheduler(device);
+    if previous == "unknown" {
+        return None;
+    }
+    Some(TweakChange {
+        kind: "sysfs".to_string(),
+        target: format!("/sys/block/{}/queue/scheduler", device),
+        previous_value: previous,
+        new_value: new_value.to_string(),
+    })
+}
+
+/// Snapshot everything a tweak is about to touch
+fn snapshot_tweak(tweak_id: &str, value: &str) -> Vec<TweakChange> {
+    if let Some(key) = sysctl_key_for(tweak_id) {
+        return snapshot_sysctl(key, value).into_iter().collect();
+    }
+
+    match tweak_id {
+        "cpu_governor" => snapshot_governor(value),
+        "io_scheduler" => snapshot_scheduler(&get_main_block_device(), value)
+            .into_iter()
+            .collect(),
+        "zram" => {
+            let enabled = zram_swap_active();
+            vec![TweakChange {
+                kind: "zram".to_string(),
+                target: "zram0".to_string(),
+                previous_value: if enabled { "enabled" } else { "disabled" }.to_string(),
+                new_value: value.to_string(),
+            }]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Operations that write one side of a change
+/// Everything is validated up front so a tampered journal never reaches root
+fn change_ops(change: &TweakChange, value: &str) -> Result<Vec<PrivilegedOp>> {
+    let ops = match change.kind.as_str() {
+        "sysctl" => vec![PrivilegedOp::sysctl(&change.target, value)],
+        "sysfs" => {
+            // Only the attributes the tweaks themselves write
+            check_sysfs(&change.target, value)?;
+            vec![PrivilegedOp::sysfs(&change.target, value)]
+        }
+        "zram" => {
+            if change.target != "zram0" || !matches!(value, "enabled" | "disabled") {
+                return Err(AppError::InvalidInput(format!("Invalid zram change: {} = {}", change.target, value)));
+            }
+            zram_ops(value == "enabled")
+        }
+        other => return Err(AppError::InvalidInput(format!("Unknown change kind: {}", other))),
     };
-    let io_script = format!("echo {} > /sys/block/{}/queue/scheduler", io_val, device);
-    if privileged::run_privileged_shell(&io_script).await.is_ok() {
-        results.push(format!("✓ I/O Scheduler ({})", io_val));
+    ops.iter().try_for_each(PrivilegedOp::validate)?;
+    Ok(ops)
+}
+
+/// Undo and re-apply operations for one journaled change
+struct RollbackStep {
+    undo: Vec<PrivilegedOp>,
+    redo: Vec<PrivilegedOp>,
+}
+
+/// Plan a rollback, newest change first so repeated targets end at their oldest value
+fn build_rollback_plan(changes: &[TweakChange]) -> Result<Vec<RollbackStep>> {
+    changes
+        .iter()
+        .rev()
+        .map(|change| {
+            Ok(RollbackStep {
+                undo: change_ops(change, &change.previous_value)?,
+                redo: change_ops(change, &change.new_value)?,
+            })
+        })
+        .collect()
+}
+
+/// Restore the previous values in one privileged call.
+/// If any write fails, every change is put back to its new value,
+/// so the system is never left half rolled back.
+async fn run_rollback(executor: &dyn PrivilegedExecutor, changes: &[TweakChange]) -> Result<()> {
+    let plan = build_rollback_plan(changes)?;
+    let undo: Vec<PrivilegedOp> = plan.iter().flat_map(|step| step.undo.clone()).collect();
+
+    if let Err(e) = executor.execute(&undo).await {
+        for step in &plan {
+            let _ = executor.execute(&step.redo).await;
+        }
+        return Err(e);
     }
+    Ok(())
+}
+
+/// List journaled tweak transactions (newest first)
+#[tauri::command]
+pub async fn get_tweak_history() -> Result<Vec<TweakTransaction>> {
+    let mut journal = tokio::task::spawn_blocking(load_journal).await.unwrap();
+    journal.reverse();
+    Ok(journal)
+}
 
-    Ok(results)
+/// Roll a transaction back to the values recorded before it was applied
+#[tauri::command]
+pub async fn rollback_tweak_transaction(transaction_id: u64, state: State<'_, AppState>) -> Result<String> {
+    state.tweaks().rollback(transaction_id).await
+}
+
+/// Undo the most recent transaction that is still active
+#[tauri::command]
+pub async fn undo_last_tweak(state: State<'_, AppState>) -> Result<String> {
+    state.tweaks().undo_last().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(kind: &str, target: &str, previous: &str, new: &str) -> TweakChange {
+        TweakChange {
+            kind: kind.to_string(),
+            target: target.to_string(),
+            previous_value: previous.to_string(),
+            new_value: new.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rollback_plan_restores_in_reverse() {
+        let plan = build_rollback_plan(&[
+            change("sysctl", "vm.swappiness", "60", "10"),
+            change("sysfs", "/sys/block/sda/queue/scheduler", "bfq", "mq-deadline"),
+        ])
+        .unwrap();
+
+        assert_eq!(plan[0].undo, vec![PrivilegedOp::sysfs("/sys/block/sda/queue/scheduler", "bfq")]);
+        assert_eq!(plan[1].undo, vec![PrivilegedOp::sysctl("vm.swappiness", "60")]);
+        assert_eq!(plan[1].redo, vec![PrivilegedOp::sysctl("vm.swappiness", "10")]);
+    }
+
+    #[test]
+    fn test_rollback_rejects_tampered_journal() {
+        assert!(build_rollback_plan(&[change("sysctl", "vm.swappiness", "1; reboot", "10")]).is_err());
+        assert!(build_rollback_plan(&[change("sysfs", "/etc/shadow", "x", "y")]).is_err());
+        assert!(build_rollback_plan(&[change("sysfs", "/sys/../etc/passwd", "x", "y")]).is_err());
+        assert!(build_rollback_plan(&[change("sysfs", "/sys/bus/pci/devices/0000:00:02.0/remove", "0", "1")]).is_err());
+        assert!(build_rollback_plan(&[change("sysfs", "/sys/block/sda/queue/scheduler", "bfq", "$(id)")]).is_err());
+        assert!(build_rollback_plan(&[change("zram", "zram0", "maybe", "enabled")]).is_err());
+    }
 }
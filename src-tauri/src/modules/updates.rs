@@ -0,0 +1,715 @@
+//! Automatic updates module
+//! Unattended updates per family: unattended-upgrades on Debian, dnf-automatic on Fedora,
+//! a `zypper patch` timer on SUSE and a notify-only update check on Arch
+
+use crate::adapters::{is_mock_mode, query_output};
+use crate::error::{AppError, Result};
+use crate::state::AppState;
+use crate::utils::distro::DistroFamily;
+use crate::utils::ini;
+use crate::utils::privileged::{PrivilegedExecutor, PrivilegedOp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::State;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebootPolicy {
+    Never,
+    /// Reboot when an update asks for it (kernel, glibc, ...)
+    WhenNeeded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateSchedule {
+    Daily,
+    Weekly,
+}
+
+impl UpdateSchedule {
+    /// systemd `OnCalendar=` value
+    fn on_calendar(self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    /// `APT::Periodic` interval in days
+    fn days(self) -> u32 {
+        match self {
+            Self::Daily => 1,
+            Self::Weekly => 7,
+        }
+    }
+}
+
+/// Settings shared by every backend; the Arch check only uses `enabled` and `schedule`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoUpdateConfig {
+    pub enabled: bool,
+    pub security_only: bool,
+    pub reboot: RebootPolicy,
+    /// "HH:MM" to delay the reboot to, or None to reboot right after the update
+    pub reboot_time: Option<String>,
+    pub schedule: UpdateSchedule,
+}
+
+impl Default for AutoUpdateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            security_only: true,
+            reboot: RebootPolicy::Never,
+            reboot_time: None,
+            schedule: UpdateSchedule::Daily,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoUpdateStatus {
+    /// Tool doing the work, e.g. "unattended-upgrades"
+    pub backend: String,
+    /// Its package is installed (enabling installs it otherwise)
+    pub installed: bool,
+    /// Only reports available updates, never installs them (Arch)
+    pub notify_only: bool,
+    pub config: AutoUpdateConfig,
+    pub last_run: Option<String>,
+    pub last_result: Option<String>,
+    pub last_success: Option<bool>,
+    pub next_run: Option<String>,
+}
+
+// ============================================================================
+// Backends
+// ============================================================================
+
+const APT_PERIODIC_CONF: &str = "/etc/apt/apt.conf.d/20auto-upgrades";
+const APT_UNATTENDED_CONF: &str = "/etc/apt/apt.conf.d/52glance-unattended-upgrades";
+const UNATTENDED_UPGRADES_LOG: &str = "/var/log/unattended-upgrades/unattended-upgrades.log";
+
+const DNF_AUTOMATIC_CONF: &str = "/etc/dnf/automatic.conf";
+/// dnf5 keeps its defaults here and reads /etc/dnf/automatic.conf as an override
+const DNF5_AUTOMATIC_DEFAULTS: &str = "/usr/share/dnf5/dnf5-plugins/automatic.conf";
+/// Replaces the packaged timer to change its schedule
+const DNF_AUTOMATIC_TIMER: &str = "/etc/systemd/system/dnf-automatic.timer";
+
+const ZYPPER_PATCH_SERVICE: &str = "/etc/systemd/system/glance-zypper-patch.service";
+const ZYPPER_PATCH_TIMER: &str = "/etc/systemd/system/glance-zypper-patch.timer";
+
+const UPDATE_CHECK_SERVICE: &str = "/etc/systemd/system/glance-update-check.service";
+const UPDATE_CHECK_TIMER: &str = "/etc/systemd/system/glance-update-check.timer";
+
+/// The tool behind automatic updates on one family and the systemd units that run it
+struct Backend {
+    name: &'static str,
+    /// File whose presence means the backend is installed
+    binary: &'static str,
+    /// Installs the backend when it is missing
+    install: Option<PrivilegedOp>,
+    timer: &'static str,
+    service: &'static str,
+    notify_only: bool,
+}
+
+fn backend(family: DistroFamily) -> Result<Backend> {
+    match family {
+        DistroFamily::Debian => Ok(Backend {
+            name: "unattended-upgrades",
+            binary: "/usr/bin/unattended-upgrade",
+            install: Some(PrivilegedOp::package("apt-get", &["install", "-y", "unattended-upgrades"])),
+            timer: "apt-daily-upgrade.timer",
+            service: "apt-daily-upgrade.service",
+            notify_only: false,
+        }),
+        DistroFamily::Fedora => Ok(Backend {
+            name: "dnf-automatic",
+            binary: "/usr/lib/systemd/system/dnf-automatic.timer",
+            install: Some(PrivilegedOp::package("dnf", &["install", "-y", "dnf-automatic"])),
+            timer: "dnf-automatic.timer",
+            service: "dnf-automatic.service",
+            notify_only: false,
+        }),
+        DistroFamily::Suse => Ok(Backend {
+            name: "zypper patch",
+            binary: "/usr/bin/zypper",
+            install: None,
+            timer: "glance-zypper-patch.timer",
+            service: "glance-zypper-patch.service",
+            notify_only: false,
+        }),
+        DistroFamily::Arch => Ok(Backend {
+            name: "checkupdates",
+            binary: "/usr/bin/checkupdates",
+            install: Some(PrivilegedOp::package("pacman", &["-S", "--noconfirm", "--needed", "pacman-contrib"])),
+            timer: "glance-update-check.timer",
+            service: "glance-update-check.service",
+            notify_only: true,
+        }),
+        DistroFamily::Unknown => Err(AppError::NotSupported(
+            "Automatic updates are not supported on this distribution".to_string(),
+        )),
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Automatic update backend, current settings and last run
+#[tauri::command]
+pub async fn get_auto_update_status(state: State<'_, AppState>) -> Result<AutoUpdateStatus> {
+    state.auto_updates().status().await
+}
+
+/// Apply automatic update settings and return the resulting status
+#[tauri::command]
+pub async fn set_auto_update_config(config: AutoUpdateConfig, state: State<'_, AppState>) -> Result<AutoUpdateStatus> {
+    let updates = state.auto_updates();
+    updates.configure(&config).await?;
+    updates.status().await
+}
+
+// ============================================================================
+// Automatic Updates Service
+// ============================================================================
+
+/// Writes the backend's configuration and timers through the injected privileged executor
+#[derive(Clone)]
+pub struct AutoUpdates {
+    family: DistroFamily,
+    executor: Arc<dyn PrivilegedExecutor>,
+}
+
+impl AutoUpdates {
+    pub fn new(family: DistroFamily, executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        Self { family, executor }
+    }
+
+    /// Settings read back from the system plus the outcome of the last run
+    pub async fn status(&self) -> Result<AutoUpdateStatus> {
+        let backend = backend(self.family)?;
+        let mut status = AutoUpdateStatus {
+            backend: backend.name.to_string(),
+            installed: Path::new(backend.binary).exists(),
+            notify_only: backend.notify_only,
+            config: AutoUpdateConfig::default(),
+            last_run: None,
+            last_result: None,
+            last_success: None,
+            next_run: None,
+        };
+
+        if is_mock_mode() {
+            log::info!("[MOCK] Automatic update status for {}", backend.name);
+            return Ok(status);
+        }
+
+        let timer = unit_properties(backend.timer, "UnitFileState,LastTriggerUSec,NextElapseUSecRealtime").await;
+        let service = unit_properties(backend.service, "Result,ExecMainStatus,ExecMainExitTimestamp").await;
+        let timer_enabled = timer.get("UnitFileState").is_some_and(|s| s == "enabled");
+        status.next_run = timer.get("NextElapseUSecRealtime").cloned();
+
+        match self.family {
+            DistroFamily::Debian => {
+                let dump = query_output("apt-config", &["dump"]).await?;
+                status.config = apt_config(&parse_apt_config(&dump));
+                status.config.enabled &= status.installed;
+
+                let log = fs::read_to_string(UNATTENDED_UPGRADES_LOG).unwrap_or_default();
+                if let Some(run) = parse_unattended_log(&log) {
+                    status.last_run = Some(run.started);
+                    status.last_result = Some(run.result);
+                    status.last_success = Some(run.success);
+                } else {
+                    status.last_run = timer.get("LastTriggerUSec").cloned();
+                }
+            }
+            DistroFamily::Fedora => {
+                let conf = fs::read_to_string(DNF_AUTOMATIC_CONF)
+                    .or_else(|_| fs::read_to_string(DNF5_AUTOMATIC_DEFAULTS))
+                    .unwrap_or_default();
+                let timer_unit = fs::read_to_string(DNF_AUTOMATIC_TIMER).unwrap_or_default();
+                status.config = dnf_automatic_config(&conf, &timer_unit);
+                status.config.enabled = timer_enabled;
+            }
+            DistroFamily::Suse | DistroFamily::Arch => {
+                let (service_path, timer_path) = if self.family == DistroFamily::Suse {
+                    (ZYPPER_PATCH_SERVICE, ZYPPER_PATCH_TIMER)
+                } else {
+                    (UPDATE_CHECK_SERVICE, UPDATE_CHECK_TIMER)
+                };
+                let service_unit = fs::read_to_string(service_path).unwrap_or_default();
+                let timer_unit = fs::read_to_string(timer_path).unwrap_or_default();
+                status.config = unit_config(&service_unit, &timer_unit);
+                status.config.enabled = timer_enabled;
+            }
+            DistroFamily::Unknown => {}
+        }
+
+        if self.family != DistroFamily::Debian {
+            if let Some(finished) = service.get("ExecMainExitTimestamp") {
+                let code = service.get("ExecMainStatus").and_then(|c| c.parse().ok()).unwrap_or(0);
+                let success = service.get("Result").is_some_and(|r| r == "success");
+                status.last_run = Some(finished.clone());
+                status.last_result = Some(run_result(self.family, success, code));
+                status.last_success = Some(success);
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// Install the backend if needed, write its configuration and (de)activate its timer
+    pub async fn configure(&self, config: &AutoUpdateConfig) -> Result<()> {
+        let backend = backend(self.family)?;
+        validate_config(self.family, config)?;
+
+        let installed = Path::new(backend.binary).exists();
+        let mut ops = Vec::new();
+        if config.enabled && !installed {
+            ops.extend(backend.install.clone());
+        }
+
+        // Debian writes APT::Periodic either way; the others only (re)write units they enable
+        if self.family == DistroFamily::Debian || config.enabled {
+            ops.push(PrivilegedOp::ConfigureAutoUpdates { family: self.family, config: config.clone() });
+        }
+        if self.family == DistroFamily::Debian && config.enabled {
+            for timer in ["apt-daily.timer", "apt-daily-upgrade.timer"] {
+                ops.push(PrivilegedOp::systemctl("enable", timer));
+                ops.push(PrivilegedOp::systemctl("start", timer));
+            }
+        }
+
+        // Debian keeps the apt timers (they also refresh package lists) and only changes APT::Periodic
+        if self.family != DistroFamily::Debian {
+            if config.enabled {
+                ops.push(PrivilegedOp::ReloadSystemd);
+                ops.push(PrivilegedOp::systemctl("enable", backend.timer));
+                ops.push(PrivilegedOp::systemctl("restart", backend.timer));
+            } else if installed_timer(self.family) {
+                ops.push(PrivilegedOp::systemctl("disable", backend.timer));
+                ops.push(PrivilegedOp::systemctl("stop", backend.timer));
+            }
+        }
+
+        if ops.is_empty() {
+            return Ok(());
+        }
+        self.executor.execute(&ops).await?;
+
+        Ok(())
+    }
+}
+
+/// Whether the timer to disable exists at all
+fn installed_timer(family: DistroFamily) -> bool {
+    match family {
+        DistroFamily::Fedora => Path::new("/usr/lib/systemd/system/dnf-automatic.timer").exists(),
+        DistroFamily::Suse => Path::new(ZYPPER_PATCH_TIMER).exists(),
+        DistroFamily::Arch => Path::new(UPDATE_CHECK_TIMER).exists(),
+        _ => false,
+    }
+}
+
+/// Human-readable outcome of the last backend run from its exit status
+fn run_result(family: DistroFamily, success: bool, code: i32) -> String {
+    match (family, success, code) {
+        (DistroFamily::Arch, true, 0) => "Updates available".to_string(),
+        (DistroFamily::Arch, true, _) => "System is up to date".to_string(),
+        (DistroFamily::Suse, true, 102) => "Patches installed, reboot required".to_string(),
+        (_, true, _) => "Updates applied".to_string(),
+        (_, false, code) => format!("Failed (exit status {})", code),
+    }
+}
+
+// ============================================================================
+// Configuration Files
+// ============================================================================
+
+/// Origins unattended-upgrades takes security updates from (Debian, Ubuntu and Ubuntu Pro)
+const SECURITY_ORIGINS: &[&str] = &[
+    "origin=${distro_id},label=${distro_id}-Security",
+    "origin=${distro_id},codename=${distro_codename}-security",
+    "origin=${distro_id},archive=${distro_codename}-security",
+    "origin=${distro_id}ESMApps,archive=${distro_codename}-apps-security",
+    "origin=${distro_id}ESM,archive=${distro_codename}-infra-security",
+];
+
+/// Additional origins for all stable updates
+const UPDATE_ORIGINS: &[&str] = &[
+    "origin=${distro_id},codename=${distro_codename}",
+    "origin=${distro_id},codename=${distro_codename}-updates",
+    "origin=${distro_id},archive=${distro_codename}-updates",
+];
+
+fn render_apt_periodic(config: &AutoUpdateConfig) -> String {
+    let days = if config.enabled { config.schedule.days() } else { 0 };
+    format!(
+        "// Managed by Glance\nAPT::Periodic::Update-Package-Lists \"1\";\nAPT::Periodic::Unattended-Upgrade \"{}\";\n",
+        days
+    )
+}
+
+/// Overrides the origins and reboot settings of 50unattended-upgrades
+fn render_unattended_conf(config: &AutoUpdateConfig) -> String {
+    let mut content = String::from(
+        "// Managed by Glance\n\
+         #clear Unattended-Upgrade::Allowed-Origins;\n\
+         #clear Unattended-Upgrade::Origins-Pattern;\n\
+         Unattended-Upgrade::Origins-Pattern {\n",
+    );
+    let extra: &[&str] = if config.security_only { &[] } else { UPDATE_ORIGINS };
+    for origin in SECURITY_ORIGINS.iter().chain(extra) {
+        content.push_str(&format!("        \"{}\";\n", origin));
+    }
+    content.push_str("};\n");
+
+    let reboot = config.reboot == RebootPolicy::WhenNeeded;
+    content.push_str(&format!("Unattended-Upgrade::Automatic-Reboot \"{}\";\n", reboot));
+    if reboot {
+        let time = config.reboot_time.as_deref().unwrap_or("now");
+        content.push_str(&format!("Unattended-Upgrade::Automatic-Reboot-Time \"{}\";\n", time));
+    }
+    content
+}
+
+/// `apt-config dump` lines (`Key "value";`, list items as `Key:: "value";`)
+fn parse_apt_config(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            let value = value.trim().trim_end_matches(';').trim_matches('"');
+            Some((key.trim_end_matches("::").to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn apt_config(values: &[(String, String)]) -> AutoUpdateConfig {
+    let value = |key: &str| values.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+    let days: u32 = value("APT::Periodic::Unattended-Upgrade").and_then(|d| d.parse().ok()).unwrap_or(0);
+    let origins: Vec<&str> = values
+        .iter()
+        .filter(|(k, v)| {
+            !v.is_empty() && (k == "Unattended-Upgrade::Origins-Pattern" || k == "Unattended-Upgrade::Allowed-Origins")
+        })
+        .map(|(_, v)| v.as_str())
+        .collect();
+
+    AutoUpdateConfig {
+        enabled: days > 0,
+        security_only: origins.iter().all(|o| o.to_lowercase().contains("security")),
+        reboot: if value("Unattended-Upgrade::Automatic-Reboot") == Some("true") {
+            RebootPolicy::WhenNeeded
+        } else {
+            RebootPolicy::Never
+        },
+        reboot_time: value("Unattended-Upgrade::Automatic-Reboot-Time")
+            .filter(|t| is_valid_time(t))
+            .map(|t| t.to_string()),
+        schedule: if days >= 7 { UpdateSchedule::Weekly } else { UpdateSchedule::Daily },
+    }
+}
+
+/// Last run in the unattended-upgrades log
+#[derive(Debug, PartialEq)]
+struct UnattendedRun {
+    started: String,
+    result: String,
+    success: bool,
+}
+
+/// Find the last run in the log: lines look like `2024-05-02 06:41:13,512 INFO message`
+fn parse_unattended_log(log: &str) -> Option<UnattendedRun> {
+    let entries: Vec<(&str, &str, &str)> = log
+        .lines()
+        .filter_map(|line| {
+            let (timestamp, rest) = line.get(..23).zip(line.get(24..))?;
+            let (level, message) = rest.split_once(' ')?;
+            Some((timestamp, level, message.trim()))
+        })
+        .collect();
+
+    let start = entries
+        .iter()
+        .rposition(|(_, _, message)| message.starts_with("Starting unattended upgrades script"))?;
+    let run = &entries[start..];
+    let error = run.iter().find(|(_, level, _)| *level == "ERROR");
+    let (_, _, last) = error.or_else(|| run.last())?;
+
+    Some(UnattendedRun {
+        started: run[0].0.split(',').next().unwrap_or_default().to_string(),
+        result: last.to_string(),
+        success: error.is_none(),
+    })
+}
+
+fn render_dnf_automatic(current: &str, config: &AutoUpdateConfig) -> String {
+    let when = config.reboot_time.as_deref().unwrap_or("+5");
+    let values = [
+        ("upgrade_type", if config.security_only { "security" } else { "default" }.to_string()),
+        ("download_updates", "yes".to_string()),
+        ("apply_updates", "yes".to_string()),
+        ("reboot", if config.reboot == RebootPolicy::WhenNeeded { "when-needed" } else { "never" }.to_string()),
+        ("reboot_command", format!("\"shutdown -r {} 'Rebooting after applying package updates'\"", when)),
+    ];
+    ini::set_values(current, "commands", &values)
+}
+
+fn dnf_automatic_config(conf: &str, timer: &str) -> AutoUpdateConfig {
+    let value = |key| ini::value(conf, "commands", key).unwrap_or_default();
+    AutoUpdateConfig {
+        enabled: false,
+        security_only: value("upgrade_type") == "security",
+        reboot: if matches!(value("reboot"), "when-needed" | "when-changed") {
+            RebootPolicy::WhenNeeded
+        } else {
+            RebootPolicy::Never
+        },
+        reboot_time: reboot_time(value("reboot_command")),
+        schedule: timer_schedule(timer),
+    }
+}
+
+// ============================================================================
+// systemd Units
+// ============================================================================
+
+const UPDATE_CHECK_UNIT: &str = "# Managed by Glance
+[Unit]
+Description=Check for package updates
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+Type=oneshot
+ExecStart=/usr/bin/checkupdates
+# 2: no updates available
+SuccessExitStatus=2
+";
+
+fn render_timer(description: &str, schedule: UpdateSchedule) -> String {
+    format!(
+        "# Managed by Glance\n\
+         [Unit]\n\
+         Description={}\n\n\
+         [Timer]\n\
+         OnCalendar={}\n\
+         RandomizedDelaySec=1h\n\
+         Persistent=true\n\n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        description,
+        schedule.on_calendar()
+    )
+}
+
+fn render_zypper_patch_service(config: &AutoUpdateConfig) -> String {
+    let category = if config.security_only { " --category security" } else { "" };
+    let mut content = format!(
+        "# Managed by Glance\n\
+         [Unit]\n\
+         Description=Install patches with zypper\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart=/usr/bin/zypper --non-interactive patch --auto-agree-with-licenses{}\n\
+         # 102: a reboot is needed, 103: zypper updated itself\n\
+         SuccessExitStatus=102 103\n",
+        category
+    );
+    if config.reboot == RebootPolicy::WhenNeeded {
+        let when = config.reboot_time.as_deref().unwrap_or("now");
+        content.push_str(&format!(
+            "ExecStopPost=/bin/sh -c 'if [ \"$EXIT_STATUS\" = 102 ]; then /usr/sbin/shutdown -r {}; fi'\n",
+            when
+        ));
+    }
+    content
+}
+
+/// Settings from the units Glance wrote (SUSE and Arch)
+fn unit_config(service: &str, timer: &str) -> AutoUpdateConfig {
+    let reboot = service.contains("shutdown -r");
+    AutoUpdateConfig {
+        enabled: false,
+        security_only: service.is_empty() || service.contains("--category security"),
+        reboot: if reboot { RebootPolicy::WhenNeeded } else { RebootPolicy::Never },
+        reboot_time: reboot_time(service),
+        schedule: timer_schedule(timer),
+    }
+}
+
+fn timer_schedule(timer: &str) -> UpdateSchedule {
+    let weekly = timer
+        .lines()
+        .any(|line| line.trim().strip_prefix("OnCalendar=").is_some_and(|v| v.trim() == "weekly"));
+    if weekly {
+        UpdateSchedule::Weekly
+    } else {
+        UpdateSchedule::Daily
+    }
+}
+
+/// The HH:MM in a `shutdown -r <when>` command
+fn reboot_time(command: &str) -> Option<String> {
+    let (_, rest) = command.split_once("shutdown -r ")?;
+    let when = rest.split(|c: char| c.is_whitespace() || c == ';').next()?;
+    is_valid_time(when).then(|| when.to_string())
+}
+
+/// Reject families without a backend and malformed reboot times
+pub(crate) fn validate_config(family: DistroFamily, config: &AutoUpdateConfig) -> Result<()> {
+    backend(family)?;
+    if let Some(time) = &config.reboot_time {
+        if !is_valid_time(time) {
+            return Err(AppError::InvalidInput(format!("Invalid reboot time: {}", time)));
+        }
+    }
+    Ok(())
+}
+
+/// Files `PrivilegedOp::ConfigureAutoUpdates` writes, with their rendered content
+pub(crate) fn config_files(family: DistroFamily, config: &AutoUpdateConfig) -> Vec<(&'static str, String)> {
+    match family {
+        DistroFamily::Debian => vec![
+            (APT_PERIODIC_CONF, render_apt_periodic(config)),
+            (APT_UNATTENDED_CONF, render_unattended_conf(config)),
+        ],
+        DistroFamily::Fedora => {
+            let current = fs::read_to_string(DNF_AUTOMATIC_CONF)
+                .or_else(|_| fs::read_to_string(DNF5_AUTOMATIC_DEFAULTS))
+                .unwrap_or_default();
+            vec![
+                (DNF_AUTOMATIC_CONF, render_dnf_automatic(&current, config)),
+                (DNF_AUTOMATIC_TIMER, render_timer("dnf-automatic timer", config.schedule)),
+            ]
+        }
+        DistroFamily::Suse => vec![
+            (ZYPPER_PATCH_SERVICE, render_zypper_patch_service(config)),
+            (ZYPPER_PATCH_TIMER, render_timer("Automatic zypper patch", config.schedule)),
+        ],
+        DistroFamily::Arch => vec![
+            (UPDATE_CHECK_SERVICE, UPDATE_CHECK_UNIT.to_string()),
+            (UPDATE_CHECK_TIMER, render_timer("Check for package updates", config.schedule)),
+        ],
+        DistroFamily::Unknown => Vec::new(),
+    }
+}
+
+fn is_valid_time(time: &str) -> bool {
+    match time.split_once(':') {
+        Some((h, m)) if h.len() == 2 && m.len() == 2 => {
+            matches!((h.parse::<u32>(), m.parse::<u32>()), (Ok(h), Ok(m)) if h < 24 && m < 60)
+        }
+        _ => false,
+    }
+}
+
+/// `systemctl show` properties of a unit, without unset ("n/a", empty) values
+async fn unit_properties(unit: &str, properties: &str) -> HashMap<String, String> {
+    let output = query_output("systemctl", &["show", unit, &format!("--property={}", properties)])
+        .await
+        .unwrap_or_default();
+    output
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .filter(|(_, value)| !value.is_empty() && *value != "n/a" && *value != "0")
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::privileged::RecordingExecutor;
+
+    #[test]
+    fn test_apt_config() {
+        let config = AutoUpdateConfig {
+            enabled: true,
+            security_only: true,
+            reboot: RebootPolicy::WhenNeeded,
+            reboot_time: Some("02:30".to_string()),
+            schedule: UpdateSchedule::Weekly,
+        };
+        let rendered = render_unattended_conf(&config);
+        assert!(rendered.contains("#clear Unattended-Upgrade::Origins-Pattern;\n"));
+        assert!(rendered.contains("Unattended-Upgrade::Automatic-Reboot-Time \"02:30\";\n"));
+        assert!(!rendered.contains("-updates"));
+        assert!(render_apt_periodic(&config).contains("APT::Periodic::Unattended-Upgrade \"7\";"));
+
+        let dump = "APT::Periodic::Unattended-Upgrade \"7\";\n\
+                    Unattended-Upgrade \"\";\n\
+                    Unattended-Upgrade::Origins-Pattern \"\";\n\
+                    Unattended-Upgrade::Origins-Pattern:: \"origin=Debian,codename=bookworm-security\";\n\
+                    Unattended-Upgrade::Origins-Pattern:: \"origin=Debian,label=Debian-Security\";\n\
+                    Unattended-Upgrade::Automatic-Reboot \"true\";\n\
+                    Unattended-Upgrade::Automatic-Reboot-Time \"02:30\";\n";
+        assert_eq!(apt_config(&parse_apt_config(dump)), config);
+    }
+
+    #[test]
+    fn test_parse_unattended_log() {
+        let log = "\
+2024-05-01 06:12:40,101 INFO Starting unattended upgrades script
+2024-05-01 06:12:41,220 INFO No packages found that can be upgraded unattended and no pending auto-removals
+2024-05-02 06:41:13,512 INFO Starting unattended upgrades script
+2024-05-02 06:41:13,513 INFO Allowed origins are: origin=Debian,codename=bookworm-security
+2024-05-02 06:41:20,004 ERROR Installing the upgrades failed!
+2024-05-02 06:41:20,010 INFO All upgrades installed
+";
+        let run = parse_unattended_log(log).unwrap();
+        assert_eq!(run.started, "2024-05-02 06:41:13");
+        assert_eq!(run.result, "Installing the upgrades failed!");
+        assert!(!run.success);
+        assert_eq!(parse_unattended_log(""), None);
+    }
+
+    #[test]
+    fn test_render_dnf_automatic() {
+        let current = "[commands]\n# comment\nupgrade_type = default\napply_updates = no\n\n[emitters]\nemit_via = stdio\n";
+        let config = AutoUpdateConfig { enabled: true, reboot_time: Some("03:00".to_string()), ..Default::default() };
+        let rendered = render_dnf_automatic(current, &config);
+        assert!(rendered.starts_with("[commands]\n# comment\nupgrade_type = security\napply_updates = yes\n"));
+        assert!(rendered.ends_with("[emitters]\nemit_via = stdio\n"));
+        assert_eq!(ini::value(&rendered, "commands", "download_updates"), Some("yes"));
+        assert_eq!(dnf_automatic_config(&rendered, "").reboot_time.as_deref(), Some("03:00"));
+    }
+
+    #[test]
+    fn test_configure_suse_writes_units_then_starts_timer() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let recorder = Arc::new(RecordingExecutor::new());
+        let updates = AutoUpdates::new(DistroFamily::Suse, recorder.clone());
+        let config = AutoUpdateConfig { enabled: true, reboot: RebootPolicy::WhenNeeded, ..Default::default() };
+
+        runtime.block_on(updates.configure(&config)).unwrap();
+
+        let calls = recorder.calls();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[0], PrivilegedOp::ConfigureAutoUpdates { family: DistroFamily::Suse, config: config.clone() });
+        let files = config_files(DistroFamily::Suse, &config);
+        assert_eq!(files[0].0, ZYPPER_PATCH_SERVICE);
+        assert!(files[0].1.contains("patch --auto-agree-with-licenses --category security\n"));
+        assert_eq!(unit_config(&files[0].1, "").reboot, RebootPolicy::WhenNeeded);
+        assert_eq!(calls[1], PrivilegedOp::ReloadSystemd);
+        assert_eq!(calls[3], PrivilegedOp::systemctl("restart", "glance-zypper-patch.timer"));
+
+        let bad = AutoUpdateConfig { reboot_time: Some("25:00".to_string()), ..config };
+        assert!(runtime.block_on(updates.configure(&bad)).is_err());
+    }
+}
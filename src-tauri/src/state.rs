@@ -1,8 +1,16 @@
 //! Shared application state
 //! Thread-safe cache for system information and distro context
 
+use std::future::Future;
 use std::sync::{Arc, Mutex};
 use sysinfo::System;
+use tauri::AppHandle;
+use crate::error::Result;
+use crate::modules::{cleaner::Cleaner, dns::Dns, hosts::Hosts, keyrings::Keyrings, mirrorlist::Mirrorlist, packages::{PackageIndex, Packages, SearchCache}, processes::Processes};
+use crate::modules::{repositories::Repositories, resources::Resources, services::Services, system_stats::SystemStats};
+use crate::modules::{tweaks::Tweaks, updates::AutoUpdates};
+use crate::utils::privileged::{self, PrivilegedExecutor};
+use crate::utils::progress::{self, Operations, Progress};
 use crate::utils::{DistroContext, DistroFamily, DesktopEnvironment};
 
 /// Shared system state with cached data and distro context
@@ -11,15 +19,44 @@ pub struct AppState {
     pub sys: Arc<Mutex<System>>,
     /// Distro-specific runtime context
     pub context: DistroContext,
+    /// Runs privileged commands (pkexec, sudo/doas, or a recording mock)
+    pub executor: Arc<dyn PrivilegedExecutor>,
+    /// Long-running operations that report progress and can be cancelled
+    pub operations: Operations,
+    /// Installed native packages, until the package database changes
+    pub package_index: Arc<PackageIndex>,
+    /// Recent remote package search results
+    pub search_cache: Arc<SearchCache>,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        Self::with_executor(privileged::executor())
+    }
+
+    /// State with a specific privileged executor (e.g. a recording mock in tests)
+    pub fn with_executor(executor: Arc<dyn PrivilegedExecutor>) -> Self {
+        log::info!("Privileged executor: {}", executor.name());
         Self {
             sys: Arc::new(Mutex::new(System::new_all())),
-            context: DistroContext::new(),
+            context: DistroContext::new(executor.clone()),
+            executor,
+            operations: Operations::new(),
+            package_index: Arc::new(PackageIndex::new()),
+            search_cache: Arc::new(SearchCache::new()),
         }
     }
+
+    /// Run a long operation, emitting its progress to the frontend and making it cancellable
+    /// through `cancel_operation(operation_id)`
+    pub async fn track<T, F, Fut>(&self, app: &AppHandle, operation_id: Option<String>, f: F) -> Result<T>
+    where
+        F: FnOnce(Progress) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let progress = self.operations.start(operation_id, Some(progress::emitter(app.clone())))?;
+        self.operations.run(progress.clone(), f(progress)).await
+    }
     
     /// Get the detected distro family
     pub fn distro_family(&self) -> DistroFamily {
@@ -35,6 +72,67 @@ impl AppState {
     pub fn has_feature(&self, feature: &str) -> bool {
         self.context.has_feature(feature)
     }
+
+    // ========================================================================
+    // Services (plain Rust, shared by Tauri commands and the CLI)
+    // ========================================================================
+
+    pub fn auto_updates(&self) -> AutoUpdates {
+        AutoUpdates::new(self.context.family, self.executor.clone())
+    }
+
+    pub fn cleaner(&self) -> Cleaner<'_> {
+        Cleaner::new(&self.context, self.executor.clone())
+    }
+
+    pub fn dns(&self) -> Dns {
+        Dns::new(self.executor.clone())
+    }
+
+    pub fn hosts(&self) -> Hosts {
+        Hosts::new(self.executor.clone())
+    }
+
+    pub fn keyrings(&self) -> Keyrings {
+        Keyrings::new(self.context.family, self.executor.clone())
+    }
+
+    pub fn mirrorlist(&self) -> Mirrorlist {
+        Mirrorlist::new(self.executor.clone())
+    }
+
+    pub fn packages(&self) -> Packages {
+        Packages::new(
+            self.context.package_manager.clone(),
+            self.context.package_sources.clone(),
+            self.package_index.clone(),
+            self.search_cache.clone(),
+        )
+    }
+
+    pub fn processes(&self) -> Processes {
+        Processes::new(self.sys.clone())
+    }
+
+    pub fn repositories(&self) -> Repositories {
+        Repositories::new(self.context.distro.clone(), self.executor.clone())
+    }
+
+    pub fn resources(&self) -> Resources {
+        Resources::new(self.sys.clone())
+    }
+
+    pub fn services(&self) -> Services {
+        Services::new(self.executor.clone())
+    }
+
+    pub fn system_stats(&self) -> SystemStats {
+        SystemStats::new(self.sys.clone())
+    }
+
+    pub fn tweaks(&self) -> Tweaks {
+        Tweaks::new(self.executor.clone())
+    }
 }
 
 impl Default for AppState {
@@ -2,7 +2,9 @@
 //! Provides dynamic paths and feature availability
 
 use super::distro::{DistroFamily, DistroInfo};
+use super::privileged::PrivilegedExecutor;
 use crate::adapters::{PackageManager, DebianAdapter, ArchAdapter, FedoraAdapter, SuseAdapter};
+use crate::adapters::{PackageSource, FlatpakAdapter, SnapAdapter};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -95,14 +97,14 @@ impl DistroPaths {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeatureAvailability {
-    /// APT-style repositories (sources.list)
+    /// Repository management (sources.list, yum.repos.d, zypp repos.d, pacman.conf)
     pub repositories: bool,
     /// apt-fast integration
     pub apt_fast: bool,
     /// pacman cache cleaning (paccache)
     pub pacman_cache: bool,
-    /// dnf automatic updates
-    pub dnf_automatic: bool,
+    /// Automatic updates (unattended-upgrades, dnf-automatic, zypper patch timer, Arch update check)
+    pub auto_updates: bool,
     /// zypper patterns
     pub zypper_patterns: bool,
     /// Flatpak support
@@ -118,16 +120,16 @@ impl FeatureAvailability {
                 repositories: true,
                 apt_fast: true,
                 pacman_cache: false,
-                dnf_automatic: false,
+                auto_updates: true,
                 zypper_patterns: false,
                 flatpak: true,
                 snap: true,
             },
             DistroFamily::Arch => Self {
-                repositories: false, // Uses mirrorlist
+                repositories: true, // pacman.conf
                 apt_fast: false,
                 pacman_cache: true,
-                dnf_automatic: false,
+                auto_updates: true,
                 zypper_patterns: false,
                 flatpak: true,
                 snap: false, // Snap in AUR but not common
@@ -136,7 +138,7 @@ impl FeatureAvailability {
                 repositories: true, // yum.repos.d
                 apt_fast: false,
                 pacman_cache: false,
-                dnf_automatic: true,
+                auto_updates: true,
                 zypper_patterns: false,
                 flatpak: true,
                 snap: false,
@@ -145,7 +147,7 @@ impl FeatureAvailability {
                 repositories: true, // zypper repos
                 apt_fast: false,
                 pacman_cache: false,
-                dnf_automatic: false,
+                auto_updates: true,
                 zypper_patterns: true,
                 flatpak: true,
                 snap: false,
@@ -154,7 +156,7 @@ impl FeatureAvailability {
                 repositories: false,
                 apt_fast: false,
                 pacman_cache: false,
-                dnf_automatic: false,
+                auto_updates: false,
                 zypper_patterns: false,
                 flatpak: false,
                 snap: false,
@@ -175,6 +177,8 @@ pub struct DistroContext {
     pub family: DistroFamily,
     /// Package manager adapter
     pub package_manager: Arc<dyn PackageManager>,
+    /// Flatpak/Snap, where the distro supports them (each checks for its tool at runtime)
+    pub package_sources: Vec<Arc<dyn PackageSource>>,
     /// Dynamic paths
     pub paths: DistroPaths,
     /// Feature availability
@@ -183,25 +187,36 @@ pub struct DistroContext {
 
 impl DistroContext {
     /// Create a new context by detecting the current distro
-    pub fn new() -> Self {
+    /// The package adapters run their privileged commands through `executor`
+    pub fn new(executor: Arc<dyn PrivilegedExecutor>) -> Self {
         let distro = DistroInfo::detect().unwrap_or_default();
         let family = distro.family();
         let home = std::env::var("HOME").unwrap_or_else(|_| "/root".to_string());
         
         let package_manager: Arc<dyn PackageManager> = match family {
-            DistroFamily::Debian => Arc::new(DebianAdapter::new()),
-            DistroFamily::Arch => Arc::new(ArchAdapter::new()),
-            DistroFamily::Fedora => Arc::new(FedoraAdapter::new()),
-            DistroFamily::Suse => Arc::new(SuseAdapter::new()),
-            DistroFamily::Unknown => Arc::new(DebianAdapter::new()), // Fallback
+            DistroFamily::Debian => Arc::new(DebianAdapter::new(executor.clone())),
+            DistroFamily::Arch => Arc::new(ArchAdapter::new(executor.clone())),
+            DistroFamily::Fedora => Arc::new(FedoraAdapter::new(executor.clone())),
+            DistroFamily::Suse => Arc::new(SuseAdapter::new(executor.clone())),
+            DistroFamily::Unknown => Arc::new(DebianAdapter::new(executor.clone())), // Fallback
         };
         
+        let features = FeatureAvailability::for_family(family);
+        let mut package_sources: Vec<Arc<dyn PackageSource>> = Vec::new();
+        if features.flatpak {
+            package_sources.push(Arc::new(FlatpakAdapter::new(executor.clone())));
+        }
+        if features.snap {
+            package_sources.push(Arc::new(SnapAdapter::new(executor)));
+        }
+        
         Self {
             distro,
             family,
             package_manager,
+            package_sources,
             paths: DistroPaths::for_family(family, &home),
-            features: FeatureAvailability::for_family(family),
+            features,
         }
     }
     
@@ -216,7 +231,7 @@ impl DistroContext {
             "repositories" => self.features.repositories,
             "apt_fast" => self.features.apt_fast,
             "pacman_cache" => self.features.pacman_cache,
-            "dnf_automatic" => self.features.dnf_automatic,
+            "auto_updates" => self.features.auto_updates,
             "zypper_patterns" => self.features.zypper_patterns,
             "flatpak" => self.features.flatpak,
             "snap" => self.features.snap,
@@ -225,8 +240,3 @@ impl DistroContext {
     }
 }
 
-impl Default for DistroContext {
-    fn default() -> Self {
-        Self::new()
-    }
-}
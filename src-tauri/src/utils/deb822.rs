@@ -0,0 +1,170 @@
+//! deb822 files
+//! Stanza-aware reads and in-place edits of APT `.sources` files, keeping comments,
+//! field order and the formatting of untouched fields
+
+/// One `Name: value` field; continuation lines are joined with '\n' ("." lines become empty)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub value: String,
+    /// 0-based first line
+    first: usize,
+    /// 0-based index of the line after its last continuation line
+    end: usize,
+}
+
+/// A paragraph of fields separated from the next one by a blank line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stanza {
+    /// 1-based first line, leading comments included
+    pub line: usize,
+    /// 0-based index of the line after the stanza
+    pub end: usize,
+    pub fields: Vec<Field>,
+}
+
+impl Stanza {
+    /// Field value; names are case-insensitive
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(name))
+            .map(|f| f.value.as_str())
+    }
+
+    /// Whitespace-separated values of a field (Types, URIs, Suites, Components)
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.get(name).map(|v| v.split_whitespace().collect()).unwrap_or_default()
+    }
+}
+
+/// All stanzas that have at least one field
+pub fn parse(content: &str) -> Vec<Stanza> {
+    let mut stanzas = Vec::new();
+    let mut current: Option<Stanza> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            if let Some(stanza) = current.take() {
+                stanzas.push(stanza);
+            }
+            continue;
+        }
+        let stanza = current.get_or_insert_with(|| Stanza { line: i + 1, end: i + 1, fields: Vec::new() });
+        stanza.end = i + 1;
+
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some(field) = stanza.fields.last_mut() {
+                let continuation = line.trim();
+                field.value.push('\n');
+                field.value.push_str(if continuation == "." { "" } else { continuation });
+                field.end = i + 1;
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            stanza.fields.push(Field {
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+                first: i,
+                end: i + 1,
+            });
+        }
+    }
+    if let Some(stanza) = current {
+        stanzas.push(stanza);
+    }
+
+    stanzas.retain(|s| !s.fields.is_empty());
+    stanzas
+}
+
+fn render_field(name: &str, value: &str) -> Vec<String> {
+    let mut parts = value.split('\n');
+    let first = parts.next().unwrap_or_default();
+    let mut lines = vec![if first.is_empty() { format!("{}:", name) } else { format!("{}: {}", name, first) }];
+    lines.extend(parts.map(|part| format!(" {}", if part.is_empty() { "." } else { part })));
+    lines
+}
+
+/// Set a field of the stanza starting on `line` (1-based), appending it after the last field
+/// when missing
+pub fn set_field(content: &str, line: usize, name: &str, value: &str) -> Option<String> {
+    let stanza = parse(content).into_iter().find(|s| s.line == line)?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+    match stanza.fields.iter().find(|f| f.name.eq_ignore_ascii_case(name)) {
+        Some(field) => {
+            lines.splice(field.first..field.end, render_field(&field.name, value));
+        }
+        None => {
+            let at = stanza.fields.last().map_or(stanza.end, |f| f.end);
+            lines.splice(at..at, render_field(name, value));
+        }
+    }
+    Some(lines.join("\n") + "\n")
+}
+
+/// Drop the stanza starting on `line` (1-based) and the blank lines after it
+pub fn remove_stanza(content: &str, line: usize) -> Option<String> {
+    let stanza = parse(content).into_iter().find(|s| s.line == line)?;
+    let lines: Vec<&str> = content.lines().collect();
+    let end = stanza.end + lines[stanza.end..].iter().take_while(|l| l.trim().is_empty()).count();
+
+    let mut kept: Vec<&str> = lines[..stanza.line - 1].iter().chain(&lines[end..]).copied().collect();
+    while kept.last().is_some_and(|l| l.trim().is_empty()) {
+        kept.pop();
+    }
+    Some(if kept.is_empty() { String::new() } else { kept.join("\n") + "\n" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCES: &str = "\
+Types: deb
+# http://snapshot.debian.org/archive/debian/20250520T000000Z
+URIs: http://deb.debian.org/debian
+Suites: bookworm bookworm-updates
+Components: main
+Signed-By: /usr/share/keyrings/debian-archive-keyring.gpg
+
+# Backports
+Types: deb deb-src
+URIs: http://deb.debian.org/debian
+Suites: bookworm-backports
+Components: main contrib
+Signed-By:
+ -----BEGIN PGP PUBLIC KEY BLOCK-----
+ .
+ mQINBGPL0BUBEADmW5NlvV5cyuTFBsBE
+ -----END PGP PUBLIC KEY BLOCK-----
+Enabled: no
+";
+
+    #[test]
+    fn test_parse_and_edit() {
+        let stanzas = parse(SOURCES);
+        assert_eq!(stanzas.len(), 2);
+        assert_eq!(stanzas[0].values("suites"), ["bookworm", "bookworm-updates"]);
+        assert_eq!(stanzas[1].line, 8);
+        assert_eq!(stanzas[1].values("Types"), ["deb", "deb-src"]);
+        assert!(stanzas[1].get("Signed-By").unwrap().starts_with("\n-----BEGIN PGP PUBLIC KEY BLOCK-----\n\nmQINB"));
+
+        // Unchanged values write back byte for byte
+        let signed_by = stanzas[1].get("Signed-By").unwrap();
+        assert_eq!(set_field(SOURCES, 8, "Signed-By", signed_by).unwrap(), SOURCES);
+
+        let enabled = set_field(SOURCES, 8, "Enabled", "yes").unwrap();
+        assert!(enabled.ends_with("-----END PGP PUBLIC KEY BLOCK-----\nEnabled: yes\n"));
+        let disabled = set_field(SOURCES, 1, "Enabled", "no").unwrap();
+        assert!(disabled.contains("Signed-By: /usr/share/keyrings/debian-archive-keyring.gpg\nEnabled: no\n\n# Backports"));
+        assert_eq!(set_field(SOURCES, 2, "Enabled", "no"), None);
+
+        let removed = remove_stanza(SOURCES, 1).unwrap();
+        assert!(removed.starts_with("# Backports\nTypes: deb deb-src\n"));
+        assert_eq!(remove_stanza(&removed, 1).unwrap(), "");
+    }
+}
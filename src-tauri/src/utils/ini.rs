@@ -0,0 +1,151 @@
+//! INI files
+//! Section-aware reads and in-place edits for dnf, zypp and systemd-style configuration,
+//! keeping comments and unknown keys untouched
+
+/// One `[name]` section and the lines it spans
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub name: String,
+    /// 1-based line of the `[name]` header
+    pub line: usize,
+    /// 0-based index of the first line after the section
+    pub end: usize,
+    pub entries: Vec<(String, String)>,
+}
+
+impl Section {
+    /// First value of `key`; continuation lines of multi-line values are dropped
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+fn header(line: &str) -> Option<&str> {
+    line.trim().strip_prefix('[')?.strip_suffix(']')
+}
+
+/// All sections of a file, in order
+pub fn sections(content: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if let Some(name) = header(line) {
+            if let Some(last) = sections.last_mut() {
+                last.end = i;
+            }
+            sections.push(Section { name: name.trim().to_string(), line: i + 1, end: i + 1, entries: Vec::new() });
+        } else if let Some(section) = sections.last_mut() {
+            section.end = i + 1;
+            let trimmed = line.trim();
+            if trimmed.starts_with('#') || trimmed.starts_with(';') {
+                continue;
+            }
+            if let Some((key, value)) = trimmed.split_once('=') {
+                section.entries.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+    }
+    sections
+}
+
+/// Value of `key` in `[section]`
+pub fn value<'a>(content: &'a str, section: &str, key: &str) -> Option<&'a str> {
+    let mut current = "";
+    for line in content.lines().map(str::trim) {
+        if let Some(name) = header(line) {
+            current = name;
+        } else if current == section {
+            if let Some((k, v)) = line.split_once('=') {
+                if k.trim() == key {
+                    return Some(v.trim());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Set keys in `[section]` (appending the section if it is missing), keeping everything else
+pub fn set_values(content: &str, section: &str, values: &[(&str, String)]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut written = vec![false; values.len()];
+    let mut in_section = false;
+    let mut found_section = false;
+
+    let flush = |lines: &mut Vec<String>, written: &mut Vec<bool>| {
+        // Before the blank lines that separate the section from the next one
+        let at = lines.len() - lines.iter().rev().take_while(|l| l.trim().is_empty()).count();
+        let missing: Vec<String> = values
+            .iter()
+            .zip(written.iter_mut())
+            .filter(|(_, done)| !**done)
+            .map(|((key, value), done)| {
+                *done = true;
+                format!("{} = {}", key, value)
+            })
+            .collect();
+        lines.splice(at..at, missing);
+    };
+
+    for line in content.lines() {
+        if let Some(name) = header(line) {
+            if in_section {
+                flush(&mut lines, &mut written);
+            }
+            in_section = name.trim() == section;
+            found_section |= in_section;
+        } else if in_section {
+            let key = line.trim().split_once('=').map(|(k, _)| k.trim());
+            if let Some(i) = values.iter().position(|(k, _)| Some(*k) == key) {
+                lines.push(format!("{} = {}", values[i].0, values[i].1));
+                written[i] = true;
+                continue;
+            }
+        }
+        lines.push(line.to_string());
+    }
+
+    if !found_section {
+        if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(format!("[{}]", section));
+    }
+    if in_section || !found_section {
+        flush(&mut lines, &mut written);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Drop the section whose header is on `line` (1-based), with the blank lines after it
+pub fn remove_section(content: &str, line: usize) -> Option<String> {
+    let section = sections(content).into_iter().find(|s| s.line == line)?;
+    let lines: Vec<&str> = content.lines().collect();
+    let kept: Vec<&str> = lines[..section.line - 1]
+        .iter()
+        .chain(&lines[section.end..])
+        .copied()
+        .collect();
+    Some(kept.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_sections() {
+        let content = "[main]\ngpgcheck=1\n\n[updates]\n# comment\nname=Updates\nenabled=0\n\n[extra]\nenabled=1\n";
+        let parsed = sections(content);
+        assert_eq!(parsed.len(), 3);
+        assert_eq!((parsed[1].line, parsed[1].end), (4, 8));
+        assert_eq!(parsed[1].get("name"), Some("Updates"));
+
+        let edited = set_values(content, "updates", &[("enabled", "1".to_string()), ("priority", "10".to_string())]);
+        assert_eq!(value(&edited, "updates", "enabled"), Some("1"));
+        assert!(edited.contains("enabled = 1\npriority = 10\n\n[extra]"));
+        assert_eq!(set_values("", "commands", &[("reboot", "never".to_string())]), "[commands]\nreboot = never\n");
+
+        assert_eq!(remove_section(content, 4).unwrap(), "[main]\ngpgcheck=1\n\n[extra]\nenabled=1\n");
+        assert_eq!(remove_section(content, 2), None);
+    }
+}
@@ -3,7 +3,11 @@
 pub mod distro;
 pub mod privileged;
 pub mod context;
+pub mod deb822;
 pub mod desktop;
+pub mod ini;
+pub mod persistence;
+pub mod progress;
 
 pub use distro::{DistroInfo, DistroFamily};
 pub use context::{DistroContext, DistroPaths, FeatureAvailability};
@@ -0,0 +1,403 @@
+//! Boot persistence for tweaks
+//! Renders Glance-owned sysctl.d, udev and tmpfiles.d drop-ins shared by all modules
+
+use crate::error::{AppError, Result};
+use crate::utils::privileged::{check_sysctl, PrivilegedExecutor, PrivilegedOp};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Glance-owned sysctl drop-in (all owners share this one file)
+pub const SYSCTL_DROPIN_PATH: &str = "/etc/sysctl.d/90-glance.conf";
+/// Glance-owned udev rule for I/O schedulers
+pub const UDEV_RULE_PATH: &str = "/etc/udev/rules.d/60-glance-io-scheduler.rules";
+/// Glance-owned tmpfiles.d entry for the CPU governor
+pub const TMPFILES_PATH: &str = "/etc/tmpfiles.d/glance-cpu-governor.conf";
+
+/// Files written by older versions of the Gaming Center, folded into the drop-in
+const LEGACY_SYSCTL_PATHS: &[&str] = &[
+    "/etc/sysctl.d/90-game-optimizer.conf",
+    "/etc/sysctl.d/99-gaming.conf",
+];
+
+/// Schedulers a udev rule (or a live `SysfsWrite`) may select
+pub(crate) const IO_SCHEDULERS: &[&str] = &["none", "mq-deadline", "bfq", "kyber", "deadline", "cfq", "noop"];
+/// Governors the tmpfiles.d entry (or a live `SysfsWrite`) may select
+pub(crate) const CPU_GOVERNORS: &[&str] = &["performance", "powersave", "schedutil", "ondemand", "conservative", "userspace"];
+
+const MANAGED_HEADER: &str = "# Managed by Glance - changes here are overwritten";
+const OWNER_PREFIX: &str = "# owner: ";
+/// A value an owner still wants but a later owner overrode
+const SHADOWED_PREFIX: &str = "# shadowed: ";
+/// The live value before Glance first persisted a key
+const DEFAULT_PREFIX: &str = "# default: ";
+
+/// Owner of general system tweaks
+pub const OWNER_TWEAKS: &str = "tweaks";
+/// Owner of Gaming Center tweaks
+pub const OWNER_GAMING: &str = "gaming";
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedSysctl {
+    pub value: String,
+    pub owner: String, // "tweaks", "gaming"
+}
+
+/// Everything Glance restores at boot
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistentSettings {
+    /// sysctl key -> one value per owning module, the last one is in effect
+    pub sysctl: BTreeMap<String, Vec<PersistedSysctl>>,
+    /// sysctl key -> value before any owner changed it, restored once the last owner lets go
+    pub sysctl_defaults: BTreeMap<String, String>,
+    /// block device -> I/O scheduler
+    pub io_schedulers: BTreeMap<String, String>,
+    /// CPU governor applied to every core
+    pub cpu_governor: Option<String>,
+}
+
+// ============================================================================
+// Parsing & Rendering
+// ============================================================================
+
+/// Parse `key = value` lines, tracking `# owner:` sections
+/// Shadowed values go under the one in effect; a later file overrides an earlier one
+fn parse_sysctl(content: &str, default_owner: &str, settings: &mut PersistentSettings) {
+    let mut owner = default_owner.to_string();
+    let mut shadowed = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix(OWNER_PREFIX) {
+            owner = name.trim().to_string();
+            continue;
+        }
+        if let Some((key, value)) = trimmed.strip_prefix(DEFAULT_PREFIX).and_then(|rest| rest.split_once('=')) {
+            settings.sysctl_defaults.insert(key.trim().to_string(), value.trim().to_string());
+            continue;
+        }
+        if let Some((key, value)) = trimmed.strip_prefix(SHADOWED_PREFIX).and_then(|rest| rest.split_once('=')) {
+            shadowed.push((key.trim().to_string(), value.trim().to_string(), owner.clone()));
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+        if let Some((key, value)) = trimmed.split_once('=') {
+            settings.push_sysctl(&owner, key.trim(), value.trim());
+        }
+    }
+
+    for (key, value, owner) in shadowed {
+        let stack = settings.sysctl.entry(key).or_default();
+        stack.retain(|entry| entry.owner != owner);
+        stack.insert(0, PersistedSysctl { value, owner });
+    }
+}
+
+/// Parse `KERNEL=="sda", ATTR{queue/scheduler}="bfq"` rules
+fn parse_udev(content: &str, settings: &mut PersistentSettings) {
+    let field = |line: &str, prefix: &str| -> Option<String> {
+        let start = line.find(prefix)? + prefix.len();
+        let end = line[start..].find('"')?;
+        Some(line[start..start + end].to_string())
+    };
+
+    for line in content.lines().filter(|l| !l.trim_start().starts_with('#')) {
+        if let (Some(device), Some(scheduler)) = (
+            field(line, "KERNEL==\""),
+            field(line, "ATTR{queue/scheduler}=\""),
+        ) {
+            settings.io_schedulers.insert(device, scheduler);
+        }
+    }
+}
+
+/// Parse `w /sys/.../scaling_governor - - - - performance`
+fn parse_tmpfiles(content: &str, settings: &mut PersistentSettings) {
+    settings.cpu_governor = content
+        .lines()
+        .filter(|l| l.contains("scaling_governor") && !l.trim_start().starts_with('#'))
+        .filter_map(|l| l.split_whitespace().next_back().map(|s| s.to_string()))
+        .next_back();
+}
+
+fn render_sysctl(settings: &PersistentSettings) -> String {
+    let mut by_owner: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (key, stack) in &settings.sysctl {
+        for (i, entry) in stack.iter().enumerate() {
+            let prefix = if i + 1 == stack.len() { "" } else { SHADOWED_PREFIX };
+            by_owner
+                .entry(entry.owner.as_str())
+                .or_default()
+                .push(format!("{}{} = {}", prefix, key, entry.value));
+        }
+    }
+
+    let mut out = format!("{}\n", MANAGED_HEADER);
+    for (key, value) in &settings.sysctl_defaults {
+        out.push_str(&format!("{}{} = {}\n", DEFAULT_PREFIX, key, value));
+    }
+    for (owner, lines) in by_owner {
+        out.push_str(&format!("\n{}{}\n", OWNER_PREFIX, owner));
+        for line in lines {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Live value of a sysctl, with runs of whitespace (tcp_rmem) collapsed
+fn read_live_sysctl(key: &str) -> Option<String> {
+    fs::read_to_string(format!("/proc/sys/{}", key.replace('.', "/")))
+        .ok()
+        .map(|value| value.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|value| !value.is_empty())
+}
+
+fn render_udev(settings: &PersistentSettings) -> String {
+    let mut out = format!("{}\n", MANAGED_HEADER);
+    for (device, scheduler) in &settings.io_schedulers {
+        out.push_str(&format!(
+            "ACTION==\"add|change\", KERNEL==\"{}\", ATTR{{queue/scheduler}}=\"{}\"\n",
+            device, scheduler
+        ));
+    }
+    out
+}
+
+fn render_tmpfiles(governor: &str) -> String {
+    format!(
+        "{}\nw /sys/devices/system/cpu/cpu*/cpufreq/scaling_governor - - - - {}\n",
+        MANAGED_HEADER, governor
+    )
+}
+
+/// Owners are written into a `# owner:` comment line
+fn is_safe_owner(owner: &str) -> bool {
+    !owner.is_empty() && owner.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+impl PersistentSettings {
+    /// Load what is currently persisted on disk (including legacy gaming files)
+    pub fn load() -> Self {
+        let mut settings = Self::default();
+
+        for legacy in LEGACY_SYSCTL_PATHS {
+            if let Ok(content) = fs::read_to_string(legacy) {
+                parse_sysctl(&content, OWNER_GAMING, &mut settings);
+            }
+        }
+        if let Ok(content) = fs::read_to_string(SYSCTL_DROPIN_PATH) {
+            parse_sysctl(&content, OWNER_TWEAKS, &mut settings);
+        }
+        if let Ok(content) = fs::read_to_string(UDEV_RULE_PATH) {
+            parse_udev(&content, &mut settings);
+        }
+        if let Ok(content) = fs::read_to_string(TMPFILES_PATH) {
+            parse_tmpfiles(&content, &mut settings);
+        }
+
+        settings
+    }
+
+    /// Persist a sysctl key for `owner`; it takes effect over other owners, who keep their value
+    /// The first time any owner persists the key its current live value is remembered
+    pub fn set_sysctl(&mut self, owner: &str, key: &str, value: &str) {
+        if let Some(live) = read_live_sysctl(key).filter(|live| check_sysctl(key, live).is_ok()) {
+            self.record_default(key, &live);
+        }
+        self.push_sysctl(owner, key, value);
+    }
+
+    fn push_sysctl(&mut self, owner: &str, key: &str, value: &str) {
+        let stack = self.sysctl.entry(key.to_string()).or_default();
+        stack.retain(|entry| entry.owner != owner);
+        stack.push(PersistedSysctl {
+            value: value.to_string(),
+            owner: owner.to_string(),
+        });
+    }
+
+    /// Remember the value a key had before Glance persisted it (no-op once known or persisted)
+    pub fn record_default(&mut self, key: &str, value: &str) {
+        if !self.sysctl.contains_key(key) && !self.sysctl_defaults.contains_key(key) {
+            self.sysctl_defaults.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    /// Stop persisting `owner`'s value of a sysctl key
+    /// Returns the value the key should go back to: the next owner's, or the remembered default
+    pub fn remove_sysctl(&mut self, owner: &str, key: &str) -> Option<String> {
+        let stack = self.sysctl.get_mut(key)?;
+        let was_effective = stack.last().is_some_and(|entry| entry.owner == owner);
+        let before = stack.len();
+        stack.retain(|entry| entry.owner != owner);
+        if stack.len() == before {
+            return None;
+        }
+
+        if let Some(next) = stack.last() {
+            return was_effective.then(|| next.value.clone());
+        }
+        self.sysctl.remove(key);
+        self.sysctl_defaults.remove(key)
+    }
+
+    /// Drop every sysctl key owned by a module
+    /// Returns the keys whose live value should change, with the value to restore
+    pub fn remove_owner(&mut self, owner: &str) -> Vec<(String, String)> {
+        let keys: Vec<String> = self.sysctl.keys().cloned().collect();
+        keys.into_iter()
+            .filter_map(|key| self.remove_sysctl(owner, &key).map(|value| (key, value)))
+            .collect()
+    }
+
+    /// Whether any owner persists a sysctl key
+    pub fn has_sysctl(&self, key: &str) -> bool {
+        self.sysctl.contains_key(key)
+    }
+
+    /// The persisted value in effect for a sysctl key
+    pub fn sysctl_value(&self, key: &str) -> Option<&str> {
+        self.sysctl.get(key)?.last().map(|entry| entry.value.as_str())
+    }
+
+    /// Check every entry before root renders it (`PrivilegedOp::PersistSettings`)
+    pub fn validate(&self) -> Result<()> {
+        let refuse = |what: String| Err(AppError::PermissionDenied(format!("Refusing to persist {}", what)));
+
+        for (key, entry) in self.sysctl.iter().flat_map(|(key, stack)| stack.iter().map(move |e| (key, e))) {
+            check_sysctl(key, &entry.value)?;
+            if !is_safe_owner(&entry.owner) {
+                return refuse(format!("sysctl owner '{}'", entry.owner));
+            }
+        }
+        for (key, value) in &self.sysctl_defaults {
+            check_sysctl(key, value)?;
+        }
+        for (device, scheduler) in &self.io_schedulers {
+            if device.is_empty() || !device.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return refuse(format!("I/O scheduler for device '{}'", device));
+            }
+            if !IO_SCHEDULERS.contains(&scheduler.as_str()) {
+                return refuse(format!("I/O scheduler '{}'", scheduler));
+            }
+        }
+        if let Some(governor) = &self.cpu_governor {
+            if !CPU_GOVERNORS.contains(&governor.as_str()) {
+                return refuse(format!("CPU governor '{}'", governor));
+            }
+        }
+        Ok(())
+    }
+
+    /// Each drop-in with its rendered content, or `None` when it should be removed
+    /// Legacy gaming files are folded into the shared drop-in, so they are always removed
+    pub fn files(&self) -> Vec<(&'static str, Option<String>)> {
+        let mut files = vec![
+            (SYSCTL_DROPIN_PATH, (!self.sysctl.is_empty()).then(|| render_sysctl(self))),
+            (UDEV_RULE_PATH, (!self.io_schedulers.is_empty()).then(|| render_udev(self))),
+            (TMPFILES_PATH, self.cpu_governor.as_deref().map(render_tmpfiles)),
+        ];
+        files.extend(LEGACY_SYSCTL_PATHS.iter().map(|legacy| (*legacy, None)));
+        files
+    }
+
+    /// Write all drop-ins in a single privileged call
+    pub async fn save(&self, executor: &dyn PrivilegedExecutor) -> Result<()> {
+        executor.execute(&[PrivilegedOp::PersistSettings { settings: self.clone() }]).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_parse_roundtrip() {
+        let mut settings = PersistentSettings::default();
+        settings.set_sysctl(OWNER_TWEAKS, "vm.swappiness", "10");
+        settings.set_sysctl(OWNER_GAMING, "vm.max_map_count", "2147483642");
+        settings.io_schedulers.insert("nvme0n1".to_string(), "none".to_string());
+        settings.cpu_governor = Some("performance".to_string());
+
+        let mut parsed = PersistentSettings::default();
+        parse_sysctl(&render_sysctl(&settings), OWNER_TWEAKS, &mut parsed);
+        parse_udev(&render_udev(&settings), &mut parsed);
+        parse_tmpfiles(&render_tmpfiles("performance"), &mut parsed);
+
+        assert_eq!(parsed, settings);
+    }
+
+    #[test]
+    fn test_remove_owner_keeps_other_modules() {
+        let mut settings = PersistentSettings::default();
+        settings.set_sysctl(OWNER_TWEAKS, "vm.swappiness", "10");
+        settings.set_sysctl(OWNER_GAMING, "vm.max_map_count", "2147483642");
+
+        settings.remove_owner(OWNER_GAMING);
+
+        assert!(settings.has_sysctl("vm.swappiness"));
+        assert!(!settings.has_sysctl("vm.max_map_count"));
+    }
+
+    #[test]
+    fn test_owners_stack_and_restore_defaults() {
+        let mut settings = PersistentSettings::default();
+        settings.record_default("vm.swappiness", "60");
+        settings.push_sysctl(OWNER_TWEAKS, "vm.swappiness", "30");
+        settings.push_sysctl(OWNER_GAMING, "vm.swappiness", "10");
+        settings.record_default("vm.max_map_count", "65530");
+        settings.push_sysctl(OWNER_GAMING, "vm.max_map_count", "2147483642");
+        assert_eq!(settings.sysctl_value("vm.swappiness"), Some("10"));
+
+        // The shadowed tweaks value survives a round trip through the drop-in
+        let mut parsed = PersistentSettings::default();
+        parse_sysctl(&render_sysctl(&settings), OWNER_TWEAKS, &mut parsed);
+        assert_eq!(parsed, settings);
+
+        let mut restore = parsed.remove_owner(OWNER_GAMING);
+        restore.sort();
+        assert_eq!(
+            restore,
+            vec![
+                ("vm.max_map_count".to_string(), "65530".to_string()),
+                ("vm.swappiness".to_string(), "30".to_string()),
+            ]
+        );
+        assert_eq!(parsed.sysctl_value("vm.swappiness"), Some("30"));
+        assert_eq!(parsed.remove_sysctl(OWNER_TWEAKS, "vm.swappiness").as_deref(), Some("60"));
+        assert!(parsed.sysctl_defaults.is_empty());
+    }
+
+    #[test]
+    fn test_validate_rejects_injected_entries() {
+        let mut settings = PersistentSettings::default();
+        settings.set_sysctl(OWNER_TWEAKS, "vm.swappiness", "10");
+        settings.io_schedulers.insert("sda".to_string(), "bfq".to_string());
+        assert!(settings.validate().is_ok());
+
+        let mut core = settings.clone();
+        core.set_sysctl(OWNER_TWEAKS, "kernel.core_pattern", "core");
+        assert!(core.validate().is_err());
+
+        let mut udev = settings.clone();
+        udev.io_schedulers.insert("sda".to_string(), "bfq\", RUN+=\"/tmp/x".to_string());
+        assert!(udev.validate().is_err());
+
+        let mut tmpfiles = settings;
+        tmpfiles.cpu_governor = Some("performance\nL+ /etc/shadow".to_string());
+        assert!(tmpfiles.validate().is_err());
+    }
+}
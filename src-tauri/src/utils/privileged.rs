@@ -1,136 +1,1478 @@
 //! Privileged command execution
-//! Safe async wrapper for pkexec with timeout
+//! Pluggable executors (pkexec, sudo/doas, helper, recording mock) with an authentication timeout
+//! Every root action is a typed `PrivilegedOp`, validated and run without a shell
 
 use crate::error::{AppError, Result};
+use crate::helper::{self, HelperExecutor};
+use crate::modules::packages::{validate_flatpak_ref, validate_package_name};
+use crate::modules::mirrorlist::{MIRRORLIST_BACKUP_PATH, MIRRORLIST_PATH};
+use crate::modules::repositories::{self, APT_FAST_CONF};
+use crate::modules::updates::{self, AutoUpdateConfig};
+use crate::utils::distro::DistroFamily;
+use crate::utils::persistence::{PersistentSettings, CPU_GOVERNORS, IO_SCHEDULERS};
+use crate::utils::progress;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
 use std::process::Stdio;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Command;
 use tokio::time::{timeout, Duration};
 
-/// Timeout for privileged operations (30 seconds)
+/// Time allowed for the authentication prompt (30 seconds); the command itself may run longer
 const PKEXEC_TIMEOUT_SECS: u64 = 30;
 
-/// Whitelist of allowed commands for privileged execution
-const ALLOWED_COMMANDS: &[&str] = &[
-    // System
-    "sysctl",
-    "journalctl",
-    "systemctl",
-    "rm",
-    "bash",
-    "cp",
-    "tee",
-    // Debian/Ubuntu
-    "apt",
-    "apt-get",
-    "apt-fast",
-    "add-apt-repository",
-    // Arch
-    "pacman",
-    "paccache",
-    // Fedora
-    "dnf",
-    "rpm",
-    // OpenSUSE
-    "zypper",
+pub const HOSTS_PATH: &str = "/etc/hosts";
+pub const RESOLVED_CONF_PATH: &str = "/etc/systemd/resolved.conf";
+
+/// Files `WriteFile` may replace: plain data that root never executes or trusts
+/// Entries ending in '/' or '.' are prefixes, everything else must match exactly
+const WRITABLE_PATHS: &[&str] = &[HOSTS_PATH, RESOLVED_CONF_PATH];
+
+/// (source, destination) pairs `CopyFile` accepts, matched like `WRITABLE_PATHS`
+const COPY_RULES: &[(&str, &str)] = &[
+    (HOSTS_PATH, "/etc/hosts.backup."),
+    ("/etc/hosts.backup.", HOSTS_PATH),
+    (MIRRORLIST_PATH, MIRRORLIST_BACKUP_PATH),
+    ("/etc/apt/trusted.gpg.d/", "/etc/apt/keyrings/"),
+    ("/etc/apt/trusted.gpg.d/", "/usr/share/keyrings/"),
 ];
 
-/// Execute a command with root privileges via pkexec (async with timeout)
-/// 
-/// # Security
-/// - Only whitelisted commands are allowed
-/// - Uses pkexec for GUI-friendly authentication
-/// - 30 second timeout to prevent app freeze if user ignores dialog
-/// 
-/// # Mock Mode
-/// When FORCE_DISTRO env var is set, commands are logged but not executed
-pub async fn run_privileged(cmd: &str, args: &[&str]) -> Result<String> {
-    // Validate command is whitelisted
-    if !ALLOWED_COMMANDS.contains(&cmd) {
-        return Err(AppError::PermissionDenied(format!(
-            "Command '{}' is not in the allowed list",
-            cmd
-        )));
-    }
-    
-    // Check for mock mode (FORCE_DISTRO set)
-    if std::env::var("FORCE_DISTRO").is_ok() {
-        let mock_cmd = format!("pkexec {} {}", cmd, args.join(" "));
-        log::info!("[MOCK EXEC] Would run: {}", mock_cmd);
-        println!("[MOCK EXEC] Would run: {}", mock_cmd);
-        return Ok(format!("Mock Success: {}", mock_cmd));
+/// Repository files `WriteRepoFile` and `RemoveFile` may touch: directory and extensions
+const REPO_DIRS: &[(&str, &[&str])] = &[
+    ("/etc/apt/sources.list.d/", &[".list", ".sources"]),
+    ("/etc/yum.repos.d/", &[".repo"]),
+    ("/etc/zypp/repos.d/", &[".repo"]),
+];
+/// Single-file repository configurations `WriteRepoFile` may rewrite (never remove)
+const REPO_CONFIGS: &[&str] = &["/etc/apt/sources.list", "/etc/pacman.conf"];
+
+/// Keyrings a new `signed-by` may point at, and `RemoveFile` may delete
+const KEYRING_DIRS: &[&str] = &["/etc/apt/keyrings/", "/usr/share/keyrings/", "/etc/apt/trusted.gpg.d/"];
+/// Where `MigrateKey` may export a key to
+const SIGNED_BY_DIRS: &[&str] = &["/etc/apt/keyrings/", "/usr/share/keyrings/"];
+/// The keyring `apt-key add` wrote to; keys only ever leave it one at a time
+const LEGACY_APT_KEYRING: &str = "/etc/apt/trusted.gpg";
+/// gpg home for key operations; only root can create entries in /run
+const ROOT_GNUPG_HOME: &str = "/run/glance-gnupg";
+/// Where a new dnf/zypper `gpgkey` may come from
+const RPM_KEY_SOURCES: &[&str] = &["file:///etc/pki/rpm-gpg/", "https://download.copr.fedorainfracloud.org/"];
+
+/// sysctls naming a program the kernel runs as root
+const SYSCTL_HELPER_KEYS: &[&str] = &["kernel.core_pattern", "kernel.modprobe", "kernel.poweroff_cmd", "kernel.hotplug"];
+
+/// zram0 compression algorithms `SysfsWrite` may select
+const ZRAM_ALGORITHMS: &[&str] = &["lzo", "lzo-rle", "lz4", "lz4hc", "zstd", "842"];
+
+/// `SetNofileLimit` drop-in, and the name older versions used
+const NOFILE_LIMITS_PATH: &str = "/etc/security/limits.d/90-game-limits.conf";
+const LEGACY_NOFILE_LIMITS_PATH: &str = "/etc/security/limits.d/99-gaming.conf";
+/// fs.nr_open default; a higher soft limit breaks logins
+const MAX_NOFILE_LIMIT: u32 = 1_048_576;
+
+/// Directories `RemoveFiles` may prune
+const PRUNABLE_DIRS: &[&str] = &["/var/log", "/var/crash"];
+
+const SYSTEMCTL_ACTIONS: &[&str] = &["start", "stop", "restart", "reload", "enable", "disable"];
+
+/// (program, actions, flags) accepted for package transactions
+/// Flags ending in '=' take an alphanumeric value
+const TRANSACTION_RULES: &[(&str, &[&str], &[&str])] = &[
+    ("apt-get", &["install", "remove", "purge", "autoremove", "upgrade"], &["-y", "--only-upgrade", "--with-new-pkgs"]),
+    ("pacman", &["-S", "-Syu", "-R", "-Rns"], &["--noconfirm", "--needed"]),
+    ("dnf", &["install", "remove", "autoremove", "upgrade", "history"], &["-y"]),
+    ("zypper", &["install", "remove", "update"], &["-y", "-n", "--clean-deps", "--dry-run"]),
+    ("snap", &["remove"], &["--revision="]),
+    ("flatpak", &["install", "uninstall"], &["-y", "--noninteractive", "--system", "--unused"]),
+];
+
+/// (program, args) of each `RefreshRepositories` backend
+const REFRESH_COMMANDS: &[(&str, &[&str])] = &[
+    ("apt-get", &["update"]),
+    ("apt-fast", &["update"]),
+    ("dnf", &["check-update", "-y"]),
+    ("zypper", &["refresh"]),
+    ("pacman", &["-Sy"]),
+];
+
+/// (program, args) of each `CleanPackageCache` backend
+const CLEAN_COMMANDS: &[(&str, &[&str])] = &[
+    ("apt-get", &["clean"]),
+    ("dnf", &["clean", "all"]),
+    ("zypper", &["clean", "--all"]),
+    ("pacman", &["-Sc", "--noconfirm"]),
+    ("paccache", &["-r", "-k", "1"]),
+];
+
+/// Foreign architectures `AddArchitecture` may enable (Debian multiarch)
+const FOREIGN_ARCHITECTURES: &[&str] = &["i386"];
+
+/// Flatpak remotes `AddFlatpakRemote` may add, by name
+const FLATPAK_REMOTES: &[(&str, &str)] = &[("flathub", "https://dl.flathub.org/repo/flathub.flatpakrepo")];
+
+/// Release packages `AddRpmFusion` installs, `{}` being the Fedora release
+const RPM_FUSION_RELEASES: &[&str] = &[
+    "https://mirrors.rpmfusion.org/free/fedora/rpmfusion-free-release-{}.noarch.rpm",
+    "https://mirrors.rpmfusion.org/nonfree/fedora/rpmfusion-nonfree-release-{}.noarch.rpm",
+];
+
+/// Where pacman keeps downloaded packages; `InstallCachedPackages` only installs from here
+const PACMAN_PKG_CACHE: &str = "/var/cache/pacman/pkg";
+const PACMAN_PKG_EXTENSIONS: &[&str] = &[".pkg.tar.zst", ".pkg.tar.xz"];
+
+const MAX_FILE_BYTES: usize = 64 * 1024 * 1024;
+
+// ============================================================================
+// Typed Operations
+// ============================================================================
+
+/// A single root-level change; every variant is validated and expands to plain argv commands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PrivilegedOp {
+    /// Replace an inert whitelisted file in place and set its mode
+    WriteFile { path: String, content: String, mode: u32 },
+    /// Delete a repository file or an existing keyring (never /etc/apt/trusted.gpg itself)
+    RemoveFile { path: String },
+    /// Export one key from /etc/apt/trusted.gpg into a new `signed-by` keyring
+    MigrateKey { fingerprint: String, target: String },
+    /// Delete one key from /etc/apt/trusted.gpg
+    DeleteLegacyKey { fingerprint: String },
+    /// Back up or restore a file (see `COPY_RULES`)
+    CopyFile { from: String, to: String },
+    /// Write a repository file; lines that weaken signature checks may not be added
+    WriteRepoFile { path: String, content: String },
+    /// Render the Glance sysctl.d, udev and tmpfiles.d drop-ins
+    PersistSettings { settings: PersistentSettings },
+    /// Raise the open file limit for every user (ESYNC/FSYNC)
+    SetNofileLimit { limit: u32 },
+    ResetNofileLimit,
+    /// Render /etc/apt-fast.conf
+    ConfigureAptFast { max_connections: u32 },
+    /// Render the automatic update configuration and units of a family
+    ConfigureAutoUpdates { family: DistroFamily, config: AutoUpdateConfig },
+    /// Replace the pacman mirrorlist; only comments and http(s) `Server =` lines
+    WriteMirrorlist { content: String },
+    /// Delete files matching glob patterns below a prunable directory
+    RemoveFiles { dir: String, patterns: Vec<String>, recursive: bool },
+    SysctlSet { key: String, value: String },
+    SysfsWrite { path: String, value: String },
+    SystemctlAction { action: String, unit: String },
+    LoadModule { name: String, params: Vec<String> },
+    UnloadModule { name: String },
+    /// mkswap + swapon for a zram device
+    EnableSwap { device: String, priority: u32 },
+    DisableSwap { device: String },
+    PackageTransaction { program: String, args: Vec<String> },
+    /// `dpkg --add-architecture`
+    AddArchitecture { arch: String },
+    /// `systemctl daemon-reload` after unit files changed
+    ReloadSystemd,
+    AddFlatpakRemote { name: String },
+    /// Install the RPM Fusion free and nonfree release packages for a Fedora release
+    AddRpmFusion { release: u32 },
+    /// `pacman -U` package files from the pacman cache, e.g. to downgrade
+    InstallCachedPackages { files: Vec<String> },
+    /// Download fresh repository metadata with one of `REFRESH_COMMANDS`
+    RefreshRepositories { program: String },
+    /// Empty the package cache with one of `CLEAN_COMMANDS`
+    CleanPackageCache { program: String },
+    /// `journalctl --vacuum-size`
+    VacuumJournal { max_mb: u32 },
+    /// `add-apt-repository` for a Launchpad `ppa:owner/name`
+    AddPpa { ppa: String },
+    RemovePpa { ppa: String },
+}
+
+/// One argv invocation an operation expands to (never a shell)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Piped to the command; its stdout (an echo of the input) is discarded
+    pub stdin: Option<String>,
+}
+
+impl OpCommand {
+    fn new(program: &str, args: &[&str]) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            stdin: None,
+        }
+    }
+
+    fn with_stdin(mut self, stdin: &str) -> Self {
+        self.stdin = Some(stdin.to_string());
+        self
+    }
+}
+
+fn denied(message: String) -> AppError {
+    AppError::PermissionDenied(message)
+}
+
+fn is_safe_value(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ' '))
+}
+
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '@' | ':'))
+}
+
+fn is_safe_path(path: &str) -> bool {
+    path.starts_with('/')
+        && !path.contains("..")
+        && path
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '_' | '-' | '.' | '+' | ':'))
+}
+
+fn check_writable(path: &str) -> Result<()> {
+    let allowed = WRITABLE_PATHS.iter().any(|entry| path_matches(entry, path));
+    if !is_safe_path(path) || !allowed {
+        return Err(denied(format!("Path '{}' is not writable by Glance", path)));
+    }
+    Ok(())
+}
+
+/// `ppa:owner/name` with plain Launchpad names
+fn check_ppa(ppa: &str) -> Result<()> {
+    let valid = ppa
+        .strip_prefix("ppa:")
+        .and_then(|rest| rest.split_once('/'))
+        .is_some_and(|(owner, name)| {
+            [owner, name].iter().all(|part| {
+                !part.is_empty()
+                    && !part.starts_with(['-', '.'])
+                    && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '+'))
+            })
+        });
+    if !valid {
+        return Err(AppError::InvalidInput(format!("Invalid PPA '{}'. Use ppa:user/repo", ppa)));
+    }
+    Ok(())
+}
+
+/// Look up the fixed argv of a refresh or clean backend
+fn backend_args(table: &[(&str, &'static [&'static str])], program: &str) -> Option<&'static [&'static str]> {
+    table.iter().find(|(name, _)| *name == program).map(|(_, args)| *args)
+}
+
+/// Whether `path` matches a whitelist entry; entries ending in '/' or '.' are prefixes
+fn path_matches(entry: &str, path: &str) -> bool {
+    if entry.ends_with('/') || entry.ends_with('.') {
+        path.len() > entry.len() && path.starts_with(entry)
+    } else {
+        path == entry
+    }
+}
+
+/// A repository file directly inside one of `REPO_DIRS`
+fn is_repo_file(path: &str) -> bool {
+    REPO_DIRS.iter().any(|(dir, extensions)| {
+        path.strip_prefix(dir).is_some_and(|name| {
+            !name.contains('/') && extensions.iter().any(|ext| name.len() > ext.len() && name.ends_with(ext))
+        })
+    })
+}
+
+fn check_repo_path(path: &str, removing: bool) -> Result<()> {
+    let allowed = is_repo_file(path) || (!removing && REPO_CONFIGS.contains(&path));
+    if !is_safe_path(path) || !allowed {
+        return Err(denied(format!("'{}' is not a repository file", path)));
+    }
+    Ok(())
+}
+
+/// `line` lowercased without whitespace, so `Trusted : yes` and `trusted=yes` compare alike
+fn normalized(line: &str) -> String {
+    line.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_lowercase()
+}
+
+/// Whether a repository file line turns off or redirects signature checks
+fn is_insecure_repo_line(path: &str, raw_line: &str) -> bool {
+    let line = normalized(raw_line);
+    if line.starts_with('#') || line.starts_with(';') {
+        return false;
+    }
+    let keyring_ok = |value: &str| !value.starts_with('/') || KEYRING_DIRS.iter().any(|dir| value.starts_with(dir));
+
+    if path.ends_with(".sources") {
+        let (field, value) = line.split_once(':').unwrap_or((&line, ""));
+        return match field {
+            "trusted" | "allow-insecure" | "allow-weak" | "allow-downgrade-to-insecure" => value == "yes",
+            "signed-by" => !keyring_ok(value),
+            // Inline keys are continuation lines of Signed-By
+            _ => line.contains("beginpgppublickeyblock"),
+        };
+    }
+    if path.starts_with("/etc/apt/") {
+        let options = line.split_once('[').and_then(|(_, rest)| rest.split_once(']')).map(|(o, _)| o).unwrap_or_default();
+        return ["trusted=yes", "allow-insecure=yes", "allow-weak=yes", "allow-downgrade-to-insecure=yes"]
+            .iter()
+            .any(|option| options.contains(option))
+            || options.split(',').any(|o| o.strip_prefix("signed-by=").is_some_and(|v| !keyring_ok(v)));
+    }
+
+    let Some((key, value)) = line.split_once('=') else { return false };
+    if path == "/etc/pacman.conf" {
+        return match key {
+            // SigLevel, LocalFileSigLevel and RemoteFileSigLevel
+            key if key.ends_with("siglevel") => ["never", "optional", "trustall"].iter().any(|level| value.contains(level)),
+            "include" => !value.starts_with("/etc/pacman.d/") || value.contains(".."),
+            "xfercommand" | "hookdir" | "gpgdir" | "dbpath" | "rootdir" | "cachedir" => true,
+            _ => false,
+        };
+    }
+    match key {
+        "gpgcheck" | "pkg_gpgcheck" | "repo_gpgcheck" | "sslverify" => matches!(value, "0" | "false" | "no" | "off"),
+        // dnf takes several keys separated by commas or whitespace; split before `normalized` joins them
+        "gpgkey" => {
+            let raw = raw_line.split_once('=').map_or("", |(_, v)| v);
+            !raw.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|key| !key.is_empty())
+                .all(|key| RPM_KEY_SOURCES.iter().any(|source| key.starts_with(source)))
+        }
+        _ => false,
+    }
+}
+
+/// The insecure lines of a repository file, each with the section it belongs to: the `[name]`
+/// of a .repo file or pacman.conf, the `URIs` of a deb822 stanza, nothing for one-line sources
+fn insecure_repo_lines(path: &str, content: &str) -> Vec<(String, String)> {
+    let deb822 = path.ends_with(".sources");
+    let mut sections: Vec<(String, Vec<&str>)> = vec![(String::new(), Vec::new())];
+    for line in content.lines() {
+        let starts_section = if deb822 {
+            line.trim().is_empty()
+        } else {
+            !path.starts_with("/etc/apt/") && line.trim_start().starts_with('[')
+        };
+        if starts_section {
+            sections.push((normalized(line), Vec::new()));
+        }
+        if let Some((name, lines)) = sections.last_mut() {
+            lines.push(line);
+            if deb822 && normalized(line).starts_with("uris:") {
+                *name = normalized(line);
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    for (name, lines) in sections {
+        let mut in_gpgkey = false;
+        for line in lines {
+            // Indented lines continue the previous key; more keys under gpgkey are not checked one by one
+            let continued = line.starts_with(char::is_whitespace) && !line.trim().is_empty();
+            let insecure = if continued && in_gpgkey {
+                true
+            } else {
+                in_gpgkey = !deb822 && normalized(line).starts_with("gpgkey=");
+                is_insecure_repo_line(path, line)
+            };
+            if insecure {
+                found.push((name.clone(), normalized(line)));
+            }
+        }
+    }
+    found
+}
+
+/// Reject repository content that adds insecure lines; ones already in the same section may stay
+fn check_repo_content(path: &str, content: &str) -> Result<()> {
+    let current = fs::read_to_string(path).unwrap_or_default();
+    let existing = insecure_repo_lines(path, &current);
+    let added = insecure_repo_lines(path, content).into_iter().find(|line| !existing.contains(line));
+    if let Some((_, line)) = added {
+        return Err(denied(format!("Refusing to weaken signature checks in {}: {}", path, line)));
+    }
+    Ok(())
+}
+
+/// Only comments and `Server = http(s)://...` lines
+fn check_mirrorlist(content: &str) -> Result<()> {
+    let url_ok = |url: &str| {
+        (url.starts_with("https://") || url.starts_with("http://"))
+            && url.chars().all(|c| c.is_ascii_alphanumeric() || "-._~:/$?=&%+@".contains(c))
+    };
+    for line in content.lines().map(str::trim) {
+        let server = line.strip_prefix("Server").map(|rest| rest.trim_start());
+        let valid = line.is_empty()
+            || line.starts_with('#')
+            || server.and_then(|rest| rest.strip_prefix('=')).is_some_and(|url| url_ok(url.trim()));
+        if !valid {
+            return Err(denied(format!("Refusing mirrorlist line: {}", line)));
+        }
+    }
+    Ok(())
+}
+
+/// Key and value charset shared by live and persisted sysctls
+pub(crate) fn check_sysctl(key: &str, value: &str) -> Result<()> {
+    let key_ok = !SYSCTL_HELPER_KEYS.contains(&key)
+        && !key.is_empty()
+        && !key.contains("..")
+        && !key.starts_with('.')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if !key_ok || !is_safe_value(value) {
+        return Err(denied(format!("Refusing sysctl {}={}", key, value)));
+    }
+    Ok(())
+}
+
+/// The sysfs attributes the tweaks write, each with the values it accepts:
+/// block device I/O schedulers, CPU governors and the zram0 setup
+pub(crate) fn check_sysfs(path: &str, value: &str) -> Result<()> {
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let scheduler_device = path.strip_prefix("/sys/block/").and_then(|rest| rest.strip_suffix("/queue/scheduler"));
+    let cpu = path
+        .strip_prefix("/sys/devices/system/cpu/cpu")
+        .and_then(|rest| rest.strip_suffix("/cpufreq/scaling_governor"));
+
+    let allowed = match (scheduler_device, cpu) {
+        (Some(device), _) => {
+            !device.is_empty() && device.chars().all(|c| c.is_ascii_alphanumeric()) && IO_SCHEDULERS.contains(&value)
+        }
+        (_, Some(cpu)) => is_number(cpu) && CPU_GOVERNORS.contains(&value),
+        _ => match path.strip_prefix("/sys/block/zram0/") {
+            Some("comp_algorithm") => ZRAM_ALGORITHMS.contains(&value),
+            Some("disksize") => is_number(value),
+            Some("reset") => value == "1",
+            _ => false,
+        },
+    };
+    if !allowed {
+        return Err(denied(format!("Refusing sysfs write {} = {}", path, value)));
+    }
+    Ok(())
+}
+
+/// A keyring file directly inside one of `dirs`
+fn is_keyring_file(dirs: &[&str], path: &str) -> bool {
+    dirs.iter().any(|dir| {
+        path.strip_prefix(dir).is_some_and(|name| {
+            !name.contains('/') && !name.starts_with('.') && (name.ends_with(".gpg") || name.ends_with(".asc"))
+        })
+    })
+}
+
+/// A full v4 (40) or v5 (64) hex fingerprint
+fn check_fingerprint(fingerprint: &str) -> Result<()> {
+    if ![40, 64].contains(&fingerprint.len()) || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(denied(format!("Invalid key fingerprint '{}'", fingerprint)));
+    }
+    Ok(())
+}
+
+/// One gpg run against the legacy APT keyring in a fresh root-only home, cleaned up afterwards
+fn legacy_keyring_commands(args: &[&str]) -> Vec<OpCommand> {
+    let mut gpg = OpCommand::new(
+        "gpg",
+        &["--homedir", ROOT_GNUPG_HOME, "--batch", "--no-default-keyring", "--keyring", LEGACY_APT_KEYRING],
+    );
+    gpg.args.extend(args.iter().map(|a| a.to_string()));
+    vec![
+        OpCommand::new("rm", &["-rf", ROOT_GNUPG_HOME]),
+        OpCommand::new("mkdir", &["-m", "700", ROOT_GNUPG_HOME]),
+        gpg,
+        OpCommand::new("gpgconf", &["--homedir", ROOT_GNUPG_HOME, "--kill", "all"]),
+        OpCommand::new("rm", &["-rf", ROOT_GNUPG_HOME]),
+    ]
+}
+
+/// `tee` the content, then set its mode
+fn write_commands(path: &str, content: &str, mode: u32) -> Vec<OpCommand> {
+    vec![
+        OpCommand::new("tee", &[path]).with_stdin(content),
+        OpCommand::new("chmod", &[&format!("{:o}", mode), path]),
+    ]
+}
+
+fn check_zram_device(device: &str) -> Result<()> {
+    let valid = device
+        .strip_prefix("/dev/zram")
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    if !valid {
+        return Err(denied(format!("Refusing swap device: {}", device)));
+    }
+    Ok(())
+}
+
+fn check_transaction(program: &str, args: &[String]) -> Result<()> {
+    let (_, actions, flags) = TRANSACTION_RULES
+        .iter()
+        .find(|(name, _, _)| *name == program)
+        .ok_or_else(|| denied(format!("Package manager '{}' is not allowed", program)))?;
+
+    let mut action_count = 0;
+    for arg in args {
+        if actions.contains(&arg.as_str()) {
+            action_count += 1;
+        } else if arg.starts_with('-') {
+            let allowed = flags.iter().any(|flag| match flag.strip_suffix('=') {
+                Some(_) => arg
+                    .strip_prefix(flag)
+                    .is_some_and(|v| !v.is_empty() && v.chars().all(|c| c.is_ascii_alphanumeric())),
+                None => arg == flag,
+            });
+            if !allowed {
+                return Err(denied(format!("Flag '{}' is not allowed", arg)));
+            }
+        } else if program == "flatpak" {
+            validate_flatpak_ref(arg)?;
+        } else {
+            validate_package_name(arg)?;
+        }
+    }
+
+    if action_count != 1 {
+        return Err(denied("A package transaction needs exactly one action".to_string()));
+    }
+    Ok(())
+}
+
+impl PrivilegedOp {
+    pub fn write_file(path: &str, content: &str, mode: u32) -> Self {
+        Self::WriteFile { path: path.to_string(), content: content.to_string(), mode }
+    }
+
+    pub fn remove_file(path: &str) -> Self {
+        Self::RemoveFile { path: path.to_string() }
+    }
+
+    pub fn migrate_key(fingerprint: &str, target: &str) -> Self {
+        Self::MigrateKey { fingerprint: fingerprint.to_string(), target: target.to_string() }
+    }
+
+    pub fn delete_legacy_key(fingerprint: &str) -> Self {
+        Self::DeleteLegacyKey { fingerprint: fingerprint.to_string() }
+    }
+
+    pub fn write_repo_file(path: &str, content: &str) -> Self {
+        Self::WriteRepoFile { path: path.to_string(), content: content.to_string() }
+    }
+
+    pub fn sysctl(key: &str, value: &str) -> Self {
+        Self::SysctlSet { key: key.to_string(), value: value.to_string() }
+    }
+
+    pub fn sysfs(path: &str, value: &str) -> Self {
+        Self::SysfsWrite { path: path.to_string(), value: value.to_string() }
+    }
+
+    pub fn systemctl(action: &str, unit: &str) -> Self {
+        Self::SystemctlAction { action: action.to_string(), unit: unit.to_string() }
+    }
+
+    pub fn load_module(name: &str, params: &[&str]) -> Self {
+        Self::LoadModule {
+            name: name.to_string(),
+            params: params.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    pub fn package(program: &str, args: &[&str]) -> Self {
+        Self::PackageTransaction {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    pub fn add_architecture(arch: &str) -> Self {
+        Self::AddArchitecture { arch: arch.to_string() }
     }
 
-    // Spawn the pkexec process
-    let child = Command::new("pkexec")
-        .arg(cmd)
-        .args(args)
-        .stdout(Stdio::piped())
+    pub fn add_flatpak_remote(name: &str) -> Self {
+        Self::AddFlatpakRemote { name: name.to_string() }
+    }
+
+    pub fn add_rpm_fusion(release: u32) -> Self {
+        Self::AddRpmFusion { release }
+    }
+
+    pub fn install_cached_packages(files: &[String]) -> Self {
+        Self::InstallCachedPackages { files: files.to_vec() }
+    }
+
+    pub fn refresh_repositories(program: &str) -> Self {
+        Self::RefreshRepositories { program: program.to_string() }
+    }
+
+    pub fn clean_package_cache(program: &str) -> Self {
+        Self::CleanPackageCache { program: program.to_string() }
+    }
+
+    pub fn add_ppa(ppa: &str) -> Self {
+        Self::AddPpa { ppa: ppa.to_string() }
+    }
+
+    pub fn remove_ppa(ppa: &str) -> Self {
+        Self::RemovePpa { ppa: ppa.to_string() }
+    }
+
+    /// Reject anything Glance should not do as root
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            PrivilegedOp::WriteFile { path, content, mode } => {
+                check_writable(path)?;
+                // No setuid/sticky bits, nothing world-writable
+                if mode & !0o775 != 0 {
+                    return Err(denied(format!("Refusing file mode {:o}", mode)));
+                }
+                if content.len() > MAX_FILE_BYTES || content.contains('\0') {
+                    return Err(denied(format!("Refusing content for {}", path)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::RemoveFile { path } => {
+                // Existing keyrings may go; anything else must be a repository file
+                if is_safe_path(path) && is_keyring_file(KEYRING_DIRS, path) && Path::new(path).is_file() {
+                    return Ok(());
+                }
+                check_repo_path(path, true)
+            }
+            PrivilegedOp::MigrateKey { fingerprint, target } => {
+                check_fingerprint(fingerprint)?;
+                if !is_safe_path(target) || !is_keyring_file(SIGNED_BY_DIRS, target) || !target.ends_with(".asc") {
+                    return Err(denied(format!("Refusing to export a key to {}", target)));
+                }
+                if Path::new(target).exists() {
+                    return Err(AppError::InvalidInput(format!("{} already exists", target)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::DeleteLegacyKey { fingerprint } => check_fingerprint(fingerprint),
+            PrivilegedOp::CopyFile { from, to } => {
+                let allowed = COPY_RULES.iter().any(|(source, target)| path_matches(source, from) && path_matches(target, to));
+                if !is_safe_path(from) || !is_safe_path(to) || !allowed {
+                    return Err(denied(format!("Refusing to copy {} to {}", from, to)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::WriteRepoFile { path, content } => {
+                check_repo_path(path, false)?;
+                if content.len() > MAX_FILE_BYTES || content.contains('\0') {
+                    return Err(denied(format!("Refusing content for {}", path)));
+                }
+                check_repo_content(path, content)
+            }
+            PrivilegedOp::PersistSettings { settings } => settings.validate(),
+            PrivilegedOp::SetNofileLimit { limit } => {
+                if !(1024..=MAX_NOFILE_LIMIT).contains(limit) {
+                    return Err(denied(format!("Refusing open file limit {}", limit)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::ResetNofileLimit => Ok(()),
+            PrivilegedOp::ConfigureAptFast { max_connections } => {
+                if !(1..=32).contains(max_connections) {
+                    return Err(AppError::InvalidInput(format!("apt-fast connections must be 1-32, not {}", max_connections)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::ConfigureAutoUpdates { family, config } => updates::validate_config(*family, config),
+            PrivilegedOp::WriteMirrorlist { content } => check_mirrorlist(content),
+            PrivilegedOp::RemoveFiles { dir, patterns, .. } => {
+                let valid_patterns = !patterns.is_empty()
+                    && patterns.iter().all(|p| {
+                        !p.is_empty()
+                            && p.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '*' | '?' | '.' | '_' | '-'))
+                    });
+                if !PRUNABLE_DIRS.contains(&dir.as_str()) || !valid_patterns {
+                    return Err(denied(format!("Refusing to prune {}", dir)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::SysctlSet { key, value } => {
+                check_sysctl(key, value)?;
+                if !Path::new("/proc/sys").join(key.replace('.', "/")).exists() {
+                    return Err(denied(format!("Unknown sysctl key: {}", key)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::SysfsWrite { path, value } => check_sysfs(path, value),
+            PrivilegedOp::SystemctlAction { action, unit } => {
+                if !SYSTEMCTL_ACTIONS.contains(&action.as_str()) || !is_safe_name(unit) {
+                    return Err(denied(format!("Refusing systemctl {} {}", action, unit)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::LoadModule { name, params } => {
+                let params_ok = params.iter().all(|p| {
+                    p.split_once('=')
+                        .is_some_and(|(k, v)| is_safe_name(k) && is_safe_value(v) && !v.contains(' '))
+                });
+                if !is_safe_name(name) || !params_ok {
+                    return Err(denied(format!("Refusing module {}", name)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::UnloadModule { name } => {
+                if !is_safe_name(name) {
+                    return Err(denied(format!("Refusing module {}", name)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::EnableSwap { device, .. } | PrivilegedOp::DisableSwap { device } => {
+                check_zram_device(device)
+            }
+            PrivilegedOp::PackageTransaction { program, args } => check_transaction(program, args),
+            PrivilegedOp::AddArchitecture { arch } => {
+                if !FOREIGN_ARCHITECTURES.contains(&arch.as_str()) {
+                    return Err(denied(format!("Refusing architecture {}", arch)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::ReloadSystemd => Ok(()),
+            PrivilegedOp::AddFlatpakRemote { name } => {
+                if !FLATPAK_REMOTES.iter().any(|(known, _)| known == name) {
+                    return Err(denied(format!("Unknown Flatpak remote: {}", name)));
+                }
+                Ok(())
+            }
+            PrivilegedOp::AddRpmFusion { release } => {
+                if *release == 0 {
+                    return Err(denied("Refusing Fedora release 0".to_string()));
+                }
+                Ok(())
+            }
+            PrivilegedOp::InstallCachedPackages { files } => {
+                if files.is_empty() {
+                    return Err(denied("No cached packages to install".to_string()));
+                }
+                for file in files {
+                    validate_package_name(file)?;
+                    if !PACMAN_PKG_EXTENSIONS.iter().any(|ext| file.ends_with(ext)) {
+                        return Err(denied(format!("Not a cached package: {}", file)));
+                    }
+                }
+                Ok(())
+            }
+            PrivilegedOp::RefreshRepositories { program } => match backend_args(REFRESH_COMMANDS, program) {
+                Some(_) => Ok(()),
+                None => Err(denied(format!("Cannot refresh repositories with '{}'", program))),
+            },
+            PrivilegedOp::CleanPackageCache { program } => match backend_args(CLEAN_COMMANDS, program) {
+                Some(_) => Ok(()),
+                None => Err(denied(format!("Cannot clean the package cache with '{}'", program))),
+            },
+            PrivilegedOp::VacuumJournal { max_mb } => {
+                if *max_mb == 0 {
+                    return Err(denied("Refusing to vacuum the journal to 0 MB".to_string()));
+                }
+                Ok(())
+            }
+            PrivilegedOp::AddPpa { ppa } | PrivilegedOp::RemovePpa { ppa } => check_ppa(ppa),
+        }
+    }
+
+    /// The argv commands that carry out this operation, in order
+    pub fn commands(&self) -> Vec<OpCommand> {
+        match self {
+            PrivilegedOp::WriteFile { path, content, mode } => write_commands(path, content, *mode),
+            PrivilegedOp::RemoveFile { path } => vec![OpCommand::new("rm", &["-f", path])],
+            PrivilegedOp::CopyFile { from, to } => vec![OpCommand::new("cp", &[from, to])],
+            PrivilegedOp::MigrateKey { fingerprint, target } => {
+                let mut commands = legacy_keyring_commands(&["--armor", "--output", target, "--export", fingerprint]);
+                commands.push(OpCommand::new("chmod", &["644", target]));
+                commands
+            }
+            PrivilegedOp::DeleteLegacyKey { fingerprint } => {
+                legacy_keyring_commands(&["--yes", "--delete-keys", fingerprint])
+            }
+            PrivilegedOp::WriteRepoFile { path, content } => write_commands(path, content, 0o644),
+            PrivilegedOp::PersistSettings { settings } => settings
+                .files()
+                .into_iter()
+                .flat_map(|(path, content)| match content {
+                    Some(content) => write_commands(path, &content, 0o644),
+                    None => vec![OpCommand::new("rm", &["-f", path])],
+                })
+                .collect(),
+            PrivilegedOp::SetNofileLimit { limit } => {
+                let content = format!("# Game Optimizer - ESYNC/FSYNC\n* hard nofile {0}\n* soft nofile {0}\n", limit);
+                write_commands(NOFILE_LIMITS_PATH, &content, 0o644)
+            }
+            PrivilegedOp::ResetNofileLimit => {
+                vec![OpCommand::new("rm", &["-f", NOFILE_LIMITS_PATH, LEGACY_NOFILE_LIMITS_PATH])]
+            }
+            PrivilegedOp::ConfigureAptFast { max_connections } => {
+                write_commands(APT_FAST_CONF, &repositories::render_apt_fast_conf(*max_connections), 0o644)
+            }
+            PrivilegedOp::ConfigureAutoUpdates { family, config } => updates::config_files(*family, config)
+                .into_iter()
+                .flat_map(|(path, content)| write_commands(path, &content, 0o644))
+                .collect(),
+            PrivilegedOp::WriteMirrorlist { content } => write_commands(MIRRORLIST_PATH, content, 0o644),
+            PrivilegedOp::RemoveFiles { dir, patterns, recursive } => {
+                let mut args = vec![dir.clone()];
+                if !recursive {
+                    args.extend(["-maxdepth".to_string(), "1".to_string()]);
+                }
+                args.extend(["-type".to_string(), "f".to_string(), "(".to_string()]);
+                for (i, pattern) in patterns.iter().enumerate() {
+                    if i > 0 {
+                        args.push("-o".to_string());
+                    }
+                    args.extend(["-name".to_string(), pattern.clone()]);
+                }
+                args.extend([")".to_string(), "-delete".to_string()]);
+                vec![OpCommand { program: "find".to_string(), args, stdin: None }]
+            }
+            PrivilegedOp::SysctlSet { key, value } => {
+                vec![OpCommand::new("sysctl", &["-w", &format!("{}={}", key, value)])]
+            }
+            PrivilegedOp::SysfsWrite { path, value } => {
+                vec![OpCommand::new("tee", &[path]).with_stdin(value)]
+            }
+            PrivilegedOp::SystemctlAction { action, unit } => {
+                vec![OpCommand::new("systemctl", &[action, unit])]
+            }
+            PrivilegedOp::LoadModule { name, params } => {
+                let mut command = OpCommand::new("modprobe", &[name]);
+                command.args.extend(params.iter().cloned());
+                vec![command]
+            }
+            PrivilegedOp::UnloadModule { name } => vec![OpCommand::new("modprobe", &["-r", name])],
+            PrivilegedOp::EnableSwap { device, priority } => vec![
+                OpCommand::new("mkswap", &[device]),
+                OpCommand::new("swapon", &["-p", &priority.to_string(), device]),
+            ],
+            PrivilegedOp::DisableSwap { device } => vec![OpCommand::new("swapoff", &[device])],
+            PrivilegedOp::PackageTransaction { program, args } => vec![OpCommand {
+                program: program.clone(),
+                args: args.clone(),
+                stdin: None,
+            }],
+            PrivilegedOp::AddArchitecture { arch } => vec![OpCommand::new("dpkg", &["--add-architecture", arch])],
+            PrivilegedOp::ReloadSystemd => vec![OpCommand::new("systemctl", &["daemon-reload"])],
+            PrivilegedOp::AddFlatpakRemote { name } => {
+                let url = FLATPAK_REMOTES
+                    .iter()
+                    .find(|(known, _)| known == name)
+                    .map(|(_, url)| *url)
+                    .unwrap_or_default();
+                vec![OpCommand::new("flatpak", &["remote-add", "--if-not-exists", name, url])]
+            }
+            PrivilegedOp::AddRpmFusion { release } => {
+                let mut command = OpCommand::new("dnf", &["install", "-y"]);
+                command.args.extend(RPM_FUSION_RELEASES.iter().map(|url| url.replace("{}", &release.to_string())));
+                vec![command]
+            }
+            PrivilegedOp::InstallCachedPackages { files } => {
+                let mut command = OpCommand::new("pacman", &["-U", "--noconfirm"]);
+                command.args.extend(files.iter().map(|file| format!("{}/{}", PACMAN_PKG_CACHE, file)));
+                vec![command]
+            }
+            PrivilegedOp::RefreshRepositories { program } => {
+                vec![OpCommand::new(program, backend_args(REFRESH_COMMANDS, program).unwrap_or_default())]
+            }
+            PrivilegedOp::CleanPackageCache { program } => {
+                vec![OpCommand::new(program, backend_args(CLEAN_COMMANDS, program).unwrap_or_default())]
+            }
+            PrivilegedOp::VacuumJournal { max_mb } => {
+                vec![OpCommand::new("journalctl", &[&format!("--vacuum-size={}M", max_mb)])]
+            }
+            PrivilegedOp::AddPpa { ppa } => vec![OpCommand::new("add-apt-repository", &["-y", ppa])],
+            PrivilegedOp::RemovePpa { ppa } => vec![OpCommand::new("add-apt-repository", &["-r", "-y", ppa])],
+        }
+    }
+}
+
+impl fmt::Display for PrivilegedOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrivilegedOp::WriteFile { path, content, mode } => {
+                write!(f, "write {} ({} bytes, mode {:o})", path, content.len(), mode)
+            }
+            PrivilegedOp::WriteRepoFile { path, content } => write!(f, "write {} ({} bytes)", path, content.len()),
+            PrivilegedOp::WriteMirrorlist { content } => {
+                write!(f, "write {} ({} bytes)", MIRRORLIST_PATH, content.len())
+            }
+            PrivilegedOp::SysfsWrite { path, value } => write!(f, "write {} = {}", path, value),
+            _ => {
+                let commands: Vec<String> = self
+                    .commands()
+                    .iter()
+                    .map(|c| format!("{} {}", c.program, c.args.join(" ")))
+                    .collect();
+                write!(f, "{}", commands.join("; "))
+            }
+        }
+    }
+}
+
+impl PrivilegedOp {
+    /// Package manager runs, which leave dpkg/rpm half configured when killed
+    pub fn is_transaction(&self) -> bool {
+        matches!(
+            self,
+            PrivilegedOp::PackageTransaction { .. }
+                | PrivilegedOp::InstallCachedPackages { .. }
+                | PrivilegedOp::AddRpmFusion { .. }
+        )
+    }
+
+    /// Adds a repository, PPA or signing key; the root helper asks polkit again before these
+    pub fn adds_package_source(&self) -> bool {
+        match self {
+            PrivilegedOp::AddPpa { .. }
+            | PrivilegedOp::MigrateKey { .. }
+            | PrivilegedOp::AddFlatpakRemote { .. }
+            | PrivilegedOp::AddRpmFusion { .. } => true,
+            PrivilegedOp::WriteRepoFile { path, content } => {
+                let current = fs::read_to_string(path).unwrap_or_default();
+                let known = source_references(&current);
+                source_references(content).iter().any(|reference| !known.contains(reference))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// URLs and file paths on the active lines of a repository file: mirrors, key URLs, `signed-by`
+/// keyrings, includes. Comments don't count, so uncommenting a source counts as adding it
+fn source_references(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#') && !line.starts_with(';'))
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || matches!(c, '=' | ',' | '[' | ']')))
+        .filter(|token| token.contains("://") || token.starts_with('/'))
+        .collect()
+}
+
+fn validate_all(ops: &[PrivilegedOp]) -> Result<()> {
+    ops.iter().try_for_each(PrivilegedOp::validate)
+}
+
+// ============================================================================
+// Executor Trait
+// ============================================================================
+
+/// Runs operations as root; injected through `AppState` so tests can swap it out
+#[async_trait]
+pub trait PrivilegedExecutor: Send + Sync {
+    /// Short name for logs ("pkexec", "sudo", "doas", "helper", "recording")
+    fn name(&self) -> &'static str;
+
+    /// Validate every operation, then run them in order, stopping at the first failure
+    async fn execute(&self, ops: &[PrivilegedOp]) -> Result<String>;
+}
+
+/// Spawn `program command...` (or the command itself when `program` is None), feed stdin,
+/// wait with timeout and map auth failures
+/// stdout lines are forwarded to the current `Progress`; dropping the future kills the child
+async fn spawn_command(
+    program: Option<&str>,
+    command: &OpCommand,
+    cancel_markers: &[&str],
+) -> Result<String> {
+    let mut builder = match program {
+        Some(program) => {
+            let mut builder = Command::new(program);
+            builder.arg(&command.program);
+            builder
+        }
+        None => Command::new(&command.program),
+    };
+    let launcher = program.unwrap_or(&command.program).to_string();
+
+    let mut child = builder
+        .args(&command.args)
+        .stdin(if command.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        // `tee` echoes its input; discard it so a large write can't fill the pipe and stall
+        .stdout(if command.stdin.is_some() { Stdio::null() } else { Stdio::piped() })
         .stderr(Stdio::piped())
+        .kill_on_drop(true)
         .spawn()
-        .map_err(|e| AppError::CommandFailed(format!("Failed to spawn pkexec: {}", e)))?;
+        .map_err(|e| AppError::CommandFailed(format!("Failed to spawn {}: {}", launcher, e)))?;
+
+    if let (Some(input), Some(mut stdin)) = (&command.stdin, child.stdin.take()) {
+        stdin.write_all(input.as_bytes()).await?;
+    }
 
-    // Wait with timeout
-    let output = timeout(Duration::from_secs(PKEXEC_TIMEOUT_SECS), child.wait_with_output())
-        .await
-        .map_err(|_| AppError::Timeout("Authentication dialog timed out after 30 seconds".to_string()))?
-        .map_err(|e| AppError::CommandFailed(format!("Command execution failed: {}", e)))?;
+    let pid = child.id();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let progress = progress::current();
+    let wait = async {
+        let read_stdout = async {
+            let mut output = Vec::new();
+            let Some(stdout) = stdout else { return output };
+            let mut reader = BufReader::new(stdout);
+            let mut line = Vec::new();
+            while matches!(reader.read_until(b'\n', &mut line).await, Ok(n) if n > 0) {
+                if let Some(progress) = &progress {
+                    progress.log(String::from_utf8_lossy(&line).trim_end());
+                }
+                output.append(&mut line);
+            }
+            output
+        };
+        let read_stderr = async {
+            let mut output = Vec::new();
+            if let Some(mut stderr) = stderr {
+                let _ = stderr.read_to_end(&mut output).await;
+            }
+            output
+        };
+        let (stdout, stderr, status) = tokio::join!(read_stdout, read_stderr, child.wait());
+        status.map(|status| (status, stdout, stderr))
+    };
 
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    let (status, stdout, stderr) = match program {
+        Some(program) => after_authentication(program, pid, wait).await?,
+        None => wait.await,
+    }
+    .map_err(|e| AppError::CommandFailed(format!("Command execution failed: {}", e)))?;
+
+    if status.success() {
+        if command.stdin.is_some() {
+            return Ok(String::new());
+        }
+        Ok(String::from_utf8_lossy(&stdout).to_string())
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        
+        let stderr = String::from_utf8_lossy(&stderr);
+
         // Check if user cancelled
-        if stderr.contains("dismissed") || stderr.contains("cancelled") || stderr.contains("Not authorized") {
+        if cancel_markers.iter().any(|m| stderr.contains(m)) {
             return Err(AppError::UserCancelled);
         }
-        
-        Err(AppError::CommandFailed(stderr.to_string()))
+
+        let argv = format!("{} {}", command.program, command.args.join(" "));
+        Err(AppError::from_command(argv.trim_end(), status.code(), &stderr))
+    }
+}
+
+/// Await an elevated child; its authentication prompt gets a timeout, the command itself does not
+async fn after_authentication<T>(launcher: &str, pid: Option<u32>, wait: impl std::future::Future<Output = T>) -> Result<T> {
+    let Some(pid) = pid else { return Ok(wait.await) };
+    tokio::pin!(wait);
+    let auth = timeout(Duration::from_secs(PKEXEC_TIMEOUT_SECS), authenticated(pid, launcher));
+    tokio::select! {
+        result = &mut wait => Ok(result),
+        auth = auth => match auth {
+            Ok(()) => Ok(wait.await),
+            Err(_) => Err(AppError::Timeout("Authentication dialog timed out after 30 seconds".to_string())),
+        },
+    }
+}
+
+/// Resolves once the launcher (pkexec, sudo, doas) at `pid` got past authentication:
+/// it has exec'd the command (pkexec), spawned it (sudo, doas) or exited
+async fn authenticated(pid: u32, launcher: &str) {
+    loop {
+        let launched = match fs::read_to_string(format!("/proc/{}/comm", pid)) {
+            Ok(comm) => comm.trim() != launcher || has_child(pid),
+            Err(_) => true,
+        };
+        if launched {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
     }
 }
 
-/// Execute a shell command with root privileges (async with timeout)
-/// Only for specific, validated operations
-pub async fn run_privileged_shell(script: &str) -> Result<String> {
-    // Basic validation - no dangerous patterns
-    let dangerous_patterns = ["rm -rf /", "dd if=", "mkfs", "> /dev/"];
-    for pattern in dangerous_patterns {
-        if script.contains(pattern) {
-            return Err(AppError::PermissionDenied(format!(
-                "Script contains dangerous pattern: {}",
-                pattern
-            )));
+fn has_child(pid: u32) -> bool {
+    let Ok(entries) = fs::read_dir("/proc") else { return false };
+    entries.flatten().any(|entry| {
+        fs::read_to_string(entry.path().join("stat"))
+            .ok()
+            .and_then(|stat| {
+                // Fields after the parenthesised command name: state, ppid, ...
+                let rest = stat.rsplit_once(')')?.1;
+                rest.split_whitespace().nth(1)?.parse::<u32>().ok()
+            })
+            == Some(pid)
+    })
+}
+
+/// Validate, then run each operation's commands through `program` (None when already root)
+async fn run_ops(program: Option<&str>, ops: &[PrivilegedOp], cancel_markers: &[&str]) -> Result<String> {
+    validate_all(ops)?;
+    // Children of pkexec/sudo run as root, so dropping them would not stop the command
+    let _guard = program.is_some().then(progress::uncancellable).transpose()?;
+
+    let mut output = String::new();
+    for op in ops {
+        for command in op.commands() {
+            output.push_str(&spawn_command(program, &command, cancel_markers).await?);
         }
     }
-    
-    // Check for mock mode (FORCE_DISTRO set)
+    Ok(output)
+}
+
+/// Carry out operations in this process; only for code already running as root (the helper)
+pub async fn execute_as_root(ops: &[PrivilegedOp]) -> Result<String> {
+    run_ops(None, ops, &[]).await
+}
+
+// ============================================================================
+// pkexec (GUI default)
+// ============================================================================
+
+const PKEXEC_CANCEL_MARKERS: &[&str] = &["dismissed", "cancelled", "Not authorized"];
+
+/// Polkit authentication dialog - used when the root helper is not running as a daemon
+/// Every `execute` is one pkexec call: the helper (or this binary) with `--once` runs the whole batch
+pub struct PkexecExecutor;
+
+impl PkexecExecutor {
+    /// The installed helper, else the running binary, which also understands `--once`
+    fn elevated_binary() -> Result<std::path::PathBuf> {
+        helper::find_helper()
+            .map(Ok)
+            .unwrap_or_else(std::env::current_exe)
+            .map_err(|e| AppError::System(format!("Cannot locate the Glance binary: {}", e)))
+    }
+}
+
+#[async_trait]
+impl PrivilegedExecutor for PkexecExecutor {
+    fn name(&self) -> &'static str {
+        "pkexec"
+    }
+
+    async fn execute(&self, ops: &[PrivilegedOp]) -> Result<String> {
+        validate_all(ops)?;
+        // The batch runs as root, so killing pkexec would not stop it
+        let _guard = progress::uncancellable()?;
+        let input = serde_json::to_string(ops).map_err(|e| AppError::System(format!("Failed to encode operations: {}", e)))?;
+
+        let mut child = Command::new("pkexec")
+            .arg(Self::elevated_binary()?)
+            .arg(helper::ONCE_FLAG)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| AppError::CommandFailed(format!("Failed to spawn pkexec: {}", e)))?;
+        // The batch is read whole before anything is printed, so writing it first cannot stall
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes()).await?;
+        }
+
+        let pid = child.id();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let progress = progress::current();
+        let wait = async {
+            let read_stdout = async {
+                let mut response = None;
+                let Some(stdout) = stdout else { return response };
+                let mut lines = BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if let Ok(helper::HelperLog { log }) = serde_json::from_str(&line) {
+                        if let Some(progress) = &progress {
+                            progress.log(log);
+                        }
+                    } else if let Ok(reply) = serde_json::from_str::<helper::HelperResponse>(&line) {
+                        response = Some(reply);
+                    }
+                }
+                response
+            };
+            let read_stderr = async {
+                let mut output = String::new();
+                if let Some(mut stderr) = stderr {
+                    let _ = stderr.read_to_string(&mut output).await;
+                }
+                output
+            };
+            let (response, stderr, status) = tokio::join!(read_stdout, read_stderr, child.wait());
+            (response, stderr, status)
+        };
+        let (response, stderr, status) = after_authentication("pkexec", pid, wait).await?;
+
+        match response {
+            Some(response) => response.into_result(),
+            // pkexec exits 126 when the dialog is dismissed and 127 when not authorized
+            None if matches!(status.as_ref().ok().and_then(|s| s.code()), Some(126) | Some(127))
+                || PKEXEC_CANCEL_MARKERS.iter().any(|m| stderr.contains(m)) =>
+            {
+                Err(AppError::UserCancelled)
+            }
+            None => Err(AppError::CommandFailed(format!("pkexec {} failed: {}", helper::ONCE_FLAG, stderr.trim()))),
+        }
+    }
+}
+
+// ============================================================================
+// sudo / doas (terminals, SSH sessions)
+// ============================================================================
+
+/// Terminal elevation for headless use, where no polkit agent is running
+pub struct SudoExecutor {
+    program: &'static str,
+}
+
+impl SudoExecutor {
+    pub fn sudo() -> Self {
+        Self { program: "sudo" }
+    }
+
+    pub fn doas() -> Self {
+        Self { program: "doas" }
+    }
+}
+
+/// stderr fragments sudo/doas print when authentication is refused
+const SUDO_CANCEL_MARKERS: &[&str] = &[
+    "incorrect password",
+    "a password is required",
+    "Authentication failed",
+    "not in the sudoers file",
+];
+
+#[async_trait]
+impl PrivilegedExecutor for SudoExecutor {
+    fn name(&self) -> &'static str {
+        self.program
+    }
+
+    async fn execute(&self, ops: &[PrivilegedOp]) -> Result<String> {
+        run_ops(Some(self.program), ops, SUDO_CANCEL_MARKERS).await
+    }
+}
+
+// ============================================================================
+// Recording mock (FORCE_DISTRO simulation & tests)
+// ============================================================================
+
+/// Validates and records every operation instead of executing it
+#[derive(Default)]
+pub struct RecordingExecutor {
+    calls: Mutex<Vec<PrivilegedOp>>,
+}
+
+impl RecordingExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything recorded so far, in call order
+    pub fn calls(&self) -> Vec<PrivilegedOp> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl PrivilegedExecutor for RecordingExecutor {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn execute(&self, ops: &[PrivilegedOp]) -> Result<String> {
+        validate_all(ops)?;
+        let mut calls = self.calls.lock().unwrap();
+        for op in ops {
+            log::info!("[MOCK EXEC] Would run: {}", op);
+            calls.push(op.clone());
+        }
+        Ok(format!("Mock Success: {} operation(s)", ops.len()))
+    }
+}
+
+// ============================================================================
+// Shared Executor
+// ============================================================================
+
+static EXECUTOR: OnceLock<Arc<dyn PrivilegedExecutor>> = OnceLock::new();
+
+/// Choose an executor from the environment
+/// FORCE_DISTRO (simulation) always records; GLANCE_ELEVATION=pkexec|sudo|doas picks the tool,
+/// otherwise the root helper is used when installed and pkexec when not
+pub fn executor_from_env() -> Arc<dyn PrivilegedExecutor> {
     if std::env::var("FORCE_DISTRO").is_ok() {
-        log::info!("[MOCK EXEC] Would run shell: {}", script);
-        println!("[MOCK EXEC] Would run shell: {}", script);
-        return Ok(format!("Mock Success: shell script"));
+        return Arc::new(RecordingExecutor::new());
     }
 
-    // Spawn the pkexec bash process
-    let child = Command::new("pkexec")
-        .args(["bash", "-c", script])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| AppError::CommandFailed(format!("Failed to spawn pkexec: {}", e)))?;
+    match std::env::var("GLANCE_ELEVATION").as_deref() {
+        Ok("sudo") => Arc::new(SudoExecutor::sudo()),
+        Ok("doas") => Arc::new(SudoExecutor::doas()),
+        Ok("pkexec") => Arc::new(PkexecExecutor),
+        _ => match helper::find_helper() {
+            Some(path) => Arc::new(HelperExecutor::new(path)),
+            None => Arc::new(PkexecExecutor),
+        },
+    }
+}
 
-    // Wait with timeout
-    let output = timeout(Duration::from_secs(PKEXEC_TIMEOUT_SECS), child.wait_with_output())
-        .await
-        .map_err(|_| AppError::Timeout("Authentication dialog timed out after 30 seconds".to_string()))?
-        .map_err(|e| AppError::CommandFailed(format!("Command execution failed: {}", e)))?;
+/// Process-wide executor; `AppState` starts from this one
+pub fn executor() -> Arc<dyn PrivilegedExecutor> {
+    EXECUTOR.get_or_init(executor_from_env).clone()
+}
 
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        if stderr.contains("dismissed") || stderr.contains("Not authorized") {
-            return Err(AppError::UserCancelled);
+/// Install the process-wide executor before first use
+/// Returns false if one was already chosen
+pub fn set_executor(executor: Arc<dyn PrivilegedExecutor>) -> bool {
+    EXECUTOR.set(executor).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_executor_validates_and_records() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let recorder = RecordingExecutor::new();
+
+        runtime.block_on(recorder.execute(&[PrivilegedOp::sysctl("vm.swappiness", "10")])).unwrap();
+        assert!(runtime.block_on(recorder.execute(&[PrivilegedOp::refresh_repositories("curl")])).is_err());
+        assert!(runtime.block_on(recorder.execute(&[PrivilegedOp::clean_package_cache("bash")])).is_err());
+
+        assert_eq!(recorder.calls(), vec![PrivilegedOp::sysctl("vm.swappiness", "10")]);
+    }
+
+    #[test]
+    fn test_ops_reject_injection() {
+        assert!(PrivilegedOp::sysctl("vm.swappiness", "10; reboot").validate().is_err());
+        assert!(PrivilegedOp::sysfs("/sys/block/sda/queue/scheduler", "none").validate().is_ok());
+        assert!(PrivilegedOp::sysfs("/sys/../etc/shadow", "x").validate().is_err());
+        assert!(PrivilegedOp::sysfs("/sys/block/sda/queue/scheduler", "$(reboot)").validate().is_err());
+        assert!(PrivilegedOp::sysfs("/sys/block/sda/device/delete", "1").validate().is_err());
+        assert!(PrivilegedOp::sysfs("/sys/power/state", "mem").validate().is_err());
+        assert!(PrivilegedOp::sysfs("/sys/devices/system/cpu/cpu12/cpufreq/scaling_governor", "performance").validate().is_ok());
+        assert!(PrivilegedOp::sysfs("/sys/devices/system/cpu/cpu0/online", "0").validate().is_err());
+        assert!(PrivilegedOp::sysfs("/sys/block/zram0/disksize", "8589934592").validate().is_ok());
+        assert!(PrivilegedOp::sysfs("/sys/block/zram0/comp_algorithm", "../x").validate().is_err());
+        assert!(PrivilegedOp::write_file(HOSTS_PATH, "127.0.0.1 localhost\n", 0o644).validate().is_ok());
+        assert!(PrivilegedOp::write_file("/etc/shadow", "", 0o644).validate().is_err());
+        assert!(PrivilegedOp::write_file("/etc/hosts.allow", "", 0o644).validate().is_err());
+        assert!(PrivilegedOp::write_file(HOSTS_PATH, "", 0o4755).validate().is_err());
+        assert!(PrivilegedOp::sysctl("kernel.core_pattern", "core").validate().is_err());
+        assert!(PrivilegedOp::systemctl("stop", "--global").validate().is_err());
+        assert!(PrivilegedOp::package("apt-get", &["remove", "-y", "vim"]).validate().is_ok());
+        assert!(PrivilegedOp::package("apt-get", &["remove", "-o", "APT::x=1"]).validate().is_err());
+        assert!(PrivilegedOp::package("snap", &["remove", "core", "--revision=123"]).validate().is_ok());
+        assert!(PrivilegedOp::package("bash", &["-c", "id"]).validate().is_err());
+        // A bare sync without the upgrade is a partial upgrade
+        assert!(PrivilegedOp::package("pacman", &["-Sy", "--noconfirm"]).validate().is_err());
+        assert!(PrivilegedOp::package("flatpak", &["install", "-y", "--noninteractive", "flathub", "com.valvesoftware.Steam"]).validate().is_ok());
+        assert!(PrivilegedOp::package("flatpak", &["uninstall", "--system", "-y", "org.freedesktop.Platform//23.08"]).validate().is_ok());
+        assert!(PrivilegedOp::package("flatpak", &["uninstall", "-y", "org.x//../../etc"]).validate().is_err());
+        assert!(PrivilegedOp::package("dnf", &["history", "undo", "-y", "42"]).validate().is_ok());
+        assert!(PrivilegedOp::install_cached_packages(&["bash-5.2.026-2-x86_64.pkg.tar.zst".to_string()]).validate().is_ok());
+        assert!(PrivilegedOp::install_cached_packages(&["../../tmp/evil.pkg.tar.zst".to_string()]).validate().is_err());
+        assert!(PrivilegedOp::install_cached_packages(&["bash.sh".to_string()]).validate().is_err());
+        assert!(PrivilegedOp::add_architecture("i386").validate().is_ok());
+        assert!(PrivilegedOp::add_architecture("amd64").validate().is_err());
+        assert!(PrivilegedOp::add_flatpak_remote("evil").validate().is_err());
+        assert!(PrivilegedOp::add_rpm_fusion(0).validate().is_err());
+        assert!(PrivilegedOp::add_rpm_fusion(40).commands()[0].args[2].ends_with("rpmfusion-free-release-40.noarch.rpm"));
+        assert!(PrivilegedOp::add_ppa("ppa:graphics-drivers/ppa").validate().is_ok());
+        assert!(PrivilegedOp::add_ppa("ppa:x/y -o APT::Update::Pre-Invoke::=id").validate().is_err());
+        assert!(PrivilegedOp::remove_ppa("deb http://evil/ ./").validate().is_err());
+        assert_eq!(PrivilegedOp::clean_package_cache("paccache").commands()[0].args, vec!["-r", "-k", "1"]);
+    }
+
+    #[test]
+    fn test_file_ops_stay_inert() {
+        // Anything root would run or trust needs a typed op
+        for path in ["/etc/sysctl.d/x.conf", "/etc/udev/rules.d/x.rules", "/etc/systemd/system/x.service",
+            "/etc/apt/apt.conf.d/99x", "/etc/apt/sources.list.d/x.list"] {
+            assert!(PrivilegedOp::write_file(path, "", 0o644).validate().is_err(), "{}", path);
         }
-        Err(AppError::CommandFailed(stderr.to_string()))
+        assert!(PrivilegedOp::remove_file(HOSTS_PATH).validate().is_err());
+        assert!(PrivilegedOp::remove_file("/etc/apt/sources.list").validate().is_err());
+        assert!(PrivilegedOp::remove_file("/etc/yum.repos.d/x.repo").validate().is_ok());
+        // Keys are exported by root itself, never written from the app
+        assert!(PrivilegedOp::write_file("/etc/apt/trusted.gpg.d/x.gpg", "", 0o644).validate().is_err());
+        assert!(PrivilegedOp::write_file("/etc/apt/keyrings/x.asc", "", 0o644).validate().is_err());
+        assert!(PrivilegedOp::remove_file("/etc/apt/trusted.gpg").validate().is_err());
+        assert!(PrivilegedOp::remove_file("/etc/apt/keyrings/missing-glance-test.gpg").validate().is_err());
+        let fingerprint = "6F71F525282841EEDAF851B42F59B5F99B1BE0B4";
+        assert!(PrivilegedOp::migrate_key(fingerprint, "/etc/apt/keyrings/missing-glance-test.asc").validate().is_ok());
+        assert!(PrivilegedOp::migrate_key(fingerprint, "/etc/apt/trusted.gpg.d/x.asc").validate().is_err());
+        assert!(PrivilegedOp::migrate_key("--export-secret-keys", "/etc/apt/keyrings/x.asc").validate().is_err());
+        assert!(PrivilegedOp::delete_legacy_key(fingerprint).commands().iter().any(|c| c.args.ends_with(&[
+            "--keyring".to_string(), LEGACY_APT_KEYRING.to_string(), "--yes".to_string(), "--delete-keys".to_string(), fingerprint.to_string()
+        ])));
+        assert!(PrivilegedOp::CopyFile { from: "/tmp/x".into(), to: HOSTS_PATH.into() }.validate().is_err());
+        assert!(PrivilegedOp::CopyFile { from: HOSTS_PATH.into(), to: "/etc/hosts.backup.1".into() }.validate().is_ok());
+
+        assert!(PrivilegedOp::SetNofileLimit { limit: MAX_NOFILE_LIMIT + 1 }.validate().is_err());
+        assert!(PrivilegedOp::ConfigureAptFast { max_connections: 0 }.validate().is_err());
+        assert!(PrivilegedOp::WriteMirrorlist { content: "## x\nServer = https://m.org/$repo/os/$arch\n".into() }.validate().is_ok());
+        assert!(PrivilegedOp::WriteMirrorlist { content: "XferCommand = /tmp/x %u\n".into() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_repo_files_keep_signature_checks() {
+        let ok = |path: &str, content: &str| PrivilegedOp::write_repo_file(path, content).validate().is_ok();
+
+        assert!(ok("/etc/apt/sources.list.d/x.list", "deb [signed-by=/etc/apt/keyrings/x.gpg] https://x/ stable main\n"));
+        assert!(!ok("/etc/apt/sources.list.d/x.list", "deb [arch=amd64 trusted=yes] https://x/ stable main\n"));
+        assert!(!ok("/etc/apt/sources.list.d/x.list", "deb [signed-by=/tmp/x.gpg] https://x/ stable main\n"));
+        assert!(!ok("/etc/apt/sources.list.d/x.sources", "Types: deb\nURIs: https://x/\nTrusted: yes\n"));
+        assert!(!ok("/etc/apt/sources.list.d/x.sources", "Signed-By:\n -----BEGIN PGP PUBLIC KEY BLOCK-----\n"));
+        assert!(!ok("/etc/apt/sources.list.d/x.conf", "deb https://x/ stable main\n"));
+        assert!(ok("/etc/yum.repos.d/x.repo", "[x]\nbaseurl=https://x/\ngpgcheck=1\ngpgkey=file:///etc/pki/rpm-gpg/RPM-GPG-KEY-x\n"));
+        assert!(!ok("/etc/yum.repos.d/x.repo", "[x]\nbaseurl=https://x/\ngpgcheck = 0\n"));
+        assert!(!ok("/etc/zypp/repos.d/x.repo", "[x]\ngpgkey=https://evil/key\n"));
+        assert!(!ok("/etc/yum.repos.d/x.repo", "[x]\n# comment\nsslverify=False\n"));
+        assert!(ok("/etc/pacman.conf", "[multilib]\nInclude = /etc/pacman.d/mirrorlist\n"));
+        assert!(!ok("/etc/pacman.conf", "[options]\nSigLevel = Never\n"));
+        assert!(!ok("/etc/pacman.conf", "[options]\nXferCommand = /tmp/x %u %o\n"));
+        assert!(ok("/etc/pacman.conf", "#SigLevel = Never\n"));
+        assert!(!ok("/etc/pacman.conf", "[options]\nSigLevel = Required DatabaseOptional\n"));
+        assert!(!ok("/etc/pacman.conf", "[x]\nSigLevel = PackageOptional\n"));
+        assert!(!ok("/etc/pacman.conf", "[x]\nInclude = /etc/pacman.d/../../tmp/x\n"));
+        // Every gpgkey entry counts, on the line or continued below it
+        let fedora_key = "file:///etc/pki/rpm-gpg/RPM-GPG-KEY-fedora";
+        assert!(ok("/etc/yum.repos.d/x.repo", &format!("[x]\ngpgkey={0}-1, {0}-2\n", fedora_key)));
+        assert!(!ok("/etc/yum.repos.d/x.repo", &format!("[x]\ngpgkey={} https://evil.example/k.asc\n", fedora_key)));
+        assert!(!ok("/etc/yum.repos.d/x.repo", &format!("[x]\ngpgkey={}\n  https://evil.example/k.asc\n", fedora_key)));
+    }
+
+    #[test]
+    fn test_new_sources_need_fresh_authorization() {
+        let list = "deb [signed-by=/etc/apt/keyrings/a.gpg] https://a.example/ stable main\n# deb https://old.example/ stable main\n";
+        let refs = source_references(list);
+        assert_eq!(refs, ["/etc/apt/keyrings/a.gpg", "https://a.example/"]);
+        // Uncommenting a line or pointing at another keyring brings in something new
+        let enabled = list.replace("# deb", "deb");
+        assert!(source_references(&enabled).iter().any(|r| !refs.contains(r)));
+        let rekeyed = list.replace("a.gpg", "b.gpg");
+        assert!(source_references(&rekeyed).iter().any(|r| !refs.contains(r)));
+        assert!(PrivilegedOp::add_ppa("ppa:graphics-drivers/ppa").adds_package_source());
+        assert!(!PrivilegedOp::remove_ppa("ppa:graphics-drivers/ppa").adds_package_source());
+    }
+
+    #[test]
+    fn test_insecure_lines_compare_per_section() {
+        let current = "[legacy]\nbaseurl=https://old/\ngpgcheck=0\n\n[x]\nbaseurl=https://x/\ngpgcheck=1\n";
+        let existing = insecure_repo_lines("/etc/yum.repos.d/x.repo", current);
+        let edited = current.replace("[x]\nbaseurl=https://x/\ngpgcheck=1", "[x]\nbaseurl=https://x/\ngpgcheck=0");
+        let added = insecure_repo_lines("/etc/yum.repos.d/x.repo", &edited);
+        assert_eq!(existing.len(), 1);
+        assert!(added.iter().any(|line| !existing.contains(line)));
+        // Re-enabling a section keeps its own existing line
+        let disabled = current.replace("baseurl=https://old/", "baseurl=https://old/\nenabled=0");
+        assert!(insecure_repo_lines("/etc/yum.repos.d/x.repo", &disabled).iter().all(|line| existing.contains(line)));
+    }
+
+    #[test]
+    fn test_write_file_runs_without_shell() {
+        let commands = PrivilegedOp::write_file(HOSTS_PATH, "127.0.0.1 a'b\n", 0o644).commands();
+        assert_eq!(commands[0].program, "tee");
+        assert_eq!(commands[0].args, vec![HOSTS_PATH.to_string()]);
+        assert_eq!(commands[0].stdin.as_deref(), Some("127.0.0.1 a'b\n"));
+        assert_eq!(commands[1].args, vec!["644".to_string(), HOSTS_PATH.to_string()]);
     }
 }
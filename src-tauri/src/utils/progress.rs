@@ -0,0 +1,339 @@
+//! Progress reporting for long-running operations
+//! Modules report steps and command output through a `Progress` handle; the GUI forwards
+//! them as Tauri events, the CLI prints them and the root helper streams them over its socket
+//! Cancelling an operation drops its future, which kills the child processes it spawned;
+//! steps that can't be killed safely hold an `Uncancellable` guard and cancel is refused meanwhile
+
+use crate::error::{AppError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+/// Tauri event carrying every `ProgressEvent`
+pub const PROGRESS_EVENT: &str = "operation-progress";
+
+/// One update of a running operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub operation_id: String,
+    /// Current step, e.g. "Downloading StevenBlack Unified"
+    pub step: String,
+    /// Overall completion (0-100), when known
+    pub percent: Option<u8>,
+    /// An output line of the running command
+    pub line: Option<String>,
+    /// Set on the last event of an operation
+    pub done: bool,
+}
+
+/// Where progress events go (Tauri emitter, CLI printer, helper socket)
+pub type ProgressSink = Arc<dyn Fn(ProgressEvent) + Send + Sync>;
+
+tokio::task_local! {
+    /// The operation the current task works for; lets `utils::privileged` stream command output
+    static CURRENT: Progress;
+}
+
+/// Progress of the operation running on the current task, if any
+pub fn current() -> Option<Progress> {
+    CURRENT.try_with(|progress| progress.clone()).ok()
+}
+
+// ============================================================================
+// Progress Handle
+// ============================================================================
+
+/// Cheap to clone; all clones report to the same sink and share one cancel flag
+#[derive(Clone)]
+pub struct Progress {
+    id: String,
+    sink: Option<ProgressSink>,
+    /// Last step and percent, repeated on log lines so every event is self-contained
+    state: Arc<Mutex<(String, Option<u8>)>>,
+    cancelled: Arc<AtomicBool>,
+    /// Uncancellable steps in flight; `cancel` takes this lock so it can't race a step starting
+    uncancellable: Arc<Mutex<usize>>,
+    notify: Arc<Notify>,
+}
+
+impl Progress {
+    pub fn new(id: impl Into<String>, sink: Option<ProgressSink>) -> Self {
+        Self {
+            id: id.into(),
+            sink,
+            state: Arc::new(Mutex::new((String::new(), None))),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            uncancellable: Arc::new(Mutex::new(0)),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// A handle nobody listens to, for callers that don't report progress
+    pub fn silent() -> Self {
+        Self::new("", None)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn emit(&self, line: Option<String>, done: bool) {
+        let Some(sink) = &self.sink else { return };
+        let (step, percent) = self.state.lock().unwrap().clone();
+        sink(ProgressEvent {
+            operation_id: self.id.clone(),
+            step,
+            percent,
+            line,
+            done,
+        });
+    }
+
+    /// Start a new step; `percent` is the overall completion so far
+    pub fn step(&self, step: impl Into<String>, percent: Option<u8>) {
+        *self.state.lock().unwrap() = (step.into(), percent.map(|p| p.min(100)));
+        self.emit(None, false);
+    }
+
+    /// Report an output line under the current step
+    pub fn log(&self, line: impl Into<String>) {
+        self.emit(Some(line.into()), false);
+    }
+
+    /// Send the final event
+    pub fn finish(&self, step: impl Into<String>, percent: Option<u8>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.0 = step.into();
+            state.1 = percent.or(state.1);
+        }
+        self.emit(None, true);
+    }
+
+    /// Cancel the operation; refused (false) while it is in an uncancellable step
+    pub fn cancel(&self) -> bool {
+        let uncancellable = self.uncancellable.lock().unwrap();
+        if *uncancellable > 0 {
+            return false;
+        }
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+        true
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the operation is cancelled
+    pub async fn cancelled(&self) {
+        loop {
+            // Registered before the flag check, so a cancel in between still wakes us
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Run `fut` as this operation: command output is reported here, and cancelling
+    /// drops the future (killing its child processes) and returns `UserCancelled`
+    pub async fn run<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        tokio::select! {
+            result = CURRENT.scope(self.clone(), fut) => result,
+            _ = self.cancelled() => Err(AppError::UserCancelled),
+        }
+    }
+}
+
+/// Refuses cancellation of the current operation until dropped
+pub struct Uncancellable(Option<Progress>);
+
+/// Enter a step that must run to completion: a root child started by pkexec or sudo can't be
+/// killed from here, and killing a package manager mid-transaction leaves dpkg/rpm half done
+/// Fails with `UserCancelled` when the operation was cancelled before the step started
+pub fn uncancellable() -> Result<Uncancellable> {
+    let Some(progress) = current() else { return Ok(Uncancellable(None)) };
+    {
+        let mut uncancellable = progress.uncancellable.lock().unwrap();
+        if progress.is_cancelled() {
+            return Err(AppError::UserCancelled);
+        }
+        *uncancellable += 1;
+    }
+    Ok(Uncancellable(Some(progress)))
+}
+
+impl Drop for Uncancellable {
+    fn drop(&mut self) {
+        if let Some(progress) = &self.0 {
+            *progress.uncancellable.lock().unwrap() -= 1;
+        }
+    }
+}
+
+/// Sink forwarding events to the frontend as `PROGRESS_EVENT`
+pub fn emitter(app: AppHandle) -> ProgressSink {
+    Arc::new(move |event| {
+        if let Err(e) = app.emit(PROGRESS_EVENT, event) {
+            log::warn!("Failed to emit progress: {}", e);
+        }
+    })
+}
+
+// ============================================================================
+// Running Operations
+// ============================================================================
+
+/// Operations in flight, by id, so `cancel_operation` can reach them
+#[derive(Default)]
+pub struct Operations {
+    running: Mutex<HashMap<String, Progress>>,
+    next_id: AtomicU64,
+}
+
+impl Operations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an operation; the frontend picks the id so it can cancel before the command returns
+    /// An id that is still running is refused rather than replacing (and orphaning) that operation
+    pub fn start(&self, id: Option<String>, sink: Option<ProgressSink>) -> Result<Progress> {
+        let id = id
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| format!("op-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1));
+        let mut running = self.running.lock().unwrap();
+        if running.contains_key(&id) {
+            return Err(AppError::InvalidInput(format!("Operation {} is already running", id)));
+        }
+        let progress = Progress::new(id.clone(), sink);
+        running.insert(id, progress.clone());
+        Ok(progress)
+    }
+
+    /// Drive a started operation to completion or cancellation, then forget it
+    pub async fn run<T>(&self, progress: Progress, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        let result = progress.run(fut).await;
+        self.running.lock().unwrap().remove(progress.id());
+
+        match &result {
+            Ok(_) => progress.finish("Done", Some(100)),
+            Err(AppError::UserCancelled) => progress.finish("Cancelled", None),
+            Err(e) => progress.finish(format!("Failed: {}", e), None),
+        }
+        result
+    }
+
+    /// Cancel a running operation; false if it is unknown or already finished
+    /// Fails while the operation is in a step that has to run to completion
+    pub fn cancel(&self, id: &str) -> Result<bool> {
+        match self.running.lock().unwrap().get(id) {
+            Some(progress) if progress.cancel() => Ok(true),
+            Some(_) => Err(AppError::NotSupported(
+                "A privileged step is running and can't be interrupted safely; it will finish first".to_string(),
+            )),
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_cancel_kills_running_operation() {
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let sink_events = events.clone();
+            let operations = Arc::new(Operations::new());
+            let progress = operations
+                .start(
+                    Some("sleep".to_string()),
+                    Some(Arc::new(move |e: ProgressEvent| sink_events.lock().unwrap().push(e))),
+                )
+                .unwrap();
+            assert!(operations.start(Some("sleep".to_string()), None).is_err());
+
+            let child_pid = Arc::new(Mutex::new(None));
+            let pid_slot = child_pid.clone();
+            let task = {
+                let operations = operations.clone();
+                tokio::spawn(async move {
+                    operations
+                        .run(progress, async move {
+                            current().unwrap().step("Sleeping", Some(10));
+                            let mut child = tokio::process::Command::new("sleep")
+                                .arg("30")
+                                .kill_on_drop(true)
+                                .spawn()?;
+                            *pid_slot.lock().unwrap() = child.id();
+                            child.wait().await?;
+                            Ok(())
+                        })
+                        .await
+                })
+            };
+
+            while child_pid.lock().unwrap().is_none() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            assert!(operations.running.lock().unwrap().contains_key("sleep"));
+            assert!(operations.cancel("sleep").unwrap());
+
+            let result = task.await.unwrap();
+            assert!(matches!(result, Err(AppError::UserCancelled)));
+            assert!(operations.running.lock().unwrap().is_empty());
+            assert!(!operations.cancel("sleep").unwrap());
+
+            // The child was killed (and reaped by tokio), not left running for 30s
+            let pid = child_pid.lock().unwrap().unwrap();
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let state = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+            assert!(state.is_empty() || state.contains(" Z "));
+
+            let events = events.lock().unwrap();
+            assert_eq!(events.first().map(|e| e.step.as_str()), Some("Sleeping"));
+            let last = events.last().unwrap();
+            assert!(last.done);
+            assert_eq!(last.step, "Cancelled");
+        });
+    }
+
+    #[test]
+    fn test_cancel_refused_during_uncancellable_step() {
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let operations = Arc::new(Operations::new());
+            let progress = operations.start(Some("install".to_string()), None).unwrap();
+            let (entered_tx, entered_rx) = tokio::sync::oneshot::channel();
+            let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+            let task = {
+                let operations = operations.clone();
+                tokio::spawn(async move {
+                    operations
+                        .run(progress, async move {
+                            let guard = uncancellable()?;
+                            entered_tx.send(()).unwrap();
+                            release_rx.await.unwrap();
+                            drop(guard);
+                            Ok("installed")
+                        })
+                        .await
+                })
+            };
+
+            entered_rx.await.unwrap();
+            assert!(operations.cancel("install").is_err());
+            release_tx.send(()).unwrap();
+            assert_eq!(task.await.unwrap().unwrap(), "installed");
+        });
+    }
+}
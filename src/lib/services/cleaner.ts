@@ -38,8 +38,8 @@ export async function getCleanupCategories(): Promise<CleanupCategory[]> {
     return invoke('get_cleanup_categories');
 }
 
-export async function cleanCategory(categoryId: string): Promise<CleanupResult> {
-    return invoke('clean_category', { categoryId });
+export async function cleanCategory(categoryId: string, operationId?: string): Promise<CleanupResult> {
+    return invoke('clean_category', { categoryId, operationId: operationId || null });
 }
 
 export async function getTotalReclaimable(): Promise<number> {
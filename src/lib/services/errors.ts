@@ -0,0 +1,52 @@
+// TypeScript helpers for the structured errors every command rejects with
+// Branch on `code`; `message` and `hint` are English fallbacks
+
+// ============================================================================
+// Types
+// ============================================================================
+
+export type ErrorCode =
+    | 'system'
+    | 'io'
+    | 'permission_denied'
+    | 'command_failed'
+    | 'timeout'
+    | 'user_cancelled'
+    | 'network'
+    | 'invalid_input'
+    | 'not_supported'
+    | 'package_locked'
+    | 'package_interrupted';
+
+export interface CommandContext {
+    command: string;
+    status: number | null;
+    stderr: string; // Trimmed excerpt
+}
+
+export interface AppError {
+    code: ErrorCode;
+    message: string;
+    hint?: string;
+    context?: CommandContext;
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+export function isAppError(error: unknown): error is AppError {
+    return typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+}
+
+export function hasErrorCode(error: unknown, code: ErrorCode): boolean {
+    return isAppError(error) && error.code === code;
+}
+
+/** Human-readable text for any rejected invoke (message plus hint) */
+export function errorMessage(error: unknown): string {
+    if (isAppError(error)) {
+        return error.hint ? `${error.message}\n${error.hint}` : error.message;
+    }
+    return String(error);
+}
@@ -32,8 +32,9 @@ export async function getAdBlockStats(): Promise<AdBlockStats> {
     return invoke('get_adblock_stats');
 }
 
-export async function applyBlocklists(sourceIds: string[]): Promise<number> {
-    return invoke('apply_blocklists', { sourceIds });
+// Pass an operation id to receive progress events (see progress.ts)
+export async function applyBlocklists(sourceIds: string[], operationId?: string): Promise<number> {
+    return invoke('apply_blocklists', { sourceIds, operationId: operationId || null });
 }
 
 export async function clearBlocklists(): Promise<void> {
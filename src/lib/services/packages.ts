@@ -5,13 +5,16 @@ import { invoke } from '@tauri-apps/api/core';
 // Types
 // ============================================================================
 
+export type PackageOrigin = 'native' | 'flatpak' | 'snap';
+
 export interface PackageInfo {
-    name: string;
+    name: string; // Flatpak runtimes are listed as "ID//BRANCH"
     version: string;
     size_bytes: number;
     description: string;
     is_auto: boolean;
     category: string;
+    origin: PackageOrigin;
 }
 
 export interface PackageAction {
@@ -21,6 +24,85 @@ export interface PackageAction {
     message: string;
 }
 
+export interface CleanupResult {
+    category: string;
+    items_removed: number;
+    bytes_freed: number;
+    success: boolean;
+    message: string;
+}
+
+export interface SearchResult extends PackageInfo {
+    installed: boolean;
+    repository: string | null;
+}
+
+export interface UpgradablePackage {
+    name: string;
+    current_version: string;
+    new_version: string;
+    repository: string | null;
+}
+
+export interface PackageDetails {
+    name: string;
+    version: string; // Installed version, or the candidate if not installed
+    description: string;
+    installed: boolean;
+    size_bytes: number;
+    repository: string | null; // "local" when no repository provides it
+    dependencies: string[];
+    reverse_dependencies: string[]; // Installed packages that depend on it
+    files: string[];
+}
+
+export type ChangeKind = 'install' | 'upgrade' | 'downgrade' | 'reinstall' | 'remove';
+
+export interface PackageChange {
+    name: string;
+    kind: ChangeKind;
+    old_version: string | null; // null for installs
+    new_version: string | null; // null for removals
+}
+
+export interface PackageTransaction {
+    id: string; // dnf history id, otherwise the start time
+    timestamp: string;
+    action: ChangeKind | 'mixed';
+    command: string | null;
+    changes: PackageChange[];
+}
+
+export interface RemovedPackage {
+    name: string;
+    version: string;
+    size_bytes: number;
+}
+
+// Dry run of an uninstall, purge or autoremove, dependents included
+export interface RemovalPreview {
+    action: 'uninstall' | 'purge' | 'autoremove';
+    packages: RemovedPackage[];
+    bytes_freed: number;
+}
+
+export interface PackageUsage {
+    name: string;
+    size_bytes: number;
+    is_auto: boolean;
+    required_by: number; // Installed packages depending on it directly
+    exclusive_size_bytes: number; // Its size plus dependencies nothing else needs
+    orphan: boolean; // Auto-installed and no longer needed
+    leaf: boolean; // Explicitly installed and needed by nothing
+}
+
+export interface PackageAnalysis {
+    packages: PackageUsage[]; // Largest exclusive size first
+    orphan_count: number;
+    orphan_bytes: number;
+    leaf_count: number;
+}
+
 export type PackageStats = [number, number, number]; // [total, auto, size]
 
 // ============================================================================
@@ -35,8 +117,42 @@ export async function searchPackages(query: string): Promise<PackageInfo[]> {
     return invoke('search_packages', { query });
 }
 
-export async function uninstallPackage(name: string): Promise<PackageAction> {
-    return invoke('uninstall_package', { name });
+// Searches the distro repositories; results are cached for a few minutes
+export async function searchRemotePackages(query: string): Promise<SearchResult[]> {
+    return invoke('search_remote_packages', { query });
+}
+
+export async function getPackageDetails(name: string): Promise<PackageDetails> {
+    return invoke('get_package_details', { name });
+}
+
+// Pass an operation id to receive progress events (see progress.ts)
+export async function installPackage(name: string, operationId?: string): Promise<PackageAction> {
+    return invoke('install_package', { name, operationId: operationId || null });
+}
+
+export async function getUpgradablePackages(): Promise<UpgradablePackage[]> {
+    return invoke('get_upgradable_packages');
+}
+
+export async function upgradePackages(names: string[], operationId?: string): Promise<PackageAction> {
+    return invoke('upgrade_packages', { names, operationId: operationId || null });
+}
+
+export async function upgradeAllPackages(operationId?: string): Promise<PackageAction> {
+    return invoke('upgrade_all_packages', { operationId: operationId || null });
+}
+
+export async function uninstallPackage(name: string, origin: PackageOrigin = 'native'): Promise<PackageAction> {
+    return invoke('uninstall_package', { name, origin });
+}
+
+// Removes unused Flatpak runtimes or disabled snap revisions
+export async function cleanUnusedPackages(
+    origin: Exclude<PackageOrigin, 'native'>,
+    operationId?: string,
+): Promise<CleanupResult> {
+    return invoke('clean_unused_packages', { origin, operationId: operationId || null });
 }
 
 export async function purgePackage(name: string): Promise<PackageAction> {
@@ -47,6 +163,34 @@ export async function autoremovePackages(): Promise<PackageAction> {
     return invoke('autoremove_packages');
 }
 
+// Dry runs of the removals above (native packages only); nothing is changed
+export async function simulateUninstallPackage(name: string): Promise<RemovalPreview> {
+    return invoke('simulate_uninstall_package', { name });
+}
+
+export async function simulatePurgePackage(name: string): Promise<RemovalPreview> {
+    return invoke('simulate_purge_package', { name });
+}
+
+export async function simulateAutoremovePackages(): Promise<RemovalPreview> {
+    return invoke('simulate_autoremove_packages');
+}
+
+// Newest first, from the package manager's log
+export async function getPackageHistory(): Promise<PackageTransaction[]> {
+    return invoke('get_package_history');
+}
+
+// Removes what the transaction installed and reinstalls what it removed
+export async function undoPackageTransaction(id: string, operationId?: string): Promise<PackageAction> {
+    return invoke('undo_package_transaction', { id, operationId: operationId || null });
+}
+
 export async function getPackageStats(): Promise<PackageStats> {
     return invoke('get_package_stats');
 }
+
+// Native packages only
+export async function getPackageAnalysis(): Promise<PackageAnalysis> {
+    return invoke('get_package_analysis');
+}
@@ -0,0 +1,31 @@
+// TypeScript services for system profile import/export
+import { invoke } from '@tauri-apps/api/core';
+
+// ============================================================================
+// Types
+// ============================================================================
+
+export interface ProfileChange {
+    section: string; // "tweaks", "dns", "hosts", "services", "startup"
+    item: string;
+    current: string;
+    desired: string;
+    applied: boolean;
+    message: string;
+}
+
+// ============================================================================
+// API Functions
+// ============================================================================
+
+export async function exportProfile(): Promise<string> {
+    return invoke('export_profile');
+}
+
+export async function previewProfileImport(profileToml: string): Promise<ProfileChange[]> {
+    return invoke('preview_profile_import', { profileToml });
+}
+
+export async function importProfile(profileToml: string): Promise<ProfileChange[]> {
+    return invoke('import_profile', { profileToml });
+}
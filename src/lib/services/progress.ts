@@ -0,0 +1,58 @@
+// TypeScript services for long-running operation progress and cancellation
+// Commands that accept an `operationId` emit `operation-progress` events until they return
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+// ============================================================================
+// Types
+// ============================================================================
+
+export const PROGRESS_EVENT = 'operation-progress';
+
+export interface ProgressEvent {
+    operation_id: string;
+    step: string;
+    percent: number | null; // Overall 0-100, when known
+    line: string | null; // Output line of the running command
+    done: boolean; // Last event of the operation
+}
+
+// ============================================================================
+// API Functions
+// ============================================================================
+
+export function newOperationId(): string {
+    return crypto.randomUUID();
+}
+
+export async function onProgress(
+    operationId: string,
+    handler: (event: ProgressEvent) => void,
+): Promise<UnlistenFn> {
+    return listen<ProgressEvent>(PROGRESS_EVENT, (event) => {
+        if (event.payload.operation_id === operationId) {
+            handler(event.payload);
+        }
+    });
+}
+
+// Resolves false if the operation already finished; rejects while a privileged step
+// (package transaction, pkexec/sudo command) can't be interrupted
+export async function cancelOperation(operationId: string): Promise<boolean> {
+    return invoke('cancel_operation', { operationId });
+}
+
+// Run a command with a fresh operation id, forwarding its progress events to `handler`
+export async function withProgress<T>(
+    run: (operationId: string) => Promise<T>,
+    handler: (event: ProgressEvent) => void,
+): Promise<T> {
+    const operationId = newOperationId();
+    const unlisten = await onProgress(operationId, handler);
+    try {
+        return await run(operationId);
+    } finally {
+        unlisten();
+    }
+}
@@ -8,14 +8,16 @@ import { invoke } from '@tauri-apps/api/core';
 export interface Repository {
     file_path: string;
     line_number: number;
-    repo_type: string;
+    repo_type: string; // deb, deb-src, rpm-md, yast2 or pacman
     uri: string;
-    suite: string;
+    suite: string; // repo id on dnf, zypper and pacman
+    name: string | null;
     components: string[];
     is_enabled: boolean;
     is_ppa: boolean;
     raw_line: string;
-    ppa_name: string | null;
+    ppa_name: string | null; // "ppa:user/repo" or "copr:owner/project"
+    signed_by: string | null; // APT keyring path or fingerprint
 }
 
 export interface MirrorInfo {
@@ -23,7 +25,77 @@ export interface MirrorInfo {
     uri: string;
     country: string;
     country_code: string;
+    // Benchmark results, null until tested
     latency_ms: number | null;
+    throughput_kbps: number | null;
+    http_status: number | null;
+    synced_at: number | null; // Unix time the mirror's index was generated
+    sync_lag_secs: number | null; // Behind the main archive (or the freshest mirror)
+    outdated: boolean; // More than a day behind
+    score: number | null; // Latency, throughput and freshness combined; higher is better
+    error: string | null;
+}
+
+// Arch: a `Server =` line of /etc/pacman.d/mirrorlist
+export interface MirrorlistServer {
+    url: string; // As written, with $repo/os/$arch
+    base: string; // Mirror root
+    country: string | null; // "## Country" heading above it
+    is_enabled: boolean;
+    line_number: number;
+}
+
+export interface RankedMirror extends MirrorInfo {
+    in_mirrorlist: boolean;
+}
+
+export interface MirrorlistUpdate {
+    backup_path: string | null;
+    mirrors: RankedMirror[]; // Servers written, in order
+}
+
+// APT signing keys
+export type KeyringLocation = 'legacy' | 'trusted_parts' | 'local' | 'shared';
+export type KeyIssue = 'expired' | 'unused' | 'legacy_apt_key' | 'global_trust' | 'missing_keyring' | 'unknown_signer';
+
+export interface SigningKey {
+    fingerprint: string;
+    uids: string[];
+    created: number | null; // Unix time
+    expires: number | null;
+    expired: boolean;
+    subkeys: string[];
+    used: boolean; // Signs a repository's Release file
+    removable: boolean; // Can be deleted on its own from /etc/apt/trusted.gpg
+}
+
+export interface Keyring {
+    path: string;
+    location: KeyringLocation;
+    package: string | null; // Owning package; removed with it
+    keys: SigningKey[];
+    used_by: string[]; // "uri suite" of repositories verified with it
+    usage_unknown: boolean; // A repository trusting it has no signature to check yet
+    issues: KeyIssue[];
+    removable: boolean;
+}
+
+export interface RepositoryKey {
+    file_path: string;
+    line_number: number;
+    uri: string;
+    suite: string;
+    enabled: boolean;
+    signed_by: string | null;
+    keyring: string | null; // Keyring holding the key that signed the Release file
+    fingerprint: string | null;
+    issues: KeyIssue[];
+    can_migrate: boolean; // Globally trusted key can move to signed-by
+}
+
+export interface KeyInventory {
+    keyrings: Keyring[];
+    repositories: RepositoryKey[];
 }
 
 export interface RegionInfo {
@@ -50,8 +122,13 @@ export async function toggleRepository(filePath: string, lineNumber: number): Pr
     return invoke('toggle_repository', { filePath, lineNumber });
 }
 
-export async function deleteRepository(filePath: string, isWholeFile: boolean): Promise<string> {
-    return invoke('delete_repository', { filePath, isWholeFile });
+export async function deleteRepository(filePath: string, isWholeFile: boolean, lineNumber?: number): Promise<string> {
+    return invoke('delete_repository', { filePath, isWholeFile, lineNumber: lineNumber ?? null });
+}
+
+// "ppa:user/repo" (Debian), "copr:owner/project" (Fedora) or "<id> <url>" (Fedora, openSUSE, Arch)
+export async function addRepository(spec: string): Promise<string> {
+    return invoke('add_repository', { spec });
 }
 
 export async function addPpa(ppa: string): Promise<string> {
@@ -70,20 +147,54 @@ export async function getMirrors(region?: string): Promise<MirrorInfo[]> {
     return invoke('get_mirrors', { region: region || null });
 }
 
-export async function testMirrorSpeed(uri: string): Promise<number> {
+export async function testMirrorSpeed(uri: string): Promise<MirrorInfo> {
     return invoke('test_mirror_speed', { uri });
 }
 
-export async function testAllMirrors(region?: string): Promise<MirrorInfo[]> {
-    return invoke('test_all_mirrors', { region: region || null });
+export async function testAllMirrors(region?: string, operationId?: string): Promise<MirrorInfo[]> {
+    return invoke('test_all_mirrors', { region: region || null, operationId: operationId || null });
 }
 
 export async function setMirror(newUri: string): Promise<string> {
     return invoke('set_mirror', { newUri });
 }
 
-export async function aptUpdate(): Promise<string> {
-    return invoke('apt_update');
+// Arch mirrorlist functions
+export async function getArchMirrorlist(): Promise<MirrorlistServer[]> {
+    return invoke('get_arch_mirrorlist');
+}
+
+export async function rankArchMirrors(region?: string, operationId?: string): Promise<RankedMirror[]> {
+    return invoke('rank_arch_mirrors', { region: region || null, operationId: operationId || null });
+}
+
+export async function generateArchMirrorlist(region?: string, count?: number, operationId?: string): Promise<MirrorlistUpdate> {
+    return invoke('generate_arch_mirrorlist', {
+        region: region || null,
+        count: count ?? null,
+        operationId: operationId || null,
+    });
+}
+
+// APT signing key functions
+export async function getKeyInventory(): Promise<KeyInventory> {
+    return invoke('get_key_inventory');
+}
+
+export async function removeKeyring(path: string): Promise<string> {
+    return invoke('remove_keyring', { path });
+}
+
+export async function removeLegacyKey(fingerprint: string): Promise<string> {
+    return invoke('remove_legacy_key', { fingerprint });
+}
+
+export async function migrateRepositoryKey(filePath: string, lineNumber: number): Promise<string> {
+    return invoke('migrate_repository_key', { filePath, lineNumber });
+}
+
+export async function aptUpdate(operationId?: string): Promise<string> {
+    return invoke('apt_update', { operationId: operationId || null });
 }
 
 // apt-fast functions
@@ -20,6 +20,7 @@ export interface Tweak {
     max_value: number | null;
     options: string[] | null;
     tweak_type: string; // "slider", "selector", "preset"
+    is_persisted: boolean; // restored at boot via Glance drop-ins
 }
 
 export interface TweakCategory {
@@ -36,6 +37,21 @@ export interface DeviceInfo {
     disk_device: string;
 }
 
+export interface TweakChange {
+    kind: string; // "sysctl", "sysfs", "zram"
+    target: string;
+    previous_value: string;
+    new_value: string;
+}
+
+export interface TweakTransaction {
+    id: number;
+    timestamp: number; // unix seconds
+    description: string;
+    changes: TweakChange[];
+    rolled_back: boolean;
+}
+
 // ============================================================================
 // API Functions
 // ============================================================================
@@ -44,18 +60,34 @@ export async function getTweaks(): Promise<TweakCategory[]> {
     return invoke('get_tweaks');
 }
 
-export async function applyTweak(tweakId: string, value: string): Promise<string> {
-    return invoke('apply_tweak', { tweakId, value });
+export async function applyTweak(tweakId: string, value: string, persist?: boolean): Promise<string> {
+    return invoke('apply_tweak', { tweakId, value, persist });
+}
+
+export async function applyAllRecommended(persist?: boolean): Promise<string[]> {
+    return invoke('apply_all_recommended', { persist });
 }
 
-export async function applyAllRecommended(): Promise<string[]> {
-    return invoke('apply_all_recommended');
+export async function setTweakPersistence(tweakId: string, persist: boolean): Promise<string> {
+    return invoke('set_tweak_persistence', { tweakId, persist });
 }
 
 export async function getDeviceInfo(): Promise<DeviceInfo> {
     return invoke('get_device_info');
 }
 
+export async function getTweakHistory(): Promise<TweakTransaction[]> {
+    return invoke('get_tweak_history');
+}
+
+export async function rollbackTweakTransaction(transactionId: number): Promise<string> {
+    return invoke('rollback_tweak_transaction', { transactionId });
+}
+
+export async function undoLastTweak(): Promise<string> {
+    return invoke('undo_last_tweak');
+}
+
 // Helper: Format bytes for display
 export function formatBufferSize(bytes: number): string {
     if (bytes >= 1048576) {
@@ -0,0 +1,41 @@
+// TypeScript services for automatic updates
+import { invoke } from '@tauri-apps/api/core';
+
+// ============================================================================
+// Types
+// ============================================================================
+
+export type RebootPolicy = 'never' | 'when_needed';
+
+export type UpdateSchedule = 'daily' | 'weekly';
+
+export interface AutoUpdateConfig {
+    enabled: boolean;
+    security_only: boolean;
+    reboot: RebootPolicy;
+    reboot_time: string | null; // "HH:MM", null = right after the update
+    schedule: UpdateSchedule;
+}
+
+export interface AutoUpdateStatus {
+    backend: string; // "unattended-upgrades", "dnf-automatic", "zypper patch", "checkupdates"
+    installed: boolean;
+    notify_only: boolean; // Arch: reports available updates, never installs them
+    config: AutoUpdateConfig;
+    last_run: string | null;
+    last_result: string | null;
+    last_success: boolean | null;
+    next_run: string | null;
+}
+
+// ============================================================================
+// API Functions
+// ============================================================================
+
+export async function getAutoUpdateStatus(): Promise<AutoUpdateStatus> {
+    return invoke('get_auto_update_status');
+}
+
+export async function setAutoUpdateConfig(config: AutoUpdateConfig): Promise<AutoUpdateStatus> {
+    return invoke('set_auto_update_config', { config });
+}